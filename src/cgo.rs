@@ -162,9 +162,8 @@ impl Trace {
         corset: &Corset,
     ) -> Value {
         let column = corset.columns.column(cref).unwrap();
-        let handle = &column.handle;
-        // Determine spilling needed for the given module.
-        let spilling = corset.spilling_of(&handle.module).unwrap_or(0);
+        // Determine spilling needed for the given column.
+        let spilling = corset.spilling_for_column(cref).unwrap_or(0);
         // consider the option
         if let Some(v) = column.padding_value.as_ref() {
             v.clone()
@@ -185,7 +184,7 @@ impl Trace {
                 // Determine padding value based on the type of
                 // computation.
                 match c {
-                    Computation::Composite { exp, .. } => exp
+                    Computation::Composite { exp, .. } | Computation::Fixpoint { exp, .. } => exp
                         .eval(
                             0,
                             |_, _, _| Some(Value::zero()),
@@ -193,12 +192,19 @@ impl Trace {
                             &EvalSettings::default(),
                         )
                         .unwrap_or_else(Value::zero),
-                    Computation::Interleaved { .. } => Value::zero(),
+                    Computation::Interleaved { .. } | Computation::InterleavedStrided { .. } => {
+                        Value::zero()
+                    }
                     Computation::Sorted { .. } => Value::zero(),
                     Computation::CyclicFrom { .. } => Value::zero(),
+                    Computation::Clamp { .. } => Value::zero(),
+                    Computation::Recurrence { init, .. } => init.clone(),
                     Computation::SortingConstraints { .. } => Value::zero(),
                     Computation::ExoOperation { .. } => Value::zero(), // TODO: FIXME:
                     Computation::ExoConstant { value, .. } => value.clone(),
+                    Computation::ConstantTable { .. } => Value::zero(),
+                    Computation::LookupMultiplicity { .. } => Value::zero(),
+                    Computation::ActiveRows { .. } => Value::zero(),
                 }
             }
         }
@@ -221,21 +227,23 @@ pub fn corset_from_file(filename: &str) -> Result<Corset> {
     let binfile = &std::fs::read_to_string(filename)
         .with_context(|| anyhow!("while reading `{}`", filename))?;
     //
-    let constraints = if RON_BINFILE {
-        ron::from_str(binfile).with_context(|| anyhow!("while parsing `{}` (RON)", filename))?
-    } else {
-        serde_json::from_str(binfile)
-            .with_context(|| anyhow!("while parsing `{}` (JSON)", filename))?
-    };
+    let constraints = ConstraintSet::load(binfile, RON_BINFILE).with_context(|| {
+        anyhow!(
+            "while parsing `{}` ({})",
+            filename,
+            if RON_BINFILE { "RON" } else { "JSON" }
+        )
+    })?;
     make_corset(constraints)
 }
 
 pub fn corset_from_str(binfile: &str) -> Result<Corset> {
-    let constraints = if RON_BINFILE {
-        ron::from_str(binfile).with_context(|| anyhow!("while parsing bin file (RON)"))?
-    } else {
-        serde_json::from_str(binfile).with_context(|| anyhow!("while parsing bin file (JSON)"))?
-    };
+    let constraints = ConstraintSet::load(binfile, RON_BINFILE).with_context(|| {
+        anyhow!(
+            "while parsing bin file ({})",
+            if RON_BINFILE { "RON" } else { "JSON" }
+        )
+    })?;
     make_corset(constraints)
 }
 
@@ -244,7 +252,7 @@ pub fn compute_trace_from_file(
     tracefile: &str,
     fail_on_missing: bool,
 ) -> Result<Trace> {
-    compute::compute_trace(tracefile, constraints, fail_on_missing)
+    compute::compute_trace(tracefile, constraints, fail_on_missing, false)
         .with_context(|| format!("while computing from file `{}`", tracefile))?;
     Ok(Trace::from_constraints(constraints))
 }
@@ -254,7 +262,7 @@ pub fn compute_trace_from_str(
     tracestr: &str,
     fail_on_missing: bool,
 ) -> Result<Trace> {
-    compute::compute_trace_str(tracestr.as_bytes(), constraints, fail_on_missing)
+    compute::compute_trace_str(tracestr.as_bytes(), constraints, fail_on_missing, false)
         .with_context(|| format!("while computing from string `{}`", tracestr))?;
     Ok(Trace::from_constraints(constraints))
 }
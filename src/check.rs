@@ -1,6 +1,9 @@
+use crate::utils::Colorize;
 use crate::{
-    column::{ColumnSet, Value},
-    compiler::{Constraint, ConstraintSet, Domain, EvalSettings, Expression, Node},
+    column::{ColumnSet, Computation, Value},
+    compiler::{
+        ColumnRef, Constraint, ConstraintSet, Domain, EvalSettings, Expression, Intrinsic, Node,
+    },
     pretty::*,
     structs::Handle,
 };
@@ -8,9 +11,12 @@ use anyhow::*;
 use cached::SizedCache;
 use itertools::Itertools;
 use log::*;
-use owo_colors::OwoColorize;
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
 use rayon::prelude::*;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -41,6 +47,11 @@ pub struct DebugSettings {
     full_trace: bool,
     /// whether to display the original source code along the compiled form
     src: bool,
+    /// whether to check the constraints on the padding rows as well
+    check_padding: bool,
+    /// if set, only check the vanishing constraints on every n-th row, and
+    /// on the rows at the boundaries of the trace
+    sample: Option<usize>,
 }
 impl DebugSettings {
     pub fn new() -> Self {
@@ -53,6 +64,8 @@ impl DebugSettings {
             context_span_after: 2,
             full_trace: false,
             src: false,
+            check_padding: false,
+            sample: None,
         }
     }
     pub fn dim(self, x: bool) -> Self {
@@ -109,6 +122,15 @@ impl DebugSettings {
             ..self
         }
     }
+    pub fn check_padding(self, x: bool) -> Self {
+        Self {
+            check_padding: x,
+            ..self
+        }
+    }
+    pub fn and_sample(self, x: Option<usize>) -> Self {
+        Self { sample: x, ..self }
+    }
 }
 
 /// Pretty print an expresion and all its intermediate value for debugging (or
@@ -259,55 +281,83 @@ fn check_constraint_at(
     Ok(())
 }
 
-fn check_inrange(expr: &Node, cs: &ConstraintSet, max: &Value) -> Result<()> {
-    let l = cs.dependencies_len(expr, false)?;
-    if let Some(l) = l {
-        for i in 0..l as isize {
-            let r = expr
-                .eval(
-                    i,
-                    |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
-                    &mut None,
-                    &Default::default(),
-                )
-                .unwrap();
-            if r.ge(max) {
-                bail!(
+/// Find the first row where `expr` is not lower than `max`, and describe the
+/// failure
+fn inrange_mismatch(
+    expr: &Node,
+    cs: &ConstraintSet,
+    max: &Value,
+) -> Result<Option<(usize, String)>> {
+    let Some(l) = cs.dependencies_len(expr, false)? else {
+        return Ok(None);
+    };
+    for i in 0..l {
+        let r = expr
+            .eval(
+                i as isize,
+                |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
+                &mut None,
+                &Default::default(),
+            )
+            .unwrap();
+        // negative integers stand for the upper half of the field
+        let r_field = num_traits::Euclid::rem_euclid(&r.to_bi(), crate::constants::field_modulus());
+        if r_field >= max.to_bi() {
+            return Ok(Some((
+                i,
+                format!(
                     "{} = {} > {}",
                     expr.to_string().white().bold(),
                     r.pretty().red().bold(),
                     max.pretty().blue()
-                )
-            }
+                ),
+            )));
         }
-        Ok(())
-    } else {
-        Ok(())
     }
+    Ok(None)
 }
 
-fn check_constraint(
+fn check_inrange(expr: &Node, cs: &ConstraintSet, max: &Value) -> Result<()> {
+    match inrange_mismatch(expr, cs, max)? {
+        Some((_, mismatch)) => bail!(mismatch),
+        None => Ok(()),
+    }
+}
+
+/// The rows on which `expr` has to be checked, and whether it should wrap
+/// around the trace when doing so -- i.e. when its domain is explicitly given
+fn checked_rows<'a>(
     cs: &ConstraintSet,
     expr: &Node,
-    domain: &Option<Domain<isize>>,
-    name: &Handle,
+    domain: &'a Option<Domain<isize>>,
+    name: &'a Handle,
     settings: DebugSettings,
-) -> Result<()> {
-    let mut cache = Some(cached::SizedCache::with_size(200000)); // ~1.60MB cache
-    match domain {
+) -> Result<(Box<dyn Iterator<Item = isize> + 'a>, bool)> {
+    Ok(match domain {
         Some(is) => {
-            for i in is.iter() {
-                let err = check_constraint_at(cs, expr, i, true, true, &mut cache, settings)
-                    .map_err(|e| CheckingError::FailingConstraint(name.clone(), e.to_string()));
-
-                if err.is_err() {
-                    if settings.continue_on_error {
-                        eprintln!("{:?}", err);
-                    } else {
-                        bail!(err.err().unwrap());
+            // Rows of the domain lying outside of the trace can not be
+            // checked; they are reported and ignored.
+            let l = cs
+                .dependencies_len(expr, true)
+                .map_err(CheckingError::MismatchingLengths)?
+                .map(|l| l as isize);
+            (
+                Box::new(is.iter().filter(move |&i| {
+                    if let Some(l) = l {
+                        if i >= l || i < -l {
+                            warn!(
+                                "{}: domain row {} is out of the {}-long trace, skipping",
+                                name.pretty(),
+                                i,
+                                l
+                            );
+                            return false;
+                        }
                     }
-                }
-            }
+                    true
+                })),
+                true,
+            )
         }
         None => {
             // Determine columns accessed by this expression
@@ -316,55 +366,106 @@ fn check_constraint(
                 .map_err(CheckingError::MismatchingLengths)?;
             // Determine number of rows for checking
             let nrows = if let Some(l) = l { l as isize } else { 1 };
-            // Check all the rows
-            for i in 0..nrows as isize {
-                let err = check_constraint_at(cs, expr, i, false, false, &mut cache, settings)
-                    .map_err(|e| CheckingError::FailingConstraint(name.clone(), e.to_string()));
-
-                if err.is_err() {
-                    if settings.continue_on_error {
-                        eprintln!("{:?}", err);
-                    } else {
-                        bail!(err.err().unwrap());
-                    }
-                }
+            // The last padding rows are followed by the actual trace, and
+            // transition constraints may legitimately fail there when
+            // reading across the padding boundary
+            let tolerated = if settings.check_padding {
+                0..0
+            } else {
+                let padding = padding_rows(cs, expr);
+                (padding - expr.future_spill()).max(0)..padding
+            };
+            // When sampling, the rows where the shifts of the constraint
+            // reach outside of the trace are always checked
+            let boundary = expr.future_spill().max(-expr.past_spill()) + 1;
+            let sampled = move |i: &isize| {
+                settings.sample.map_or(true, |k| {
+                    i % k as isize == 0 || *i < boundary || *i >= nrows - boundary
+                })
+            };
+            (
+                Box::new(
+                    (0..nrows)
+                        .filter(move |i| !tolerated.contains(i))
+                        .filter(sampled),
+                ),
+                false,
+            )
+        }
+    })
+}
+
+fn check_constraint(
+    cs: &ConstraintSet,
+    expr: &Node,
+    domain: &Option<Domain<isize>>,
+    name: &Handle,
+    settings: DebugSettings,
+) -> Result<()> {
+    let mut cache = Some(cached::SizedCache::with_size(200000)); // ~1.60MB cache
+    let (rows, wrap) = checked_rows(cs, expr, domain, name, settings)?;
+    // Rows out of the trace are only failures if they have been explicitly
+    // required by the domain of the constraint
+    let fail_on_oob = wrap;
+    for i in rows {
+        let err = check_constraint_at(cs, expr, i, wrap, fail_on_oob, &mut cache, settings)
+            .map_err(|e| CheckingError::FailingConstraint(name.clone(), e.to_string()));
+
+        if err.is_err() {
+            if settings.continue_on_error {
+                eprintln!("{:?}", err);
+            } else {
+                bail!(err.err().unwrap());
             }
         }
-    };
+    }
     info!("{} validated", name.pretty());
     Ok(())
 }
 
-fn check_lookup(
+/// The number of padding rows prepended to the trace of the module of `expr`,
+/// in rows of the columns it depends on
+fn padding_rows(cs: &ConstraintSet, expr: &Node) -> isize {
+    expr.dependencies()
+        .iter()
+        .next()
+        .and_then(|h| {
+            let module = cs.columns.module_of(h);
+            cs.columns
+                .padding
+                .get(&module)
+                .map(|p| (p * cs.length_multiplier(h)) as isize)
+        })
+        .unwrap_or(0)
+}
+
+/// Evaluate the tuple formed by the `exps` of a lookup at row `i`, missing
+/// values defaulting to the padding value of their column
+fn lookup_row(cs: &ColumnSet, exps: &[Node], i: usize) -> Vec<Value> {
+    exps.iter()
+        .map(|e| {
+            e.eval(
+                i as isize,
+                |handle, j, wrap| {
+                    cs.get(handle, j, wrap.explicit())
+                        .or_else(|| cs.column(handle).unwrap().padding_value.clone())
+                },
+                &mut None,
+                &EvalSettings::default(),
+            )
+            .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Find the first row-tuple of the `children` that is not found among the
+/// row-tuples of the `parents`, and describe the failure
+fn lookup_mismatch(
     cs: &ConstraintSet,
     handle: &Handle,
     parents: &[Node],
     children: &[Node],
-) -> Result<()> {
-    // Compute the LC \sum_k (k+1) × x_k[i]
-    fn pseudo_rlc(exps: &[Node], i: usize, cs: &ColumnSet) -> Value {
-        let mut ax = Value::zero();
-
-        for (j, exp) in exps.iter().enumerate() {
-            let mut x = Value::from(j + 2);
-            let col_value = exp
-                .eval(
-                    i as isize,
-                    |handle, j, _| {
-                        cs.get(handle, j, false)
-                            .or_else(|| cs.column(handle).unwrap().padding_value.as_ref().cloned())
-                    },
-                    &mut None,
-                    &EvalSettings::default(),
-                )
-                .unwrap_or_default();
-
-            x.mul_assign(&col_value);
-            ax.add_assign(&x);
-        }
-        ax
-    }
-
+) -> Result<Option<(usize, String)>> {
     // Check that we have the same number of columns; should be guaranteed by the com
     if children.len() != parents.len() {
         bail!("parents and children are not of the same length")
@@ -384,7 +485,7 @@ fn check_lookup(
     match (children_empty, parent_empty) {
         (true, true) | (true, false) => {
             warn!("skipping empty lookup {}", handle.pretty());
-            return Ok(());
+            return Ok(None);
         }
         (false, true) => bail!(
             "parents ({}) are empty, but not children",
@@ -393,52 +494,285 @@ fn check_lookup(
         (false, false) => {}
     }
 
-    let parent_module = cs.module_of_exprs(parents).unwrap();
-    let parent_len = cs.iter_len(&parent_module);
-
-    let child_module = cs.module_of_exprs(children).unwrap();
-    let child_len = cs.iter_len(&child_module);
+    let parent_len = cs.iter_len_of_exprs(parents).unwrap();
+    let child_len = cs.iter_len_of_exprs(children).unwrap();
 
-    let parent_hashes: HashSet<_> = (0..parent_len)
-        .map(|i| pseudo_rlc(parents, i, &cs.columns))
+    let parent_rows: HashSet<_> = (0..parent_len)
+        .map(|i| lookup_row(&cs.columns, parents, i))
         .collect();
 
     for i in 0..child_len {
-        if !parent_hashes.contains(&pseudo_rlc(children, i, &cs.columns)) {
-            let pretty_expected_matches = parents
-                .iter()
-                .zip(children.iter().zip(children.iter().map(|e| {
-                    e.eval(
-                        i as isize,
-                        |handle, j, _| {
-                            cs.columns.get(handle, j, false).or_else(|| {
-                                cs.columns
-                                    .column(handle)
-                                    .unwrap()
-                                    .padding_value
-                                    .as_ref()
-                                    .cloned()
-                            })
-                        },
-                        &mut None,
-                        &EvalSettings::default(),
-                    )
-                    .unwrap_or_default()
-                })))
-                .map(|(parent, (child, value))| {
-                    format!(
-                        "{} - {}: {}",
-                        parent.pretty(),
-                        child.pretty(),
-                        value.pretty_with_base(Base::Hex)
-                    )
-                })
-                .join("\n");
-            bail!("mismatch line {}:\n{}", i, pretty_expected_matches);
+        let values = lookup_row(&cs.columns, children, i);
+        if !parent_rows.contains(&values) {
+            return Ok(Some((
+                i,
+                format!(
+                    "row {}: [{}] not found in [{}]:\n{}",
+                    i,
+                    children.iter().map(|c| c.pretty()).join(", "),
+                    parents.iter().map(|p| p.pretty()).join(", "),
+                    children
+                        .iter()
+                        .zip(values.iter())
+                        .map(|(child, value)| format!(
+                            "{}: {}",
+                            child.pretty(),
+                            value.pretty_with_base(Base::Hex)
+                        ))
+                        .join("\n")
+                ),
+            )));
         }
     }
 
-    Ok(())
+    Ok(None)
+}
+
+/// Ensure that every row-tuple of the `children` is found among the row-tuples
+/// of the `parents`, reporting the first one that is not
+fn check_lookup(
+    cs: &ConstraintSet,
+    handle: &Handle,
+    parents: &[Node],
+    children: &[Node],
+) -> Result<()> {
+    match lookup_mismatch(cs, handle, parents, children)? {
+        Some((_, mismatch)) => bail!(mismatch),
+        None => Ok(()),
+    }
+}
+
+/// Find the first `included` row that is not found in the `including` ones,
+/// or the first row of the table where the `multiplicity` column does not sum
+/// to the number of times it is looked up, and describe the failure
+fn lookup_multiplicity_mismatch(
+    cs: &ConstraintSet,
+    handle: &Handle,
+    including: &[Node],
+    included: &[Node],
+    multiplicity: &ColumnRef,
+) -> Result<Option<(usize, String)>> {
+    if let Some(mismatch) = lookup_mismatch(cs, handle, including, included)? {
+        return Ok(Some(mismatch));
+    }
+    let (Some(including_len), Some(included_len)) = (
+        cs.iter_len_of_exprs(including),
+        cs.iter_len_of_exprs(included),
+    ) else {
+        return Ok(None);
+    };
+
+    let row = |exps: &[Node], i: usize| lookup_row(&cs.columns, exps, i);
+
+    let mut expected = HashMap::<Vec<Value>, Value>::new();
+    for i in 0..included_len {
+        expected
+            .entry(row(included, i))
+            .or_insert_with(Value::zero)
+            .add_assign(&Value::one());
+    }
+    // the multiplicity of each distinct row, and the first row where it is found
+    let mut found = HashMap::<Vec<Value>, (usize, Value)>::new();
+    for i in 0..including_len {
+        found
+            .entry(row(including, i))
+            .or_insert_with(|| (i, Value::zero()))
+            .1
+            .add_assign(
+                &cs.columns
+                    .get(multiplicity, i as isize, false)
+                    .unwrap_or_default(),
+            );
+    }
+
+    for (values, (i, count)) in found.into_iter().sorted_by_key(|(_, (i, _))| *i) {
+        let wanted = expected.remove(&values).unwrap_or_else(Value::zero);
+        if count.to_bi() != wanted.to_bi() {
+            return Ok(Some((
+                i,
+                format!(
+                    "[{}] is looked up {} times, but its multiplicity is {}",
+                    values.iter().map(|v| v.pretty()).join(", "),
+                    wanted.pretty(),
+                    count.pretty()
+                ),
+            )));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Ensure that the `included` rows are all found in the `including` ones, and
+/// that, for each distinct row of the table, the `multiplicity` column sums to
+/// the number of times it is looked up
+fn check_lookup_multiplicity(
+    cs: &ConstraintSet,
+    handle: &Handle,
+    including: &[Node],
+    included: &[Node],
+    multiplicity: &ColumnRef,
+) -> Result<()> {
+    match lookup_multiplicity_mismatch(cs, handle, including, included, multiplicity)? {
+        Some((_, mismatch)) => bail!(mismatch),
+        None => Ok(()),
+    }
+}
+
+/// Find the first row where the `inverted` column is not the inverse of the
+/// `reference` expression -- i.e. where their product is not 1 for a non-zero
+/// reference, or `inverted` is not 0 for a zero one -- and describe the failure
+fn normalization_mismatch(
+    cs: &ConstraintSet,
+    reference: &Node,
+    inverted: &ColumnRef,
+) -> Option<(usize, String)> {
+    for i in 0..cs.columns.len(inverted).unwrap_or_default() {
+        let (Some(x), Some(inv)) = (
+            reference.eval(
+                i as isize,
+                |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
+                &mut None,
+                &Default::default(),
+            ),
+            cs.columns.get(inverted, i as isize, false),
+        ) else {
+            continue;
+        };
+        let mut product = x.clone();
+        product.mul_assign(&inv);
+        let valid = if x.is_zero() {
+            inv.is_zero()
+        } else {
+            product.is_one()
+        };
+        if !valid {
+            return Some((
+                i,
+                format!(
+                    "row {}: {} = {} is not the inverse of {} = {}",
+                    i,
+                    cs.handle(inverted).pretty(),
+                    inv.pretty().red().bold(),
+                    reference.to_string().white().bold(),
+                    x.pretty()
+                ),
+            ));
+        }
+    }
+    None
+}
+
+/// Find the first row where the `to` columns fail to be a reordering of the
+/// `from` columns sorted according to the signs of the matching sorting
+/// computation, and describe the failure
+fn permutation_mismatch(
+    cs: &ConstraintSet,
+    from: &[ColumnRef],
+    to: &[ColumnRef],
+) -> Option<(usize, String)> {
+    let from_len = cs.columns.len(&from[0]).unwrap_or_default();
+    let to_len = cs.columns.len(&to[0]).unwrap_or_default();
+    if from_len == 0 && to_len == 0 {
+        return None;
+    }
+    if from_len != to_len {
+        return Some((
+            from_len.min(to_len),
+            format!(
+                "source columns are {} long, but permuted columns are {} long",
+                from_len, to_len
+            ),
+        ));
+    }
+
+    let row = |cols: &[ColumnRef], i: usize| -> Vec<Value> {
+        cols.iter()
+            .map(|c| cs.columns.get(c, i as isize, false).unwrap_or_default())
+            .collect()
+    };
+    let pretty_row = |cols: &[ColumnRef], values: &[Value]| -> String {
+        cols.iter()
+            .zip(values.iter())
+            .map(|(c, v)| format!("{} = {}", cs.handle(c).pretty(), v.pretty()))
+            .join(", ")
+    };
+    let signs = match cs.computations.computation_for(&to[0]) {
+        Some(Computation::Sorted { signs, .. }) => signs.as_slice(),
+        _ => &[],
+    };
+    // Sort as the sorting computation does, then along all the columns to
+    // compare both sides as multisets
+    let sorted = |cols: &[ColumnRef]| {
+        let mut rows = (0..from_len).map(|i| row(cols, i)).collect::<Vec<_>>();
+        rows.sort_by(|a, b| {
+            for (k, (x, y)) in a.iter().zip(b.iter()).enumerate() {
+                match x.cmp(y) {
+                    Ordering::Equal => continue,
+                    o => {
+                        return if signs.get(k).cloned().unwrap_or(true) {
+                            o
+                        } else {
+                            o.reverse()
+                        }
+                    }
+                }
+            }
+            Ordering::Equal
+        });
+        rows
+    };
+
+    // Multiset equality
+    for (i, (expected, found)) in sorted(from).iter().zip(sorted(to).iter()).enumerate() {
+        if expected != found {
+            return Some((
+                i,
+                format!(
+                    "sorted row {}: source gives {}, but permuted columns give {}",
+                    i,
+                    pretty_row(from, expected),
+                    pretty_row(to, found)
+                ),
+            ));
+        }
+    }
+
+    // Sortedness, only on the columns having a sign
+    let sorted = &to[..signs.len()];
+    for i in 1..to_len {
+        let (previous, current) = (row(sorted, i - 1), row(sorted, i));
+        for (sign, (x, y)) in signs.iter().zip(previous.iter().zip(current.iter())) {
+            match x.cmp(y) {
+                Ordering::Equal => continue,
+                o => {
+                    if (o == Ordering::Greater) == *sign {
+                        return Some((
+                            i,
+                            format!(
+                                "row {}: {} is not sorted after {}",
+                                i,
+                                pretty_row(sorted, &current),
+                                pretty_row(sorted, &previous)
+                            ),
+                        ));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Ensure that the `to` columns are a reordering of the `from` columns, sorted
+/// according to the signs of the matching sorting computation
+fn check_permutation(cs: &ConstraintSet, from: &[ColumnRef], to: &[ColumnRef]) -> Result<()> {
+    match permutation_mismatch(cs, from, to) {
+        Some((_, mismatch)) => bail!(mismatch),
+        None => Ok(()),
+    }
 }
 
 pub fn check(
@@ -464,123 +798,155 @@ pub fn check(
 
     let failed = todo
         .par_iter()
-        .filter_map(|c| {
-            match c {
-                Constraint::Vanishes {
-                    handle: name,
-                    domain,
-                    expr,
-                } => {
-                    if matches!(expr.e(), Expression::Void) {
-                        return None;
-                    }
+        .filter_map(|c| match c {
+            Constraint::Vanishes {
+                handle: name,
+                domain,
+                expr,
+            } => {
+                if matches!(expr.e(), Expression::Void) {
+                    return None;
+                }
 
-                    match expr.as_ref().e() {
-                        Expression::List(es) => {
-                            for e in es {
-                                if let Err(err) = check_constraint(cs, e, domain, name, settings) {
-                                    match err.downcast_ref::<CheckingError>() {
-                                        Some(err) => match err {
-                                            CheckingError::NoColumnsFound(_) => {
-                                                warn!("{}", err);
-                                                break;
-                                            }
-                                            CheckingError::FailingConstraint(handle, trace) => {
-                                                if settings.report {
-                                                    println!(
-                                                        "{} failed:\n{}\n",
-                                                        handle.to_string().red().bold(),
-                                                        trace
-                                                    );
-                                                }
-                                                return Some(name.to_owned());
-                                            }
-                                            CheckingError::MismatchingLengths(err) => {
-                                                error!("{err}");
-                                                return Some(name.to_owned());
-                                            }
-                                        },
-                                        None => {
+                match expr.as_ref().e() {
+                    Expression::List(es) => {
+                        for e in es {
+                            if let Err(err) = check_constraint(cs, e, domain, name, settings) {
+                                match err.downcast_ref::<CheckingError>() {
+                                    Some(err) => match err {
+                                        CheckingError::NoColumnsFound(_) => {
                                             warn!("{}", err);
                                             break;
                                         }
+                                        CheckingError::FailingConstraint(handle, trace) => {
+                                            if settings.report {
+                                                println!(
+                                                    "{} failed:\n{}\n",
+                                                    handle.to_string().red().bold(),
+                                                    trace
+                                                );
+                                            }
+                                            return Some(name.to_owned());
+                                        }
+                                        CheckingError::MismatchingLengths(err) => {
+                                            error!("{err}");
+                                            return Some(name.to_owned());
+                                        }
+                                    },
+                                    None => {
+                                        warn!("{}", err);
+                                        break;
                                     }
                                 }
                             }
-                            None
                         }
-                        _ => {
-                            if let Err(err) = check_constraint(cs, expr, domain, name, settings) {
-                                match err.downcast_ref::<CheckingError>() {
-                                    Some(CheckingError::NoColumnsFound(_)) => {
-                                        warn!("{}", err);
-                                        None
-                                    }
-                                    Some(CheckingError::FailingConstraint(handle, trace)) => {
-                                        if settings.report {
-                                            println!(
-                                                "{} failed:\n{}\n",
-                                                handle.to_string().red().bold(),
-                                                trace
-                                            );
-                                        }
-                                        Some(name.to_owned())
-                                    }
-                                    Some(CheckingError::MismatchingLengths(err)) => {
-                                        error!("{err}");
-                                        return Some(name.to_owned());
-                                    }
-                                    None => {
-                                        warn!("{}", err);
-                                        None
+                        None
+                    }
+                    _ => {
+                        if let Err(err) = check_constraint(cs, expr, domain, name, settings) {
+                            match err.downcast_ref::<CheckingError>() {
+                                Some(CheckingError::NoColumnsFound(_)) => {
+                                    warn!("{}", err);
+                                    None
+                                }
+                                Some(CheckingError::FailingConstraint(handle, trace)) => {
+                                    if settings.report {
+                                        println!(
+                                            "{} failed:\n{}\n",
+                                            handle.to_string().red().bold(),
+                                            trace
+                                        );
                                     }
+                                    Some(name.to_owned())
+                                }
+                                Some(CheckingError::MismatchingLengths(err)) => {
+                                    error!("{err}");
+                                    return Some(name.to_owned());
+                                }
+                                None => {
+                                    warn!("{}", err);
+                                    None
                                 }
-                            } else {
-                                None
                             }
+                        } else {
+                            None
                         }
                     }
                 }
-                Constraint::Lookup {
-                    handle,
-                    including,
-                    included,
-                } => {
-                    if let Err(trace) = check_lookup(cs, handle, including, included) {
-                        if settings.report {
-                            println!("{} failed:\n{:?}\n", handle, trace);
-                        }
-                        Some(handle.to_owned())
-                    } else {
-                        None
+            }
+            Constraint::Lookup {
+                handle,
+                including,
+                included,
+            } => {
+                if let Err(trace) = check_lookup(cs, handle, including, included) {
+                    if settings.report {
+                        println!("{} failed:\n{:?}\n", handle, trace);
                     }
+                    Some(handle.to_owned())
+                } else {
+                    None
                 }
-                Constraint::Permutation {
-                    handle: _name,
-                    from: _from,
-                    to: _to,
-                    ..
-                } => {
-                    // warn!("Permutation validation not yet implemented");
+            }
+            Constraint::LookupMultiplicity {
+                handle,
+                including,
+                included,
+                multiplicity,
+            } => {
+                if let Err(trace) =
+                    check_lookup_multiplicity(cs, handle, including, included, multiplicity)
+                {
+                    if settings.report {
+                        println!("{} failed:\n{:?}\n", handle, trace);
+                    }
+                    Some(handle.to_owned())
+                } else {
                     None
                 }
-                Constraint::InRange { handle, exp, max } => {
-                    if let Err(trace) = check_inrange(exp, &cs, max) {
-                        if settings.report {
-                            println!("{} failed:\n{:?}\n", handle, trace);
-                        }
-                        Some(handle.to_owned())
-                    } else {
-                        None
+            }
+            Constraint::Permutation { handle, from, to } => {
+                if let Err(trace) = check_permutation(cs, from, to) {
+                    if settings.report {
+                        println!("{} failed:\n{:?}\n", handle, trace);
                     }
+                    Some(handle.to_owned())
+                } else {
+                    None
                 }
-                Constraint::Normalization { .. } => {
-                    // We trust ourselves
+            }
+            Constraint::InRange { handle, exp, max } => {
+                if let Err(trace) = check_inrange(exp, &cs, max) {
+                    if settings.report {
+                        println!("{} failed:\n{:?}\n", handle, trace);
+                    }
+                    Some(handle.to_owned())
+                } else {
+                    None
+                }
+            }
+            Constraint::Normalization {
+                handle,
+                reference,
+                inverted,
+            } => {
+                if let Some((_, trace)) = normalization_mismatch(cs, reference, inverted) {
+                    if settings.report {
+                        println!("{} failed:\n{:?}\n", handle, trace);
+                    }
+                    Some(handle.to_owned())
+                } else {
                     None
                 }
             }
         })
         .collect::<HashSet<_>>();
+    if let Some(k) = settings.sample {
+        warn!(
+            "vanishing constraints have only been checked on every {}th row; these results are partial",
+            k
+        );
+    }
     if failed.is_empty() {
         info!("Validation successful");
         Ok(())
@@ -596,6 +962,726 @@ pub fn check(
     }
 }
 
+/// Whether `expr` is vacuously satisfied at row `i`, i.e. whether one of its
+/// guards is not triggered, so that the guarded body is never evaluated
+fn is_vacuous_at(cs: &ConstraintSet, expr: &Node, i: isize, wrap: bool) -> bool {
+    match expr.e() {
+        Expression::Funcall {
+            func: func @ (Intrinsic::IfZero | Intrinsic::IfNotZero),
+            args,
+        } => {
+            let guard = args[0].eval(
+                i,
+                |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
+                &mut None,
+                &EvalSettings::new().wrap(wrap),
+            );
+            match guard {
+                Some(guard) => {
+                    if guard.is_zero() == (*func == Intrinsic::IfZero) {
+                        is_vacuous_at(cs, &args[1], i, wrap)
+                    } else {
+                        args.get(2)
+                            .map(|e| is_vacuous_at(cs, e, i, wrap))
+                            .unwrap_or(true)
+                    }
+                }
+                None => true,
+            }
+        }
+        Expression::List(es) => es.iter().all(|e| is_vacuous_at(cs, e, i, wrap)),
+        _ => false,
+    }
+}
+
+#[derive(Serialize)]
+pub struct ConstraintCoverage {
+    pub name: String,
+    /// the rows where the body of the constraint is evaluated
+    pub active: usize,
+    /// the rows where the constraint is trivially satisfied by its guards
+    pub vacuous: usize,
+}
+
+/// How many rows of a trace actually exercise each vanishing constraint
+#[derive(Serialize)]
+pub struct CoverageReport {
+    pub constraints: Vec<ConstraintCoverage>,
+}
+impl std::fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .constraints
+            .iter()
+            .map(|c| c.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("constraint".len());
+        write!(
+            f,
+            "{:<width$} {:>10} {:>10}",
+            "constraint",
+            "active",
+            "vacuous",
+            width = width
+        )?;
+        for c in self.constraints.iter() {
+            let line = format!(
+                "{:<width$} {:>10} {:>10}",
+                c.name,
+                c.active,
+                c.vacuous,
+                width = width
+            );
+            if c.active == 0 {
+                write!(f, "\n{}", line.yellow())?;
+            } else {
+                write!(f, "\n{}", line)?;
+            }
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+/// Count, for every vanishing constraint, the rows of the trace where it is
+/// actively checked, and the ones where it is vacuously satisfied
+pub fn coverage(
+    cs: &ConstraintSet,
+    only: &Option<Vec<String>>,
+    skip: &[String],
+) -> Result<CoverageReport> {
+    let mut constraints = cs
+        .constraints
+        .par_iter()
+        .filter(|c| only.as_ref().map(|o| o.contains(&c.name())).unwrap_or(true))
+        .filter(|c| !skip.contains(&c.name()))
+        .filter_map(|c| match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } if !matches!(expr.e(), Expression::Void) => Some((handle, domain, expr)),
+            _ => None,
+        })
+        .map(|(handle, domain, expr)| {
+            let l = cs.dependencies_len(expr, true)?.map(|l| l as isize);
+            let (rows, wrap): (Vec<isize>, _) = match domain {
+                Some(is) => (
+                    is.iter()
+                        .filter(|i| l.map(|l| *i < l && *i >= -l).unwrap_or(true))
+                        .collect(),
+                    true,
+                ),
+                None => ((0..l.unwrap_or(1)).collect(), false),
+            };
+            let vacuous = rows
+                .iter()
+                .filter(|i| is_vacuous_at(cs, expr, **i, wrap))
+                .count();
+            Ok(ConstraintCoverage {
+                name: handle.to_string(),
+                active: rows.len() - vacuous,
+                vacuous,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    constraints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(CoverageReport { constraints })
+}
+
+#[derive(Serialize)]
+pub struct RangeMargin {
+    pub name: String,
+    /// the smallest value taken by the range-checked expression
+    pub min: String,
+    /// the largest value taken by the range-checked expression
+    pub max: String,
+    /// the declared exclusive upper bound of the range
+    pub bound: String,
+    /// how much the largest value could grow before breaking the range
+    pub margin: String,
+}
+
+/// How close the values of the range-checked expressions come to their bounds
+#[derive(Serialize)]
+pub struct MarginReport {
+    pub constraints: Vec<RangeMargin>,
+}
+impl std::fmt::Display for MarginReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .constraints
+            .iter()
+            .map(|c| c.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("constraint".len());
+        write!(
+            f,
+            "{:<width$} {:>20} {:>20} {:>20} {:>20}",
+            "constraint",
+            "min",
+            "max",
+            "bound",
+            "margin",
+            width = width
+        )?;
+        for c in self.constraints.iter() {
+            write!(
+                f,
+                "\n{:<width$} {:>20} {:>20} {:>20} {:>20}",
+                c.name,
+                c.min,
+                c.max,
+                c.bound,
+                c.margin,
+                width = width
+            )?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+/// Compute, for every range constraint, the extremal values taken in the
+/// trace by the range-checked expression, and their distance to the bound
+pub fn margins(
+    cs: &ConstraintSet,
+    only: &Option<Vec<String>>,
+    skip: &[String],
+) -> Result<MarginReport> {
+    let mut constraints = cs
+        .constraints
+        .par_iter()
+        .filter(|c| only.as_ref().map(|o| o.contains(&c.name())).unwrap_or(true))
+        .filter(|c| !skip.contains(&c.name()))
+        .filter_map(|c| match c {
+            Constraint::InRange { handle, exp, max } => Some((handle, exp, max)),
+            _ => None,
+        })
+        .map(|(handle, exp, max)| {
+            let l = cs.dependencies_len(exp, false)?.unwrap_or(0) as isize;
+            // the padding rows would only drag the minimum down to zero
+            let values = (padding_rows(cs, exp).min(l)..l)
+                .filter_map(|i| {
+                    exp.eval(
+                        i,
+                        |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
+                        &mut None,
+                        &Default::default(),
+                    )
+                })
+                .map(BigInt::from)
+                .collect::<Vec<_>>();
+            let bound = BigInt::from(max);
+            let (min, max) = match values.iter().minmax().into_option() {
+                Some((min, max)) => (min.to_string(), max.clone()),
+                None => ("-".to_string(), BigInt::zero()),
+            };
+            Ok(RangeMargin {
+                name: handle.to_string(),
+                min,
+                margin: (&bound - BigInt::one() - &max).to_string(),
+                max: max.to_string(),
+                bound: bound.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    constraints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(MarginReport { constraints })
+}
+
+#[derive(Serialize)]
+pub struct RowEvaluation {
+    pub name: String,
+    /// the value taken by each of the constraint expressions at this row, or
+    /// `None` if it reads outside of the trace
+    pub values: Vec<Option<String>>,
+    /// whether any of these values does not vanish
+    pub failing: bool,
+}
+
+/// The values taken by the vanishing constraints at a single row of the trace
+#[derive(Serialize)]
+pub struct RowReport {
+    pub row: isize,
+    pub constraints: Vec<RowEvaluation>,
+}
+impl std::fmt::Display for RowReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}", self.row)?;
+        for c in self.constraints.iter() {
+            let values = c
+                .values
+                .iter()
+                .map(|v| v.as_deref().unwrap_or("-"))
+                .join(", ");
+            if c.failing {
+                write!(f, "\n{}: {}", c.name.red().bold(), values.red().bold())?;
+            } else {
+                write!(f, "\n{}: {}", c.name, values)?;
+            }
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+/// Evaluate every vanishing constraint at the given row of the trace; the
+/// constraints whose domain does not include this row are left out
+pub fn evaluate_row(
+    cs: &ConstraintSet,
+    only: &Option<Vec<String>>,
+    skip: &[String],
+    row: isize,
+) -> RowReport {
+    let mut constraints = cs
+        .constraints
+        .iter()
+        .filter(|c| only.as_ref().map(|o| o.contains(&c.name())).unwrap_or(true))
+        .filter(|c| !skip.contains(&c.name()))
+        .filter_map(|c| match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => {
+                if domain.as_ref().map(|d| !d.contains(row)).unwrap_or(false) {
+                    return None;
+                }
+                let exprs = match expr.e() {
+                    Expression::List(es) => es.iter().collect::<Vec<_>>(),
+                    Expression::Void => return None,
+                    _ => vec![expr.as_ref()],
+                };
+                let values = exprs
+                    .into_iter()
+                    .map(|e| {
+                        e.eval(
+                            row,
+                            |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
+                            &mut None,
+                            &EvalSettings::new().wrap(domain.is_some()),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                Some(RowEvaluation {
+                    name: handle.to_string(),
+                    failing: values.iter().flatten().any(|v| !v.is_zero()),
+                    values: values.into_iter().map(|v| v.map(|v| v.pretty())).collect(),
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    constraints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    RowReport { row, constraints }
+}
+
+/// How many characters of a failing expression are kept in a violation
+const SNIPPET_LENGTH: usize = 120;
+
+/// A row of the trace where a constraint does not hold
+#[derive(Serialize, Deserialize)]
+pub struct ConstraintViolation {
+    pub name: String,
+    pub module: String,
+    pub row: isize,
+    /// the value taken by the failing expression, or `None` if it reads
+    /// outside of the trace or the constraint is not a vanishing one
+    pub value: Option<String>,
+    /// the beginning of the failing expression, or a description of the
+    /// failure for the constraints that are not vanishing ones
+    pub snippet: String,
+}
+
+/// All the violations of the constraints found in a trace
+#[derive(Serialize, Deserialize)]
+pub struct ViolationReport {
+    pub violations: Vec<ConstraintViolation>,
+}
+impl std::fmt::Display for ViolationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, v) in self.violations.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{} at row {}: {} = {}",
+                v.name.red().bold(),
+                v.row,
+                v.snippet,
+                v.value.as_deref().unwrap_or("-")
+            )?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+/// List the rows where the vanishing constraints fail, on the same rows as
+/// [`check`] would; only the first one of each constraint is reported, unless
+/// `continue_on_error` is set. The first mismatching row of each failing
+/// lookup, permutation, range and normalization constraint is reported as well.
+pub fn violations(
+    cs: &ConstraintSet,
+    only: &Option<Vec<String>>,
+    skip: &[String],
+    settings: DebugSettings,
+) -> Result<ViolationReport> {
+    let mut violations = cs
+        .constraints
+        .par_iter()
+        .filter(|c| only.as_ref().map(|o| o.contains(&c.name())).unwrap_or(true))
+        .filter(|c| !skip.contains(&c.name()))
+        .filter_map(|c| match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => Some((handle, domain, expr)),
+            _ => None,
+        })
+        .map(|(handle, domain, expr)| {
+            let exprs = match expr.e() {
+                Expression::List(es) => es.iter().collect::<Vec<_>>(),
+                Expression::Void => vec![],
+                _ => vec![expr.as_ref()],
+            };
+            let mut r = Vec::new();
+            for e in exprs {
+                let mut cache = Some(cached::SizedCache::with_size(200000));
+                let (rows, wrap) = checked_rows(cs, e, domain, handle, settings)?;
+                for i in rows {
+                    let value = e.eval(
+                        i,
+                        |handle, i, wrap| cs.columns.get_raw(handle, i, wrap),
+                        &mut cache,
+                        &EvalSettings::new().wrap(wrap),
+                    );
+                    let failing = match value.as_ref() {
+                        Some(x) => !x.is_zero(),
+                        None => wrap,
+                    };
+                    if failing {
+                        r.push(ConstraintViolation {
+                            name: handle.to_string(),
+                            module: handle.module.clone(),
+                            row: i,
+                            value: value.map(|x| x.pretty()),
+                            snippet: e
+                                .dbg()
+                                .cloned()
+                                .unwrap_or_else(|| e.to_string())
+                                .chars()
+                                .take(SNIPPET_LENGTH)
+                                .collect(),
+                        });
+                        if !settings.continue_on_error {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(r)
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    violations.extend(
+        cs.constraints
+            .iter()
+            .filter(|c| only.as_ref().map(|o| o.contains(&c.name())).unwrap_or(true))
+            .filter(|c| !skip.contains(&c.name()))
+            .map(|c| {
+                let mismatch = match c {
+                    Constraint::Vanishes { .. } => None,
+                    Constraint::Lookup {
+                        handle,
+                        including,
+                        included,
+                    } => lookup_mismatch(cs, handle, including, included)?,
+                    Constraint::LookupMultiplicity {
+                        handle,
+                        including,
+                        included,
+                        multiplicity,
+                    } => {
+                        lookup_multiplicity_mismatch(cs, handle, including, included, multiplicity)?
+                    }
+                    Constraint::Permutation { from, to, .. } => permutation_mismatch(cs, from, to),
+                    Constraint::InRange { exp, max, .. } => inrange_mismatch(exp, cs, max)?,
+                    Constraint::Normalization {
+                        reference,
+                        inverted,
+                        ..
+                    } => normalization_mismatch(cs, reference, inverted),
+                };
+                Ok(mismatch.map(|(row, mismatch)| ConstraintViolation {
+                    name: c.name(),
+                    module: c.handle().module.clone(),
+                    row: row as isize,
+                    value: None,
+                    snippet: mismatch,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten(),
+    );
+    violations.sort_by(|a, b| a.name.cmp(&b.name).then(a.row.cmp(&b.row)));
+
+    Ok(ViolationReport { violations })
+}
+
+/// Parse a value of a trace or of a computed witness, where they are written
+/// as `0x0` followed by their decimal representation, reduced in the field
+fn parse_expected(handle: &ColumnRef, x: &serde_json::Value) -> Result<BigInt> {
+    let s = match x {
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => s.strip_prefix("0x0").unwrap_or(s).to_owned(),
+        _ => bail!(
+            "expected numeric value for {}, found `{}`",
+            handle.pretty(),
+            x
+        ),
+    };
+    let x = s
+        .parse::<BigInt>()
+        .with_context(|| anyhow!("invalid value for {}: `{}`", handle.pretty(), s))?;
+    Ok(num_traits::Euclid::rem_euclid(
+        &x,
+        crate::constants::field_modulus(),
+    ))
+}
+
+/// Compare, as field elements, the values computed for `handle` past its first
+/// `skip` rows to the `expected` ones
+fn compare_column(
+    cs: &ConstraintSet,
+    handle: &ColumnRef,
+    expected: &[serde_json::Value],
+    skip: usize,
+) -> Result<ColumnDiff> {
+    let expected = expected
+        .iter()
+        .map(|x| parse_expected(handle, x))
+        .collect::<Result<Vec<_>>>()?;
+    let computed_rows = cs.columns.len(handle).unwrap_or(0).saturating_sub(skip);
+    let computed = |i: usize| {
+        cs.columns
+            .get(handle, (skip + i) as isize, false)
+            .map(|x| num_traits::Euclid::rem_euclid(&x.to_bi(), crate::constants::field_modulus()))
+    };
+
+    let mut mismatches = 0;
+    let mut first_divergence = None;
+    for i in 0..expected.len().max(computed_rows) {
+        let (golden, computed) = (
+            expected.get(i).cloned(),
+            if i < computed_rows { computed(i) } else { None },
+        );
+        if golden != computed {
+            mismatches += 1;
+            first_divergence.get_or_insert_with(|| Divergence {
+                row: i,
+                golden: golden.map(|x| x.to_string()),
+                computed: computed.map(|x| x.to_string()),
+            });
+        }
+    }
+    Ok(ColumnDiff {
+        name: cs.handle(handle).to_string(),
+        golden_rows: expected.len(),
+        computed_rows,
+        mismatches,
+        first_divergence,
+    })
+}
+
+/// Ensure that the columns listed in `expected`, a JSON object mapping modules
+/// to their columns values as in a trace file, have been computed to these
+/// exact values, reporting the first mismatching cell otherwise
+pub fn check_expected(cs: &ConstraintSet, expected: &serde_json::Value) -> Result<()> {
+    let modules = expected
+        .as_object()
+        .ok_or_else(|| anyhow!("expected values must be a JSON object of modules"))?;
+
+    let mut first_mismatch: Option<(usize, String)> = None;
+    for (module, columns) in modules.iter().sorted_by_key(|(m, _)| *m) {
+        let columns = columns
+            .as_object()
+            .ok_or_else(|| anyhow!("expected values of {} must be a JSON object", module))?;
+        for (name, values) in columns.iter().sorted_by_key(|(c, _)| *c) {
+            let handle: ColumnRef = Handle::new(module, name).into();
+            cs.columns
+                .column(&handle)
+                .with_context(|| anyhow!("unknown expected column {}", handle.pretty()))?;
+            if cs.columns.backing(&handle).is_none() {
+                bail!("{} has not been computed", handle.pretty());
+            }
+            let values = values
+                .as_array()
+                .ok_or_else(|| anyhow!("expected values of {} must be a list", handle.pretty()))?;
+
+            // the trace does not feature the padding rows
+            let length_multiplier = cs.columns.register_of(&handle).length_multiplier;
+            let padding = cs.columns.padding.get(module).cloned().unwrap_or(0) * length_multiplier;
+            let diff = compare_column(cs, &handle, values, padding)?;
+            if diff.computed_rows != diff.golden_rows {
+                bail!(
+                    "{} has {} computed values, but {} are expected",
+                    handle.pretty(),
+                    diff.computed_rows.to_string().red().bold(),
+                    diff.golden_rows.to_string().blue()
+                );
+            }
+
+            if let Some(d) = diff.first_divergence {
+                if first_mismatch
+                    .as_ref()
+                    .map(|(row, _)| d.row < *row)
+                    .unwrap_or(true)
+                {
+                    first_mismatch = Some((
+                        d.row,
+                        format!(
+                            "{} row {}: expected {}, computed {}",
+                            handle.pretty(),
+                            d.row,
+                            d.golden.unwrap_or_default().blue(),
+                            d.computed.unwrap_or_default().red().bold()
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some((_, mismatch)) = first_mismatch {
+        bail!(mismatch)
+    }
+    Ok(())
+}
+
+/// The first row where a computed column departs from its golden values; a
+/// missing value means that the row only exists on the other side
+#[derive(Serialize)]
+pub struct Divergence {
+    pub row: usize,
+    pub golden: Option<String>,
+    pub computed: Option<String>,
+}
+
+/// How the values of a computed column compare to the golden ones
+#[derive(Serialize)]
+pub struct ColumnDiff {
+    pub name: String,
+    pub golden_rows: usize,
+    pub computed_rows: usize,
+    /// how many rows differ, including the ones found on a single side
+    pub mismatches: usize,
+    pub first_divergence: Option<Divergence>,
+}
+
+/// The comparison of a computed witness with a golden one
+#[derive(Serialize)]
+pub struct WitnessDiff {
+    /// the columns found on both sides
+    pub columns: Vec<ColumnDiff>,
+    /// the computed columns absent from the golden witness
+    pub missing: Vec<String>,
+    /// the golden columns absent from the constraint system
+    pub unknown: Vec<String>,
+}
+impl WitnessDiff {
+    pub fn diverging(&self) -> impl Iterator<Item = &ColumnDiff> {
+        self.columns.iter().filter(|c| c.mismatches > 0)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.diverging().next().is_none() && self.missing.is_empty() && self.unknown.is_empty()
+    }
+}
+impl std::fmt::Display for WitnessDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.diverging() {
+            let d = c.first_divergence.as_ref().unwrap();
+            writeln!(
+                f,
+                "{}: first divergence at row {}, golden {}, computed {} ({} of {} rows differ)",
+                c.name.red().bold(),
+                d.row,
+                d.golden.as_deref().unwrap_or("-").blue(),
+                d.computed.as_deref().unwrap_or("-").red(),
+                c.mismatches,
+                c.golden_rows.max(c.computed_rows)
+            )?;
+        }
+        for name in self.missing.iter() {
+            writeln!(f, "{}: missing from the golden witness", name.red().bold())?;
+        }
+        for name in self.unknown.iter() {
+            writeln!(f, "{}: unknown column", name.red().bold())?;
+        }
+        write!(
+            f,
+            "{} columns compared, {} diverging, {} missing, {} unknown",
+            self.columns.len(),
+            self.diverging().count(),
+            self.missing.len(),
+            self.unknown.len()
+        )
+    }
+}
+
+/// Compare all the columns of `cs`, once computed, to the `golden` witness, as
+/// written by `compute`, reporting for each column its first divergence and
+/// how many of its rows differ
+pub fn diff_expected(cs: &ConstraintSet, golden: &serde_json::Value) -> Result<WitnessDiff> {
+    let golden = golden
+        .get("columns")
+        .and_then(|c| c.as_object())
+        .ok_or_else(|| anyhow!("golden witness must be a JSON object of columns"))?;
+    let mut golden_columns = golden.iter().collect::<HashMap<_, _>>();
+
+    let mut columns = Vec::new();
+    let mut missing = Vec::new();
+    for (r, name) in cs
+        .columns
+        .all()
+        .into_iter()
+        .map(|r| {
+            let name = cs.handle(&r).to_string();
+            (r, name)
+        })
+        .sorted_by(|a, b| a.1.cmp(&b.1))
+    {
+        let Some(values) = golden_columns.remove(&name) else {
+            missing.push(name);
+            continue;
+        };
+        let golden_values = values
+            .get("values")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow!("golden values of {} must be a list", name))?;
+        // the computed witness features the padding rows
+        columns.push(compare_column(cs, &r, golden_values, 0)?);
+    }
+
+    Ok(WitnessDiff {
+        columns,
+        missing,
+        unknown: golden_columns
+            .into_keys()
+            .map(|k| k.to_owned())
+            .sorted()
+            .collect(),
+    })
+}
+
 fn to_column_name(h: &Handle, max_perspective: usize) -> String {
     match &h.perspective {
         Some(p) => format!("{} {}", p, h.name),
@@ -1,5 +1,6 @@
+use crate::utils::Colorize;
 use crate::{
-    compiler::{ColumnRef, EvalSettings, Intrinsic, Kind, Magma, Node},
+    compiler::{ColumnRef, EvalSettings, Intrinsic, Kind, Magma, Node, Wrap},
     constants, errors,
     pretty::{opcodes, Base, Pretty},
     structs::Handle,
@@ -10,7 +11,6 @@ use ark_ff::{fields::Field, BigInteger, PrimeField};
 use itertools::Itertools;
 use num_bigint::{BigInt, BigUint, Sign};
 use num_traits::{Euclid, FromPrimitive, Num, One, ToPrimitive, Zero};
-use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
 use std::ops::{AddAssign, MulAssign, SubAssign};
 use std::{
@@ -57,7 +57,8 @@ fn get_limbs(x: &Fr) -> &[u64; 4] {
 
 impl From<FrDef> for Fr {
     fn from(def: FrDef) -> Fr {
-        Fr::from(ark_ff::BigInt(def.limbs))
+        // the limbs are serialized in Montgomery form, see `get_limbs`
+        Fr::new_unchecked(ark_ff::BigInt(def.limbs))
     }
 }
 
@@ -140,6 +141,23 @@ impl Value {
         }
     }
 
+    /// Raise `self` to the power `exp` by square-and-multiply, i.e. in
+    /// O(log exp) multiplications.
+    pub(crate) fn pow(&self, exp: &BigInt) -> Value {
+        let mut ax = Value::one().same_as(self);
+        let mut square = self.clone();
+        for k in 0..exp.bits() {
+            if exp.bit(k) {
+                ax.mul_assign(&square);
+            }
+            if k + 1 < exp.bits() {
+                let base = square.clone();
+                square.mul_assign(&base);
+            }
+        }
+        ax
+    }
+
     pub(crate) fn negate(&mut self) {
         let mut ax = Value::zero().same_as(self);
         ax.sub_assign(self);
@@ -323,6 +341,32 @@ impl Value {
         }
     }
 
+    /// Return the remainder of the euclidean division of `self` by `m`, as
+    /// integers
+    pub(crate) fn checked_rem(&self, m: &Value) -> Result<Value> {
+        let m = m.to_bi();
+        if m.is_zero() {
+            bail!(errors::RuntimeError::DivisionByZero(self.clone()))
+        }
+        Ok(Value::try_from(self.to_bi().rem_euclid(&m))?)
+    }
+
+    /// Return the quotient of the division of `self` by `d` as integers,
+    /// provided that it is exact
+    pub(crate) fn checked_div(&self, d: &Value) -> Result<Value> {
+        let (x, d) = (self.to_bi(), d.to_bi());
+        if d.is_zero() {
+            bail!(errors::RuntimeError::DivisionByZero(self.clone()))
+        }
+        if !(&x % &d).is_zero() {
+            bail!(errors::RuntimeError::InexactDivision(
+                self.clone(),
+                Value::big_int(d)
+            ))
+        }
+        Ok(Value::try_from(x / d)?)
+    }
+
     pub(crate) fn fr_zero() -> Value {
         Value::Native(Fr::zero())
     }
@@ -679,7 +723,7 @@ impl ValueBacking {
             .cloned(),
             ValueBacking::Expression { e, .. } => e.eval(
                 i,
-                |handle, j, _| {
+                |handle, j, wrap| {
                     cs.get(handle, j, wrap)
                         .or_else(|| cs.column(handle).unwrap().padding_value.as_ref().cloned())
                 },
@@ -707,7 +751,7 @@ impl ValueBacking {
             .cloned(),
             ValueBacking::Expression { e, .. } => e.eval(
                 i,
-                |handle, j, _| {
+                |handle, j, wrap| {
                     cs.get(handle, j, wrap)
                         .or_else(|| cs.column(handle).unwrap().padding_value.as_ref().cloned())
                 },
@@ -908,6 +952,9 @@ pub struct Column {
     pub intrinsic_size_factor: Option<usize>,
     pub base: Base,
     pub handle: Handle,
+    /// arbitrary labels attached to the column in its definition
+    #[serde(default)]
+    pub tags: Vec<String>,
     computed: bool,
 }
 #[buildstructor::buildstructor]
@@ -923,6 +970,7 @@ impl Column {
         intrinsic_size_factor: Option<usize>,
         base: Option<Base>,
         handle: Handle,
+        tags: Option<Vec<String>>,
     ) -> Self {
         Column {
             register,
@@ -935,6 +983,7 @@ impl Column {
             base: base.unwrap_or(Base::Dec),
             computed: false,
             handle,
+            tags: tags.unwrap_or_default(),
         }
     }
 }
@@ -950,6 +999,14 @@ pub struct ColumnSet {
     pub field_registers: Vec<FieldRegister>,
     pub registers: Vec<Register>,
     pub spilling: HashMap<String, isize>, // module -> (past-spilling, future-spilling)
+    /// the height statically declared for some modules, which their imported
+    /// columns must match
+    #[serde(default)]
+    pub heights: HashMap<String, usize>,
+    /// how many padding rows have been prepended to the imported trace of
+    /// every module
+    #[serde(default)]
+    pub padding: HashMap<String, usize>,
 }
 
 impl ColumnSet {
@@ -1294,12 +1351,32 @@ impl ColumnSet {
         &self.registers[reg]
     }
 
-    pub fn get(&self, h: &ColumnRef, i: isize, wrap: bool) -> Option<Value> {
-        self.register_of(h).get(i, wrap, self)
+    pub fn get(&self, h: &ColumnRef, i: isize, wrap: impl Into<Wrap>) -> Option<Value> {
+        match wrap.into() {
+            Wrap::Trace => self.register_of(h).get(self.rotate(h, i), false, self),
+            wrap => self.register_of(h).get(i, wrap == Wrap::Column, self),
+        }
     }
 
-    pub fn get_raw(&self, h: &ColumnRef, i: isize, wrap: bool) -> Option<Value> {
-        self.register_of(h).get_raw(i, wrap, self)
+    pub fn get_raw(&self, h: &ColumnRef, i: isize, wrap: impl Into<Wrap>) -> Option<Value> {
+        match wrap.into() {
+            Wrap::Trace => self.register_of(h).get_raw(self.rotate(h, i), false, self),
+            wrap => self.register_of(h).get_raw(i, wrap == Wrap::Column, self),
+        }
+    }
+
+    /// Bring `i` back within the rows of `h` imported from the trace, i.e.
+    /// skipping the padding rows prepended to them
+    fn rotate(&self, h: &ColumnRef, i: isize) -> isize {
+        let register = self.register_of(h);
+        let padding = (self.padding.get(&self.module_of(h)).cloned().unwrap_or(0)
+            * register.length_multiplier) as isize;
+        let len = register.len().unwrap_or(0) as isize;
+        if len <= padding || (padding..len).contains(&i) {
+            i
+        } else {
+            padding + (i - padding).rem_euclid(len - padding)
+        }
     }
 
     pub fn len(&self, h: &ColumnRef) -> Option<usize> {
@@ -1404,6 +1481,43 @@ impl std::fmt::Display for ExoOperation {
     }
 }
 
+/// The order in which the bytes of a value are spread over the columns of its
+/// decomposition
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Endianness {
+    /// the first column holds the least significant byte
+    #[default]
+    Little,
+    /// the first column holds the most significant byte
+    Big,
+}
+impl Endianness {
+    /// Decompose `x` into `n` bytes, in the order of their columns
+    pub fn decompose(&self, x: &Value, n: usize) -> Vec<Value> {
+        let mut bytes = x
+            .to_bytes()
+            .into_iter()
+            .rev()
+            .map(|b| Value::from(b as usize))
+            .chain(std::iter::repeat(Value::zero()))
+            .take(n)
+            .collect::<Vec<_>>();
+        if *self == Endianness::Big {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    /// The power of 256 weighting the `i`th of the `n` columns of a
+    /// decomposition
+    pub fn weight(&self, i: usize, n: usize) -> u32 {
+        match self {
+            Endianness::Little => i as u32,
+            Endianness::Big => (n - 1 - i) as u32,
+        }
+    }
+}
+
 // TODO: add a targets() function to automatize computation insertion
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Computation {
@@ -1424,10 +1538,20 @@ pub enum Computation {
         target: ColumnRef,
         froms: Vec<ColumnRef>,
     },
+    /// `target` is filled by taking, in turn, `strides[i]` consecutive rows
+    /// from every `froms[i]`
+    InterleavedStrided {
+        target: ColumnRef,
+        froms: Vec<ColumnRef>,
+        strides: Vec<usize>,
+    },
     Sorted {
         froms: Vec<ColumnRef>,
         tos: Vec<ColumnRef>,
         signs: Vec<bool>,
+        /// the byte order of the decomposition of the sorting delta
+        #[serde(default)]
+        endianness: Endianness,
     },
     CyclicFrom {
         target: ColumnRef,
@@ -1442,6 +1566,49 @@ pub enum Computation {
         signs: Vec<bool>,
         froms: Vec<ColumnRef>,
         sorted: Vec<ColumnRef>,
+        #[serde(default)]
+        endianness: Endianness,
+    },
+    /// `target` is `exp` saturated in [lo; hi]; `below` (resp. `above`) is
+    /// set to 1 wherever `exp` is lower than `lo` (resp. higher than `hi`)
+    Clamp {
+        target: ColumnRef,
+        below: ColumnRef,
+        above: ColumnRef,
+        exp: Node,
+        lo: Value,
+        hi: Value,
+    },
+    /// `target` is computed row by row from `exp`, which may refer to the
+    /// previous row of `target`; `init` stands for the row before the first one
+    Recurrence {
+        target: ColumnRef,
+        exp: Node,
+        init: Value,
+    },
+    /// `target` is computed from `exp` together with all the other fixpoint
+    /// computations it depends on, iterating until none of them change
+    Fixpoint {
+        target: ColumnRef,
+        exp: Node,
+    },
+    /// `target` is filled with the constant `values`, e.g. to materialize an
+    /// inline set used as a lookup table; it is alone in its own module
+    ConstantTable {
+        target: ColumnRef,
+        values: Vec<Value>,
+    },
+    /// `target` holds, for each row of `including`, the number of rows of
+    /// `included` matching it, as required by a logUp-style lookup
+    LookupMultiplicity {
+        target: ColumnRef,
+        including: Vec<Node>,
+        included: Vec<Node>,
+    },
+    /// `target` is 1 on the rows of its module coming from the trace, and 0
+    /// on its padding rows
+    ActiveRows {
+        target: ColumnRef,
     },
 }
 impl std::fmt::Display for Computation {
@@ -1458,6 +1625,22 @@ impl std::fmt::Display for Computation {
                     froms.iter().map(|c| c.pretty()).join(", ")
                 )
             }
+            Computation::InterleavedStrided {
+                target,
+                froms,
+                strides,
+            } => {
+                write!(
+                    f,
+                    "{} ⪡ {}",
+                    target.pretty(),
+                    froms
+                        .iter()
+                        .zip(strides.iter())
+                        .map(|(c, s)| format!("{}×{}", c.pretty(), s))
+                        .join(", ")
+                )
+            }
             Computation::ExoOperation {
                 op,
                 sources,
@@ -1468,7 +1651,9 @@ impl std::fmt::Display for Computation {
             Computation::ExoConstant { value, target } => {
                 write!(f, "{} := {}", target, value)
             }
-            Computation::Sorted { froms, tos, signs } => write!(
+            Computation::Sorted {
+                froms, tos, signs, ..
+            } => write!(
                 f,
                 "[{}] ⇳ [{}]",
                 tos.iter().map(|c| c.pretty()).join(" "),
@@ -1484,6 +1669,50 @@ impl std::fmt::Display for Computation {
                 froms.iter().map(|c| c.pretty()).join(", "),
                 target
             ),
+            Computation::Clamp {
+                target,
+                exp,
+                lo,
+                hi,
+                ..
+            } => write!(
+                f,
+                "{} = clamp({}, {}, {})",
+                target.pretty(),
+                exp.pretty(),
+                lo.pretty(),
+                hi.pretty()
+            ),
+            Computation::Recurrence { target, exp, init } => write!(
+                f,
+                "{} = {} from {}",
+                target.pretty(),
+                exp.pretty(),
+                init.pretty()
+            ),
+            Computation::Fixpoint { target, exp } => {
+                write!(f, "{} = fixpoint({})", target.pretty(), exp.pretty())
+            }
+            Computation::ConstantTable { target, values } => write!(
+                f,
+                "{} := {{{}}}",
+                target.pretty(),
+                values.iter().map(|v| v.pretty()).join(" ")
+            ),
+            Computation::LookupMultiplicity {
+                target,
+                including,
+                included,
+            } => write!(
+                f,
+                "{} = #[{}] in [{}]",
+                target.pretty(),
+                included.iter().map(|e| e.pretty()).join(" "),
+                including.iter().map(|e| e.pretty()).join(" ")
+            ),
+            Computation::ActiveRows { target } => {
+                write!(f, "{} = 1 outside of the padding", target.pretty())
+            }
             Computation::SortingConstraints { sorted, .. } => write!(
                 f,
                 "Sorting constraints for {}",
@@ -1497,6 +1726,7 @@ impl Computation {
         match self {
             Computation::Composite { target, .. }
             | Computation::Interleaved { target, .. }
+            | Computation::InterleavedStrided { target, .. }
             | Computation::ExoOperation { target, .. }
             | Computation::ExoConstant { target, .. } => target.to_string(),
             Computation::Sorted { tos, .. } => tos
@@ -1504,7 +1734,18 @@ impl Computation {
                 .map(|t| t.to_string())
                 .collect::<Vec<_>>()
                 .join(", "),
-            Computation::CyclicFrom { target, .. } => target.to_string(),
+            Computation::CyclicFrom { target, .. }
+            | Computation::Recurrence { target, .. }
+            | Computation::Fixpoint { target, .. }
+            | Computation::ConstantTable { target, .. }
+            | Computation::LookupMultiplicity { target, .. }
+            | Computation::ActiveRows { target } => target.to_string(),
+            Computation::Clamp {
+                target,
+                below,
+                above,
+                ..
+            } => format!("{}, {}, {}", target, below, above),
             Computation::SortingConstraints { ats: target, .. } => target
                 .iter()
                 .map(|t| t.to_string())
@@ -1514,6 +1755,29 @@ impl Computation {
     }
 
     pub fn is_interleaved(&self) -> bool {
-        matches!(self, Computation::Interleaved { .. })
+        matches!(
+            self,
+            Computation::Interleaved { .. } | Computation::InterleavedStrided { .. }
+        )
+    }
+
+    /// A short name for the kind of this computation
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Computation::Composite { .. } => "composite",
+            Computation::ExoOperation { .. } => "exo-operation",
+            Computation::ExoConstant { .. } => "exo-constant",
+            Computation::Interleaved { .. } => "interleaved",
+            Computation::InterleavedStrided { .. } => "strided interleaved",
+            Computation::Sorted { .. } => "sorted",
+            Computation::CyclicFrom { .. } => "cyclic",
+            Computation::SortingConstraints { .. } => "sorting-constraints",
+            Computation::Clamp { .. } => "clamp",
+            Computation::Recurrence { .. } => "recurrence",
+            Computation::Fixpoint { .. } => "fixpoint",
+            Computation::ConstantTable { .. } => "constant-table",
+            Computation::LookupMultiplicity { .. } => "lookup-multiplicity",
+            Computation::ActiveRows { .. } => "active-rows",
+        }
     }
 }
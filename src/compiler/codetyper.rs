@@ -1,5 +1,6 @@
+use crate::utils::Colorize;
 use itertools::Itertools;
-use owo_colors::{colored::Color, OwoColorize};
+use owo_colors::colored::Color;
 
 #[derive(Debug, Default)]
 struct Line {
@@ -19,6 +19,14 @@ pub enum Kind<T> {
     /// a composite column is similar to a phantom column, but the expression
     /// computing it is known at parsing time (i.e. defined in the Corset code)
     Expression(T),
+    /// a recurrent column is computed row by row from an expression that may
+    /// refer to its value on the previous row, which is `init` before the
+    /// first row
+    Recurrence { exp: T, init: T },
+    /// a fixpoint column is computed from an expression that may refer,
+    /// directly or not, to the column itself; its value is reached by
+    /// iterating the computation until it stabilizes
+    Fixpoint(T),
 }
 impl<T> Kind<T> {
     pub fn to_nil(&self) -> Kind<()> {
@@ -26,6 +34,8 @@ impl<T> Kind<T> {
             Kind::Commitment => Kind::Commitment,
             Kind::Computed => Kind::Computed,
             Kind::Expression(_) => Kind::Expression(()),
+            Kind::Recurrence { .. } => Kind::Recurrence { exp: (), init: () },
+            Kind::Fixpoint(_) => Kind::Fixpoint(()),
         }
     }
 }
@@ -35,6 +45,9 @@ pub enum Domain<T> {
     Range(T, T),
     SteppedRange(T, T, T),
     Set(Vec<T>),
+    /// The given number of final rows, whatever the length of the module
+    /// turns out to be once computed
+    Last(T),
 }
 impl<T> Domain<T> {
     pub fn iter_nodes(&self) -> Box<dyn Iterator<Item = &T> + '_> {
@@ -44,6 +57,7 @@ impl<T> Domain<T> {
                 Box::new(Box::new([start, step, stop].into_iter()))
             }
             Domain::Set(is) => Box::new(is.iter()),
+            Domain::Last(n) => Box::new([n].into_iter()),
         }
     }
 }
@@ -60,6 +74,13 @@ impl Domain<AstNode> {
             Domain::Set(is) => Ok(Domain::Set(
                 is.iter().map(reduce).collect::<Result<Vec<_>>>()?,
             )),
+            Domain::Last(n) => {
+                let n = reduce(n)?;
+                if n < 1 {
+                    bail!("expected a positive number of final rows, found {}", n)
+                }
+                Ok(Domain::Last(n))
+            }
         }
     }
 }
@@ -76,6 +97,7 @@ impl<T: Display> Display for Domain<T> {
                 }
                 write!(f, "}}")
             }
+            Domain::Last(n) => write!(f, "(last {})", n),
         }
     }
 }
@@ -88,6 +110,23 @@ impl Domain<isize> {
                 Box::new((*start..=*stop).step_by((*step).try_into().unwrap()))
             }
             Domain::Set(is) => Box::new(is.iter().cloned()),
+            // negative rows are counted from the end of the trace
+            Domain::Last(n) => Box::new(-*n..=-1),
+        }
+    }
+
+    /// Iterate over the values of the domain following its direction, i.e.
+    /// downwards for the ranges starting above their end
+    pub fn iter_directed(&self) -> Box<dyn Iterator<Item = isize> + '_> {
+        match self {
+            Domain::Range(start, stop) if start > stop => Box::new((*stop..=*start).rev()),
+            Domain::SteppedRange(start, step, stop) if start > stop => {
+                Box::new((*stop..=*start).rev().step_by(step.unsigned_abs()))
+            }
+            Domain::SteppedRange(start, step, stop) => {
+                Box::new((*start..=*stop).step_by(step.unsigned_abs()))
+            }
+            _ => self.iter(),
         }
     }
 
@@ -98,6 +137,7 @@ impl Domain<isize> {
                 x >= *start && x <= *stop && (x - *start) % *step == 0
             }
             Domain::Set(is) => is.contains(&x),
+            Domain::Last(n) => x >= -*n && x <= -1,
         }
     }
 
@@ -107,6 +147,7 @@ impl Domain<isize> {
                 (stop - start + 1).try_into().unwrap()
             }
             Domain::Set(is) => is.len(),
+            Domain::Last(n) => (*n).try_into().unwrap(),
         }
     }
 
@@ -114,6 +155,7 @@ impl Domain<isize> {
         match self {
             Domain::Range(start, stop) | Domain::SteppedRange(start, _, stop) => start >= stop,
             Domain::Set(x) => x.is_empty(),
+            Domain::Last(n) => *n < 1,
         }
     }
 }
@@ -123,21 +165,55 @@ impl Domain<isize> {
 pub enum Form {
     For,
     Let,
+    /// like `let`, but each binding is visible to the following ones
+    LetStar,
     Debug,
     Todo,
     Reduce,
+    Map,
 }
 
 /// A builtin is a regular applicable that acts on already reduced arguments
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Builtin {
     Len,
+    /// Selects an element of an array column
+    Nth,
     Shift,
+    /// Shifts an expression, wrapping around the bounds of its columns
+    RotShift,
     /// This represents normalisation in the presence of
     /// field agnosticity.  Perhaps it might be considered
     /// "vector normalisation"?
     NormFlat,
     If,
+    /// Saturates a bounded expression within constant bounds
+    Clamp,
+    /// Evaluates the polynomial a0 + a1·x + a2·x² + ... in Horner form
+    Horner,
+    /// Compresses several expressions into a single one by a random linear
+    /// combination c1 + r·c2 + r²·c3 + ... of a challenge r
+    Rlc,
+    /// Multiplies an expression by 2^N
+    ScaleUp,
+    /// Divides an expression by 2^N, iff it provably is a multiple of 2^N
+    ScaleDown,
+    /// Reduces an expression modulo another one
+    Mod,
+    /// Divides an expression by another one that is known to divide it
+    Div,
+    /// The exclusive-or of two binary expressions
+    Xor,
+    /// The disjunction of binary expressions
+    Or,
+    /// The smallest of several compile-time constants
+    Min,
+    /// The largest of several compile-time constants
+    Max,
+    /// Vanishes iff a column keeps its value on the next row
+    Stays,
+    /// Holds iff a column changes its value on the next row
+    Changes,
 }
 impl std::fmt::Display for Builtin {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -146,9 +222,24 @@ impl std::fmt::Display for Builtin {
             "{}",
             match self {
                 Builtin::Len => "len",
+                Builtin::Nth => "nth",
                 Builtin::Shift => "shift",
+                Builtin::RotShift => "rot-shift",
                 Builtin::NormFlat => "~>>",
                 Builtin::If => "if?",
+                Builtin::Clamp => "clamp",
+                Builtin::Horner => "horner",
+                Builtin::Rlc => "rlc",
+                Builtin::ScaleUp => "scale-up",
+                Builtin::ScaleDown => "scale-down",
+                Builtin::Mod => "mod",
+                Builtin::Div => "/",
+                Builtin::Xor => "xor",
+                Builtin::Or => "or",
+                Builtin::Min => "min",
+                Builtin::Max => "max",
+                Builtin::Stays => "stays",
+                Builtin::Changes => "changes",
             }
         )
     }
@@ -171,6 +262,10 @@ pub enum Intrinsic {
     Neg,
     Inv,
     Normalize,
+    /// The remainder of the euclidean division of two expressions
+    Mod,
+    /// The quotient of the exact division of two expressions
+    Div,
 
     Begin,
 
@@ -197,6 +292,10 @@ impl Intrinsic {
     pub fn typing(&self, argtype: &[Type]) -> Result<Type> {
         Ok(match self {
             Intrinsic::Inv => argtype[0],
+            // The remainder is smaller than the dividend
+            Intrinsic::Mod => argtype[0],
+            // The quotient is smaller than the dividend
+            Intrinsic::Div => argtype[0],
             Intrinsic::Normalize => argtype[0].with_raw_magma(RawMagma::Binary),
             Intrinsic::Add | Intrinsic::Sub | Intrinsic::Neg => {
                 // Boolean is a corner case, as it is not stable under these operations
@@ -210,7 +309,19 @@ impl Intrinsic {
             Intrinsic::Exp => argtype[0],
             Intrinsic::Mul => argtype.iter().max().cloned().unwrap_or(Type::INFIMUM),
             Intrinsic::IfZero | Intrinsic::IfNotZero => {
-                argtype[1].max(argtype.get(2).cloned().unwrap_or(Type::INFIMUM))
+                let t = match (argtype[1], argtype.get(2)) {
+                    (then_t, Some(else_t)) => then_t.max(*else_t),
+                    (then_t @ (Type::Void | Type::List(_) | Type::ArrayColumn(_)), None) => then_t,
+                    // An omitted else branch evaluates to 0, that fits in any
+                    // magma without imposing any conditioning
+                    (then_t, None) => then_t.maxed(&Type::Scalar(Magma::BINARY))?,
+                };
+                // The result varies along with the condition
+                if matches!(argtype[0], Type::Column(_)) && matches!(t, Type::Scalar(_)) {
+                    t.with_scale(argtype[0])
+                } else {
+                    t
+                }
             }
             Intrinsic::Begin => Type::List(max_type(argtype)?.m()),
         })
@@ -232,6 +343,8 @@ impl std::fmt::Display for Intrinsic {
                 Intrinsic::Neg => "-",
                 Intrinsic::Inv => "inv",
                 Intrinsic::Normalize => "~",
+                Intrinsic::Mod => "mod",
+                Intrinsic::Div => "/",
                 Intrinsic::Begin => "begin",
                 Intrinsic::IfZero => "if-zero",
                 Intrinsic::IfNotZero => "if-not-zero",
@@ -266,7 +379,7 @@ impl Arity {
         }
     }
 
-    fn validate(&self, l: usize) -> Result<()> {
+    pub(crate) fn validate(&self, l: usize) -> Result<()> {
         if match self {
             Arity::AtLeast(x) => l >= *x,
             Arity::Monadic => l == 1,
@@ -280,6 +393,24 @@ impl Arity {
         }
     }
 }
+/// A builtin provided by a user of the library rather than by Corset itself,
+/// to be registered with [`Scope::register_builtin`](super::tables::Scope::register_builtin)
+/// before compilation; it acts on already reduced arguments.
+pub trait CustomBuiltin: std::fmt::Debug + Send + Sync {
+    /// The name under which the builtin is called
+    fn name(&self) -> &str;
+
+    /// The arity of the builtin
+    fn arity(&self) -> Arity;
+
+    /// Returns the type of the result for the given argument types, or `Err`
+    /// if they are not accepted
+    fn typing(&self, args: &[Type]) -> Result<Type>;
+
+    /// Lower a call of the builtin into an expression
+    fn lower(&self, args: Vec<Node>) -> Result<Node>;
+}
+
 /// The `FuncVerifier` trait defines a function that can check that
 /// it is called with valid arguments
 pub trait FuncVerifier<T> {
@@ -305,9 +436,19 @@ impl FuncVerifier<Node> for Builtin {
     fn arity(&self) -> Arity {
         match self {
             Builtin::Len => Arity::Monadic,
-            Builtin::Shift => Arity::Dyadic,
+            Builtin::Nth => Arity::Dyadic,
+            Builtin::Shift | Builtin::RotShift => Arity::Dyadic,
             Builtin::NormFlat => Arity::Monadic,
             Builtin::If => Arity::Between(2, 3),
+            Builtin::Clamp => Arity::Exactly(3),
+            Builtin::Horner => Arity::AtLeast(2),
+            Builtin::Rlc => Arity::AtLeast(2),
+            Builtin::ScaleUp | Builtin::ScaleDown => Arity::Dyadic,
+            Builtin::Mod | Builtin::Div => Arity::Dyadic,
+            Builtin::Xor => Arity::Dyadic,
+            Builtin::Or => Arity::AtLeast(2),
+            Builtin::Min | Builtin::Max => Arity::AtLeast(2),
+            Builtin::Stays | Builtin::Changes => Arity::Monadic,
         }
     }
 
@@ -315,18 +456,50 @@ impl FuncVerifier<Node> for Builtin {
         let args_t = args.iter().map(|a| a.t()).collect::<Vec<_>>();
         let expected_t: &[&[Type]] = match self {
             Builtin::Len => &[&[Type::ArrayColumn(Magma::ANY)]],
-            Builtin::Shift => &[&[Type::Column(Magma::ANY)], &[Type::Scalar(Magma::ANY)]],
+            Builtin::Nth => &[
+                &[Type::ArrayColumn(Magma::ANY)],
+                &[Type::Scalar(Magma::ANY)],
+            ],
+            Builtin::Shift | Builtin::RotShift => {
+                &[&[Type::Column(Magma::ANY)], &[Type::Scalar(Magma::ANY)]]
+            }
             Builtin::NormFlat => &[&[Type::Column(Magma::ANY)]],
             Builtin::If => &[&[Type::Any(Magma::ANY)], &[Type::Any(Magma::ANY)]],
+            Builtin::Clamp => &[
+                &[Type::Any(Magma::ANY)],
+                &[Type::Scalar(Magma::ANY)],
+                &[Type::Scalar(Magma::ANY)],
+            ],
+            Builtin::Horner => &[&[Type::Any(Magma::ANY)]],
+            Builtin::Rlc => &[
+                &[Type::Scalar(Magma::ANY), Type::Column(Magma::ANY)],
+                &[Type::Any(Magma::ANY)],
+            ],
+            Builtin::ScaleUp | Builtin::ScaleDown => {
+                &[&[Type::Any(Magma::ANY)], &[Type::Scalar(Magma::ANY)]]
+            }
+            Builtin::Mod | Builtin::Div => &[&[Type::Scalar(Magma::ANY), Type::Column(Magma::ANY)]],
+            // Wider arguments are rejected rather than silently widening the
+            // result
+            Builtin::Xor | Builtin::Or => {
+                &[&[Type::Scalar(Magma::BINARY), Type::Column(Magma::BINARY)]]
+            }
+            Builtin::Min | Builtin::Max => &[&[Type::Scalar(Magma::ANY)]],
+            Builtin::Stays | Builtin::Changes => &[&[Type::Column(Magma::ANY)]],
         };
 
-        if super::compatible_with_repeating(expected_t, &args_t) {
+        // loobeans would be read the other way around by a disjunction or an
+        // exclusive disjunction
+        let loobean_args =
+            matches!(self, Builtin::Or | Builtin::Xor) && args_t.iter().any(|t| t.m().is_loobean());
+        if super::compatible_with_repeating(expected_t, &args_t) && !loobean_args {
             Ok(())
         } else {
             bail!(CompileError::TypeError(
                 self.to_string(),
                 expected_t,
-                args_t
+                args_t,
+                None
             ))
         }
     }
@@ -338,8 +511,8 @@ impl FuncVerifier<AstNode> for Form {
             Form::For => Arity::Exactly(3),
             Form::Debug => Arity::AtLeast(1),
             Form::Todo => Arity::AtLeast(0),
-            Form::Let => Arity::Dyadic,
-            Form::Reduce => Arity::Dyadic,
+            Form::Let | Form::LetStar => Arity::Dyadic,
+            Form::Reduce | Form::Map => Arity::Dyadic,
         }
     }
     fn validate_types(&self, args: &[AstNode]) -> Result<()> {
@@ -357,11 +530,18 @@ impl FuncVerifier<AstNode> for Form {
             }
             Form::Debug => Ok(()),
             Form::Todo => Ok(()),
-            Form::Let => {
+            Form::Let | Form::LetStar => {
                 if let Result::Ok(pairs) = args[0].as_list() {
                     for pair in pairs {
                         if let Result::Ok(pair) = pair.as_list() {
-                            if !(pair.len() == 2 && matches!(pair[0].class, Token::Symbol(_))) {
+                            // a binding is either a single symbol, or a list of
+                            // symbols destructuring a list value
+                            let bindable = pair.len() == 2
+                                && (matches!(pair[0].class, Token::Symbol(_))
+                                    || pair[0].as_list().is_ok_and(|names| {
+                                        names.iter().all(|n| matches!(n.class, Token::Symbol(_)))
+                                    }));
+                            if !bindable {
                                 bail!("LET expects a pair of bindings, found `{:?}`", pair)
                             }
                         } else {
@@ -373,9 +553,13 @@ impl FuncVerifier<AstNode> for Form {
                     bail!("LET expects a list of bindings, found `{:?}`", args[0])
                 }
             }
-            Form::Reduce => {
-                if args[0].as_symbol().is_err() {
-                    bail!("REDUCE expects a symbol, found `{:?}`", args[0])
+            Form::Reduce | Form::Map => {
+                if args[0].as_symbol().is_err() && args[0].as_lambda().is_err() {
+                    bail!(
+                        "{} expects a symbol or a lambda, found `{:?}`",
+                        format!("{:?}", self).to_uppercase(),
+                        args[0]
+                    )
                 }
                 Ok(())
             }
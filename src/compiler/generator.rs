@@ -1,12 +1,14 @@
+use crate::utils::Colorize;
 use anyhow::*;
+use ark_bls12_377::fr::Fr;
+use ark_ff::PrimeField;
 use cached::Cached;
 use itertools::Itertools;
 use log::*;
 use logging_timer::time;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, Sign};
 use num_traits::cast::ToPrimitive;
-use num_traits::{One, Zero};
-use owo_colors::OwoColorize;
+use num_traits::{Euclid, One, Zero};
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 
@@ -19,12 +21,12 @@ use std::sync::atomic::AtomicUsize;
 use super::node::ColumnRef;
 use super::parser::{Ast, AstNode, Token};
 use super::tables::{ComputationTable, Scope};
-use super::{common::*, CompileSettings, Conditioning, Expression, Magma, Node, Type};
+use super::{common::*, CompileSettings, Conditioning, Expression, Magma, Node, RawMagma, Type};
 use crate::column::{Column, ColumnSet, Computation, RegisterID, Value, ValueBacking};
 use crate::dag::ComputationDag;
 use crate::errors::{self, CompileError, RuntimeError};
 use crate::pretty::Pretty;
-use crate::structs::Handle;
+use crate::structs::{Handle, PERSPECTIVE_SEPARATOR};
 use crate::utils::hash_strings;
 
 static COUNTER: OnceLock<AtomicUsize> = OnceLock::new();
@@ -51,6 +53,17 @@ pub enum Constraint {
         including: Vec<Node>,
         included: Vec<Node>,
     },
+    /// A logUp-style lookup, where `multiplicity` holds, for each row of the
+    /// including columns, the number of times it is looked up by the included
+    /// ones. Only the multiplicity is materialized: the inverse and
+    /// running-sum columns of the logUp argument depend on the challenges of
+    /// the verifier, and are left to the proof system.
+    LookupMultiplicity {
+        handle: Handle,
+        including: Vec<Node>,
+        included: Vec<Node>,
+        multiplicity: ColumnRef,
+    },
     Permutation {
         handle: Handle,
         from: Vec<ColumnRef>,
@@ -69,16 +82,32 @@ pub enum Constraint {
     },
 }
 impl Constraint {
-    pub fn name(&self) -> String {
+    pub fn handle(&self) -> &Handle {
+        match self {
+            Constraint::Vanishes { handle, .. } => handle,
+            Constraint::Lookup { handle, .. } => handle,
+            Constraint::LookupMultiplicity { handle, .. } => handle,
+            Constraint::Permutation { handle, .. } => handle,
+            Constraint::InRange { handle, .. } => handle,
+            Constraint::Normalization { handle, .. } => handle,
+        }
+    }
+
+    pub fn handle_mut(&mut self) -> &mut Handle {
         match self {
-            Constraint::Vanishes { handle, .. } => handle.to_string(),
-            Constraint::Lookup { handle, .. } => handle.to_string(),
-            Constraint::Permutation { handle, .. } => handle.to_string(),
-            Constraint::InRange { handle, .. } => handle.to_string(),
-            Constraint::Normalization { handle, .. } => handle.to_string(),
+            Constraint::Vanishes { handle, .. } => handle,
+            Constraint::Lookup { handle, .. } => handle,
+            Constraint::LookupMultiplicity { handle, .. } => handle,
+            Constraint::Permutation { handle, .. } => handle,
+            Constraint::InRange { handle, .. } => handle,
+            Constraint::Normalization { handle, .. } => handle,
         }
     }
 
+    pub fn name(&self) -> String {
+        self.handle().to_string()
+    }
+
     pub fn add_id_to_handles(&mut self, set_id: &dyn Fn(&mut ColumnRef)) {
         match self {
             Constraint::Vanishes { expr, .. } => expr.add_id_to_handles(set_id),
@@ -90,6 +119,17 @@ impl Constraint {
                 .iter_mut()
                 .chain(ys.iter_mut())
                 .for_each(|e| e.add_id_to_handles(set_id)),
+            Constraint::LookupMultiplicity {
+                including: xs,
+                included: ys,
+                multiplicity,
+                ..
+            } => {
+                xs.iter_mut()
+                    .chain(ys.iter_mut())
+                    .for_each(|e| e.add_id_to_handles(set_id));
+                set_id(multiplicity);
+            }
             Constraint::Permutation {
                 from: hs1, to: hs2, ..
             } => hs1.iter_mut().chain(hs2.iter_mut()).for_each(set_id),
@@ -109,11 +149,57 @@ impl Constraint {
         match self {
             Constraint::Vanishes { expr, .. } => expr.size(),
             Constraint::Lookup { .. } => 1,
+            Constraint::LookupMultiplicity { .. } => 1,
             Constraint::Permutation { .. } => 1,
             Constraint::InRange { .. } => 1,
             Constraint::Normalization { .. } => 1,
         }
     }
+
+    pub(crate) fn degree(&self) -> usize {
+        match self {
+            Constraint::Vanishes { expr, .. } => expr.degree(),
+            Constraint::Lookup {
+                including,
+                included,
+                ..
+            }
+            | Constraint::LookupMultiplicity {
+                including,
+                included,
+                ..
+            } => including
+                .iter()
+                .chain(included.iter())
+                .map(Node::degree)
+                .max()
+                .unwrap_or(0),
+            Constraint::Permutation { .. } => 1,
+            Constraint::InRange { exp, .. } => exp.degree(),
+            Constraint::Normalization { reference, .. } => reference.degree() + 1,
+        }
+    }
+
+    /// Whether a column access in this constraint wraps around the rows of
+    /// its trace
+    pub(crate) fn wraps(&self) -> bool {
+        match self {
+            Constraint::Vanishes { expr, .. } => expr.wraps(),
+            Constraint::Lookup {
+                including,
+                included,
+                ..
+            }
+            | Constraint::LookupMultiplicity {
+                including,
+                included,
+                ..
+            } => including.iter().chain(included.iter()).any(Node::wraps),
+            Constraint::Permutation { .. } => false,
+            Constraint::InRange { exp, .. } => exp.wraps(),
+            Constraint::Normalization { reference, .. } => reference.wraps(),
+        }
+    }
 }
 
 /// Options used when evaluating an expression
@@ -137,6 +223,37 @@ impl EvalSettings {
     }
 }
 
+/// How a column access outside of the rows of its column is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    /// Negative indices go up in the padding
+    No,
+    /// Negative indices loop from the end of the column
+    Column,
+    /// Indices loop over the rows imported from the trace, skipping the
+    /// padding rows prepended to them
+    Trace,
+}
+impl Wrap {
+    /// Only keep the wrapping required by the expression itself, negative
+    /// indices otherwise going up in the padding
+    pub fn explicit(self) -> Self {
+        match self {
+            Wrap::Trace => Wrap::Trace,
+            _ => Wrap::No,
+        }
+    }
+}
+impl From<bool> for Wrap {
+    fn from(wrap: bool) -> Self {
+        if wrap {
+            Wrap::Column
+        } else {
+            Wrap::No
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub handle: Handle,
@@ -152,6 +269,9 @@ pub enum FunctionClass {
     Builtin(Builtin),
     /// A field element function
     Intrinsic(Intrinsic),
+    /// A builtin function registered by a user of the library
+    #[allow(dead_code)]
+    Custom(std::sync::Arc<dyn CustomBuiltin>),
     /// A name alias to any other function (including another alias)
     Alias(String),
 }
@@ -179,14 +299,50 @@ impl Defined {
         Ok(())
     }
 
+    /// Find the specialization matching the given argument types. Calls are
+    /// first resolved by arity, then by types; if several specializations
+    /// accept the arguments, the most specific one is selected, and the call
+    /// is rejected as ambiguous if there is none.
     pub(crate) fn get_specialization(&self, args_t: &[Type]) -> Result<&Specialization> {
-        for s in self.specializations.iter() {
-            if crate::compiler::compatible_with(&s.in_types, args_t) {
-                return Ok(s);
-            }
+        let same_arity = self
+            .specializations
+            .iter()
+            .filter(|s| s.in_types.len() == args_t.len())
+            .collect::<Vec<_>>();
+        if same_arity.is_empty() {
+            bail!(
+                "expected {} arguments, found {}",
+                self.specializations
+                    .iter()
+                    .map(|s| s.in_types.len())
+                    .sorted()
+                    .dedup()
+                    .join(" or ")
+                    .blue(),
+                args_t.len().to_string().red()
+            )
+        }
+
+        let candidates = same_arity
+            .iter()
+            .filter(|s| crate::compiler::compatible_with(&s.in_types, args_t))
+            .collect::<Vec<_>>();
+        if let Some(s) = candidates.iter().find(|s| {
+            candidates
+                .iter()
+                .all(|o| crate::compiler::compatible_with(&o.in_types, &s.in_types))
+        }) {
+            return Ok(s);
+        }
+        if candidates.len() > 1 {
+            bail!(
+                "ambiguous call, matching specializations:\n{}",
+                candidates.iter().join("\n")
+            )
         }
+
         let mut msg = "available specializations:".to_string();
-        for s in self.specializations.iter() {
+        for s in same_arity.iter() {
             let (expected_str, found_str) =
                 errors::compiler::type_comparison_message(&s.in_types, args_t);
             msg += &format!(
@@ -251,6 +407,7 @@ impl FuncVerifier<Node> for Intrinsic {
             Intrinsic::Neg => Arity::Monadic,
             Intrinsic::Inv => Arity::Monadic,
             Intrinsic::Normalize => Arity::Monadic,
+            Intrinsic::Mod | Intrinsic::Div => Arity::Dyadic,
             Intrinsic::Begin => Arity::AtLeast(0),
             Intrinsic::IfZero | Intrinsic::IfNotZero => Arity::Between(2, 3),
         }
@@ -265,6 +422,8 @@ impl FuncVerifier<Node> for Intrinsic {
             | Intrinsic::Mul
             | Intrinsic::Neg
             | Intrinsic::Normalize
+            | Intrinsic::Mod
+            | Intrinsic::Div
             | Intrinsic::VectorAdd
             | Intrinsic::VectorSub
             | Intrinsic::VectorMul => {
@@ -328,6 +487,9 @@ impl FuncVerifier<Node> for Intrinsic {
             Intrinsic::Exp => &[&[Type::Any(Magma::ANY)], &[Type::Scalar(Magma::ANY)]],
             Intrinsic::Neg => &[&[Type::Scalar(Magma::ANY), Type::Column(Magma::ANY)]],
             Intrinsic::Inv | Intrinsic::Normalize => &[&[Type::Any(Magma::ANY)]],
+            Intrinsic::Mod | Intrinsic::Div => {
+                &[&[Type::Scalar(Magma::ANY), Type::Column(Magma::ANY)]]
+            }
             Intrinsic::IfZero | Intrinsic::IfNotZero => &[
                 // condition type
                 &[Type::Any(Magma::ANY)],
@@ -337,25 +499,57 @@ impl FuncVerifier<Node> for Intrinsic {
             Intrinsic::Begin => &[&[Type::Any(Magma::ANY)]],
         };
 
-        if super::compatible_with_repeating(expected_t, &args_t) {
-            Ok(())
-        } else {
+        // The exponent drives a square-and-multiply at evaluation time, and
+        // must therefore be known and non-negative; this is checked before the
+        // types, so that e.g. a column exponent is reported as such
+        if matches!(self, Intrinsic::Exp)
+            && !args[1]
+                .pure_eval()
+                .map(|exp| exp.sign() != Sign::Minus)
+                .unwrap_or(false)
+        {
+            bail!(CompileError::InvalidExponent(args[1].to_string()))
+        }
+
+        if !super::compatible_with_repeating(expected_t, &args_t) {
             bail!(CompileError::TypeError(
                 self.to_string(),
                 expected_t,
-                args_t
+                args_t,
+                None
             ))
         }
+
+        Ok(())
     }
 }
 
+/// The shifts of a module responsible for its spilling
+#[derive(Debug)]
+pub struct SpillingExplanation {
+    /// the smallest backward shift, and where it is found
+    pub past: Option<(isize, String)>,
+    /// the largest forward shift, and where it is found
+    pub future: Option<(isize, String)>,
+    /// the resulting spilling
+    pub spilling: isize,
+}
+
 pub type PerspectiveTable = HashMap<String, HashMap<String, Node>>;
 
 pub const ADDER_MODULE: &str = "#adder";
 pub const MULER_MODULE: &str = "#muler";
 
+/// The version of the serialized form of a [`ConstraintSet`], to be bumped on
+/// every change that can not be read back from a previously compiled file
+pub const FORMAT_VERSION: u32 = 1;
+
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct ConstraintSet {
+    /// the version of the format this set has been serialized with; sets
+    /// serialized before it was introduced default to 0
+    #[serde(default)]
+    pub format_version: u32,
     pub columns: ColumnSet,
     pub constraints: Vec<Constraint>,
     pub constants: HashMap<Handle, BigInt>,
@@ -363,7 +557,178 @@ pub struct ConstraintSet {
     pub perspectives: PerspectiveTable,
     pub transformations: u32,
     pub auto_constraints: u32,
+    /// the columns assignment (CE) identifier declared in the source, if any
+    #[serde(default)]
+    pub columns_assignment: Option<String>,
+    /// aliases pointing, directly or not, to a constant, mapped to the
+    /// constant they stand for
+    #[serde(default)]
+    pub constant_aliases: HashMap<Handle, Handle>,
+    /// if set, replaces the default prefix of the names of the auxiliary
+    /// columns introduced by the expansions and the auto-constraints
+    #[serde(default)]
+    pub aux_prefix: Option<String>,
+    /// the warnings raised while compiling this set
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+/// Apply `f` to all the column references found in the constraints, the
+/// computations and the perspectives of a constraint set
+fn visit_refs_mut(
+    constraints: &mut [Constraint],
+    computations: &mut ComputationTable,
+    perspectives: &mut PerspectiveTable,
+    f: &dyn Fn(&mut ColumnRef),
+) {
+    constraints.iter_mut().for_each(|x| x.add_id_to_handles(&f));
+
+    computations.dependencies = computations
+        .dependencies
+        .iter()
+        .map(|(k, v)| {
+            let mut k = k.clone();
+            f(&mut k);
+            (k, *v)
+        })
+        .collect();
+
+    for c in computations.iter_mut() {
+        match c {
+            Computation::Composite { target, exp }
+            | Computation::Fixpoint { target, exp }
+            | Computation::Recurrence { target, exp, .. } => {
+                f(target);
+                exp.add_id_to_handles(&f);
+            }
+            Computation::Interleaved { target, froms }
+            | Computation::InterleavedStrided { target, froms, .. } => {
+                std::iter::once(target).chain(froms.iter_mut()).for_each(f)
+            }
+            Computation::Sorted { froms, tos, .. } => {
+                froms.iter_mut().chain(tos.iter_mut()).for_each(f)
+            }
+            Computation::CyclicFrom { target, froms, .. } => {
+                std::iter::once(target).chain(froms.iter_mut()).for_each(f)
+            }
+            Computation::Clamp {
+                target,
+                below,
+                above,
+                exp,
+                ..
+            } => {
+                [target, below, above].into_iter().for_each(f);
+                exp.add_id_to_handles(&f);
+            }
+            Computation::SortingConstraints {
+                ats,
+                eq,
+                delta,
+                delta_bytes,
+                froms,
+                sorted,
+                ..
+            } => ats
+                .iter_mut()
+                .chain([eq, delta])
+                .chain(delta_bytes.iter_mut())
+                .chain(froms.iter_mut())
+                .chain(sorted.iter_mut())
+                .for_each(f),
+            Computation::ExoOperation {
+                sources, target, ..
+            } => {
+                for source in sources.iter_mut() {
+                    source.add_id_to_handles(&f);
+                }
+                f(target);
+            }
+            Computation::ExoConstant { target, .. }
+            | Computation::ConstantTable { target, .. }
+            | Computation::ActiveRows { target } => {
+                f(target);
+            }
+            Computation::LookupMultiplicity {
+                target,
+                including,
+                included,
+            } => {
+                f(target);
+                including
+                    .iter_mut()
+                    .chain(included.iter_mut())
+                    .for_each(|e| e.add_id_to_handles(&f));
+            }
+        }
+    }
+
+    for p in perspectives.values_mut().flat_map(|k| k.values_mut()) {
+        p.add_id_to_handles(&f)
+    }
+}
+
+/// Narrow the types of `e` and its sub-expressions that are still `Any`,
+/// either in shape or in magma, `context` being the known magma of the
+/// arithmetic operation `e` is an operand of, if any -- e.g. the declared
+/// return type of the function it has been computed by. Return the
+/// sub-expressions whose magma could not be narrowed.
+fn narrow_any(e: &mut Node, context: Option<Magma>) -> Vec<Node> {
+    let mut ambiguous = Vec::new();
+    let t = e.t();
+    if let Expression::Funcall { func, args } = e.e_mut() {
+        // Operands of an arithmetic operation can not be larger than its
+        // result, whose magma may be known even if theirs is not; the magmas
+        // of the other operands say nothing about them.
+        let arithmetic = matches!(
+            func,
+            Intrinsic::Add
+                | Intrinsic::Sub
+                | Intrinsic::Mul
+                | Intrinsic::Neg
+                | Intrinsic::VectorAdd
+                | Intrinsic::VectorSub
+                | Intrinsic::VectorMul
+        );
+        let operands_context = if !arithmetic {
+            None
+        } else if t.rm() != RawMagma::Any {
+            Some(t.m())
+        } else {
+            context
+        };
+        for a in args.iter_mut() {
+            ambiguous.extend(narrow_any(a, operands_context));
+        }
+        if t.rm() == RawMagma::Any {
+            if let Result::Ok(narrowed) =
+                func.typing(&args.iter().map(|a| a.t()).collect::<Vec<_>>())
+            {
+                *e = e.clone().with_type(narrowed);
+            }
+        }
+    }
+
+    let mut t = e.t();
+    if let Type::Any(m) = t {
+        t = if e.dependencies().is_empty() {
+            Type::Scalar(m)
+        } else {
+            Type::Column(m)
+        };
+    }
+    if t.rm() == RawMagma::Any {
+        if let Some(m) = context {
+            t = t.with_magma(m.with_conditioning(t.c()));
+        } else if ambiguous.is_empty() {
+            ambiguous.push(e.clone());
+        }
+    }
+    if t != e.t() {
+        *e = e.clone().with_type(t);
+    }
+    ambiguous
 }
+
 impl ConstraintSet {
     pub fn new(
         columns: ColumnSet,
@@ -373,6 +738,7 @@ impl ConstraintSet {
         perspectives: PerspectiveTable,
     ) -> Result<Self> {
         let mut r = ConstraintSet {
+            format_version: FORMAT_VERSION,
             constraints,
             columns,
             constants,
@@ -380,15 +746,110 @@ impl ConstraintSet {
             perspectives,
             transformations: 0,
             auto_constraints: 0,
+            columns_assignment: None,
+            constant_aliases: Default::default(),
+            aux_prefix: None,
+            warnings: Vec::new(),
         };
         r.convert_refs_to_ids()?;
         r.allocate_registers();
         r.fill_perspectives()?;
         r.compute_spillings();
+        for (handle, e) in r.narrow_any_types() {
+            warn!(
+                "in {}, unable to narrow the type of {}",
+                handle.pretty(),
+                e.pretty()
+            );
+        }
         r.validate()?;
         Ok(r)
     }
 
+    /// The prefix of the auxiliary columns names, `default` unless a custom one
+    /// has been set
+    pub(crate) fn aux_prefix<'a>(&'a self, default: &'a str) -> &'a str {
+        self.aux_prefix.as_deref().unwrap_or(default)
+    }
+
+    /// Deserialize a compiled constraint set, either in JSON or in RON,
+    /// ensuring that its format is supported, and migrating it from a
+    /// previous format version if required.
+    pub fn load(contents: &str, ron: bool) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct VersionTag {
+            #[serde(default)]
+            format_version: u32,
+        }
+
+        let version = if ron {
+            ron::from_str::<VersionTag>(contents)?.format_version
+        } else {
+            serde_json::from_str::<VersionTag>(contents)?.format_version
+        };
+        if version > FORMAT_VERSION {
+            bail!(
+                "constraint set serialized with format version {}, but only versions up to {} are supported; it should be re-compiled",
+                version,
+                FORMAT_VERSION
+            )
+        }
+
+        let mut r: ConstraintSet = if ron {
+            ron::from_str(contents)?
+        } else {
+            serde_json::from_str(contents)?
+        };
+        r.migrate(version)?;
+        Ok(r)
+    }
+
+    /// Serialize the constraint set to `path` in the bincode binary format,
+    /// much faster to load back than JSON or RON
+    pub fn save_bin(&self, path: &str) -> Result<()> {
+        crate::utils::write_atomically(path, |out| {
+            bincode::serialize_into(out, self)
+                .with_context(|| anyhow!("while serializing to `{}`", path))
+        })
+    }
+
+    /// Deserialize a constraint set written by [`ConstraintSet::save_bin`].
+    /// The bincode format is not self-describing, so sets serialized with
+    /// another format version can not be migrated, and must be re-compiled.
+    pub fn load_bin(path: &str) -> Result<Self> {
+        let contents = std::fs::read(path).with_context(|| anyhow!("while reading `{}`", path))?;
+        // The version is the first field, and can be read on its own
+        let version: u32 = bincode::deserialize(&contents)?;
+        if version != FORMAT_VERSION {
+            bail!(
+                "binary constraint set serialized with format version {}, but only version {} is supported; it should be re-compiled",
+                version,
+                FORMAT_VERSION
+            )
+        }
+
+        let mut r: ConstraintSet = bincode::deserialize(&contents)?;
+        // Make sure that the column references point to the deserialized
+        // columns, and that the spillings match the deserialized constraints
+        r.convert_refs_to_ids()?;
+        r.compute_spillings();
+        Ok(r)
+    }
+
+    /// Bring a constraint set deserialized from the format version `from` up
+    /// to date with the current one.
+    fn migrate(&mut self, from: u32) -> Result<()> {
+        for version in from..FORMAT_VERSION {
+            match version {
+                // Version 1 only introduced the version tag
+                0 => {}
+                _ => unreachable!(),
+            }
+        }
+        self.format_version = FORMAT_VERSION;
+        Ok(())
+    }
+
     fn allocate_registers(&mut self) {
         #[derive(Default, Debug)]
         struct ColumnPool {
@@ -493,8 +954,14 @@ impl ConstraintSet {
             {
                 match c {
                     Computation::Interleaved { target, .. }
+                    | Computation::InterleavedStrided { target, .. }
                     | Computation::CyclicFrom { target, .. }
-                    | Computation::Composite { target, .. } => {
+                    | Computation::Composite { target, .. }
+                    | Computation::Recurrence { target, .. }
+                    | Computation::Fixpoint { target, .. }
+                    | Computation::ConstantTable { target, .. }
+                    | Computation::LookupMultiplicity { target, .. }
+                    | Computation::ActiveRows { target } => {
                         let col = self.columns.column(&target).unwrap();
                         let reg = self.columns.new_register(
                             col.handle.clone(),
@@ -519,6 +986,27 @@ impl ConstraintSet {
                             self.columns.assign_register(t, *reg).unwrap();
                         }
                     }
+                    Computation::Clamp {
+                        target,
+                        below,
+                        above,
+                        ..
+                    } => {
+                        // the flags do not depend on the result, and may thus
+                        // be reached in an earlier slice
+                        if self.columns.column(&target).unwrap().register.is_some() {
+                            continue;
+                        }
+                        for r in [&target, &below, &above] {
+                            let col = self.columns.column(r).unwrap();
+                            let reg = self.columns.new_register(
+                                col.handle.clone(),
+                                col.t,
+                                col.intrinsic_size_factor.unwrap_or(1),
+                            );
+                            self.columns.assign_register(r, reg).unwrap();
+                        }
+                    }
                     Computation::SortingConstraints {
                         ats,
                         eq,
@@ -606,6 +1094,7 @@ impl ConstraintSet {
                         mut froms,
                         mut tos,
                         mut signs,
+                        ..
                     } => {
                         if let Some(perspective) = froms
                             .iter()
@@ -702,6 +1191,21 @@ impl ConstraintSet {
                     self.columns.mark_used(&c).unwrap();
                 }
             }
+            Constraint::LookupMultiplicity {
+                including,
+                included,
+                multiplicity,
+                ..
+            } => {
+                for c in including
+                    .iter()
+                    .flat_map(Node::dependencies)
+                    .chain(included.iter().flat_map(Node::dependencies))
+                    .chain(std::iter::once(multiplicity.clone()))
+                {
+                    self.columns.mark_used(&c).unwrap();
+                }
+            }
             Constraint::Permutation { from, to, .. } => {
                 for c in from.iter().chain(to.iter()) {
                     self.columns.mark_used(c).unwrap();
@@ -791,63 +1295,171 @@ impl ConstraintSet {
     }
 
     pub fn convert_refs_to_ids(&mut self) -> Result<()> {
+        let columns = &self.columns;
         let convert_to_id = |h: &mut ColumnRef| {
-            let id = self.columns.id_of(h);
+            let id = columns.id_of(h);
             h.set_id(id);
         };
-        self.constraints
-            .iter_mut()
-            .for_each(|x| x.add_id_to_handles(&convert_to_id));
+        visit_refs_mut(
+            &mut self.constraints,
+            &mut self.computations,
+            &mut self.perspectives,
+            &convert_to_id,
+        );
+
+        Ok(())
+    }
 
-        self.computations.dependencies = self
-            .computations
-            .dependencies
+    /// Evaluate every vanishing constraint on the loaded trace, and list all
+    /// the rows of their domain where they do not vanish, as well as the first
+    /// mismatching row of the failing permutations
+    pub fn check(&self) -> Result<Vec<crate::check::ConstraintViolation>> {
+        crate::check::violations(
+            self,
+            &None,
+            &[],
+            crate::check::DebugSettings::new().continue_on_error(true),
+        )
+        .map(|report| report.violations)
+    }
+
+    /// Fail if any vanishing constraint has a multiplicative degree higher
+    /// than `max`, listing all the offending ones
+    pub fn check_max_degree(&self, max: usize) -> Result<()> {
+        let too_high = self
+            .constraints
             .iter()
-            .map(|(k, v)| {
-                let mut k = k.clone();
-                convert_to_id(&mut k);
-                (k, *v)
+            .filter_map(|c| match c {
+                Constraint::Vanishes { handle, expr, .. } if expr.degree() > max => {
+                    Some((handle, expr.degree()))
+                }
+                _ => None,
             })
-            .collect();
+            .sorted()
+            .collect::<Vec<_>>();
+        if !too_high.is_empty() {
+            bail!(
+                "{} constraints exceed the maximal degree of {}:\n{}",
+                too_high.len(),
+                max.to_string().bold(),
+                too_high
+                    .iter()
+                    .map(|(h, d)| format!("  {}: degree {}", h.pretty(), d.to_string().red()))
+                    .join("\n")
+            )
+        }
+        Ok(())
+    }
 
-        for c in self.computations.iter_mut() {
-            match c {
-                Computation::Composite { target, exp } => {
-                    convert_to_id(target);
-                    exp.add_id_to_handles(&convert_to_id);
-                }
-                Computation::Interleaved { target, froms } => std::iter::once(target)
-                    .chain(froms.iter_mut())
-                    .for_each(convert_to_id),
-                Computation::Sorted { froms, tos, .. } => froms
-                    .iter_mut()
-                    .chain(tos.iter_mut())
-                    .for_each(convert_to_id),
-                Computation::CyclicFrom { target, froms, .. } => std::iter::once(target)
-                    .chain(froms.iter_mut())
-                    .for_each(convert_to_id),
-                Computation::SortingConstraints { .. } => {
-                    // These computations are built with IDs from the very start
-                }
-                Computation::ExoOperation {
-                    sources, target, ..
-                } => {
-                    for source in sources.iter_mut() {
-                        source.add_id_to_handles(&convert_to_id);
-                    }
-                    convert_to_id(target);
-                }
-                Computation::ExoConstant { target, .. } => {
-                    convert_to_id(target);
-                }
+    /// Rename the module `old` to `new` in all the handles of the constraint
+    /// set, i.e. its columns, registers, constraints, computations and
+    /// constants, then refresh the column references accordingly.
+    pub fn rename_module(&mut self, old: &str, new: &str) -> Result<()> {
+        let modules = self.columns.modules();
+        if !modules.contains(old) && !self.constants.keys().any(|h| h.module == old) {
+            bail!("module {} not found", old.red().bold())
+        }
+        if modules.contains(new) || self.constants.keys().any(|h| h.module == new) {
+            bail!("module {} already exists", new.red().bold())
+        }
+
+        let rename = |h: &mut Handle| {
+            if h.module == old {
+                h.module = new.to_owned();
+            }
+        };
+        fn rename_key<T>(m: &mut HashMap<String, T>, old: &str, new: &str) {
+            if let Some(x) = m.remove(old) {
+                m.insert(new.to_owned(), x);
+            }
+        }
+
+        // Columns & registers
+        for c in self.columns._cols.iter_mut() {
+            rename(&mut c.handle);
+        }
+        self.columns.cols = std::mem::take(&mut self.columns.cols)
+            .into_iter()
+            .map(|(mut h, i)| {
+                rename(&mut h);
+                (h, i)
+            })
+            .collect();
+        for r in self.columns.registers.iter_mut() {
+            if let Some(h) = r.handle.as_mut() {
+                rename(h);
             }
         }
+        for r in self.columns.field_registers.iter_mut() {
+            rename(&mut r.handle);
+        }
+        rename_key(&mut self.columns.effective_len, old, new);
+        rename_key(&mut self.columns.min_len, old, new);
+        rename_key(&mut self.columns.spilling, old, new);
+        rename_key(&mut self.columns.heights, old, new);
+        rename_key(&mut self.columns.padding, old, new);
+        rename_key(&mut self.perspectives, old, new);
+
+        // Constants
+        self.constants = std::mem::take(&mut self.constants)
+            .into_iter()
+            .map(|(mut h, v)| {
+                rename(&mut h);
+                (h, v)
+            })
+            .collect();
+        self.constant_aliases = std::mem::take(&mut self.constant_aliases)
+            .into_iter()
+            .map(|(mut from, mut to)| {
+                rename(&mut from);
+                rename(&mut to);
+                (from, to)
+            })
+            .collect();
 
-        for p in self.perspectives.values_mut().flat_map(|k| k.values_mut()) {
-            p.add_id_to_handles(&convert_to_id)
+        // Constraints & computations
+        for c in self.constraints.iter_mut() {
+            rename(c.handle_mut());
         }
+        visit_refs_mut(
+            &mut self.constraints,
+            &mut self.computations,
+            &mut self.perspectives,
+            &|r: &mut ColumnRef| {
+                if let Some(h) = r.handle_mut() {
+                    rename(h)
+                }
+            },
+        );
 
-        Ok(())
+        self.convert_refs_to_ids()
+    }
+
+    /// Returns the columns carrying the tag `tag`
+    pub fn columns_with_tag(&self, tag: &str) -> Vec<Handle> {
+        self.columns
+            .iter_cols()
+            .filter(|c| c.tags.iter().any(|t| t == tag))
+            .map(|c| c.handle.clone())
+            .collect()
+    }
+
+    /// Returns the computed columns in the order in which they are filled when
+    /// expanding a trace; i.e. a topological sorting of their dependencies.
+    /// Columns that may be computed concurrently are sorted by name.
+    pub fn computation_order(&self) -> Vec<Handle> {
+        ComputationDag::from_computations(self.computations.iter())
+            .job_slices()
+            .into_iter()
+            .flat_map(|slice| {
+                slice
+                    .iter()
+                    .filter(|h| self.computations.computation_idx_for(h).is_some())
+                    .map(|h| self.handle(h).to_owned())
+                    .sorted_by_cached_key(|h| h.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
     pub fn effective_len_for(&self, m: &str) -> Option<isize> {
@@ -858,53 +1470,136 @@ impl ConstraintSet {
         *self.columns.effective_len.entry(m.to_string()).or_insert(x)
     }
 
+    /// Returns the spilling of the column `h`, in rows of this column; i.e.
+    /// the spilling of its module scaled by its length multiplier.
     pub fn spilling_for_column(&self, h: &ColumnRef) -> Option<isize> {
         let module = if h.is_handle() {
             &h.as_handle().module
         } else {
             &self.columns.column(h).ok()?.handle.module
         };
+        let length_multiplier = if self.columns.column(h).is_ok() {
+            self.length_multiplier(h)
+        } else {
+            // h may also be a register, e.g. when importing binary traces
+            self.columns
+                .register(h)
+                .map(|r| r.length_multiplier)
+                .unwrap_or(1)
+        };
         self.spilling_of(module)
+            .map(|s| s * length_multiplier as isize)
     }
 
-    pub(crate) fn compute_spilling(&mut self, m: &str) -> isize {
-        let spilling = self
-            .computations
+    /// Returns all the expressions of module `m` that contribute to its
+    /// spilling, along with the name of the item they stem from
+    fn spilling_sources<'a>(&'a self, m: &'a str) -> impl Iterator<Item = (String, &'a Node)> + 'a {
+        self.computations
             .iter()
-            .filter_map(|c| match c {
-                Computation::Composite { target, exp } => {
+            .filter_map(move |c| match c {
+                Computation::Composite { target, exp }
+                | Computation::Fixpoint { target, exp }
+                | Computation::Recurrence { target, exp, .. } => {
                     if target.as_handle().module == m {
-                        Some(exp.past_spill().abs().max(exp.future_spill().abs()))
+                        Some((format!("computation of {}", target.as_handle()), exp))
                     } else {
                         None
                     }
                 }
                 _ => None,
             })
-            .chain(self.constraints.iter().filter_map(|c| match c {
+            .chain(self.constraints.iter().filter_map(move |c| match c {
                 Constraint::Vanishes { handle, expr, .. } => {
                     if handle.module == m {
-                        Some(expr.past_spill().abs().max(expr.future_spill().abs()))
+                        Some((format!("constraint {}", handle), expr.as_ref()))
                     } else {
                         None
                     }
                 }
                 _ => None,
             }))
+    }
+
+    pub(crate) fn compute_spilling(&mut self, m: &str) -> isize {
+        let spilling = self
+            .spilling_sources(m)
+            .map(|(_, e)| e.past_spill().abs().max(e.future_spill().abs()))
             .max()
             .unwrap_or(0);
         self.columns.spilling.insert(m.to_owned(), spilling);
         spilling
     }
 
-    pub fn iter_len(&self, module: &str) -> usize {
-        self.effective_len_for(module)
-            // If the module is empty, use its spilling
-            .or_else(|| self.spilling_of(module))
-            .unwrap() as usize
+    /// Returns the value of the constant `name`, either qualified as
+    /// `module.name` or relative to the main module, following aliases.
+    pub fn constant(&self, name: &str) -> Option<&BigInt> {
+        let handle = match name.rsplit_once('.') {
+            Some((module, name)) => Handle::new(module, name),
+            None => Handle::new(super::MAIN_MODULE, name),
+        };
+        self.constants.get(&handle).or_else(|| {
+            self.constant_aliases
+                .get(&handle)
+                .and_then(|target| self.constants.get(target))
+        })
     }
 
-    pub fn spilling_of(&self, m: &str) -> Option<isize> {
+    /// Returns the value of the constant `name` as a field element, if its
+    /// magnitude is smaller than the field modulus.
+    pub fn constant_as_fr(&self, name: &str) -> Option<Fr> {
+        let x = self.constant(name)?;
+        if x.magnitude() >= crate::constants::field_modulus().magnitude() {
+            return None;
+        }
+        let (sign, bytes) = x.to_bytes_le();
+        let f = Fr::from_le_bytes_mod_order(&bytes);
+        Some(if sign == Sign::Minus { -f } else { f })
+    }
+
+    /// Split the vanishing constraints into boundary constraints, that only
+    /// apply on their explicit domain, and transition constraints, that apply
+    /// on every row.
+    pub fn partition_constraints(&self) -> (Vec<&Constraint>, Vec<&Constraint>) {
+        self.constraints
+            .iter()
+            .filter(|c| matches!(c, Constraint::Vanishes { .. }))
+            .partition(|c| {
+                matches!(
+                    c,
+                    Constraint::Vanishes {
+                        domain: Some(_),
+                        ..
+                    }
+                )
+            })
+    }
+
+    /// Attribute the spilling of module `m` to the expressions featuring its
+    /// most extreme shifts
+    pub fn explain_spilling(&self, m: &str) -> SpillingExplanation {
+        SpillingExplanation {
+            past: self
+                .spilling_sources(m)
+                .map(|(origin, e)| (e.past_spill(), origin))
+                .filter(|(shift, _)| *shift < 0)
+                .min_by_key(|(shift, _)| *shift),
+            future: self
+                .spilling_sources(m)
+                .map(|(origin, e)| (e.future_spill(), origin))
+                .filter(|(shift, _)| *shift > 0)
+                .max_by_key(|(shift, _)| *shift),
+            spilling: self.spilling_of(m).unwrap_or(0),
+        }
+    }
+
+    pub fn iter_len(&self, module: &str) -> usize {
+        self.effective_len_for(module)
+            // If the module is empty, use its spilling
+            .or_else(|| self.spilling_of(module))
+            .unwrap() as usize
+    }
+
+    pub fn spilling_of(&self, m: &str) -> Option<isize> {
         self.columns.spilling.get(m).cloned()
     }
 
@@ -916,6 +1611,24 @@ impl ConstraintSet {
         }
     }
 
+    /// Once all the constraints are known, narrow the types left to `Any` --
+    /// e.g. by functions used across modules -- from the context they are
+    /// used in; return the expressions that could not be narrowed, along with
+    /// the constraint they belong to.
+    pub(crate) fn narrow_any_types(&mut self) -> Vec<(Handle, Node)> {
+        let mut r = Vec::new();
+        for c in self.constraints.iter_mut() {
+            if let Constraint::Vanishes { handle, expr, .. } = c {
+                r.extend(
+                    narrow_any(expr, None)
+                        .into_iter()
+                        .map(|e| (handle.clone(), e)),
+                );
+            }
+        }
+        r
+    }
+
     pub(crate) fn module_of_expr(&self, e: &Node) -> Option<String> {
         self.columns.module_for(e.dependencies())
     }
@@ -924,6 +1637,19 @@ impl ConstraintSet {
         es.iter().find_map(|e| self.module_of_expr(e))
     }
 
+    /// The number of rows to iterate over the columns of `es`, i.e. the length
+    /// of their module scaled by their size factor
+    pub(crate) fn iter_len_of_exprs(&self, es: &[Node]) -> Option<usize> {
+        let module = self.module_of_exprs(es)?;
+        let length_multiplier = es
+            .iter()
+            .flat_map(Node::dependencies)
+            .next()
+            .map(|c| self.length_multiplier(&c))
+            .unwrap_or(1);
+        Some(self.iter_len(&module) * length_multiplier)
+    }
+
     pub fn length_multiplier(&self, h: &ColumnRef) -> usize {
         self.computations
             .computation_for(h)
@@ -934,20 +1660,59 @@ impl ConstraintSet {
                     .next()
                     .map(|d| self.length_multiplier(d))
                     .unwrap_or(1),
+                // Fixpoint columns may depend on each other, so only the
+                // other dependencies may be followed
+                Computation::Fixpoint { exp, .. } => exp
+                    .dependencies()
+                    .iter()
+                    .find(|d| {
+                        !matches!(
+                            self.computations.computation_for(d),
+                            Some(Computation::Fixpoint { .. })
+                        )
+                    })
+                    .map(|d| self.length_multiplier(d))
+                    .unwrap_or(1),
                 Computation::Interleaved { froms, .. } => {
                     self.length_multiplier(&froms[0]) * froms.len()
                 }
+                // every row of the sources span over the summed strides, divided
+                // among the rows of a block of each source
+                Computation::InterleavedStrided { froms, strides, .. } => {
+                    self.length_multiplier(&froms[0]) * strides.iter().sum::<usize>() / strides[0]
+                }
                 Computation::Sorted { froms, .. } | Computation::CyclicFrom { froms, .. } => {
                     self.length_multiplier(&froms[0])
                 }
                 Computation::SortingConstraints { .. } => 1,
+                Computation::Clamp { exp, .. } => exp
+                    .dependencies()
+                    .iter()
+                    .next()
+                    .map(|d| self.length_multiplier(d))
+                    .unwrap_or(1),
+                Computation::Recurrence { exp, target, .. } => exp
+                    .dependencies()
+                    .iter()
+                    .find(|d| *d != target)
+                    .map(|d| self.length_multiplier(d))
+                    .unwrap_or(1),
                 Computation::ExoOperation { sources, .. } => sources
                     .iter()
                     .flat_map(|s| s.dependencies())
                     .next()
                     .map(|c| self.length_multiplier(&c))
                     .unwrap_or(1),
-                Computation::ExoConstant { .. } => 1,
+                // the multiplicity spans over the rows of the table
+                Computation::LookupMultiplicity { including, .. } => including
+                    .iter()
+                    .flat_map(|e| e.dependencies())
+                    .next()
+                    .map(|c| self.length_multiplier(&c))
+                    .unwrap_or(1),
+                Computation::ExoConstant { .. }
+                | Computation::ConstantTable { .. }
+                | Computation::ActiveRows { .. } => 1,
             })
             .unwrap_or(1)
             * self
@@ -958,25 +1723,93 @@ impl ConstraintSet {
                 .unwrap_or(1)
     }
 
+    /// Returns a JSON trace skeleton where every column expected from a trace
+    /// is present as an empty array, nested in its module as the importer
+    /// expects it.
+    pub fn trace_template(&self) -> serde_json::Value {
+        let mut modules: BTreeMap<String, serde_json::Map<String, serde_json::Value>> =
+            Default::default();
+        for (r, col) in self.columns.iter() {
+            // Internal modules are filled by Corset itself
+            if !matches!(col.kind, Kind::Commitment) || col.handle.module.starts_with('#') {
+                continue;
+            }
+            // Columns in perspectives are imported through the register
+            // they have been merged into
+            let name = if col.handle.perspective.is_some() {
+                self.columns
+                    .register_of(&r)
+                    .handle
+                    .as_ref()
+                    .map(|h| h.name.to_owned())
+                    .unwrap_or_else(|| col.handle.name.to_owned())
+            } else {
+                col.handle.name.to_owned()
+            };
+            modules
+                .entry(col.handle.module.to_owned())
+                .or_default()
+                .insert(name, serde_json::Value::Array(vec![]));
+        }
+
+        serde_json::Value::Object(
+            modules
+                .into_iter()
+                .map(|(module, columns)| {
+                    (
+                        module,
+                        serde_json::json!({ "Trace": serde_json::Value::Object(columns) }),
+                    )
+                })
+                .collect(),
+        )
+    }
+
     #[time("info", "Exporting expanded trace")]
     pub fn write(&mut self, out: &mut impl Write) -> Result<()> {
+        self.write_columns(out, |_| true)
+    }
+
+    /// Write the committed columns, i.e. the ones filled from the trace
+    pub fn write_committed(&mut self, out: &mut impl Write) -> Result<()> {
+        self.write_columns(out, |c| matches!(c.kind, Kind::Commitment))
+    }
+
+    /// Write the auxiliary witness, i.e. all the columns computed by Corset
+    /// (composite, interleaved, sorted, ...)
+    pub fn write_witness(&mut self, out: &mut impl Write) -> Result<()> {
+        self.write_columns(out, |c| !matches!(c.kind, Kind::Commitment))
+    }
+
+    fn write_columns(
+        &mut self,
+        out: &mut impl Write,
+        keep: impl Fn(&Column) -> bool,
+    ) -> Result<()> {
         let mut cache = cached::SizedCache::with_size(200000); // ~1.60MB cache
 
         out.write_all("{\"columns\":{\n".as_bytes())?;
 
-        for (i, module) in self.columns.modules().into_iter().enumerate() {
-            debug!("Exporting {}", &module);
-            if i > 0 {
-                out.write_all(b",")?;
-            }
-
+        // Modules and columns are sorted, so that the output is stable across runs
+        let mut first = true;
+        for module in self.columns.modules().into_iter().sorted() {
             let mut current_col = self
                 .columns
                 .all()
                 .into_iter()
                 .map(|h| (h.clone(), self.columns.column(&h).unwrap()))
-                .filter(|(_, c)| c.handle.module == module)
+                .filter(|(_, c)| c.handle.module == module && keep(c))
+                .sorted_by(|a, b| a.1.handle.cmp(&b.1.handle))
                 .peekable();
+            if current_col.peek().is_none() {
+                continue;
+            }
+
+            debug!("Exporting {}", &module);
+            if !first {
+                out.write_all(b",")?;
+            }
+            first = false;
             let empty_backing: ValueBacking = ValueBacking::default();
             while let Some((r, column)) = current_col.next() {
                 let handle = &column.handle;
@@ -989,7 +1822,8 @@ impl ConstraintSet {
                         self.computations
                             .computation_for(&r)
                             .map(|c| match c {
-                                Computation::Composite { exp, .. } => exp
+                                Computation::Composite { exp, .. }
+                                | Computation::Fixpoint { exp, .. } => exp
                                     .eval(
                                         0,
                                         |_, _, _| Some(Value::zero()),
@@ -997,12 +1831,18 @@ impl ConstraintSet {
                                         &EvalSettings::default(),
                                     )
                                     .unwrap_or_else(Value::zero),
-                                Computation::Interleaved { .. } => Value::zero(),
+                                Computation::Interleaved { .. }
+                                | Computation::InterleavedStrided { .. } => Value::zero(),
                                 Computation::Sorted { .. } => Value::zero(),
                                 Computation::CyclicFrom { .. } => Value::zero(),
+                                Computation::Clamp { .. } => Value::zero(),
+                                Computation::Recurrence { init, .. } => init.clone(),
                                 Computation::SortingConstraints { .. } => Value::zero(),
                                 Computation::ExoOperation { .. } => Value::zero(), // TODO: FIXME:
                                 Computation::ExoConstant { .. } => Value::zero(),  // TODO: FIXME:
+                                Computation::ConstantTable { .. } => Value::zero(),
+                                Computation::LookupMultiplicity { .. } => Value::zero(),
+                                Computation::ActiveRows { .. } => Value::zero(),
                             })
                             .unwrap_or_else(Value::zero)
                     })
@@ -1074,6 +1914,24 @@ impl ConstraintSet {
                         ))
                     }
                 }
+                Constraint::LookupMultiplicity {
+                    handle,
+                    including,
+                    included,
+                    multiplicity,
+                } => {
+                    if including
+                        .iter()
+                        .flat_map(|i| i.dependencies())
+                        .chain(included.iter().flat_map(|i| i.dependencies()))
+                        .any(|r| !r.is_id())
+                        || !multiplicity.is_id()
+                    {
+                        bail!(errors::compiler::Error::ConstraintWithHandles(
+                            handle.to_string()
+                        ))
+                    }
+                }
                 Constraint::Permutation {
                     handle, from, to, ..
                 } => {
@@ -1114,7 +1972,9 @@ impl ConstraintSet {
         }
         for c in self.computations.iter() {
             match c {
-                Computation::Composite { target, exp } => {
+                Computation::Composite { target, exp }
+                | Computation::Fixpoint { target, exp }
+                | Computation::Recurrence { target, exp, .. } => {
                     if !target.is_id() || exp.dependencies().into_iter().any(|r| !r.is_id()) {
                         bail!(errors::compiler::Error::ComputationWithHandles(
                             c.to_string(),
@@ -1122,6 +1982,7 @@ impl ConstraintSet {
                     }
                 }
                 Computation::Interleaved { target, froms }
+                | Computation::InterleavedStrided { target, froms, .. }
                 | Computation::CyclicFrom { target, froms, .. } => {
                     if !target.is_id() || froms.iter().any(|r| !r.is_id()) {
                         bail!(errors::compiler::Error::ComputationWithHandles(
@@ -1170,13 +2031,47 @@ impl ConstraintSet {
                         ))
                     }
                 }
-                Computation::ExoConstant { target, .. } => {
+                Computation::ExoConstant { target, .. }
+                | Computation::ConstantTable { target, .. }
+                | Computation::ActiveRows { target } => {
                     if !target.is_id() {
                         bail!(errors::compiler::Error::ComputationWithHandles(
                             target.to_string()
                         ))
                     }
                 }
+                Computation::LookupMultiplicity {
+                    target,
+                    including,
+                    included,
+                } => {
+                    if !target.is_id()
+                        || including
+                            .iter()
+                            .chain(included.iter())
+                            .flat_map(|e| e.dependencies())
+                            .any(|r| !r.is_id())
+                    {
+                        bail!(errors::compiler::Error::ComputationWithHandles(
+                            c.to_string()
+                        ))
+                    }
+                }
+                Computation::Clamp {
+                    target,
+                    below,
+                    above,
+                    exp,
+                    ..
+                } => {
+                    if [target, below, above].into_iter().any(|r| !r.is_id())
+                        || exp.dependencies().into_iter().any(|r| !r.is_id())
+                    {
+                        bail!(errors::compiler::Error::ComputationWithHandles(
+                            c.to_string()
+                        ))
+                    }
+                }
             }
         }
 
@@ -1233,7 +2128,44 @@ impl ConstraintSet {
                         }
                     }
                 }
-                Computation::Sorted { froms, tos, signs } => {
+                Computation::InterleavedStrided {
+                    target,
+                    froms,
+                    strides,
+                } => {
+                    if let Some(f) = froms
+                        .iter()
+                        .find(|f| self.columns.perspective(f).unwrap().is_some())
+                    {
+                        bail!(
+                            "{} can not be interleaved with strides within a perspective",
+                            self.handle(f).pretty()
+                        )
+                    }
+                    // the rows of every source must fit exactly in the blocks
+                    // of the target
+                    let period = strides.iter().sum::<usize>();
+                    if froms
+                        .iter()
+                        .zip(strides.iter())
+                        .map(|(f, s)| {
+                            let rows = self.length_multiplier(f) * period;
+                            (rows % s == 0).then_some(rows / s)
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .map(|ms| ms.windows(2).all(|w| w[0] == w[1]))
+                        != Some(true)
+                    {
+                        bail!(
+                            "strides {} are incoherent with the lengths of the columns interleaved in {}",
+                            strides.iter().join(", "),
+                            self.handle(target).pretty()
+                        )
+                    }
+                }
+                Computation::Sorted {
+                    froms, tos, signs, ..
+                } => {
                     assert!(froms.len() == tos.len());
                     assert!(froms.len() == signs.len());
                 }
@@ -1244,6 +2176,27 @@ impl ConstraintSet {
     }
 }
 
+/// Bind `value` to the symbol, or destructure it into the list of symbols,
+/// found in `names` in the `let` scope `ctx`
+fn bind_let(names: &AstNode, value: Node, ctx: &mut Scope) -> Result<()> {
+    if let Result::Ok(names) = names.as_list() {
+        let values = elements_of(&value, ctx)?;
+        if values.len() != names.len() {
+            bail!(
+                "can not destructure {} elements into {} symbols",
+                values.len(),
+                names.len()
+            )
+        }
+        for (name, value) in names.iter().zip(values) {
+            ctx.insert_symbol(name.as_symbol().unwrap(), value)?;
+        }
+        Ok(())
+    } else {
+        ctx.insert_symbol(names.as_symbol().unwrap(), value)
+    }
+}
+
 // Compared to a function, a form do not evaluate all of its arguments by default
 fn apply_form(
     f: Form,
@@ -1266,12 +2219,18 @@ fn apply_form(
                         .and_then(|r| r.pure_eval())
                         .and_then(|bi| bi.to_isize().ok_or_else(|| anyhow!("{} is not an i64", bi)))
                 })?;
+                if let Domain::SteppedRange(_, 0, _) = is {
+                    bail!("null step in {}", args[1].src.white().bold())
+                }
                 let mut l = vec![];
                 let mut t = Type::INFIMUM;
-                for i in is.iter() {
+                // descending ranges are walked downwards
+                for i in is.iter_directed() {
                     let mut for_ctx = ctx.derive(&uniquify(format!("{}-for-{}", ctx.name(), i)))?;
 
-                    for_ctx.insert_symbol(i_name, Expression::Const(Value::from(i)).into())?;
+                    // going through a BigInt, so that negative indices are
+                    // mapped to their field counterpart when working natively
+                    for_ctx.insert_symbol(i_name, Node::from_bigint(BigInt::from(i)))?;
 
                     if let Some(r) = reduce(&body.clone(), &mut for_ctx, settings)? {
                         t = t.max(r.t());
@@ -1317,47 +2276,124 @@ fn apply_form(
             let mut sub_ctx = ctx.derive(&sub_ctx_name)?;
             for pair in args[0].as_list().unwrap().iter() {
                 let pair = pair.as_list().unwrap();
-                let name = pair[0].as_symbol().unwrap();
                 let value = reduce(&pair[1], &mut sub_ctx, settings)?.unwrap();
-                sub_ctx.insert_symbol(name, value)?;
+                bind_let(&pair[0], value, &mut sub_ctx)?;
+            }
+            let body = reduce(&args[1], &mut sub_ctx, settings)?.unwrap();
+
+            Ok(Some(body))
+        }
+        Form::LetStar => {
+            // Each binding lives in its own scope, derived from the one of the
+            // previous binding; so that it may shadow any of the previous ones
+            let mut sub_ctx = ctx.derive(&uniquify(format!("{}-let*", ctx.name())))?;
+            for pair in args[0].as_list().unwrap().iter() {
+                let pair = pair.as_list().unwrap();
+                let value = reduce(&pair[1], &mut sub_ctx, settings)?.unwrap();
+                sub_ctx = sub_ctx.derive(&uniquify(format!("{}-let*", ctx.name())))?;
+                bind_let(&pair[0], value, &mut sub_ctx)?;
             }
             let body = reduce(&args[1], &mut sub_ctx, settings)?.unwrap();
 
             Ok(Some(body))
         }
         Form::Reduce => {
-            let f_name = args[0].as_symbol().unwrap();
-            let f = ctx.resolve_function(f_name)?;
-
-            let mut body = reduce(&args[1], ctx, settings)?.unwrap();
-
-            return match body.e_mut() {
-                Expression::Column { .. }
-                | Expression::Void
-                | Expression::ArrayColumn { .. }
-                | Expression::Funcall { .. }
-                | Expression::Const(_) => panic!(),
-                Expression::List(xs) => {
-                    if xs.is_empty() {
-                        Ok(Some(body))
-                    } else if xs.len() == 1 {
-                        Ok(Some(xs[0].clone()))
-                    } else {
-                        let mut r = apply_function(
-                            &f,
-                            vec![xs.pop().unwrap(), xs.pop().unwrap()],
-                            ctx,
-                            settings,
-                        );
-                        while let Some(x) = xs.pop() {
-                            r = apply_function(&f, vec![x, r?.unwrap()], ctx, settings);
-                        }
-                        r
-                    }
+            let f = resolve_applicable(&args[0], ctx)?;
+            let body = reduce(&args[1], ctx, settings)?.unwrap();
+            let mut xs = elements_of(&body, ctx)?;
+
+            if xs.is_empty() {
+                Ok(Some(body))
+            } else if xs.len() == 1 {
+                Ok(Some(xs[0].clone()))
+            } else {
+                let mut r = apply_function(
+                    &f,
+                    vec![xs.pop().unwrap(), xs.pop().unwrap()],
+                    ctx,
+                    settings,
+                );
+                while let Some(x) = xs.pop() {
+                    r = apply_function(&f, vec![x, r?.unwrap()], ctx, settings);
                 }
-                Expression::ExoColumn { .. } => todo!(),
-            };
+                r
+            }
         }
+        Form::Map => {
+            let f = resolve_applicable(&args[0], ctx)?;
+            let body = reduce(&args[1], ctx, settings)?.unwrap();
+
+            let mut l = vec![];
+            let mut t = Type::INFIMUM;
+            for x in elements_of(&body, ctx)? {
+                if let Some(r) = apply_function(&f, vec![x], ctx, settings)? {
+                    t = t.max(r.t());
+                    l.push(r);
+                }
+            }
+
+            Ok(Some(Node::from(Expression::List(l)).with_type(t)))
+        }
+    }
+}
+
+/// Resolve the function argument of a higher-order form, which is either the
+/// name of a function or an anonymous `(lambda (ARGS...) BODY)`
+fn resolve_applicable(f: &AstNode, ctx: &mut Scope) -> Result<Function> {
+    if let Result::Ok((args, body)) = f.as_lambda() {
+        Ok(Function {
+            handle: Handle::new(ctx.module(), "lambda"),
+            class: FunctionClass::UserDefined(Defined {
+                specializations: vec![Specialization {
+                    pure: false,
+                    in_types: vec![Type::Any(Magma::any()); args.len()],
+                    args,
+                    out_type: None,
+                    body: body.clone(),
+                    force: false,
+                }],
+            }),
+        })
+    } else {
+        ctx.resolve_function(f.as_symbol().unwrap())
+    }
+}
+
+/// Resolve the column holding the `i`th element of the array column `array`
+fn array_element(
+    array: &Node,
+    handle: &ColumnRef,
+    domain: &Domain<isize>,
+    i: usize,
+    ctx: &mut Scope,
+) -> Result<Node> {
+    // Sanity check access within bounds
+    if domain.contains(i.try_into().unwrap()) {
+        // Construct indexed handle
+        let name = handle.as_handle().ith(i).to_string();
+        // Resolve it properly this time.
+        ctx.resolve_symbol_with_path(&name, true)
+            .map_err(Into::into)
+    } else {
+        bail!("tried to access {} at index {}", array.pretty().bold(), i)
+    }
+}
+
+/// Return the elements of either a list or an array column
+fn elements_of(e: &Node, ctx: &mut Scope) -> Result<Vec<Node>> {
+    match e.e() {
+        Expression::List(xs) => Ok(xs.clone()),
+        Expression::ArrayColumn { handle, domain, .. } => domain
+            .iter()
+            .map(|i| {
+                ctx.resolve_symbol_with_path(
+                    &handle.as_handle().ith(i.try_into().unwrap()).to_string(),
+                    true,
+                )
+                .map_err(Into::into)
+            })
+            .collect(),
+        _ => bail!("expected a list or an array, found {}", e.pretty()),
     }
 }
 
@@ -1409,16 +2445,290 @@ fn apply_defined(
         } else {
             r.t()
         };
-        Some(r.with_type(final_type))
+        // A call with constant arguments may be fully evaluated at compile-time
+        let all_constant_args = traversed_args
+            .iter()
+            .all(|a| matches!(a.e(), Expression::Const(_)));
+        match r.pure_eval() {
+            Result::Ok(x) if all_constant_args => {
+                Some(Node::from(Expression::Const(Value::big_int(x))).with_type(final_type))
+            }
+            _ => Some(r.with_type(final_type)),
+        }
     } else {
         None
     })
 }
 
+/// Expand the polynomial `coefficients[0] + x·coefficients[1] + ...` in Horner
+/// form, i.e. `a0 + x·(a1 + x·(a2 + ... + x·an))`.
+fn horner(x: &Node, coefficients: &[Node]) -> Result<Node> {
+    let mut coefficients = coefficients.iter().rev();
+    let mut r = coefficients.next().unwrap().clone();
+    for a in coefficients {
+        r = Intrinsic::Add.call(&[a.clone(), Intrinsic::Mul.call(&[x.clone(), r])?])?;
+    }
+    Ok(r)
+}
+
+/// Parse the exponent of a fixed-point scaling, which must be a non-negative
+/// constant.
+fn scaling_factor(b: &Builtin, n: &Node) -> Result<BigInt> {
+    let n = n.pure_eval().ok().and_then(|n| n.to_u32()).ok_or_else(|| {
+        anyhow!(
+            "{} expects a non-negative constant scale",
+            b.to_string().bold().yellow()
+        )
+    })?;
+    Ok(BigInt::one() << n)
+}
+
+/// Ensure that the expressions of the constraint `handle` can be arithmetized,
+/// i.e. that once their divisions by a constant are lowered to a
+/// multiplication by its field inverse, they do not feature any integer modulo
+/// or division. Field inverses are meaningless over the integers, so that in
+/// integer arithmetic, divisions by a constant are kept and evaluated exactly.
+fn ensure_arithmetizable<'a>(
+    xs: impl IntoIterator<Item = &'a mut Node>,
+    handle: &Handle,
+) -> Result<()> {
+    let native = *crate::IS_NATIVE.read().unwrap();
+    for x in xs {
+        if native {
+            invert_constant_divisors(x);
+        }
+        if let Some(f) = non_constant_integer_operation(x) {
+            bail!(CompileError::IntegerOperation(
+                f.to_string(),
+                handle.clone()
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Return the first integer operation of `x` that is not a division by a
+/// non-zero constant
+fn non_constant_integer_operation(x: &Node) -> Option<Intrinsic> {
+    match x.e() {
+        Expression::Funcall {
+            func: Intrinsic::Div,
+            args,
+        } if args[1].pure_eval().is_ok_and(|d| !d.is_zero()) => {
+            non_constant_integer_operation(&args[0])
+        }
+        Expression::Funcall {
+            func: f @ (Intrinsic::Mod | Intrinsic::Div),
+            ..
+        } => Some(*f),
+        Expression::Funcall { args, .. } | Expression::List(args) => {
+            args.iter().find_map(non_constant_integer_operation)
+        }
+        _ => None,
+    }
+}
+
+/// Rewrite in place every `x/d` in `x` where `d` is a non-zero constant into
+/// `x×d⁻¹`, `d⁻¹` being the inverse of `d` in the field
+fn invert_constant_divisors(x: &mut Node) {
+    if let Expression::Funcall { func, args } = x.e_mut() {
+        args.iter_mut().for_each(invert_constant_divisors);
+        if *func == Intrinsic::Div {
+            let modulus = crate::constants::field_modulus();
+            if let Result::Ok(d) = args[1].pure_eval() {
+                let d = num_traits::Euclid::rem_euclid(&d, modulus);
+                if !d.is_zero() {
+                    let inverse = d.modpow(&(modulus - 2), modulus);
+                    *x = Intrinsic::Mul
+                        .call(&[args[0].clone(), Node::from_bigint(inverse)])
+                        .unwrap();
+                }
+            }
+        }
+    } else if let Expression::List(xs) = x.e_mut() {
+        xs.iter_mut().for_each(invert_constant_divisors);
+    }
+}
+
+/// Rewrite `x` into `x/d`, if `x` can be proven to be a multiple of `d`; i.e.
+/// if it is a constant multiple of `d`, or a sum or product whose terms or
+/// factor are.
+fn divide_exactly(x: &Node, d: &BigInt) -> Option<Node> {
+    if let Result::Ok(v) = x.pure_eval() {
+        return (&v % d).is_zero().then(|| Node::from_bigint(v / d));
+    }
+    match x.e() {
+        Expression::Funcall { func, args } => match func {
+            Intrinsic::Add
+            | Intrinsic::VectorAdd
+            | Intrinsic::Sub
+            | Intrinsic::VectorSub
+            | Intrinsic::Neg => {
+                let args = args
+                    .iter()
+                    .map(|a| divide_exactly(a, d))
+                    .collect::<Option<Vec<_>>>()?;
+                func.call(&args).ok()
+            }
+            Intrinsic::Mul | Intrinsic::VectorMul => {
+                let mut args = args.to_owned();
+                let (i, divided) = args
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, a)| divide_exactly(a, d).map(|a| (i, a)))?;
+                args[i] = divided;
+                func.call(&args).ok()
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Create the columns and constraints saturating the bounded expression `x` in
+/// [`lo`; `hi`], and return the column holding the result.
+///
+/// Two binary columns flag whether `x` is below `lo` or above `hi`, and their
+/// correctness is enforced by range-checking the distance of `x` to the
+/// bounds, which is in [0; 2^w) iff the flag is set correctly.
+fn clamp_gadget(x: &Node, lo: BigInt, hi: BigInt, ctx: &mut Scope) -> Result<Node> {
+    let m = x.t().m();
+    let bits = m.bit_size();
+    if bits >= Magma::NATIVE.bit_size() {
+        bail!(
+            "{} can only saturate bounded expressions, found {}",
+            "clamp".bold().yellow(),
+            x.pretty()
+        )
+    }
+    let max = BigInt::one() << bits;
+    if lo < BigInt::zero() || hi >= max {
+        bail!(
+            "invalid clamping bounds [{}; {}] for {}",
+            lo,
+            hi,
+            x.pretty()
+        )
+    }
+
+    let mut module_ctx = ctx.module_scope();
+    let module = module_ctx.module();
+    let name = format!("clamp[{}:{}:{}]", x, lo, hi).replace(['.', PERSPECTIVE_SEPARATOR], "_");
+    if let Result::Ok(r) = module_ctx.resolve_symbol(&name, true) {
+        return Ok(r);
+    }
+
+    let handle = |suffix: &str| Handle::new(&module, format!("{}{}", name, suffix));
+    let column = |h: &Handle, kind: Kind<Box<Node>>, t: Magma| {
+        Node::column().handle(h.clone()).kind(kind).t(t).build()
+    };
+    let one = Node::one();
+    let (lo_node, hi_node) = (Node::from_bigint(lo.clone()), Node::from_bigint(hi.clone()));
+    let (r_handle, below_handle, above_handle) = (handle(""), handle("-below"), handle("-above"));
+    let r = column(&r_handle, Kind::Computed, m);
+    let below = column(&below_handle, Kind::Computed, Magma::binary());
+    let above = column(&above_handle, Kind::Computed, Magma::binary());
+    // below ⇒ lo - x - 1; ¬below ⇒ x - lo
+    let lo_delta = column(
+        &handle("-lo-delta"),
+        Kind::Expression(Box::new(Intrinsic::Add.call(&[
+            Intrinsic::Mul.call(&[
+                below.clone(),
+                Intrinsic::Sub.call(&[lo_node.clone(), x.clone(), one.clone()])?,
+            ])?,
+            Intrinsic::Mul.call(&[
+                Intrinsic::Sub.call(&[one.clone(), below.clone()])?,
+                Intrinsic::Sub.call(&[x.clone(), lo_node.clone()])?,
+            ])?,
+        ])?)),
+        m,
+    );
+    // above ⇒ x - hi - 1; ¬above ⇒ hi - x
+    let hi_delta = column(
+        &handle("-hi-delta"),
+        Kind::Expression(Box::new(Intrinsic::Add.call(&[
+            Intrinsic::Mul.call(&[
+                above.clone(),
+                Intrinsic::Sub.call(&[x.clone(), hi_node.clone(), one.clone()])?,
+            ])?,
+            Intrinsic::Mul.call(&[
+                Intrinsic::Sub.call(&[one.clone(), above.clone()])?,
+                Intrinsic::Sub.call(&[hi_node.clone(), x.clone()])?,
+            ])?,
+        ])?)),
+        m,
+    );
+    for (suffix, c) in [
+        ("", &r),
+        ("-below", &below),
+        ("-above", &above),
+        ("-lo-delta", &lo_delta),
+        ("-hi-delta", &hi_delta),
+    ] {
+        module_ctx.insert_symbol(&handle(suffix).name, c.clone())?;
+        module_ctx.resolve_symbol(&handle(suffix).name, true)?;
+    }
+
+    ctx.insert_many_computations(
+        &[
+            r_handle.clone().into(),
+            below_handle.clone().into(),
+            above_handle.clone().into(),
+        ],
+        Computation::Clamp {
+            target: r_handle.into(),
+            below: below_handle.into(),
+            above: above_handle.into(),
+            exp: x.clone(),
+            lo: Value::big_int(lo),
+            hi: Value::big_int(hi),
+        },
+    )?;
+
+    let vanishes = |suffix: &str, expr: Node| Constraint::Vanishes {
+        handle: handle(suffix),
+        domain: None,
+        expr: Box::new(expr),
+    };
+    // r = below × lo + above × hi + (1 - below - above) × x
+    ctx.insert_auxiliary_constraint(vanishes(
+        "-result",
+        Intrinsic::Sub.call(&[
+            r.clone(),
+            Intrinsic::Add.call(&[
+                Intrinsic::Mul.call(&[below.clone(), lo_node])?,
+                Intrinsic::Mul.call(&[above.clone(), hi_node])?,
+                Intrinsic::Mul.call(&[
+                    Intrinsic::Sub.call(&[one.clone(), below.clone(), above.clone()])?,
+                    x.clone(),
+                ])?,
+            ])?,
+        ])?,
+    ));
+    for (suffix, flag) in [("-below-binary", &below), ("-above-binary", &above)] {
+        ctx.insert_auxiliary_constraint(vanishes(
+            suffix,
+            Intrinsic::Mul.call(&[
+                flag.clone(),
+                Intrinsic::Sub.call(&[one.clone(), flag.clone()])?,
+            ])?,
+        ));
+    }
+    for (suffix, delta) in [("-lo-range", &lo_delta), ("-hi-range", &hi_delta)] {
+        ctx.insert_auxiliary_constraint(Constraint::InRange {
+            handle: handle(suffix),
+            exp: delta.clone(),
+            max: Value::big_int(max.clone()),
+        });
+    }
+
+    Ok(r)
+}
+
 fn apply_builtin(
     b: &Builtin,
     traversed_args: Vec<Node>,
-    _ctx: &mut Scope,
+    ctx: &mut Scope,
     _settings: &CompileSettings,
 ) -> Result<Option<Node>> {
     b.validate_args(&traversed_args)?;
@@ -1440,6 +2750,10 @@ fn apply_builtin(
             let shift = traversed_args[1].pure_eval()?.to_i16().unwrap();
             Ok(Some(traversed_args.get(0).unwrap().clone().shift(shift)))
         }
+        Builtin::RotShift => {
+            let shift = traversed_args[1].pure_eval()?.to_i16().unwrap();
+            Ok(Some(traversed_args[0].clone().shift(shift).wrap_around()))
+        }
         Builtin::NormFlat => {
             if traversed_args[0].is_exocolumn() {
                 todo!("{}", traversed_args[0].pretty())
@@ -1454,6 +2768,155 @@ fn apply_builtin(
             super::Conditioning::Boolean => Ok(Some(Intrinsic::IfNotZero.call(&traversed_args)?)),
             super::Conditioning::Loobean => Ok(Some(Intrinsic::IfZero.call(&traversed_args)?)),
         },
+        Builtin::Clamp => {
+            let lo = traversed_args[1].pure_eval()?;
+            let hi = traversed_args[2].pure_eval()?;
+            if lo > hi {
+                bail!("invalid clamping bounds [{}; {}]", lo, hi)
+            }
+            if let Result::Ok(x) = traversed_args[0].pure_eval() {
+                Ok(Some(Node::from_bigint(x.clamp(lo, hi))))
+            } else {
+                clamp_gadget(&traversed_args[0], lo, hi, ctx).map(Some)
+            }
+        }
+        Builtin::Horner => horner(&traversed_args[0], &traversed_args[1..]).map(Some),
+        Builtin::Rlc => {
+            let challenge = &traversed_args[0];
+            if challenge.pure_eval().is_err()
+                && !matches!(
+                    challenge.e(),
+                    Expression::Column { .. } | Expression::ExoColumn { .. }
+                )
+            {
+                bail!(
+                    "{} is not a valid RLC challenge, expected a constant or a column",
+                    challenge.pretty()
+                )
+            }
+            horner(challenge, &traversed_args[1..]).map(Some)
+        }
+        Builtin::Nth => {
+            if let Expression::ArrayColumn { handle, domain, .. } = traversed_args[0].e() {
+                let i = traversed_args[1]
+                    .pure_eval()
+                    .ok()
+                    .and_then(|i| i.to_usize())
+                    .ok_or_else(|| {
+                        anyhow!("{} is not a valid index", traversed_args[1].pretty())
+                    })?;
+                array_element(&traversed_args[0], handle, domain, i, ctx).map(Some)
+            } else {
+                bail!(RuntimeError::NotAnArray(traversed_args[0].e().clone()))
+            }
+        }
+        Builtin::ScaleUp => {
+            let factor = scaling_factor(b, &traversed_args[1])?;
+            Intrinsic::Mul
+                .call(&[traversed_args[0].clone(), Node::from_bigint(factor)])
+                .map(Some)
+        }
+        Builtin::ScaleDown => {
+            let factor = scaling_factor(b, &traversed_args[1])?;
+            divide_exactly(&traversed_args[0], &factor)
+                .map(Some)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} can not be proven to be a multiple of {}",
+                        traversed_args[0].pretty(),
+                        factor
+                    )
+                })
+        }
+        Builtin::Mod => {
+            if let (Result::Ok(x), Result::Ok(m)) =
+                (traversed_args[0].pure_eval(), traversed_args[1].pure_eval())
+            {
+                if m.is_zero() {
+                    bail!("{} reduced modulo zero", traversed_args[0].pretty())
+                }
+                Ok(Some(Node::from_bigint(x.rem_euclid(&m))))
+            } else {
+                Intrinsic::Mod.call(&traversed_args).map(Some)
+            }
+        }
+        Builtin::Div => {
+            let (x, d) = (&traversed_args[0], &traversed_args[1]);
+            match (x.pure_eval(), d.pure_eval()) {
+                // Both operands are known: the division must be exact
+                (_, Result::Ok(dv)) if dv.is_zero() => {
+                    bail!("{} divided by zero", x.pretty())
+                }
+                (Result::Ok(_), Result::Ok(_)) => Intrinsic::Div
+                    .call(&traversed_args)?
+                    .pure_eval()
+                    .map(|q| Some(Node::from_bigint(q))),
+                // Prefer a polynomial quotient when it can be found
+                (_, Result::Ok(dv)) => match divide_exactly(x, &dv) {
+                    Some(q) => Ok(Some(q)),
+                    None => Intrinsic::Div.call(&traversed_args).map(Some),
+                },
+                _ => Intrinsic::Div.call(&traversed_args).map(Some),
+            }
+        }
+        Builtin::Xor => {
+            // a ⊕ b = a + b - 2·a·b
+            let (a, b) = (&traversed_args[0], &traversed_args[1]);
+            let t = a.t().max(b.t()).with_raw_magma(RawMagma::Binary);
+            Ok(Some(
+                Intrinsic::Sub
+                    .call(&[
+                        Intrinsic::Add.call(&[a.clone(), b.clone()])?,
+                        Intrinsic::Mul.call(&[Node::from_isize(2), a.clone(), b.clone()])?,
+                    ])?
+                    .with_type(t),
+            ))
+        }
+        Builtin::Or => {
+            // a ∨ b ∨ ... = 1 - (1 - a)·(1 - b)·...
+            let complements = traversed_args
+                .iter()
+                .map(|a| Intrinsic::Sub.call(&[Node::one(), a.clone()]))
+                .collect::<Result<Vec<_>>>()?;
+            let t = super::max_type(&traversed_args.iter().map(Node::t).collect::<Vec<_>>())?
+                .with_raw_magma(RawMagma::Binary)
+                .with_conditioning(Conditioning::Boolean);
+            Ok(Some(
+                Intrinsic::Sub
+                    .call(&[Node::one(), Intrinsic::Mul.call(&complements)?])?
+                    .with_type(t),
+            ))
+        }
+        Builtin::Min | Builtin::Max => {
+            let values = traversed_args
+                .iter()
+                .map(|a| {
+                    a.pure_eval()
+                        .map_err(|_| anyhow!(CompileError::NotConstant(b.to_string(), a.pretty())))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let r = if matches!(b, Builtin::Min) {
+                values.into_iter().min()
+            } else {
+                values.into_iter().max()
+            };
+            Ok(Some(Node::from_bigint(r.unwrap())))
+        }
+        Builtin::Stays | Builtin::Changes => {
+            // C[i+1] - C[i], that vanishes iff C is left unchanged
+            let c = &traversed_args[0];
+            let delta = Intrinsic::Sub.call(&[c.clone().shift(1), c.clone()])?;
+            Ok(Some(if matches!(b, Builtin::Stays) {
+                let t = delta.t().with_conditioning(Conditioning::Loobean);
+                delta.with_type(t)
+            } else {
+                let t = delta
+                    .t()
+                    .with_raw_magma(RawMagma::Binary)
+                    .with_conditioning(Conditioning::Boolean);
+                Intrinsic::Normalize.call(&[delta])?.with_type(t)
+            }))
+        }
     }
 }
 
@@ -1505,7 +2968,9 @@ fn apply_intrinsic(
         | Intrinsic::Exp
         | Intrinsic::Neg
         | Intrinsic::Inv
-        | Intrinsic::Normalize) => Ok(Some(b.call(&traversed_args)?)),
+        | Intrinsic::Normalize
+        | Intrinsic::Mod
+        | Intrinsic::Div) => Ok(Some(b.call(&traversed_args)?)),
     }
 }
 
@@ -1519,19 +2984,43 @@ fn apply_function(
         FunctionClass::UserDefined(d) => apply_defined(d, &f.handle, args, ctx, settings),
         FunctionClass::Intrinsic(i) => apply_intrinsic(i, args, settings),
         FunctionClass::Builtin(b) => apply_builtin(b, args, ctx, settings),
+        FunctionClass::Custom(c) => {
+            c.arity().validate(args.len())?;
+            let t = c.typing(&args.iter().map(|a| a.t()).collect::<Vec<_>>())?;
+            Ok(Some(c.lower(args)?.with_type(t)))
+        }
         _ => unreachable!(),
     }
 }
 
+/// Attach the source location of `call` to a type error raised when
+/// validating its arguments, if it does not already have one
+fn locate_type_error(err: Error, call: &AstNode) -> Error {
+    if err.chain().count() > 1 {
+        return err;
+    }
+    match err.downcast::<CompileError>() {
+        Result::Ok(CompileError::TypeError(f, expected, found, None)) => {
+            CompileError::TypeError(f, expected, found, Some(make_ast_error(call))).into()
+        }
+        Result::Ok(err) => err.into(),
+        Err(err) => err,
+    }
+}
+
 fn apply(
     f: &Function,
+    call: &AstNode,
     args: &[AstNode],
     ctx: &mut Scope,
     settings: &CompileSettings,
 ) -> Result<Option<Node>> {
     match f.class {
         FunctionClass::Form(sf) => apply_form(sf, args, ctx, settings),
-        FunctionClass::Intrinsic(_) | FunctionClass::UserDefined(_) | FunctionClass::Builtin(_) => {
+        FunctionClass::Intrinsic(_)
+        | FunctionClass::UserDefined(_)
+        | FunctionClass::Builtin(_)
+        | FunctionClass::Custom(_) => {
             let mut traversed_args = vec![];
             for arg in args.iter() {
                 let traversed = reduce(arg, ctx, settings)?;
@@ -1540,7 +3029,11 @@ fn apply(
                 }
             }
 
-            apply_function(f, traversed_args, ctx, settings)
+            match f.class {
+                FunctionClass::UserDefined(_) => apply_function(f, traversed_args, ctx, settings),
+                _ => apply_function(f, traversed_args, ctx, settings)
+                    .map_err(|err| locate_type_error(err, call)),
+            }
         }
         _ => unreachable!(),
     }
@@ -1549,16 +3042,42 @@ fn apply(
 pub fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<Option<Node>> {
     match &e.class {
         Token::Keyword(_) | Token::Domain(_) => Ok(None),
-        Token::Value(x) => Ok(Some(
-            // We want the value to specifically be a BigInt here, as we may
-            // have negative ones, e.g. as shift arguments.
-            Node::from(Expression::Const(Value::big_int(x.clone()))).with_type(
-                if *x >= Zero::zero() && *x <= One::one() {
-                    Type::Scalar(Magma::binary())
+        Token::Value(x) => {
+            let modulus = crate::constants::field_modulus();
+            let x = if x.magnitude() >= modulus.magnitude() {
+                if settings.reduce_constants {
+                    num_traits::Euclid::rem_euclid(x, modulus)
                 } else {
-                    Type::Scalar(Magma::native())
-                },
-            ),
+                    bail!(
+                        "{} does not fit in a field element; use --reduce-constants to reduce it modulo {}",
+                        x.to_string().red().bold(),
+                        modulus
+                    )
+                }
+            } else {
+                x.clone()
+            };
+            Ok(Some(
+                // We want the value to specifically be a BigInt here, as we may
+                // have negative ones, e.g. as shift arguments.
+                Node::from(Expression::Const(Value::big_int(x.clone()))).with_type(
+                    if x >= Zero::zero() && x <= One::one() {
+                        Type::Scalar(Magma::binary())
+                    } else {
+                        Type::Scalar(Magma::native())
+                    },
+                ),
+            ))
+        }
+        Token::Boolean(x) => Ok(Some(
+            Node::from(Expression::Const(Value::big_int(if *x {
+                BigInt::one()
+            } else {
+                BigInt::zero()
+            })))
+            .with_type(Type::Scalar(
+                Magma::binary().with_conditioning(Conditioning::Boolean),
+            )),
         )),
         Token::Symbol(name) => Ok(Some(
             ctx.resolve_symbol(name, true)
@@ -1576,18 +3095,9 @@ pub fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Resul
                     .and_then(|n| n.pure_eval().ok())
                     .and_then(|b| b.to_usize())
                     .ok_or_else(|| anyhow!("{:?} is not a valid index", index))?;
-                // Sanity check access within bounds
-                if domain.contains(i.try_into().unwrap()) {
-                    // Construct indexed handle
-                    let name = handle.as_handle().ith(i.try_into().unwrap()).to_string();
-                    // Resolve it properly this time.
-                    Ok(Some(
-                        ctx.resolve_symbol_with_path(&name, true)
-                            .with_context(|| make_ast_error(e))?,
-                    ))
-                } else {
-                    bail!("tried to access {} at index {}", symbol.pretty().bold(), i)
-                }
+                array_element(&symbol, handle, domain, i, ctx)
+                    .map(Some)
+                    .with_context(|| make_ast_error(e))
             } else {
                 bail!(anyhow!(
                     "{} of type {} is not indexable",
@@ -1604,7 +3114,7 @@ pub fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Resul
                     .resolve_function(verb)
                     .with_context(|| make_ast_error(e))?;
 
-                let r = apply(&func, &args[1..], ctx, settings);
+                let r = apply(&func, e, &args[1..], ctx, settings);
                 match func.class {
                     FunctionClass::UserDefined(_) => {
                         r.map(|o| o.map(|n| n.with_debug(e.debug_info())))
@@ -1630,9 +3140,35 @@ pub fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Resul
                 })?;
                 Ok(None)
             }
+            Kind::Fixpoint(e) => {
+                let n = reduce(e, ctx, settings)?.unwrap();
+                ctx.edit_symbol(name, &|x| {
+                    if let Expression::Column { kind, .. } = x {
+                        *kind = Kind::Fixpoint(Box::new(n.clone()))
+                    }
+                })?;
+                Ok(None)
+            }
+            Kind::Recurrence { exp, init } => {
+                let exp = reduce(exp, ctx, settings)?.unwrap();
+                let init = reduce(init, ctx, settings)?.unwrap();
+                ctx.edit_symbol(name, &|x| {
+                    if let Expression::Column { kind, .. } = x {
+                        *kind = Kind::Recurrence {
+                            exp: Box::new(exp.clone()),
+                            init: Box::new(init.clone()),
+                        }
+                    }
+                })?;
+                Ok(None)
+            }
             _ => Ok(None),
         },
-        Token::DefInterleaving { target, froms } => {
+        Token::DefInterleaving {
+            target,
+            froms,
+            strides,
+        } => {
             let target_handle = if let Expression::Column { handle, .. } =
                 ctx.resolve_symbol(&target.name, true)?.e()
             {
@@ -1676,20 +3212,45 @@ pub fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Resul
                     _ => unreachable!(),
                 }
             }
-            ctx.insert_computation(
-                &target_handle,
+            let computation = if let Some(strides) = strides {
+                if strides.len() != from_handles.len() {
+                    bail!(
+                        "expected {} strides, found {}",
+                        from_handles.len(),
+                        strides.len()
+                    )
+                }
+                let strides = strides
+                    .iter()
+                    .map(|stride| {
+                        reduce(stride, ctx, settings)?
+                            .and_then(|n| n.pure_eval().ok())
+                            .and_then(|b| b.to_usize())
+                            .filter(|s| *s > 0)
+                            .ok_or_else(|| {
+                                anyhow!("{} is not a positive stride", stride.src.white().bold())
+                            })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Computation::InterleavedStrided {
+                    target: target_handle.clone(),
+                    froms: from_handles.clone(),
+                    strides,
+                }
+            } else {
                 Computation::Interleaved {
                     target: target_handle.clone(),
                     froms: from_handles.clone(),
-                },
-            )?;
+                }
+            };
+            ctx.insert_computation(&target_handle, computation)?;
             Ok(None)
         }
         Token::DefColumns(_)
         | Token::DefPerspective { .. }
         | Token::DefConstraint { .. }
         | Token::DefArrayColumn { .. }
-        | Token::DefModule(_)
+        | Token::DefModule { .. }
         | Token::DefAliases(_)
         | Token::DefAlias(..)
         | Token::DefunAlias(..)
@@ -1698,10 +3259,199 @@ pub fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Resul
         | Token::Defpurefun { .. }
         | Token::DefPermutation { .. }
         | Token::DefLookup { .. }
-        | Token::DefInrange(..) => Ok(None),
-        Token::BlockComment(_) | Token::InlineComment(_) => unreachable!(),
+        | Token::DefLookupMultiplicity { .. }
+        | Token::DefInrange(..)
+        | Token::DefColumnsAssignment(_)
+        | Token::DefModuleBase(_)
+        | Token::Include(_) => Ok(None),
+        Token::BlockComment(_) | Token::InlineComment(_) | Token::Conditional { .. } => {
+            unreachable!()
+        }
+    }
+    .map_err(|err| {
+        // a type error already located at this node should not be repeated
+        if err.chain().count() == 1
+            && matches!(
+                err.downcast_ref::<CompileError>(),
+                Some(CompileError::TypeError(.., Some(location))) if *location == make_ast_error(e)
+            )
+        {
+            err
+        } else {
+            err.context(make_ast_error(e))
+        }
+    })
+}
+
+/// Materialize the constant sets forming the table of the lookup `name` as
+/// columns of a dedicated internal module, and return these columns
+fn materialize_constant_table(
+    name: &str,
+    module: &str,
+    sets: &[AstNode],
+    ctx: &mut Scope,
+    settings: &CompileSettings,
+) -> Result<Vec<Node>> {
+    let sets = sets
+        .iter()
+        .map(|set| {
+            if let Token::Domain(d) = &set.class {
+                d.concretize(|n| {
+                    reduce(n, &mut ctx.clone(), settings)
+                        .transpose()
+                        .unwrap()
+                        .and_then(|r| r.pure_eval())
+                        .and_then(|bi| bi.to_isize().ok_or_else(|| anyhow!("{} is not an i64", bi)))
+                })
+                .map(|d| d.iter().map(Value::from).collect::<Vec<_>>())
+            } else {
+                unreachable!()
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if !sets.iter().map(Vec::len).all_equal() {
+        bail!("in {}, constant sets have different sizes", name.red())
+    }
+
+    let mut table_ctx = ctx
+        .internal_module(&format!("#{}-{}", module, name))
+        .public(true);
+    sets.into_iter()
+        .enumerate()
+        .map(|(i, values)| {
+            let column_name = format!("value-{}", i);
+            let target = Handle::new(table_ctx.module(), &column_name);
+            table_ctx.insert_symbol(
+                &column_name,
+                Node::column()
+                    .handle(target.clone())
+                    .kind(Kind::Computed)
+                    .t(Magma::native())
+                    .build(),
+            )?;
+            ctx.insert_computation(
+                &target.clone().into(),
+                Computation::ConstantTable {
+                    target: target.into(),
+                    values,
+                },
+            )?;
+            table_ctx
+                .resolve_symbol(&column_name, true)
+                .map_err(|e| anyhow!(e))
+        })
+        .collect()
+}
+
+/// Declare, in the module of the `including` columns of the lookup `name`,
+/// the computed column counting how many times each of their rows is looked
+/// up by the `included` ones
+fn declare_multiplicity(
+    name: &str,
+    including: &[Node],
+    included: &[Node],
+    ctx: &mut Scope,
+) -> Result<ColumnRef> {
+    let module = including
+        .iter()
+        .flat_map(Node::dependencies)
+        .map(|c| c.as_handle().module.to_owned())
+        .next()
+        .ok_or_else(|| anyhow!("in {}, no column found in the table", name.red()))?;
+    let mut table_ctx = if module.starts_with('#') {
+        ctx.internal_module(&module)
+    } else {
+        ctx.switch_to_module(&module)?
+    };
+
+    let column_name = format!("{}-multiplicity", name);
+    let target: ColumnRef = Handle::new(table_ctx.module(), &column_name).into();
+    table_ctx.insert_symbol(
+        &column_name,
+        Node::column()
+            .handle(target.clone())
+            .kind(Kind::Computed)
+            .t(Magma::native())
+            .build(),
+    )?;
+    ctx.insert_computation(
+        &target,
+        Computation::LookupMultiplicity {
+            target: target.clone(),
+            including: including.to_vec(),
+            included: included.to_vec(),
+        },
+    )?;
+    Ok(target)
+}
+
+/// The name of the column, shared by all the `:no-padding` constraints of a
+/// module, flagging the rows of the module that do not belong to its padding;
+/// it is prefixed like the other auxiliary columns
+const ACTIVE_ROWS: &str = "ACTIVE__";
+
+/// Return the column flagging the non-padding rows of the module of `ctx`,
+/// declaring it on first use.
+///
+/// As the padding is prepended to the trace, this column is constrained to
+/// be binary, to start at 0 on the first row, to never decrease, and to end at
+/// 1 on the last row; so that it may only switch from the padding to the
+/// active rows once.
+fn declare_active_rows(ctx: &mut Scope, settings: &CompileSettings) -> Result<Node> {
+    let name = format!(
+        "{}{}",
+        settings.aux_prefix.as_deref().unwrap_or("__"),
+        ACTIVE_ROWS
+    );
+    let mut module_ctx = ctx.module_scope();
+    if let Result::Ok(active) = module_ctx.resolve_symbol(&name, true) {
+        return Ok(active);
     }
-    .with_context(|| make_ast_error(e))
+
+    let target: ColumnRef = Handle::new(module_ctx.module(), &name).into();
+    let active = Node::column()
+        .handle(target.clone())
+        .kind(Kind::Computed)
+        .t(Magma::binary())
+        .build();
+    module_ctx.insert_symbol(&name, active.clone())?;
+    ctx.insert_computation(
+        &target,
+        Computation::ActiveRows {
+            target: target.clone(),
+        },
+    )?;
+
+    let module = module_ctx.module();
+    let step = Intrinsic::Sub.call(&[active.clone().shift(1), active.clone()])?;
+    for (suffix, domain, expr) in [
+        (
+            "binary",
+            None,
+            Intrinsic::Mul.call(&[
+                active.clone(),
+                Intrinsic::Sub.call(&[active.clone(), Node::one()])?,
+            ])?,
+        ),
+        (
+            "monotone",
+            None,
+            Intrinsic::Mul.call(&[step.clone(), Intrinsic::Sub.call(&[step, Node::one()])?])?,
+        ),
+        ("first", Some(Domain::Set(vec![0])), active.clone()),
+        (
+            "last",
+            Some(Domain::Set(vec![-1])),
+            Intrinsic::Sub.call(&[active.clone(), Node::one()])?,
+        ),
+    ] {
+        ctx.insert_auxiliary_constraint(Constraint::Vanishes {
+            handle: Handle::new(&module, format!("{}-{}", name, suffix)),
+            domain,
+            expr: Box::new(expr),
+        });
+    }
+    Ok(active)
 }
 
 pub(crate) fn reduce_toplevel(
@@ -1715,6 +3465,7 @@ pub(crate) fn reduce_toplevel(
             domain,
             guard,
             perspective,
+            no_padding,
             body,
         } => {
             let handle = Handle::new(ctx.module(), name);
@@ -1724,7 +3475,9 @@ pub(crate) fn reduce_toplevel(
             } else {
                 ctx.clone()
             };
-            let body = reduce(body, &mut ctx, settings)?.unwrap_or_else(|| Expression::Void.into());
+            let mut body =
+                reduce(body, &mut ctx, settings)?.unwrap_or_else(|| Expression::Void.into());
+            ensure_arithmetizable([&mut body], &handle)?;
             let body = if let Some(guard) = guard {
                 let guard_expr = reduce(guard, &mut ctx, settings)?
                     .with_context(|| anyhow!("guard `{:?}` is empty", guard))?;
@@ -1758,6 +3511,16 @@ pub(crate) fn reduce_toplevel(
             } else {
                 body
             };
+            let body = if *no_padding {
+                let active = declare_active_rows(&mut ctx, settings)?;
+                let body_type = body.t();
+                Intrinsic::Mul
+                    .unchecked_call(&[active, body])
+                    .with_context(|| anyhow!("constraint {}", name))?
+                    .with_type(body_type)
+            } else {
+                body
+            };
             if body.t() == Type::Void {
                 warn!(
                     "constraint {} should be of type {}, found {}",
@@ -1800,17 +3563,45 @@ pub(crate) fn reduce_toplevel(
             name,
             including: parent,
             included: child,
+        }
+        | Token::DefLookupMultiplicity {
+            name,
+            including: parent,
+            included: child,
         } => {
+            let module = ctx.module();
             *ctx = ctx.derive(&format!("lookup-{}", name))?.global(true);
             let handle = Handle::new(ctx.module(), name);
-            let parents = parent
-                .iter()
-                .map(|e| reduce(e, ctx, settings).map(Option::unwrap))
-                .collect::<Result<Vec<_>>>()?;
-            let children = child
+
+            // A constant set can only ever be a table, i.e. the including side
+            // of the lookup
+            let is_set = |e: &AstNode| matches!(e.class, Token::Domain(_));
+            if child.iter().any(is_set) {
+                bail!(
+                    "in {}, constant sets may only be used as the including side of a lookup",
+                    name.red()
+                )
+            }
+
+            let mut parents = if parent.iter().any(is_set) {
+                if !parent.iter().all(is_set) {
+                    bail!(
+                        "in {}, constant sets can not be mixed with columns",
+                        name.red()
+                    )
+                }
+                materialize_constant_table(name, &module, parent, ctx, settings)?
+            } else {
+                parent
+                    .iter()
+                    .map(|e| reduce(e, ctx, settings).map(Option::unwrap))
+                    .collect::<Result<Vec<_>>>()?
+            };
+            let mut children = child
                 .iter()
                 .map(|e| reduce(e, ctx, settings).map(Option::unwrap))
                 .collect::<Result<Vec<_>>>()?;
+            ensure_arithmetizable(parents.iter_mut().chain(children.iter_mut()), &handle)?;
             if parents.len() != children.len() {
                 bail!(
                     "in {}, parents and children have different lengths: {} and {}",
@@ -1818,6 +3609,14 @@ pub(crate) fn reduce_toplevel(
                     parents.len(),
                     children.len()
                 )
+            } else if matches!(e.class, Token::DefLookupMultiplicity { .. }) {
+                let multiplicity = declare_multiplicity(name, &parents, &children, ctx)?;
+                Ok(Some(Constraint::LookupMultiplicity {
+                    handle,
+                    including: parents,
+                    included: children,
+                    multiplicity,
+                }))
             } else {
                 Ok(Some(Constraint::Lookup {
                     handle,
@@ -1828,11 +3627,49 @@ pub(crate) fn reduce_toplevel(
         }
         Token::DefInrange(e, range) => {
             let handle = Handle::new(ctx.module(), format!("{}_lt_{}", e, range));
-            Ok(Some(Constraint::InRange {
-                handle,
-                exp: reduce(e, ctx, settings)?.unwrap(),
-                max: Value::from(*range),
-            }))
+            let mut exp = reduce(e, ctx, settings)?.unwrap();
+            let mut max =
+                reduce(range, ctx, settings)?.ok_or_else(|| anyhow!("missing maximal value"))?;
+            ensure_arithmetizable([&mut exp, &mut max], &handle)?;
+            match max.pure_eval() {
+                Result::Ok(max) if max.sign() != Sign::Plus => bail!(
+                    "the bound {} of {} must be positive",
+                    range.src.white().bold(),
+                    e.src.white().bold()
+                ),
+                Result::Ok(max) => Ok(Some(Constraint::InRange {
+                    handle,
+                    exp,
+                    max: max
+                        .to_u64()
+                        .map(Value::from)
+                        .unwrap_or_else(|| Value::big_int(max)),
+                })),
+                // A bound varying along the rows is enforced by range-checking
+                // both the expression and its distance to the bound, on a
+                // width large enough for both of them
+                Err(_) => {
+                    let bits = exp.bit_size().max(max.bit_size());
+                    if bits >= crate::constants::FIELD_BITSIZE - 1 {
+                        bail!(
+                            "the dynamic bound {} of {} must be of a type narrower than the field",
+                            range.src.white().bold(),
+                            e.src.white().bold()
+                        )
+                    }
+                    let width = Value::big_int(BigInt::one() << bits);
+                    ctx.insert_auxiliary_constraint(Constraint::InRange {
+                        handle: Handle::new(&handle.module, format!("{}-width", handle.name)),
+                        exp: exp.clone(),
+                        max: width.clone(),
+                    });
+                    Ok(Some(Constraint::InRange {
+                        handle,
+                        exp: Intrinsic::Sub.call(&[max, exp, Node::from_isize(1)])?,
+                        max: width,
+                    }))
+                }
+            }
         }
         Token::DefColumns(columns) => {
             for c in columns {
@@ -1865,19 +3702,31 @@ pub(crate) fn reduce_toplevel(
             }
             Ok(None)
         }
-        Token::DefModule(name) => {
+        Token::DefModule { name, .. } => {
             *ctx = ctx.switch_to_module(name)?;
             Ok(None)
         }
-        Token::Value(_) | Token::Symbol(_) | Token::List(_) | Token::Domain(_) => {
+        Token::Value(_)
+        | Token::Boolean(_)
+        | Token::Symbol(_)
+        | Token::List(_)
+        | Token::Domain(_) => {
             bail!("unexpected top-level form: {:?}", e)
         }
         Token::Defun { .. }
         | Token::Defpurefun { .. }
         | Token::DefAliases(_)
         | Token::DefunAlias(..)
-        | Token::DefConsts(..) => Ok(None),
-        Token::DefPermutation { from, to, signs } => {
+        | Token::DefConsts(..)
+        | Token::DefColumnsAssignment(_)
+        | Token::DefModuleBase(_)
+        | Token::Include(_) => Ok(None),
+        Token::DefPermutation {
+            from,
+            to,
+            signs,
+            endianness,
+        } => {
             let froms: Vec<ColumnRef> = from
                 .iter()
                 .map(|from| {
@@ -1919,6 +3768,7 @@ pub(crate) fn reduce_toplevel(
                     froms: froms.clone(),
                     tos: tos.clone(),
                     signs: signs.clone(),
+                    endianness: *endianness,
                 },
             )?;
             // Determine suitably unique name for the permutation
@@ -1947,11 +3797,21 @@ pub fn make_ast_error(exp: &AstNode) -> String {
     errors::parser::make_src_error(&exp.src, exp.lc)
 }
 
-pub fn pass(ast: &Ast, ctx: Scope, settings: &CompileSettings) -> Vec<Result<Constraint>> {
+/// Reduce the top-level forms of `ast` to constraints, paired with the
+/// line/column of the form they originate from
+pub fn pass(
+    ast: &Ast,
+    ctx: Scope,
+    settings: &CompileSettings,
+) -> Vec<Result<(Constraint, (usize, usize))>> {
     let mut module = ctx;
 
     ast.exprs
         .iter()
-        .filter_map(|exp| reduce_toplevel(exp, &mut module, settings).transpose())
+        .filter_map(|exp| {
+            reduce_toplevel(exp, &mut module, settings)
+                .transpose()
+                .map(|c| c.map(|c| (c, exp.lc)))
+        })
         .collect()
 }
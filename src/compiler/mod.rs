@@ -1,16 +1,16 @@
 use crate::{
-    column::{ColumnSet, Computation},
+    column::{ColumnSet, Computation, Value},
     structs::Handle,
 };
 use anyhow::*;
 use log::*;
 use std::collections::HashMap;
 
+use crate::utils::Colorize;
 pub use common::*;
-pub use generator::{Constraint, ConstraintSet, EvalSettings};
+pub use generator::{Constraint, ConstraintSet, EvalSettings, Wrap};
 pub use node::{ColumnRef, Expression, Node};
 use num_bigint::BigInt;
-use owo_colors::OwoColorize;
 pub use tables::ComputationTable;
 pub use types::*;
 
@@ -29,38 +29,91 @@ pub(crate) const MAIN_MODULE: &str = "<prelude>";
 
 pub struct CompileSettings {
     pub debug: bool,
+    /// whether to reduce modulo the field order the constants that do not
+    /// fit in a field element, instead of rejecting them
+    pub reduce_constants: bool,
+    /// whether to only keep the constraints explicitly written in the
+    /// sources, leaving out the ones implied by the columns types and the
+    /// sorting permutations
+    pub no_auto_constraints: bool,
+    /// whether to warn about the constants that are defined but never
+    /// referenced
+    pub warn_unreferenced_constant: bool,
+    /// the prefix of the auxiliary columns created while compiling, instead of
+    /// the default one
+    pub aux_prefix: Option<String>,
 }
 
 pub fn make<S1: AsRef<str>, S2: AsRef<str>>(
     sources: &[(S1, S2)],
     settings: &CompileSettings,
 ) -> Result<(Vec<Ast>, ConstraintSet)> {
-    let (mut ctx, asts) = parser::parse(sources, settings)?;
+    make_in(tables::Scope::new(), sources, settings)
+}
+
+/// Compile the sources within `ctx`, e.g. after having registered custom
+/// builtins in it
+pub fn make_in<S1: AsRef<str>, S2: AsRef<str>>(
+    ctx: tables::Scope,
+    sources: &[(S1, S2)],
+    settings: &CompileSettings,
+) -> Result<(Vec<Ast>, ConstraintSet)> {
+    let (mut ctx, asts) = parser::parse(ctx, sources, settings)?;
 
     //
     // Reduce the AST and create the constraints
     //
     let mut constraints = vec![];
+    let mut locations: HashMap<String, (&str, usize)> = Default::default();
     for (name, ast) in asts.iter() {
         for constraint in generator::pass(ast, ctx.clone(), settings) {
-            constraints.push(
-                constraint.with_context(|| anyhow!("compiling {}", name.bright_white().bold()))?,
-            );
+            let (constraint, lc) =
+                constraint.with_context(|| anyhow!("compiling {}", name.bright_white().bold()))?;
+            if let Some((other_name, other_line)) =
+                locations.insert(constraint.name(), (name.as_str(), lc.0))
+            {
+                bail!(
+                    "constraint {} defined twice: at {}:{} and {}:{}",
+                    constraint.name().red(),
+                    other_name.bright_white().bold(),
+                    other_line.to_string().blue(),
+                    name.bright_white().bold(),
+                    lc.0.to_string().blue(),
+                );
+            }
+            constraints.push(constraint);
         }
     }
+    constraints.append(&mut ctx.tree.borrow_mut().metadata_mut().auxiliary_constraints);
     // Sort by decreasing complexity for more efficient multi-threaded computation
     constraints.sort_by_cached_key(|x| -(x.size() as isize));
 
     let mut columns: ColumnSet = Default::default();
     let mut constants: HashMap<Handle, BigInt> = Default::default();
+    let mut aliases: HashMap<Handle, Handle> = Default::default();
     let mut computations = ctx.computations();
+    let tags = column_tags(&asts);
+    let mut array_tags = Vec::new();
+    let mut warnings = Vec::new();
 
     ctx.visit_mut::<()>(&mut |handle, symbol| {
         match symbol {
-            Symbol::Alias(_) => {}
+            Symbol::Alias(target) => {
+                aliases.insert(handle.clone(), Handle::new(&handle.module, target));
+            }
             Symbol::Final(symbol, used) => {
                 if !*used {
-                    warn!("{}", CompileError::NotUsed(handle.clone()));
+                    let warning = if matches!(symbol.e(), Expression::Const(_)) {
+                        settings
+                            .warn_unreferenced_constant
+                            .then(|| CompileError::UnreferencedConstant(handle.clone()))
+                    } else {
+                        Some(CompileError::NotUsed(handle.clone()))
+                    };
+                    if let Some(warning) = warning {
+                        warn!("{}", warning);
+                        warnings.push(warning.to_string());
+                    }
                 }
 
                 match symbol.e() {
@@ -82,32 +135,97 @@ pub fn make<S1: AsRef<str>, S2: AsRef<str>>(
                             .must_prove(*must_prove)
                             .used(*used)
                             .base(*base)
+                            .and_tags(tags.get(handle.as_handle()).cloned())
                             .build();
                         let id = columns.insert_column(column)?;
                         match k {
                             Kind::Commitment | Kind::Computed => (),
-                            Kind::Expression(e) => {
+                            Kind::Expression(e) | Kind::Fixpoint(e) => {
                                 computations
                                     .insert(
                                         &id,
-                                        Computation::Composite {
+                                        if matches!(k, Kind::Fixpoint(_)) {
+                                            Computation::Fixpoint {
+                                                target: id.clone(),
+                                                exp: *e.clone(),
+                                            }
+                                        } else {
+                                            Computation::Composite {
+                                                target: id.clone(),
+                                                exp: *e.clone(),
+                                            }
+                                        },
+                                    )
+                                    .map(|_| ())?;
+                                // integer operations can not be arithmetized,
+                                // so that these columns must be constrained by
+                                // hand
+                                if e.integer_operation().is_none() {
+                                    constraints.push(Constraint::Vanishes {
+                                        handle: Handle::new(
+                                            &handle.as_handle().module,
+                                            format!("prove-{}", handle.as_handle().name),
+                                        ),
+                                        domain: None,
+                                        expr: Box::new(
+                                            Intrinsic::Sub
+                                                .call(&[
+                                                    Node::column().handle(id).build(),
+                                                    *e.clone(),
+                                                ])
+                                                .unwrap(),
+                                        ),
+                                    })
+                                }
+                            }
+                            Kind::Recurrence { exp, init } => {
+                                for leaf in exp.leaves() {
+                                    if let Expression::Column {
+                                        handle: h, shift, ..
+                                    } = leaf.e()
+                                    {
+                                        if h == handle && *shift != -1 {
+                                            bail!(
+                                                "the recurrence defining {} may only refer to its previous row, found a shift of {}",
+                                                handle,
+                                                shift
+                                            );
+                                        }
+                                    }
+                                }
+                                let init = init.pure_eval().with_context(|| {
+                                    anyhow!(
+                                        "the initial value of {} must be a constant",
+                                        handle
+                                    )
+                                })?;
+                                computations
+                                    .insert(
+                                        &id,
+                                        Computation::Recurrence {
                                             target: id.clone(),
-                                            exp: *e.clone(),
+                                            exp: *exp.clone(),
+                                            init: Value::big_int(init),
                                         },
                                     )
                                     .map(|_| ())?;
-                                constraints.push(Constraint::Vanishes {
-                                    handle: Handle::new(
-                                        &handle.as_handle().module,
-                                        format!("prove-{}", handle.as_handle().name),
-                                    ),
-                                    domain: None,
-                                    expr: Box::new(
-                                        Intrinsic::Sub
-                                            .call(&[Node::column().handle(id).build(), *e.clone()])
-                                            .unwrap(),
-                                    ),
-                                })
+                                if exp.integer_operation().is_none() {
+                                    constraints.push(Constraint::Vanishes {
+                                        handle: Handle::new(
+                                            &handle.as_handle().module,
+                                            format!("prove-{}", handle.as_handle().name),
+                                        ),
+                                        domain: None,
+                                        expr: Box::new(
+                                            Intrinsic::Sub
+                                                .call(&[
+                                                    Node::column().handle(id).build(),
+                                                    *exp.clone(),
+                                                ])
+                                                .unwrap(),
+                                        ),
+                                    })
+                                }
                             }
                         }
                     }
@@ -130,12 +248,24 @@ pub fn make<S1: AsRef<str>, S2: AsRef<str>>(
                     Expression::Const(ref x) => {
                         constants.insert(handle, x.clone().into());
                     }
+                    Expression::ArrayColumn { handle, domain, .. } => {
+                        if let Some(these) = tags.get(handle.as_handle()) {
+                            for i in domain.iter() {
+                                array_tags.push((handle.as_handle().ith(i as usize), these.clone()));
+                            }
+                        }
+                    }
                     _ => {}
                 }
             }
         }
         Ok(())
     })?;
+    for (handle, these) in array_tags {
+        if let Some(column) = columns.get_col_mut(&handle.into()) {
+            column.tags = these;
+        }
+    }
 
     let perspectives = ctx
         .tree
@@ -156,7 +286,95 @@ pub fn make<S1: AsRef<str>, S2: AsRef<str>>(
         })
         .collect::<HashMap<_, _>>();
 
+    columns.heights = module_heights(&asts)?;
     let mut cs = ConstraintSet::new(columns, constraints, constants, computations, perspectives)?;
+    cs.columns_assignment = ctx.tree.borrow().metadata().columns_assignment.clone();
+    cs.warnings = warnings;
+    cs.constant_aliases = aliases
+        .keys()
+        .filter_map(|from| {
+            let mut target = from;
+            while let Some(next) = aliases.get(target) {
+                target = next;
+            }
+            cs.constants
+                .contains_key(target)
+                .then(|| (from.clone(), target.clone()))
+        })
+        .collect();
     crate::transformer::precompute(&mut cs);
     Ok((asts.into_iter().map(|x| x.1).collect(), cs))
 }
+
+/// Collect the tags attached to the columns in their definitions
+fn column_tags(asts: &[(String, Ast)]) -> HashMap<Handle, Vec<String>> {
+    fn collect(
+        e: &parser::AstNode,
+        module: &str,
+        perspective: Option<&str>,
+        tags: &mut HashMap<Handle, Vec<String>>,
+    ) {
+        match &e.class {
+            parser::Token::DefColumns(columns) => {
+                for c in columns {
+                    collect(c, module, perspective, tags);
+                }
+            }
+            parser::Token::DefPerspective { name, columns, .. } => {
+                for c in columns {
+                    collect(c, module, Some(name.as_str()), tags);
+                }
+            }
+            // the tags of an array are attached to each of its elements once
+            // its domain is known
+            parser::Token::DefColumn {
+                name, tags: these, ..
+            }
+            | parser::Token::DefArrayColumn {
+                name, tags: these, ..
+            } if !these.is_empty() => {
+                tags.insert(
+                    Handle::maybe_with_perspective(module, name, perspective.map(str::to_owned)),
+                    these.clone(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let mut tags = HashMap::new();
+    for (_, ast) in asts.iter() {
+        let mut module = MAIN_MODULE.to_owned();
+        for e in ast.exprs.iter() {
+            if let parser::Token::DefModule { name, .. } = &e.class {
+                module = name.to_owned();
+            }
+            collect(e, &module, None, &mut tags);
+        }
+    }
+    tags
+}
+
+/// Collect the statically known heights declared for the modules, ensuring
+/// that all the declarations of a module agree
+fn module_heights(asts: &[(String, Ast)]) -> Result<HashMap<String, usize>> {
+    let mut heights: HashMap<String, usize> = Default::default();
+    for e in asts.iter().flat_map(|(_, ast)| ast.exprs.iter()) {
+        if let parser::Token::DefModule {
+            name,
+            height: Some(height),
+        } = &e.class
+        {
+            match heights.insert(name.to_owned(), *height) {
+                Some(previous) if previous != *height => bail!(
+                    "module {} declared with conflicting heights {} and {}",
+                    name.blue(),
+                    previous.to_string().red(),
+                    height.to_string().red(),
+                ),
+                _ => {}
+            }
+        }
+    }
+    Ok(heights)
+}
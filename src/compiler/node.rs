@@ -1,9 +1,10 @@
 use crate::column::{ColumnID, Value};
+use crate::utils::Colorize;
 use anyhow::*;
 use cached::Cached;
 use num_bigint::BigInt;
-use num_traits::{One, ToPrimitive, Zero};
-use owo_colors::{colored::Color, OwoColorize};
+use num_traits::{Euclid, One, ToPrimitive, Zero};
+use owo_colors::colored::Color;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::write;
@@ -16,7 +17,8 @@ use crate::compiler::codetyper::Tty;
 use crate::pretty::{Base, Pretty, COLORS};
 use crate::structs::Handle;
 
-use super::{ConstraintSet, Domain, EvalSettings, Intrinsic, Kind, Magma, Type};
+use super::{ConstraintSet, Domain, EvalSettings, Intrinsic, Kind, Magma, Type, Wrap};
+use crate::errors::CompileError;
 
 #[derive(Clone, Debug, Eq)]
 pub struct ColumnRef {
@@ -85,6 +87,9 @@ impl ColumnRef {
     pub fn is_handle(&self) -> bool {
         self.h.is_some()
     }
+    pub(crate) fn handle_mut(&mut self) -> Option<&mut Handle> {
+        self.h.as_mut()
+    }
     pub fn set_id(&mut self, i: ColumnID) {
         if let Some(id) = self.id {
             if id != i {
@@ -214,6 +219,10 @@ pub enum Expression {
         padding_value: Option<i64>,
         length_multiplier: Option<usize>,
         base: Base,
+        /// if set, shifted accesses wrap around the rows imported from the
+        /// trace, whatever the [`EvalSettings`] used to evaluate the expression
+        #[serde(default)]
+        wrap: bool,
     },
     ArrayColumn {
         handle: ColumnRef,
@@ -229,6 +238,60 @@ pub enum Expression {
     List(Vec<Node>),
     Void,
 }
+impl Expression {
+    /// Structural equality, ignoring the types and debug information carried
+    /// by the nodes of both expressions.
+    pub fn struct_eq(&self, other: &Expression) -> bool {
+        fn all_struct_eq(xs: &[Node], ys: &[Node]) -> bool {
+            xs.len() == ys.len()
+                && xs
+                    .iter()
+                    .zip(ys.iter())
+                    .all(|(x, y)| x.e().struct_eq(y.e()))
+        }
+
+        match (self, other) {
+            (
+                Expression::Funcall { func: f1, args: a1 },
+                Expression::Funcall { func: f2, args: a2 },
+            ) => f1 == f2 && all_struct_eq(a1, a2),
+            (Expression::Const(x), Expression::Const(y)) => x == y,
+            (
+                Expression::Column {
+                    handle: h1,
+                    shift: s1,
+                    wrap: w1,
+                    ..
+                },
+                Expression::Column {
+                    handle: h2,
+                    shift: s2,
+                    wrap: w2,
+                    ..
+                },
+            ) => h1 == h2 && s1 == s2 && w1 == w2,
+            (
+                Expression::ExoColumn {
+                    handle: h1,
+                    shift: s1,
+                    ..
+                },
+                Expression::ExoColumn {
+                    handle: h2,
+                    shift: s2,
+                    ..
+                },
+            ) => h1 == h2 && s1 == s2,
+            (
+                Expression::ArrayColumn { handle: h1, .. },
+                Expression::ArrayColumn { handle: h2, .. },
+            ) => h1 == h2,
+            (Expression::List(xs), Expression::List(ys)) => all_struct_eq(xs, ys),
+            (Expression::Void, Expression::Void) => true,
+            _ => false,
+        }
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Node {
@@ -331,6 +394,7 @@ impl Node {
                     padding_value,
                     length_multiplier,
                     base: base.unwrap_or_else(|| t.unwrap_or(Magma::native()).into()),
+                    wrap: false,
                 },
                 _t: Some(Type::Column(t.unwrap_or(Magma::native()))),
                 dbg: None,
@@ -375,6 +439,33 @@ impl Node {
         };
         self
     }
+    /// Make all the column accesses in `self` wrap around the rows imported
+    /// from the trace, so that e.g. a shift of -1 on the first of them reads
+    /// the last one rather than the padding before it. This
+    /// holds even when computing columns, which is otherwise done with
+    /// `EvalSettings { wrap: false }`, i.e. reading the padding instead.
+    pub fn wrap_around(mut self) -> Self {
+        match self.e_mut() {
+            Expression::Funcall { args, .. } => {
+                for a in args.iter_mut() {
+                    *a = a.clone().wrap_around();
+                }
+            }
+            Expression::Column { wrap, .. } => {
+                *wrap = true;
+            }
+            Expression::List(ls) => {
+                for l in ls.iter_mut() {
+                    *l = l.clone().wrap_around();
+                }
+            }
+            Expression::ExoColumn { .. }
+            | Expression::ArrayColumn { .. }
+            | Expression::Const(_)
+            | Expression::Void => {}
+        };
+        self
+    }
     pub fn one() -> Node {
         Self::from_expr(Expression::Const(Value::one()))
     }
@@ -523,6 +614,41 @@ impl Node {
         }
     }
 
+    /// Compute the polynomial degree of the AST rooted at `self`, counting
+    /// inverses and normalizations as the single column they are expanded to
+    pub fn degree(&self) -> usize {
+        match self.e() {
+            Expression::Funcall { func, args } => match func {
+                Intrinsic::Add
+                | Intrinsic::Sub
+                | Intrinsic::VectorAdd
+                | Intrinsic::VectorSub
+                | Intrinsic::Begin => args.iter().map(Node::degree).max().unwrap_or(0),
+                Intrinsic::Mul | Intrinsic::VectorMul => args.iter().map(Node::degree).sum(),
+                Intrinsic::Neg => args[0].degree(),
+                Intrinsic::Exp => {
+                    args[0].degree()
+                        * args[1]
+                            .pure_eval()
+                            .ok()
+                            .and_then(|e| e.to_usize())
+                            .unwrap_or(1)
+                }
+                Intrinsic::Inv | Intrinsic::Normalize | Intrinsic::Mod | Intrinsic::Div => 1,
+                // (if-zero c a b) expands to a·(1 - c·c⁻¹) + b·c·c⁻¹
+                Intrinsic::IfZero | Intrinsic::IfNotZero => {
+                    args[0].degree() + 1 + args[1..].iter().map(Node::degree).max().unwrap_or(0)
+                }
+            },
+            Expression::Const(..) => 0,
+            Expression::Column { .. } => 1,
+            Expression::ExoColumn { .. } => 1,
+            Expression::ArrayColumn { .. } => 1,
+            Expression::List(xs) => xs.iter().map(Node::degree).max().unwrap_or(0),
+            Expression::Void => 0,
+        }
+    }
+
     pub fn bit_size(&self) -> usize {
         self.t().m().bit_size()
     }
@@ -572,37 +698,37 @@ impl Node {
         }
     }
 
+    /// The shifts applied to the columns in the AST rooted at `self`
+    fn shifts(&self) -> impl Iterator<Item = isize> {
+        self.leaves().into_iter().filter_map(|n| match n.e() {
+            Expression::Column { shift, .. } | Expression::ExoColumn { shift, .. } => {
+                Some(*shift as isize)
+            }
+            Expression::ArrayColumn { .. } => unreachable!(),
+            _ => None,
+        })
+    }
+
+    /// The smallest shift applied to a column in the AST rooted at `self`, if
+    /// it features any column
+    pub fn min_shift(&self) -> Option<isize> {
+        self.shifts().min()
+    }
+
+    /// The largest shift applied to a column in the AST rooted at `self`, if
+    /// it features any column
+    pub fn max_shift(&self) -> Option<isize> {
+        self.shifts().max()
+    }
+
     /// Compute the maximum past (negative) shift coefficient in the AST rooted at `self`
     pub fn past_spill(&self) -> isize {
-        self.leaves()
-            .iter()
-            .filter_map(|n| match n.e() {
-                Expression::Column { shift, .. } | Expression::ExoColumn { shift, .. } => {
-                    Some(*shift as isize)
-                }
-                Expression::ArrayColumn { .. } => unreachable!(),
-                _ => None,
-            })
-            .filter(|x| *x < 0)
-            .min()
-            .unwrap_or(0)
+        self.min_shift().unwrap_or(0).min(0)
     }
 
     /// Compute the maximum future (positive) shift coefficient in the AST rooted at `self`
     pub fn future_spill(&self) -> isize {
-        self.leaves()
-            .iter()
-            .filter_map(|n| match n.e() {
-                Expression::Column { shift, .. } | Expression::ExoColumn { shift, .. } => {
-                    Some(*shift as isize)
-                }
-                Expression::ArrayColumn { .. } => unreachable!(),
-                _ => None,
-            })
-            .filter(|x| *x > 0)
-            .max()
-            .unwrap_or(0)
-            .max(0)
+        self.max_shift().unwrap_or(0).max(0)
     }
 
     // TODO: replace with a generic map()
@@ -612,7 +738,12 @@ impl Node {
                 args.iter_mut().for_each(|e| e.add_id_to_handles(set_id))
             }
 
-            Expression::Column { handle, .. } => set_id(handle),
+            Expression::Column { handle, kind, .. } => {
+                set_id(handle);
+                if let Kind::Expression(e) = kind {
+                    e.add_id_to_handles(set_id)
+                }
+            }
             Expression::ExoColumn { handle, .. } => set_id(handle),
             Expression::List(xs) => xs.iter_mut().for_each(|x| x.add_id_to_handles(set_id)),
 
@@ -620,6 +751,33 @@ impl Node {
         }
     }
 
+    /// Return the first integer operation -- i.e. modulo or division -- in the
+    /// AST rooted at this `Node`, as these can be computed but not arithmetized
+    pub fn integer_operation(&self) -> Option<Intrinsic> {
+        match self.e() {
+            Expression::Funcall {
+                func: f @ (Intrinsic::Mod | Intrinsic::Div),
+                ..
+            } => Some(*f),
+            Expression::Funcall { args, .. } | Expression::List(args) => {
+                args.iter().find_map(Node::integer_operation)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a column access in the AST rooted at this `Node` wraps around
+    /// the rows of its trace, which most of the backends can not express
+    pub fn wraps(&self) -> bool {
+        match self.e() {
+            Expression::Column { wrap, .. } => *wrap,
+            Expression::Funcall { args, .. } | Expression::List(args) => {
+                args.iter().any(Node::wraps)
+            }
+            _ => false,
+        }
+    }
+
     /// Return all the leaves of the AST rooted at this `Node`
     pub fn leaves(&self) -> Vec<Node> {
         fn _flatten(e: &Node, ax: &mut Vec<Node>) {
@@ -700,6 +858,24 @@ impl Node {
                     })
                 }
                 Intrinsic::Neg => Ok(-args[0].pure_eval()?),
+                Intrinsic::Mod => {
+                    let m = args[1].pure_eval()?;
+                    if m.is_zero() {
+                        bail!("{} reduced modulo zero", args[0].to_string().red())
+                    }
+                    Ok(args[0].pure_eval()?.rem_euclid(&m))
+                }
+                Intrinsic::Div => {
+                    let x = args[0].pure_eval()?;
+                    let d = args[1].pure_eval()?;
+                    if d.is_zero() || !(&x % &d).is_zero() {
+                        bail!(CompileError::InexactDivision(
+                            args[0].to_string(),
+                            args[1].to_string()
+                        ))
+                    }
+                    Ok(x / d)
+                }
                 Intrinsic::Exp => {
                     let args = args
                         .iter()
@@ -716,7 +892,7 @@ impl Node {
         }
     }
 
-    pub fn eval<F: Fn(&ColumnRef, isize, bool) -> Option<Value>>(
+    pub fn eval<F: Fn(&ColumnRef, isize, Wrap) -> Option<Value>>(
         &self,
         i: isize,
         get: F,
@@ -726,7 +902,7 @@ impl Node {
         self.eval_fold(i, &get, cache, settings, &mut |_, _| {})
     }
 
-    pub fn eval_fold<F: Fn(&ColumnRef, isize, bool) -> Option<Value>>(
+    pub fn eval_fold<F: Fn(&ColumnRef, isize, Wrap) -> Option<Value>>(
         &self,
         i: isize,
         get: &F,
@@ -783,12 +959,8 @@ impl Node {
                 }
                 Intrinsic::Exp => {
                     let mantissa = args[0].eval_fold(i, get, cache, settings, f)?;
-                    let mut ax = mantissa.clone();
-                    let exp = args[1].pure_eval().unwrap().to_usize().unwrap();
-                    for _ in 1..exp {
-                        ax.mul_assign(&mantissa);
-                    }
-                    Some(ax)
+                    let exp = args[1].pure_eval().ok()?;
+                    Some(mantissa.pow(&exp))
                 }
                 Intrinsic::Neg => args[0].eval_fold(i, get, cache, settings, f).map(|mut x| {
                     x.negate();
@@ -809,6 +981,28 @@ impl Node {
                 Intrinsic::Normalize => args[0]
                     .eval_fold(i, get, cache, settings, f)
                     .map(|x| x.normalize()),
+                Intrinsic::Mod => {
+                    let x = args[0].eval_fold(i, get, cache, settings, f)?;
+                    let m = args[1].eval_fold(i, get, cache, settings, f)?;
+                    match x.checked_rem(&m) {
+                        Result::Ok(r) => Some(r),
+                        Err(e) => {
+                            log::error!("{}", e);
+                            None
+                        }
+                    }
+                }
+                Intrinsic::Div => {
+                    let x = args[0].eval_fold(i, get, cache, settings, f)?;
+                    let d = args[1].eval_fold(i, get, cache, settings, f)?;
+                    match x.checked_div(&d) {
+                        Result::Ok(r) => Some(r),
+                        Err(e) => {
+                            log::error!("{}", e);
+                            None
+                        }
+                    }
+                }
                 Intrinsic::Begin => unreachable!(),
                 Intrinsic::IfZero => {
                     if args[0].eval_fold(i, get, cache, settings, f)?.is_zero() {
@@ -830,11 +1024,22 @@ impl Node {
                 }
             },
             Expression::Const(v) => Some(v.clone()),
-            Expression::Column { handle, shift, .. } => {
-                get(handle, i + (*shift as isize), settings.wrap)
-            }
+            Expression::Column {
+                handle,
+                shift,
+                wrap,
+                ..
+            } => get(
+                handle,
+                i + (*shift as isize),
+                if *wrap {
+                    Wrap::Trace
+                } else {
+                    settings.wrap.into()
+                },
+            ),
             Expression::ExoColumn { handle, shift, .. } => {
-                get(handle, i + (*shift as isize), settings.wrap)
+                get(handle, i + (*shift as isize), settings.wrap.into())
             }
             Expression::List(xs) => xs
                 .iter()
@@ -1126,7 +1331,7 @@ impl Display for Node {
             | Expression::ExoColumn { handle, shift, .. } => {
                 write!(
                     f,
-                    "{}{}",
+                    "{}{}{}",
                     handle.to_string_short(),
                     if *shift > 0 {
                         format!("₊{}", crate::pretty::subscript(&shift.to_string()))
@@ -1134,6 +1339,11 @@ impl Display for Node {
                         crate::pretty::subscript(&shift.to_string())
                     } else {
                         Default::default()
+                    },
+                    if matches!(self.e(), Expression::Column { wrap: true, .. }) {
+                        "↻"
+                    } else {
+                        ""
                     }
                 )
             }
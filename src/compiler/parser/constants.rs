@@ -1,15 +1,20 @@
 use anyhow::*;
+use num_traits::Zero;
 
 use crate::compiler::{generator::make_ast_error, tables::Scope, CompileSettings, Node};
+use crate::errors::CompileError;
 
 use super::{Ast, AstNode, Token};
 
 fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()> {
     match &e.class {
-        Token::DefModule(name) => {
+        Token::DefModule { name, .. } => {
             *ctx = ctx.switch_to_module(name)?.public(true);
             Ok(())
         }
+        // Set along the constants, so that the base of a module applies to
+        // all its columns, wherever it is declared
+        Token::DefModuleBase(base) => ctx.set_module_base(*base),
 
         Token::DefConsts(cs) => {
             for (name, exp) in cs.iter() {
@@ -22,11 +27,18 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
                     }
                     _ => crate::compiler::generator::reduce(exp, ctx, settings)?.unwrap(),
                 };
-                ctx.insert_constant(
-                    name,
-                    value.pure_eval().with_context(|| make_ast_error(exp))?,
-                    true,
-                )?;
+                // The value may be any expression, as long as it can be
+                // folded at compile-time
+                let folded = value
+                    .pure_eval()
+                    .with_context(|| {
+                        anyhow!(CompileError::NotConstant(
+                            format!("defconst {}", name),
+                            exp.src.clone()
+                        ))
+                    })
+                    .with_context(|| make_ast_error(exp))?;
+                ctx.insert_constant(name, folded, value.t().c(), true)?;
             }
             Ok(())
         }
@@ -34,14 +46,52 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
     }
 }
 
-/// The `Definitions` pass skim through an [`Ast`] and fill the
-/// [`SymbolTableTree`] with all the required elements (columns, functions,
-/// perspectives, constraints, aliases, ...)
-pub fn pass(ast: &Ast, ctx: Scope, settings: &CompileSettings) -> Result<()> {
-    let mut module = ctx;
-    for e in ast.exprs.iter() {
-        reduce(e, &mut module, settings)?;
+/// Returns whether the condition of a `#if` block evaluates to a non-zero
+/// constant
+fn is_enabled(condition: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<bool> {
+    let value = crate::compiler::generator::reduce(condition, ctx, settings)?
+        .ok_or_else(|| anyhow!("empty condition"))?
+        .pure_eval()
+        .with_context(|| make_ast_error(condition))?;
+    Ok(!value.is_zero())
+}
+
+/// Reduce the constants of `exprs` in order, splicing in the bodies of
+/// enabled conditional blocks and dropping the disabled ones. The pure
+/// functions of enabled blocks, left aside by the `Pure functions` pass, are
+/// defined along.
+fn flatten(
+    exprs: Vec<AstNode>,
+    ctx: &mut Scope,
+    settings: &CompileSettings,
+    in_block: bool,
+) -> Result<Vec<AstNode>> {
+    let mut r = Vec::with_capacity(exprs.len());
+    for e in exprs.into_iter() {
+        if let Token::Conditional { condition, body } = e.class {
+            if is_enabled(&condition, ctx, settings)
+                .with_context(|| anyhow!("evaluating condition {}", condition.src))?
+            {
+                r.extend(flatten(body, ctx, settings, true)?);
+            }
+        } else {
+            if in_block {
+                super::purefuns::define(&e, ctx)?;
+            }
+            reduce(&e, ctx, settings)?;
+            r.push(e);
+        }
     }
+    Ok(r)
+}
+
+/// The `Constants` pass skim through an [`Ast`] and fill the
+/// [`SymbolTableTree`] with the constants it defines. Conditional blocks are
+/// resolved at the same time, and replaced in the [`Ast`] by their body if
+/// enabled.
+pub fn pass(ast: &mut Ast, ctx: Scope, settings: &CompileSettings) -> Result<()> {
+    let mut module = ctx;
+    ast.exprs = flatten(std::mem::take(&mut ast.exprs), &mut module, settings, false)?;
 
     Ok(())
 }
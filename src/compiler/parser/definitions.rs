@@ -1,8 +1,8 @@
+use crate::utils::Colorize;
 use anyhow::Context;
 use anyhow::*;
 use crossterm::style::Stylize;
 use num_traits::ToPrimitive;
-use owo_colors::OwoColorize;
 
 use crate::compiler::generator::{self, Defined, Function, FunctionClass, Specialization};
 use crate::compiler::tables::Scope;
@@ -15,18 +15,20 @@ use super::{Ast, AstNode, Kind, Token};
 fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()> {
     match &e.class {
         Token::Value(_)
+        | Token::Boolean(_)
         | Token::Symbol(_)
         | Token::Keyword(_)
         | Token::List(_)
         | Token::Domain(_)
         | Token::DefLookup { .. }
+        | Token::DefLookupMultiplicity { .. }
         | Token::Defpurefun { .. }
         | Token::DefConsts { .. }
-        | Token::DefInrange(..) => Ok(()),
+        | Token::DefInrange(..)
+        | Token::DefConstraint { .. } => Ok(()),
 
         Token::IndexedSymbol { name: _, index } => reduce(index, ctx, settings),
-        Token::DefConstraint { name, .. } => ctx.insert_constraint(name),
-        Token::DefModule(name) => {
+        Token::DefModule { name, .. } => {
             *ctx = ctx.switch_to_module(name)?.public(true);
             Ok(())
         }
@@ -50,6 +52,7 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
             length_multiplier,
             must_prove,
             base,
+            ..
         } => {
             let module_name = ctx.module();
             let symbol = Node::column()
@@ -62,12 +65,14 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
                     Kind::Commitment => Kind::Commitment,
                     Kind::Computed => Kind::Computed, // unreachable?
                     Kind::Expression(_) => Kind::Computed,
+                    Kind::Recurrence { .. } => Kind::Computed,
+                    Kind::Fixpoint(_) => Kind::Computed,
                 })
                 .and_padding_value(*padding_value)
                 .and_length_multiplier(*length_multiplier)
                 .t(t.m())
                 .must_prove(*must_prove)
-                .base(*base)
+                .base(base.unwrap_or_else(|| ctx.module_base()))
                 .build();
             ctx.insert_symbol(name, symbol)
         }
@@ -78,8 +83,10 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
             padding_value,
             must_prove,
             base,
+            ..
         } => {
             let handle = Handle::maybe_with_perspective(ctx.module(), name, ctx.perspective());
+            let base = base.unwrap_or_else(|| ctx.module_base());
             // those are inserted for symbol lookups
             let domain = domain.concretize(|n| {
                 crate::compiler::generator::reduce(n, &mut ctx.clone(), settings)
@@ -107,7 +114,7 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
                         .and_padding_value(*padding_value)
                         .t(t.m())
                         .must_prove(*must_prove)
-                        .base(*base)
+                        .base(base)
                         .build(),
                 )?;
             }
@@ -118,13 +125,13 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
                 Node::array_column()
                     .handle(handle)
                     .domain(domain)
-                    .base(*base)
+                    .base(base)
                     .t(t.m())
                     .build(),
             )?;
             Ok(())
         }
-        Token::DefInterleaving { target, froms } => {
+        Token::DefInterleaving { target, froms, .. } => {
             let node = Node::column()
                 .handle(Handle::maybe_with_perspective(
                     // TODO unsure about this
@@ -235,7 +242,14 @@ fn reduce(e: &AstNode, ctx: &mut Scope, settings: &CompileSettings) -> Result<()
         Token::DefunAlias(from, to) => ctx
             .insert_funalias(from, to)
             .with_context(|| anyhow!("defining {} -> {}", from, to)),
-        Token::BlockComment(_) | Token::InlineComment(_) => unreachable!(),
+        Token::DefColumnsAssignment(name) => ctx.set_columns_assignment(name),
+        // module bases are set by the constants pass
+        Token::DefModuleBase(_) => Ok(()),
+        // includes are resolved when loading the sources
+        Token::Include(_) => Ok(()),
+        Token::BlockComment(_) | Token::InlineComment(_) | Token::Conditional { .. } => {
+            unreachable!()
+        }
     }
 }
 
@@ -130,7 +130,7 @@ fn rec_parse(source: &str, pair: Pair<Rule>) -> Result<AstNode> {
                 src,
             })
         }
-        Rule::symbol => Ok(AstNode {
+        Rule::symbol | Rule::string | Rule::directive => Ok(AstNode {
             class: Token::Symbol(pair.as_str().to_owned()),
             lc,
             src,
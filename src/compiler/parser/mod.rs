@@ -1,11 +1,12 @@
+use crate::utils::Colorize;
 use anyhow::*;
 use log::*;
 use num_bigint::BigInt;
-use owo_colors::OwoColorize;
 
 use self::parser::DisplayableColumn;
 
 use crate::{
+    column::Endianness,
     compiler::{tables::Scope, Type},
     errors::symbols,
     pretty::Base,
@@ -85,6 +86,23 @@ impl AstNode {
             ))
         }
     }
+    /// If possible, returns the arguments and the body of the anonymous
+    /// function `(lambda (ARGS...) BODY)` encoded by this node
+    pub fn as_lambda(&self) -> Result<(Vec<String>, &AstNode), symbols::Error> {
+        let not_a_lambda = || symbols::Error::NotASomethings("lambda", format!("{:?}", self));
+        match self.as_list() {
+            Result::Ok([head, args, body]) if matches!(head.as_symbol(), Result::Ok("lambda")) => {
+                let args = args
+                    .as_list()
+                    .map_err(|_| not_a_lambda())?
+                    .iter()
+                    .map(|a| a.as_symbol().map(str::to_owned))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Result::Ok((args, body))
+            }
+            _ => Err(not_a_lambda()),
+        }
+    }
     /// If possible, returns the domain/range encoded by this node
     pub fn as_domain(&self) -> Result<Domain<AstNode>, symbols::Error> {
         if let Token::Domain(d) = &self.class {
@@ -129,6 +147,8 @@ impl std::fmt::Display for AstNode {
 pub enum Token {
     /// an immediate value; can be “arbitrarily” large
     Value(BigInt),
+    /// a boolean literal, i.e. `true` or `false`
+    Boolean(bool),
     /// a symbol referencing another element of the tree
     Symbol(String),
     /// a block comment; only used by the formatting parser
@@ -149,8 +169,12 @@ pub enum Token {
     /// from Domain<AstNode> to Domain<isize> after the parsing.
     Domain(Box<Domain<AstNode>>),
 
-    /// definition of a module; this will derive a symbol table
-    DefModule(String),
+    /// definition of a module, with its optional statically known height;
+    /// this will derive a symbol table
+    DefModule {
+        name: String,
+        height: Option<usize>,
+    },
     /// a list of constant definition: (name, value)
     DefConsts(Vec<(String, Box<AstNode>)>),
     /// a list of columns declaration, normally only DefColumn
@@ -176,8 +200,11 @@ pub enum Token {
         length_multiplier: Option<usize>,
         /// if set, generate constraint to prove the column type
         must_prove: bool,
-        /// which numeric base should be used to display column values; this is a purely aesthetic setting
-        base: Base,
+        /// which numeric base should be used to display column values; this is a purely aesthetic setting.
+        /// Defaults to the base of the module if None
+        base: Option<Base>,
+        /// arbitrary labels attached to the column, to select columns by category
+        tags: Vec<String>,
     },
     /// defines an array
     DefArrayColumn {
@@ -191,8 +218,11 @@ pub enum Token {
         padding_value: Option<i64>,
         /// if set, generate constraint to prove the column type
         must_prove: bool,
-        /// which numeric base should be used to display column values; this is a purely aesthetic setting
-        base: Base,
+        /// which numeric base should be used to display column values; this is a purely aesthetic setting.
+        /// Defaults to the base of the module if None
+        base: Option<Base>,
+        /// arbitrary labels attached to every column of the array
+        tags: Vec<String>,
     },
     /// definition of a function
     Defun {
@@ -234,6 +264,9 @@ pub enum Token {
         /// if the constraint is set in a perspective, it is automatically
         /// guarded and additional rules are applied to symbol resolution
         perspective: Option<String>,
+        /// if set, the constraint is not enforced on the padding rows of its
+        /// module
+        no_padding: bool,
         /// this expression has to reduce to 0 for the constraint to be satisfied
         body: Box<AstNode>,
     },
@@ -242,12 +275,16 @@ pub enum Token {
         from: Vec<AstNode>,
         to: Vec<DisplayableColumn>,
         signs: Vec<bool>,
+        /// the byte order of the decomposition of the sorting delta
+        endianness: Endianness,
     },
     DefInterleaving {
         /// new column, which will be filled by the interleaving of the source columns
         target: DisplayableColumn,
         /// the source columns to be interleaved
         froms: Vec<AstNode>, // either Token::Symbol or Token::IndexedSymbol
+        /// if set, how many consecutive rows to take from each source in turn
+        strides: Option<Vec<AstNode>>,
     },
     /// declaration of a lookup constraint between two sets of columns
     DefLookup {
@@ -255,8 +292,30 @@ pub enum Token {
         including: Vec<AstNode>,
         included: Vec<AstNode>,
     },
+    /// declaration of a lookup constraint between two sets of columns, where
+    /// the number of times each row of the including columns is looked up is
+    /// materialized in a computed multiplicity column
+    DefLookupMultiplicity {
+        name: String,
+        including: Vec<AstNode>,
+        included: Vec<AstNode>,
+    },
     /// this constraint ensures that exp remains lesser than max
-    DefInrange(Box<AstNode>, u64),
+    DefInrange(Box<AstNode>, Box<AstNode>),
+    /// the columns assignment (CE) identifier used by the Go exporter
+    DefColumnsAssignment(String),
+    /// the base used to display the columns of the current module that do not
+    /// specify one
+    DefModuleBase(Base),
+    /// a file whose definitions are made available to the including one;
+    /// resolved when loading the sources, relative to the including file
+    Include(String),
+    /// a group of definitions, only compiled if the condition evaluates to a
+    /// non-zero constant
+    Conditional {
+        condition: Box<AstNode>,
+        body: Vec<AstNode>,
+    },
 }
 const LIST_DISPLAY_THRESHOLD: usize = 4;
 impl Token {
@@ -272,6 +331,7 @@ impl Token {
     pub fn debug_info(&self) -> Option<String> {
         match self {
             Token::Value(x) => Some(format!("{}", x)),
+            Token::Boolean(x) => Some(format!("{}", x)),
             Token::Symbol(ref name) => Some(name.to_string()),
             Token::Keyword(ref name) => Some(name.to_string()),
             Token::List(ref args) => {
@@ -343,6 +403,7 @@ impl std::fmt::Debug for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Token::Value(x) => write!(f, "{}", x),
+            Token::Boolean(x) => write!(f, "{}", x),
             Token::Symbol(ref name) => write!(f, "{}", name),
             Token::IndexedSymbol {
                 ref name,
@@ -354,7 +415,8 @@ impl std::fmt::Debug for Token {
             }
             Token::Domain(ref args) => write!(f, "{:?}", args),
 
-            Token::DefModule(name) => write!(f, "MODULE {}", name),
+            Token::DefModule { name, .. } => write!(f, "MODULE {}", name),
+            Token::DefModuleBase(base) => write!(f, "MODULE BASE {:?}", base),
             Token::DefConsts(v) => {
                 write!(
                     f,
@@ -398,6 +460,11 @@ impl std::fmt::Debug for Token {
             Token::DefAliases(cols) => write!(f, "ALIASES {:?}", cols),
             Token::DefAlias(from, to) => write!(f, "{} -> {}", from, to),
             Token::DefunAlias(from, to) => write!(f, "{} -> {}", from, to),
+            Token::DefColumnsAssignment(name) => write!(f, "CE {}", name),
+            Token::Include(path) => write!(f, "INCLUDE {}", path),
+            Token::Conditional { condition, body } => {
+                write!(f, "IF {:?} {:?}", condition, body)
+            }
             Token::DefLookup {
                 name,
                 including,
@@ -405,6 +472,13 @@ impl std::fmt::Debug for Token {
             } => {
                 write!(f, "{}: {:?} ⊂ {:?}", name, including, included)
             }
+            Token::DefLookupMultiplicity {
+                name,
+                including,
+                included,
+            } => {
+                write!(f, "{}: {:?} ⊂# {:?}", name, including, included)
+            }
             Token::DefPerspective {
                 name,
                 trigger,
@@ -445,6 +519,27 @@ pub(crate) fn maybe_bail<R>(errs: Vec<Result<R>>) -> Result<Vec<R>> {
     }
 }
 
+/// Return the files included by `source`, in order of appearance
+pub fn includes(source: &str) -> Result<Vec<String>> {
+    fn rec_includes(nodes: &[AstNode], conditional: bool, r: &mut Vec<String>) -> Result<()> {
+        for node in nodes {
+            match &node.class {
+                Token::Include(path) if conditional => {
+                    bail!("`{}` can not be included conditionally", path)
+                }
+                Token::Include(path) => r.push(path.to_owned()),
+                Token::Conditional { body, .. } => rec_includes(body, true, r)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    let mut r = Vec::new();
+    rec_includes(&parser::parse(source)?.exprs, false, &mut r)?;
+    Ok(r)
+}
+
 /// Given a list of sources and their names, parse them and return a
 /// corresponding list of Ast
 pub(crate) fn parse_ast<S1: AsRef<str>, S2: AsRef<str>>(
@@ -484,16 +579,17 @@ pub fn parse_simple_ast<S1: AsRef<str>, S2: AsRef<str>>(
     )
 }
 
+/// Parse the sources within `ctx`, which may have been prepared beforehand,
+/// e.g. with custom builtins
 pub fn parse<S1: AsRef<str>, S2: AsRef<str>>(
+    ctx: Scope,
     sources: &[(S1, S2)],
     settings: &CompileSettings,
 ) -> Result<(Scope, Vec<(String, Ast)>)> {
-    let ctx = Scope::new();
-
     //
     // Parse the source into an AST
     //
-    let asts = maybe_bail(
+    let mut asts = maybe_bail(
         sources
             .iter()
             .map(|(name, content)| {
@@ -512,6 +608,9 @@ pub fn parse<S1: AsRef<str>, S2: AsRef<str>>(
     // 2 - constants, that may be immediate or const. expr., but then pure
     //     functions are already there;
     // 3 - the remaining elements, which may be dependent on everything else.
+    //
+    // Conditional blocks are resolved along the constants, so that their
+    // conditions may use any constant defined before them.
 
     // 1. Pure functions
     for (name, ast) in asts.iter() {
@@ -519,8 +618,8 @@ pub fn parse<S1: AsRef<str>, S2: AsRef<str>>(
             .with_context(|| anyhow!("parsing definitions in `{}`", name))?;
     }
     // 2. Constants
-    for (name, ast) in asts.iter() {
-        constants::pass(&ast, ctx.clone(), settings)
+    for (name, ast) in asts.iter_mut() {
+        constants::pass(ast, ctx.clone(), settings)
             .with_context(|| anyhow!("parsing definitions in `{}`", name))?;
     }
     // 3. The rest
@@ -528,6 +627,11 @@ pub fn parse<S1: AsRef<str>, S2: AsRef<str>>(
         definitions::pass(&ast, ctx.clone(), settings)
             .with_context(|| anyhow!("parsing definitions in `{}`", name))?;
     }
+    // 4. Now that columns are known, pure functions may be checked not to use them
+    for (name, ast) in asts.iter() {
+        purefuns::check_purity(ast, ctx.clone())
+            .with_context(|| anyhow!("parsing definitions in `{}`", name))?;
+    }
 
     Ok((ctx, asts))
 }
@@ -1,11 +1,14 @@
 use crate::compiler::{Conditioning, Magma, RawMagma, Type};
-use crate::{errors, pretty::Base};
+use crate::utils::Colorize;
+use crate::{column::Endianness, errors, pretty::Base};
 use anyhow::{anyhow, bail, Context, Result};
 use itertools::Itertools;
 use num_bigint::BigInt;
-use num_traits::One;
-use owo_colors::OwoColorize;
-use pest::{iterators::Pair, Parser};
+use num_traits::{One, Zero};
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser,
+};
 use std::cell::OnceCell;
 use std::fmt::Debug;
 use std::str::FromStr;
@@ -43,6 +46,25 @@ pub struct DisplayableColumn {
     pub base: Base,
 }
 
+/// Parses a `(last)` or `(last N)` domain, standing for the final (N) row(s)
+/// of the module
+fn parse_last_rows(x: &AstNode) -> Result<Option<Domain<AstNode>>> {
+    match &x.class {
+        Token::List(xs) if xs.first().map(|h| h.as_symbol().ok() == Some("last")) == Some(true) => {
+            match xs.len() {
+                1 => Ok(Some(Domain::Last(AstNode {
+                    class: Token::Value(BigInt::one()),
+                    src: x.src.clone(),
+                    lc: x.lc,
+                }))),
+                2 => Ok(Some(Domain::Last(xs[1].clone()))),
+                _ => bail!("expected (last) or (last N), found `{}`", x.src),
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
 fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
     mut tokens: I,
     lc: (usize, usize),
@@ -61,7 +83,7 @@ fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
         .as_symbol()?
         .to_owned();
 
-    let (domain, guard, perspective) = {
+    let (domain, guard, perspective, no_padding) = {
         let guards = tokens
             .next()
             .with_context(|| anyhow!("missing guards in constraint definitions"))??
@@ -71,6 +93,7 @@ fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
         let mut domain = None;
         let mut guard = None;
         let mut perspective = None;
+        let mut no_padding = false;
         for x in guards.iter() {
             match status {
                 GuardParser::Begin => match x.class {
@@ -79,7 +102,11 @@ fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
                     Token::Keyword(ref kw) if kw == ":perspective" => {
                         status = GuardParser::Perspective
                     }
-                    _ => bail!("expected :guard, :domain or :perspective, found `{:?}`", x),
+                    Token::Keyword(ref kw) if kw == ":no-padding" => no_padding = true,
+                    _ => bail!(
+                        "expected :guard, :domain, :perspective or :no-padding, found `{:?}`",
+                        x
+                    ),
                 },
                 GuardParser::Guard => {
                     if guard.is_some() {
@@ -103,6 +130,8 @@ fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
                     } else {
                         if let Token::Domain(range) = &x.class {
                             domain = Some(range.to_owned())
+                        } else if let Some(last) = parse_last_rows(x)? {
+                            domain = Some(Box::new(last))
                         } else {
                             bail!("expected range, found `{:?}`", x)
                         }
@@ -119,7 +148,7 @@ fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
             GuardParser::Perspective => bail!("expected perspective name, found nothing"),
         }
 
-        (domain, guard, perspective)
+        (domain, guard, perspective, no_padding)
     };
 
     let body = Box::new(
@@ -138,6 +167,7 @@ fn parse_defconstraint<I: Iterator<Item = Result<AstNode>>>(
             domain,
             guard,
             perspective,
+            no_padding,
             body,
         },
         src,
@@ -191,6 +221,10 @@ struct ColumnAttributes {
     length_multiplier: OnceCell<usize>,
     base: OnceCell<Base>,
     computation: Option<AstNode>,
+    recurrence: Option<AstNode>,
+    init: Option<AstNode>,
+    fixpoint: bool,
+    tags: Vec<String>,
 }
 
 impl std::convert::TryInto<DisplayableColumn> for ColumnAttributes {
@@ -220,9 +254,12 @@ fn parse_column_attributes(source: AstNode) -> Result<ColumnAttributes> {
         Begin,
         Array,
         Computation,
+        Recurrence,
+        Init,
         PaddingValue,
         LengthMultiplier,
         Base,
+        Tags,
     }
     let re_type = regex_lite::Regex::new(
         r"^:(?<RawMagma>i(?<Integer>\d+)|[a-z]+)?(@(?<Conditioning>bool|loob))?(?<Proven>@prove)?$",
@@ -253,6 +290,14 @@ fn parse_column_attributes(source: AstNode) -> Result<ColumnAttributes> {
                     match kw.to_lowercase().as_str() {
                         // not really used for now.
                         ":comp" => ColumnParser::Computation,
+                        // e.g. (ACC :recurrence (+ (shift ACC -1) X) :init 0)
+                        ":recurrence" => ColumnParser::Recurrence,
+                        ":init" => ColumnParser::Init,
+                        // e.g. (A :comp (+ B 1) :fixpoint)
+                        ":fixpoint" => {
+                            attributes.fixpoint = true;
+                            ColumnParser::Begin
+                        }
                         // e.g. (A :array {1 3 5}) or (A :array [5])
                         ":array" => ColumnParser::Array,
                         // a specific padding value, e.g. (NOT :padding 255)
@@ -261,6 +306,8 @@ fn parse_column_attributes(source: AstNode) -> Result<ColumnAttributes> {
                         ":display" => ColumnParser::Base,
                         // a specific length multiplier
                         ":length" => ColumnParser::LengthMultiplier,
+                        // e.g. (A :tags (range-checked sorted))
+                        ":tags" => ColumnParser::Tags,
                         _ => {
                             if let Some(caps) = re_type.captures(kw) {
                                 let raw_magma = if let Some(integer) = caps.name("Integer") {
@@ -332,6 +379,14 @@ fn parse_column_attributes(source: AstNode) -> Result<ColumnAttributes> {
                 attributes.computation = Some(x);
                 ColumnParser::Begin
             }
+            ColumnParser::Recurrence => {
+                attributes.recurrence = Some(x);
+                ColumnParser::Begin
+            }
+            ColumnParser::Init => {
+                attributes.init = Some(x);
+                ColumnParser::Begin
+            }
             ColumnParser::PaddingValue => {
                 attributes.padding_value.set(x.as_i64()?).map_err(|_| {
                     anyhow!(
@@ -372,16 +427,39 @@ fn parse_column_attributes(source: AstNode) -> Result<ColumnAttributes> {
                 })?;
                 ColumnParser::Begin
             }
+            ColumnParser::Tags => {
+                let tags = x
+                    .as_list()
+                    .map_err(|_| anyhow!(":tags expects a list of tags; found {}", x))?
+                    .iter()
+                    .map(|t| t.as_symbol().map(str::to_owned))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| anyhow!(":tags expects a list of tags; found {}", x))?;
+                attributes.tags.extend(tags);
+                ColumnParser::Begin
+            }
         };
     }
+    if attributes.init.is_some() && attributes.recurrence.is_none() {
+        bail!(":init is only valid for :recurrence columns")
+    }
+    if attributes.computation.is_some() && attributes.recurrence.is_some() {
+        bail!("a column can not be both :comp and :recurrence")
+    }
+    if attributes.fixpoint && attributes.computation.is_none() {
+        bail!(":fixpoint is only valid for :comp columns")
+    }
     // Ensure that we are in a clean state
     match state {
         ColumnParser::Begin => (),
         ColumnParser::Array => bail!("incomplete :array definition"),
         ColumnParser::Computation => bail!("incomplate :comp definition"),
+        ColumnParser::Recurrence => bail!("incomplete :recurrence definition"),
+        ColumnParser::Init => bail!("incomplete :init definition"),
         ColumnParser::PaddingValue => bail!("incomplete :padding definition"),
         ColumnParser::LengthMultiplier => bail!("incomplete :length definition"),
         ColumnParser::Base => bail!("incomplete :display definition"),
+        ColumnParser::Tags => bail!("incomplete :tags definition"),
     }
     Ok(attributes)
 }
@@ -397,7 +475,7 @@ fn parse_defcolumns<I: Iterator<Item = Result<AstNode>>>(
             c.and_then(|c| {
                 let column_attributes = parse_column_attributes(c.clone())?;
 
-                let base = column_attributes.base.get().cloned().unwrap_or(Base::Hex);
+                let base = column_attributes.base.get().cloned();
                 Ok(AstNode {
                     class: if let Some(range) = column_attributes.range.get() {
                         Token::DefArrayColumn {
@@ -413,6 +491,7 @@ fn parse_defcolumns<I: Iterator<Item = Result<AstNode>>>(
                             domain: range.clone(),
                             must_prove: column_attributes.must_prove,
                             base,
+                            tags: column_attributes.tags,
                         }
                     } else {
                         Token::DefColumn {
@@ -424,14 +503,33 @@ fn parse_defcolumns<I: Iterator<Item = Result<AstNode>>>(
                                     .cloned()
                                     .unwrap_or(Magma::native()),
                             ),
-                            kind: column_attributes
-                                .computation
-                                .map(|c| Kind::Expression(Box::new(c)))
-                                .unwrap_or(Kind::Commitment),
+                            kind: if let Some(exp) = column_attributes.recurrence {
+                                Kind::Recurrence {
+                                    exp: Box::new(exp),
+                                    // the accumulator starts at 0 by default
+                                    init: Box::new(column_attributes.init.unwrap_or(AstNode {
+                                        class: Token::Value(BigInt::zero()),
+                                        lc: c.lc,
+                                        src: c.src.clone(),
+                                    })),
+                                }
+                            } else {
+                                column_attributes
+                                    .computation
+                                    .map(|c| {
+                                        if column_attributes.fixpoint {
+                                            Kind::Fixpoint(Box::new(c))
+                                        } else {
+                                            Kind::Expression(Box::new(c))
+                                        }
+                                    })
+                                    .unwrap_or(Kind::Commitment)
+                            },
                             padding_value: column_attributes.padding_value.get().cloned(),
                             length_multiplier: column_attributes.length_multiplier.get().cloned(),
                             must_prove: column_attributes.must_prove,
                             base,
+                            tags: column_attributes.tags,
                         }
                     },
                     lc: c.lc,
@@ -449,21 +547,100 @@ fn parse_defcolumns<I: Iterator<Item = Result<AstNode>>>(
     })
 }
 
+fn parse_defce(mut pairs: Pairs<Rule>, lc: (usize, usize), src: String) -> Result<AstNode> {
+    let name = pairs
+        .next()
+        .with_context(|| anyhow!("missing columns assignment name"))?;
+    if name.as_rule() != Rule::string {
+        bail!("expected a string, found `{}`", name.as_str());
+    }
+    if let Some(x) = pairs.next() {
+        bail!("unexpected argument `{}`", x.as_str());
+    }
+
+    Ok(AstNode {
+        class: Token::DefColumnsAssignment(name.as_str().trim_matches('"').to_owned()),
+        lc,
+        src,
+    })
+}
+
+fn parse_include(mut pairs: Pairs<Rule>, lc: (usize, usize), src: String) -> Result<AstNode> {
+    let path = pairs
+        .next()
+        .with_context(|| anyhow!("missing included file name"))?;
+    if path.as_rule() != Rule::string {
+        bail!("expected a string, found `{}`", path.as_str());
+    }
+    if let Some(x) = pairs.next() {
+        bail!("unexpected argument `{}`", x.as_str());
+    }
+
+    Ok(AstNode {
+        class: Token::Include(path.as_str().trim_matches('"').to_owned()),
+        lc,
+        src,
+    })
+}
+
 fn parse_definition(pair: Pair<Rule>) -> Result<AstNode> {
     let lc = pair.as_span().start_pos().line_col();
     let src = pair.as_str().to_owned();
 
-    let mut tokens = pair.into_inner().map(rec_parse);
+    let mut pairs = pair.into_inner();
+    let definition = pairs.next().unwrap().as_str();
+    if definition == "defce" {
+        return parse_defce(pairs, lc, src);
+    }
+    if definition == "include" {
+        return parse_include(pairs, lc, src);
+    }
+    let mut tokens = pairs.map(rec_parse);
 
-    match tokens.next().unwrap().unwrap().as_symbol()? {
+    match definition {
         "module" => {
             let name = tokens
                 .next()
                 .with_context(|| anyhow!("module name missing"))??
                 .as_symbol()?
                 .to_owned();
+            let mut height = None;
+            while let Some(kw) = tokens.next() {
+                match kw?.class {
+                    Token::Keyword(ref kw) if kw == ":height" => {
+                        height = Some(
+                            tokens
+                                .next()
+                                .with_context(|| anyhow!("missing height for module {}", name))??
+                                .as_u64()
+                                .with_context(|| anyhow!("invalid height for module {}", name))?
+                                as usize,
+                        );
+                    }
+                    x => bail!("unexpected argument {:?} for module {}", x, name),
+                }
+            }
             Ok(AstNode {
-                class: Token::DefModule(name),
+                class: Token::DefModule { name, height },
+                lc,
+                src,
+            })
+        }
+        "defmodule-base" => {
+            let base = match tokens.next() {
+                Some(Result::Ok(AstNode {
+                    class: Token::Keyword(kw),
+                    ..
+                })) => kw.as_str().try_into()?,
+                _ => bail!(
+                    "defmodule-base expects one of :hex, :dec, :bin, :bytes, :opcode, :truthiness"
+                ),
+            };
+            if let Some(x) = tokens.next() {
+                bail!("unexpected argument `{}`", x?);
+            }
+            Ok(AstNode {
+                class: Token::DefModuleBase(base),
                 lc,
                 src,
             })
@@ -478,7 +655,7 @@ fn parse_definition(pair: Pair<Rule>) -> Result<AstNode> {
                     .map(|mut chunk| {
                         let name = chunk
                             .next()
-                            .ok_or_else(|| anyhow!("adsf"))??
+                            .ok_or_else(|| anyhow!("expected constant name"))??
                             .as_symbol()
                             .with_context(|| anyhow!("invalid constant name"))?
                             .to_owned();
@@ -657,16 +834,15 @@ fn parse_definition(pair: Pair<Rule>) -> Result<AstNode> {
 
             let range = tokens
                 .next()
-                .with_context(|| anyhow!("missing maximal value"))??
-                .as_u64()?;
+                .with_context(|| anyhow!("missing maximal value"))??;
 
             Ok(AstNode {
-                class: Token::DefInrange(Box::new(exp), range),
+                class: Token::DefInrange(Box::new(exp), Box::new(range)),
                 src,
                 lc,
             })
         }
-        "deflookup" => {
+        "deflookup" | "deflookup-multiplicity" => {
             let name = tokens
                 .next()
                 .with_context(|| anyhow!("expected lookup name"))??
@@ -686,10 +862,18 @@ fn parse_definition(pair: Pair<Rule>) -> Result<AstNode> {
                 .to_vec();
 
             Ok(AstNode {
-                class: Token::DefLookup {
-                    name,
-                    including,
-                    included,
+                class: if definition == "deflookup" {
+                    Token::DefLookup {
+                        name,
+                        including,
+                        included,
+                    }
+                } else {
+                    Token::DefLookupMultiplicity {
+                        name,
+                        including,
+                        included,
+                    }
                 },
                 src,
                 lc,
@@ -750,8 +934,25 @@ fn parse_definition(pair: Pair<Rule>) -> Result<AstNode> {
             }
             signs.resize(from.len(), true); // ensure that signs & froms are the same size
 
+            let mut endianness = Endianness::default();
+            for option in tokens {
+                let option = option?;
+                endianness = match &option.class {
+                    Token::Keyword(kw) if kw.to_lowercase() == ":little-endian" => {
+                        Endianness::Little
+                    }
+                    Token::Keyword(kw) if kw.to_lowercase() == ":big-endian" => Endianness::Big,
+                    _ => bail!("unexpected permutation option {}", option.src),
+                };
+            }
+
             Ok(AstNode {
-                class: Token::DefPermutation { from, to, signs },
+                class: Token::DefPermutation {
+                    from,
+                    to,
+                    signs,
+                    endianness,
+                },
                 src,
                 lc,
             })
@@ -778,8 +979,28 @@ fn parse_definition(pair: Pair<Rule>) -> Result<AstNode> {
                 })
                 .collect::<Result<Vec<_>>>()?;
 
+            let mut strides = None;
+            while let Some(kw) = tokens.next() {
+                match kw?.class {
+                    Token::Keyword(ref kw) if kw == ":strides" => {
+                        strides = Some(
+                            tokens
+                                .next()
+                                .with_context(|| anyhow!("missing strides"))??
+                                .as_list()?
+                                .to_vec(),
+                        );
+                    }
+                    x => bail!("unexpected argument {:?} for interleaving", x),
+                }
+            }
+
             Ok(AstNode {
-                class: Token::DefInterleaving { target, froms },
+                class: Token::DefInterleaving {
+                    target,
+                    froms,
+                    strides,
+                },
                 src,
                 lc,
             })
@@ -799,6 +1020,30 @@ fn rec_parse(pair: Pair<Rule>) -> Result<AstNode> {
         Rule::toplevel => {
             parse_definition(pair).with_context(|| errors::parser::make_src_error(&src, lc))
         }
+        Rule::conditional => {
+            let mut inner = pair.into_inner();
+            let condition = rec_parse(inner.next().unwrap())?;
+            let body = inner.map(rec_parse).collect::<Result<Vec<_>>>()?;
+            // Pure functions are defined before conditions can be evaluated,
+            // and must thus know their module beforehand
+            if let Some(m) = body
+                .iter()
+                .find(|e| matches!(e.class, Token::DefModule { .. }))
+            {
+                bail!(
+                    "modules can not be declared within a conditional block: {}",
+                    errors::parser::make_src_error(&m.src, m.lc)
+                )
+            }
+            Ok(AstNode {
+                class: Token::Conditional {
+                    condition: Box::new(condition),
+                    body,
+                },
+                lc,
+                src,
+            })
+        }
         Rule::sexpr => {
             let args = pair
                 .into_inner()
@@ -879,6 +1124,12 @@ fn rec_parse(pair: Pair<Rule>) -> Result<AstNode> {
             src,
             lc,
         }),
+        Rule::string => bail!("string literals are only allowed in defce"),
+        Rule::boolean => Ok(AstNode {
+            class: Token::Boolean(pair.as_str() == "true"),
+            lc,
+            src,
+        }),
         Rule::nth => {
             let mut args = pair
                 .into_inner()
@@ -1,14 +1,19 @@
+use std::collections::HashSet;
+
 use anyhow::*;
+use itertools::Itertools;
 
 use crate::compiler::generator::{Defined, Function, FunctionClass, Specialization};
 use crate::compiler::tables::Scope;
+use crate::compiler::Expression;
+use crate::pretty::Pretty;
 use crate::structs::Handle;
 
 use super::{Ast, AstNode, Token};
 
 fn reduce(e: &AstNode, ctx: &mut Scope) -> Result<()> {
     match &e.class {
-        Token::DefModule(name) => {
+        Token::DefModule { name, .. } => {
             *ctx = ctx.switch_to_module(name)?.public(true);
             Ok(())
         }
@@ -38,10 +43,23 @@ fn reduce(e: &AstNode, ctx: &mut Scope) -> Result<()> {
                 },
             )
         }
+        // Conditions can only be evaluated once the constants are known, so
+        // the pure functions of a block are only defined by the `Constants`
+        // pass, if it is enabled
+        Token::Conditional { .. } => Ok(()),
         _ => Ok(()),
     }
 }
 
+/// Define the pure function `e`, if it is one, in `ctx`
+pub(super) fn define(e: &AstNode, ctx: &mut Scope) -> Result<()> {
+    if let Token::Defpurefun { .. } = e.class {
+        reduce(e, ctx)
+    } else {
+        Ok(())
+    }
+}
+
 /// The `Definitions` pass skim through an [`Ast`] and fill the
 /// [`SymbolTableTree`] with all the required elements (columns, functions,
 /// perspectives, constraints, aliases, ...)
@@ -53,3 +71,123 @@ pub fn pass(ast: &Ast, ctx: Scope) -> Result<()> {
 
     Ok(())
 }
+
+/// Accumulate in `found` the symbols of `e` that, not being shadowed by any
+/// of the `bound` names, resolve to a column of the trace
+fn column_references(
+    e: &AstNode,
+    bound: &HashSet<String>,
+    ctx: &mut Scope,
+    found: &mut Vec<String>,
+) {
+    let mut check_symbol = |name: &String, found: &mut Vec<String>| {
+        if !bound.contains(name) {
+            if let Result::Ok(n) = ctx.resolve_symbol(name, false) {
+                if matches!(
+                    n.e(),
+                    Expression::Column { .. } | Expression::ArrayColumn { .. }
+                ) {
+                    found.push(name.to_owned());
+                }
+            }
+        }
+    };
+
+    match &e.class {
+        Token::Symbol(name) => check_symbol(name, found),
+        Token::IndexedSymbol { name, index } => {
+            check_symbol(name, found);
+            column_references(index, bound, ctx, found);
+        }
+        Token::List(args) => {
+            match args.first().map(|a| &a.class) {
+                Some(Token::Symbol(f)) if (f == "let" || f == "let*") && args.len() == 3 => {
+                    let mut bound = bound.clone();
+                    if let Token::List(pairs) = &args[1].class {
+                        for pair in pairs.iter() {
+                            if let Token::List(pair) = &pair.class {
+                                if let Some(value) = pair.get(1) {
+                                    column_references(value, &bound, ctx, found);
+                                }
+                                match pair.first().map(|n| &n.class) {
+                                    Some(Token::Symbol(name)) => {
+                                        bound.insert(name.to_owned());
+                                    }
+                                    Some(Token::List(names)) => {
+                                        bound.extend(names.iter().filter_map(|n| {
+                                            n.as_symbol().ok().map(|n| n.to_owned())
+                                        }));
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                    column_references(&args[2], &bound, ctx, found);
+                }
+                Some(Token::Symbol(f)) if f == "for" && args.len() == 4 => {
+                    let mut bound = bound.clone();
+                    if let Token::Symbol(i) = &args[1].class {
+                        bound.insert(i.to_owned());
+                    }
+                    column_references(&args[3], &bound, ctx, found);
+                }
+                // the head of a list is the function being called, not a column
+                Some(Token::Symbol(_)) => {
+                    for a in args.iter().skip(1) {
+                        column_references(a, bound, ctx, found);
+                    }
+                }
+                _ => {
+                    for a in args.iter() {
+                        column_references(a, bound, ctx, found);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check(e: &AstNode, ctx: &mut Scope) -> Result<()> {
+    match &e.class {
+        Token::DefModule { name, .. } => {
+            *ctx = ctx.switch_to_module(name)?.public(true);
+            Ok(())
+        }
+        Token::Defpurefun {
+            name, args, body, ..
+        } => {
+            let mut found = Vec::new();
+            column_references(
+                body,
+                &args.iter().cloned().collect(),
+                &mut ctx.clone(),
+                &mut found,
+            );
+            if !found.is_empty() {
+                bail!(
+                    "pure function {} can not reference the columns {}",
+                    Handle::new(ctx.module(), name).pretty(),
+                    found.into_iter().unique().join(", ")
+                )
+            }
+            Ok(())
+        }
+        // Resolved by the `Constants` pass
+        Token::Conditional { .. } => unreachable!(),
+        _ => Ok(()),
+    }
+}
+
+/// Once all the columns are defined, ensure that the body of the pure
+/// functions of an [`Ast`] do not refer to any of them -- shifting the
+/// columns given as arguments remains possible.
+pub fn check_purity(ast: &Ast, ctx: Scope) -> Result<()> {
+    let mut module = ctx;
+    for e in ast.exprs.iter() {
+        check(e, &mut module)?;
+    }
+
+    Ok(())
+}
@@ -1,24 +1,39 @@
-use super::{generator::Function, ColumnRef, Expression, Magma, Node, Type};
+use super::{
+    generator::{Constraint, Function},
+    ColumnRef, Conditioning, Expression, Magma, Node, Type,
+};
+use crate::utils::Colorize;
 use crate::{
     column::Computation,
-    compiler::{generator::FunctionClass, Builtin, Form, Intrinsic},
+    compiler::{generator::FunctionClass, Builtin, CustomBuiltin, Form, Intrinsic},
     errors::symbols,
+    pretty::Base,
     structs::{Handle, PERSPECTIVE_SEPARATOR},
 };
 use anyhow::*;
 use itertools::Itertools;
-use log::*;
 use num_bigint::BigInt;
 use num_traits::{One, Zero};
-use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
 use sorbus::{NodeID, Tree};
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet},
     rc::Rc,
 };
 
+#[cfg(test)]
+thread_local! {
+    /// How many alias indirections have been followed when resolving symbols
+    /// and functions
+    pub(crate) static ALIAS_HOPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn count_alias_hop() {
+    #[cfg(test)]
+    ALIAS_HOPS.with(|hops| hops.set(hops.get() + 1));
+}
+
 lazy_static::lazy_static! {
     /// This map contains all the special forms, builtin functions and field operations that are directly
     /// implemented by Corset.
@@ -40,20 +55,36 @@ lazy_static::lazy_static! {
             handle: Handle::new(super::MAIN_MODULE, "let"),
             class: FunctionClass::Form(Form::Let),
         },
+        "let*" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "let*"),
+            class: FunctionClass::Form(Form::LetStar),
+        },
         "reduce" => Function {
             handle: Handle::new(super::MAIN_MODULE, "reduce"),
             class: FunctionClass::Form(Form::Reduce)
         },
+        "map" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "map"),
+            class: FunctionClass::Form(Form::Map)
+        },
 
         // Builtin functions
         "len" => Function {
             handle: Handle::new(super::MAIN_MODULE, Builtin::Len.to_string()),
             class: FunctionClass::Builtin(Builtin::Len),
         },
+        "nth" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "nth"),
+            class: FunctionClass::Builtin(Builtin::Nth),
+        },
         "shift" => Function{
             handle: Handle::new(super::MAIN_MODULE, "shift"),
             class: FunctionClass::Builtin(Builtin::Shift),
         },
+        "rot-shift" => Function{
+            handle: Handle::new(super::MAIN_MODULE, "rot-shift"),
+            class: FunctionClass::Builtin(Builtin::RotShift),
+        },
         // "~>>" => Function{
         //     handle: Handle::new(super::MAIN_MODULE, "~>>"),
         //     class: FunctionClass::Builtin(Builtin::NormFlat),
@@ -62,6 +93,58 @@ lazy_static::lazy_static! {
             handle: Handle::new(super::MAIN_MODULE, "if"),
             class: FunctionClass::Builtin(Builtin::If)
         },
+        "clamp" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "clamp"),
+            class: FunctionClass::Builtin(Builtin::Clamp)
+        },
+        "horner" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "horner"),
+            class: FunctionClass::Builtin(Builtin::Horner)
+        },
+        "rlc" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "rlc"),
+            class: FunctionClass::Builtin(Builtin::Rlc)
+        },
+        "scale-up" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "scale-up"),
+            class: FunctionClass::Builtin(Builtin::ScaleUp)
+        },
+        "scale-down" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "scale-down"),
+            class: FunctionClass::Builtin(Builtin::ScaleDown)
+        },
+        "mod" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "mod"),
+            class: FunctionClass::Builtin(Builtin::Mod)
+        },
+        "/" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "/"),
+            class: FunctionClass::Builtin(Builtin::Div)
+        },
+        "xor" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "xor"),
+            class: FunctionClass::Builtin(Builtin::Xor)
+        },
+        "or" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "or"),
+            class: FunctionClass::Builtin(Builtin::Or)
+        },
+        "min" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "min"),
+            class: FunctionClass::Builtin(Builtin::Min)
+        },
+        "max" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "max"),
+            class: FunctionClass::Builtin(Builtin::Max)
+        },
+        "stays" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "stays"),
+            class: FunctionClass::Builtin(Builtin::Stays)
+        },
+        "changes" => Function {
+            handle: Handle::new(super::MAIN_MODULE, "changes"),
+            class: FunctionClass::Builtin(Builtin::Changes)
+        },
 
         // Intrinsics
         "+" => Function {
@@ -195,6 +278,13 @@ pub enum Symbol {
 pub struct GlobalData {
     computations: ComputationTable,
     pub perspectives: HashMap<String, HashMap<String, Option<Node>>>, // module -> {Perspectives}
+    /// constraints generated while reducing expressions, e.g. by gadgets
+    pub auxiliary_constraints: Vec<Constraint>,
+    /// the columns assignment (CE) identifier, if declared with `defce`
+    pub columns_assignment: Option<String>,
+    /// the default display base of the modules declaring one with
+    /// `defmodule-base`
+    pub module_bases: HashMap<String, Base>,
 }
 impl GlobalData {
     pub fn set_perspective_trigger(
@@ -273,7 +363,6 @@ impl Scope {
                 closed: true,
                 public: true,
                 global: false,
-                constraints: Default::default(),
                 funcs: BUILTINS
                     .iter()
                     .map(|(k, f)| (k.to_string(), f.clone()))
@@ -306,7 +395,6 @@ impl Scope {
                         closed: false,
                         public: false,
                         global: current_global,
-                        constraints: Default::default(),
                         funcs: Default::default(),
                         symbols: Default::default(),
                         perspective: None,
@@ -324,30 +412,62 @@ impl Scope {
         if name.starts_with('#') {
             bail!("names starting with `#` are reserved for internal usage")
         }
-        let root = self.tree.borrow().root();
-        let maybe_child = self.tree.borrow().find_child(root, |n| n.name == name);
-        match maybe_child {
-            Some(n) => Ok(self.at(n)),
+        Ok(self.find_or_create_module(name))
+    }
+
+    /// Returns the scope of the compiler-generated module `name`, which must
+    /// start with a `#` so as not to clash with user-defined modules
+    pub fn internal_module(&mut self, name: &str) -> Scope {
+        assert!(name.starts_with('#'));
+        self.find_or_create_module(name)
+    }
+
+    /// Returns the node of the module `name`, if it exists. Nested modules,
+    /// e.g. `a.b`, are children of their parent module, e.g. `a`; the prelude
+    /// is the root.
+    fn find_module(&self, name: &str) -> Option<usize> {
+        let tree = self.tree.borrow();
+        let mut n = tree.root();
+        if name == super::MAIN_MODULE {
+            return Some(n);
+        }
+        let mut path = String::new();
+        for component in name.split('.') {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(component);
+            n = tree.find_child(n, |t| t.name == path)?;
+        }
+        Some(n)
+    }
+
+    fn find_or_create_module(&mut self, name: &str) -> Scope {
+        match self.find_module(name) {
+            Some(n) => self.at(n),
             None => {
+                let parent = match name.rsplit_once('.') {
+                    Some((parent, _)) => self.find_or_create_module(parent).public(true).id,
+                    None => self.tree.borrow().root(),
+                };
                 let current_global = data!(self).global;
                 let new_node = self.tree.borrow_mut().add_node(
-                    Some(root),
+                    Some(parent),
                     Some(SymbolTable {
                         name: name.to_owned(),
                         module: name.to_owned(),
                         closed: false,
                         public: false,
                         global: current_global,
-                        constraints: Default::default(),
                         funcs: Default::default(),
                         symbols: Default::default(),
                         perspective: None,
                     }),
                 );
-                Ok(Scope {
+                Scope {
                     tree: self.tree.clone(),
                     id: new_node,
-                })
+                }
             }
         }
     }
@@ -445,6 +565,65 @@ impl Scope {
             .insert(target, computation)
     }
 
+    pub fn insert_auxiliary_constraint(&self, constraint: Constraint) {
+        self.tree
+            .borrow_mut()
+            .metadata_mut()
+            .auxiliary_constraints
+            .push(constraint)
+    }
+
+    pub fn set_columns_assignment(&self, name: &str) -> Result<()> {
+        let mut tree = self.tree.borrow_mut();
+        let ce = &mut tree.metadata_mut().columns_assignment;
+        match ce {
+            Some(previous) if previous != name => {
+                bail!("columns assignment already set to `{}`", previous)
+            }
+            _ => {
+                *ce = Some(name.to_owned());
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_module_base(&self, base: Base) -> Result<()> {
+        let module = self.module();
+        let mut tree = self.tree.borrow_mut();
+        match tree.metadata_mut().module_bases.entry(module) {
+            Entry::Occupied(e) if *e.get() != base => {
+                bail!(
+                    "base of module {} already set to {:?}",
+                    e.key().bold().yellow(),
+                    e.get()
+                )
+            }
+            Entry::Occupied(_) => Ok(()),
+            Entry::Vacant(e) => {
+                e.insert(base);
+                Ok(())
+            }
+        }
+    }
+
+    /// The base used to display the columns of the current module that do
+    /// not specify one
+    pub fn module_base(&self) -> Base {
+        self.tree
+            .borrow()
+            .metadata()
+            .module_bases
+            .get(&self.module())
+            .cloned()
+            .unwrap_or(Base::Hex)
+    }
+
+    /// Returns the top-level scope of the module this scope belongs to
+    pub fn module_scope(&self) -> Scope {
+        let root = self.tree.borrow().root();
+        self.at(self.find_module(&self.module()).unwrap_or(root))
+    }
+
     fn at(&self, id: usize) -> Scope {
         Scope {
             tree: self.tree.clone(),
@@ -562,8 +741,21 @@ impl Scope {
             ax.insert(name.to_owned());
             match tree[n].unwrap_data_mut().symbols.get_mut(name) {
                 Some(Symbol::Alias(target)) => {
+                    count_alias_hop();
                     let target = target.to_owned();
-                    Self::_resolve_symbol(n, tree, &target, ax, absolute_path, pure, used)
+                    let r = Self::_resolve_symbol(n, tree, &target, ax, absolute_path, pure, used);
+                    // Path compression: if the target is itself an alias of
+                    // this scope, it now points to the end of the chain, and
+                    // so should this one.
+                    if let Some(Symbol::Alias(next)) =
+                        tree[n].unwrap_data().symbols.get(&target).cloned()
+                    {
+                        tree[n]
+                            .unwrap_data_mut()
+                            .symbols
+                            .insert(name.to_owned(), Symbol::Alias(next));
+                    }
+                    r
                 }
                 Some(Symbol::Final(exp, ref mut visited)) => {
                     if pure && !matches!(exp.e(), Expression::Const(..)) {
@@ -633,8 +825,10 @@ impl Scope {
         if path.len() == 1 {
             self.resolve_symbol(path[0], used)
         } else {
+            // Nested modules are named after their full path
+            let nested = format!("{}.{}", data!(self).name, path[0]);
             for c in self.children() {
-                if data!(c).name == path[0] {
+                if data!(c).name == path[0] || data!(c).name == nested {
                     return self.at(c.id)._resolve_symbol_with_path(&path[1..], used);
                 }
             }
@@ -682,12 +876,29 @@ impl Scope {
             bail!(symbols::Error::CircularDefinition(name.to_owned()))
         } else {
             ax.insert(name.to_owned());
-            match data!(self).funcs.get(name) {
+            // The symbol table must not be borrowed while compressing paths
+            let f = data!(self).funcs.get(name).cloned();
+            match f {
                 Some(Function {
-                    class: FunctionClass::Alias(ref to),
+                    class: FunctionClass::Alias(to),
                     ..
-                }) => self.resolve_function(to),
-                Some(f) => Ok(f.to_owned()),
+                }) => {
+                    count_alias_hop();
+                    let r = self.resolve_function(&to);
+                    // Path compression, as for symbols
+                    let next = data!(self).funcs.get(&to).cloned();
+                    if let Some(
+                        f @ Function {
+                            class: FunctionClass::Alias(_),
+                            ..
+                        },
+                    ) = next
+                    {
+                        data_mut!(self).funcs.insert(name.to_owned(), f);
+                    }
+                    r
+                }
+                Some(f) => Ok(f),
                 None => self
                     .parent()
                     .map_or(Err(anyhow!("function {} unknown", name.red())), |parent| {
@@ -697,17 +908,6 @@ impl Scope {
         }
     }
 
-    pub fn insert_constraint(&mut self, name: &str) -> Result<()> {
-        if data!(self).constraints.contains(name) {
-            warn!("redefining constraint `{}`", name.yellow());
-        }
-        if data_mut!(self).constraints.insert(name.to_owned()) {
-            Ok(())
-        } else {
-            bail!("constraint `{}` already defined", name)
-        }
-    }
-
     pub fn insert_symbol(&mut self, name: &str, e: Node) -> Result<()> {
         if name.starts_with('#') {
             bail!("names starting with `#` are reserved for intenal usage")
@@ -727,8 +927,8 @@ impl Scope {
 
     pub fn insert_function(&mut self, name: &str, f: Function) -> Result<()> {
         let my_name = data!(self).name.to_owned();
-        // User-defined function can be polymorphic on their input arguments and
-        // thus can be declared multiple times.
+        // User-defined function can be polymorphic on the number and the types
+        // of their input arguments and thus can be declared multiple times.
         // Polymorphism is handled in the implementation for other classes of
         // functions, thus they can only be defined once.
         match &f.class {
@@ -808,16 +1008,40 @@ impl Scope {
         )
     }
 
+    /// Make a custom builtin available to all the modules compiled in this
+    /// scope; it can not shadow an existing function.
+    // do not pollute compilation output of the binary
+    #[allow(dead_code)]
+    pub fn register_builtin(&mut self, builtin: std::sync::Arc<dyn CustomBuiltin>) -> Result<()> {
+        let name = builtin.name().to_owned();
+        self.root().insert_function(
+            &name,
+            Function {
+                handle: Handle::new(super::MAIN_MODULE, &name),
+                class: FunctionClass::Custom(builtin),
+            },
+        )
+    }
+
     pub fn resolve_function(&self, name: &str) -> Result<Function> {
         self._resolve_function(name, &mut HashSet::new())
     }
 
-    pub fn insert_constant(&mut self, name: &str, value: BigInt, replace: bool) -> Result<()> {
+    /// Insert a constant in the scope; its type is inferred from its value,
+    /// but may be conditioned, e.g. for boolean literals
+    pub fn insert_constant(
+        &mut self,
+        name: &str,
+        value: BigInt,
+        conditioning: Conditioning,
+        replace: bool,
+    ) -> Result<()> {
         let t = if Zero::is_zero(&value) || One::is_one(&value) {
             Type::Scalar(Magma::binary())
         } else {
             Type::Scalar(Magma::native())
-        };
+        }
+        .with_conditioning(conditioning);
         if data!(self).symbols.contains_key(name) && !replace {
             bail!(symbols::Error::SymbolAlreadyExists(
                 name.to_owned(),
@@ -878,7 +1102,6 @@ pub struct SymbolTable {
     // it will result in a failure.
     // This setting in forcefully inherited by children scopes.
     global: bool,
-    constraints: HashSet<String>,
     funcs: HashMap<String, Function>,
     symbols: HashMap<String, Symbol>,
 }
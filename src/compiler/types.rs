@@ -1,7 +1,7 @@
 #![allow(dead_code)]
+use crate::utils::Colorize;
 use anyhow::*;
 use num_bigint::BigInt;
-use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
 use std::{cmp::Ordering, sync::OnceLock};
 
@@ -1,9 +1,14 @@
+use crate::utils::Colorize;
 use anyhow::{anyhow, bail, Context, Result};
 use itertools::Itertools;
 use log::*;
 use logging_timer::time;
-use owo_colors::OwoColorize;
-use std::{cmp::Ordering, collections::HashSet};
+use serde::Serialize;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    time::{Duration, Instant},
+};
 
 use crate::{
     column::{ColumnSet, Computation, ExoOperation, Value, ValueBacking},
@@ -125,12 +130,235 @@ fn compute_ancillaries(
     Ok(())
 }
 
+/// The time spent filling columns, both overall and for each kind of
+/// computation, along with the number of rows produced
+#[derive(Debug, Default)]
+pub struct ComputeTimings {
+    pub total: Duration,
+    pub rows: usize,
+    /// computation kind -> (count, time, rows)
+    pub kinds: BTreeMap<&'static str, (usize, Duration, usize)>,
+}
+impl ComputeTimings {
+    fn record(&mut self, comp: &Computation, elapsed: Duration, r: &Result<Vec<ComputedColumn>>) {
+        let rows = r
+            .as_ref()
+            .map(|xs| xs.iter().map(|(_, backing)| backing.len()).sum())
+            .unwrap_or(0);
+        let kind = self.kinds.entry(comp.kind_name()).or_default();
+        kind.0 += 1;
+        kind.1 += elapsed;
+        kind.2 += rows;
+        self.rows += rows;
+    }
+}
+impl std::fmt::Display for ComputeTimings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", BenchReport::from(self))
+    }
+}
+
+#[derive(Serialize)]
+pub struct BenchEntry {
+    pub kind: String,
+    pub count: usize,
+    pub rows: usize,
+    pub time_ms: f64,
+}
+impl BenchEntry {
+    fn new(kind: &str, count: usize, rows: usize, t: &Duration) -> Self {
+        BenchEntry {
+            kind: kind.to_owned(),
+            count,
+            rows,
+            time_ms: t.as_secs_f64() * 1e3,
+        }
+    }
+
+    fn rate(&self) -> String {
+        if self.time_ms == 0. {
+            "-".into()
+        } else {
+            format!("{:.0}", self.rows as f64 / self.time_ms * 1e3)
+        }
+    }
+}
+
+/// The time spent on each kind of computation when filling a trace
+#[derive(Serialize)]
+pub struct BenchReport {
+    pub kinds: Vec<BenchEntry>,
+    pub total: BenchEntry,
+}
+impl From<&ComputeTimings> for BenchReport {
+    fn from(timings: &ComputeTimings) -> Self {
+        BenchReport {
+            kinds: timings
+                .kinds
+                .iter()
+                .map(|(kind, (count, t, rows))| BenchEntry::new(kind, *count, *rows, t))
+                .collect(),
+            total: BenchEntry::new(
+                "total",
+                timings.kinds.values().map(|k| k.0).sum(),
+                timings.rows,
+                &timings.total,
+            ),
+        }
+    }
+}
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>20} {:>8} {:>12} {:>12} {:>14}",
+            "kind", "count", "rows", "time (ms)", "rows/s"
+        )?;
+        for k in self.kinds.iter().chain(std::iter::once(&self.total)) {
+            write!(
+                f,
+                "\n{:>20} {:>8} {:>12} {:>12.3} {:>14}",
+                k.kind,
+                k.count,
+                k.rows,
+                k.time_ms,
+                k.rate()
+            )?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct ModuleEstimate {
+    pub module: String,
+    /// the number of rows of the module, padding included, if known
+    pub rows: Option<usize>,
+    /// the number of computed columns in the module
+    pub columns: usize,
+    /// the number of values to compute, if the size of the module is known
+    pub values: Option<usize>,
+}
+
+/// An estimation of the resources required to fill the computed columns of a
+/// trace, from the module sizes set by an imported trace or by their declared
+/// heights
+#[derive(Serialize)]
+pub struct ComputeEstimate {
+    pub modules: Vec<ModuleEstimate>,
+    pub values: usize,
+    pub bytes: usize,
+}
+impl std::fmt::Display for ComputeEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_unknown = |x: Option<usize>| x.map(|x| x.to_string()).unwrap_or("?".into());
+        write!(
+            f,
+            "{:>20} {:>12} {:>10} {:>14}",
+            "module", "rows", "computed", "values"
+        )?;
+        for m in self.modules.iter() {
+            write!(
+                f,
+                "\n{:>20} {:>12} {:>10} {:>14}",
+                m.module,
+                or_unknown(m.rows),
+                m.columns,
+                or_unknown(m.values)
+            )?;
+        }
+        write!(
+            f,
+            "\n{:>20} {:>12} {:>10} {:>14}\n~{}",
+            "total",
+            "",
+            self.modules.iter().map(|m| m.columns).sum::<usize>(),
+            self.values,
+            ["B", "KiB", "MiB", "GiB"]
+                .iter()
+                .enumerate()
+                .map(|(i, unit)| (self.bytes as f64 / 1024f64.powi(i as i32), unit))
+                .find(|(size, unit)| *size < 1024. || **unit == "GiB")
+                .map(|(size, unit)| format!("{:.1}{}", size, unit))
+                .unwrap()
+        )
+    }
+}
+
+/// Estimate, without computing them, how many values the computed columns of
+/// `cs` will hold, and the memory they will require
+pub fn estimate(cs: &ConstraintSet) -> ComputeEstimate {
+    let mut modules: BTreeMap<String, ModuleEstimate> = BTreeMap::new();
+    for (h, column) in cs.columns.iter() {
+        let computation = if let Some(computation) = cs.computations.computation_for(&h) {
+            computation
+        } else {
+            continue;
+        };
+        let module = &column.handle.module;
+        // The size of a module is set by its imported columns, or by the
+        // height it has been declared with, to which the padding row is
+        // prepended; constant tables set the size of their module themselves
+        let rows = cs
+            .effective_len_for(module)
+            .map(|l| l as usize)
+            .or_else(|| cs.columns.heights.get(module).map(|h| h + 1))
+            .or(match computation {
+                Computation::ConstantTable { values, .. } => Some(values.len() + 1),
+                _ => None,
+            });
+        let values = rows.map(|rows| {
+            rows * cs.length_multiplier(&h) + cs.spilling_for_column(&h).unwrap_or(0) as usize
+        });
+
+        let m = modules
+            .entry(module.to_owned())
+            .or_insert_with(|| ModuleEstimate {
+                module: module.to_owned(),
+                rows,
+                columns: 0,
+                values: Some(0),
+            });
+        m.columns += 1;
+        m.values = m.values.zip(values).map(|(ax, x)| ax + x);
+    }
+
+    let values = modules.values().filter_map(|m| m.values).sum();
+    ComputeEstimate {
+        modules: modules.into_values().collect(),
+        values,
+        bytes: values * std::mem::size_of::<Value>(),
+    }
+}
+
 #[time("info", "Computing expanded columns")]
-fn compute_all(cs: &mut ConstraintSet) -> Result<()> {
+fn compute_all(
+    cs: &mut ConstraintSet,
+    mut timings: Option<&mut ComputeTimings>,
+    fail_fast: bool,
+) -> Result<()> {
+    let start = Instant::now();
     // Computations are split in sequentially dependent sets, where each set as
     // to be completely computed before the next one is started, but all
     // computations within a set can be processed in parallel
     let jobs = ComputationDag::from_computations(cs.computations.iter());
+    let groups = jobs.fixpoint_groups();
+
+    // Constant tables are not filled from a trace, and thus have to set the
+    // length of their module themselves
+    let table_lens = cs
+        .computations
+        .iter()
+        .filter_map(|c| match c {
+            Computation::ConstantTable { target, values } => {
+                Some((cs.columns.module_of(target), values.len() as isize + 1))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for (module, len) in table_lens {
+        cs.effective_len_or_set(&module, len);
+    }
 
     let mut exo_operations = HashSet::new();
 
@@ -147,12 +375,61 @@ fn compute_all(cs: &mut ConstraintSet) -> Result<()> {
             .map(|i| cs.computations.get(*i).unwrap().to_owned())
             .collect::<Vec<_>>();
 
+        // Mutually dependent fixpoint computations have to be computed
+        // together rather than one by one
+        let (fixpoints, comps): (Vec<_>, Vec<_>) = comps
+            .into_iter()
+            .partition(|c| matches!(c, Computation::Fixpoint { .. }));
+        let fixpoint_groups = fixpoints.iter().into_group_map_by(|c| {
+            if let Computation::Fixpoint { target, .. } = c {
+                groups.get(target).unwrap_or(target).clone()
+            } else {
+                unreachable!()
+            }
+        });
+        let fixpoint_results = fixpoint_groups
+            .into_values()
+            .filter_map(|comps| {
+                let group = comps
+                    .iter()
+                    .filter_map(|c| match c {
+                        Computation::Fixpoint { target, exp } => Some((target, exp)),
+                        _ => None,
+                    })
+                    .filter(|(target, _)| !cs.columns.is_computed(target))
+                    .collect::<Vec<_>>();
+                if group.is_empty() {
+                    return None;
+                }
+                let start = Instant::now();
+                let r = compute_fixpoint(cs, &group).with_context(|| {
+                    anyhow!(
+                        "while computing {}",
+                        group.iter().map(|(t, _)| t.to_string()).join(", ")
+                    )
+                });
+                if let Some(timings) = timings.as_mut() {
+                    timings.record(comps[0], start.elapsed(), &r);
+                }
+                Some(r)
+            })
+            .collect::<Vec<_>>();
+
         for r in comps
             .iter()
             // .into_par_iter() // TODO: is that a bottleneck?
-            .filter_map(|comp| apply_computation(cs, comp, &mut exo_operations))
+            .filter_map(|comp| {
+                let start = Instant::now();
+                let r = apply_computation(cs, comp, &mut exo_operations)?
+                    .with_context(|| anyhow!("while computing {}", comp.pretty_target()));
+                if let Some(timings) = timings.as_mut() {
+                    timings.record(comp, start.elapsed(), &r);
+                }
+                Some(r)
+            })
             .collect::<Vec<_>>()
             .into_iter()
+            .chain(fixpoint_results)
         {
             match r {
                 Ok(xs) => {
@@ -163,13 +440,22 @@ fn compute_all(cs: &mut ConstraintSet) -> Result<()> {
                             .with_context(|| anyhow!("while filling {}", h.pretty()))?;
                     }
                 }
-                Err(e) => warn!("{}", e),
+                Err(e) => {
+                    if fail_fast {
+                        return Err(e);
+                    } else {
+                        warn!("{:#}", e)
+                    }
+                }
             }
         }
     }
 
     compute_ancillaries(cs, exo_operations)?;
 
+    if let Some(timings) = timings {
+        timings.total = start.elapsed();
+    }
     Ok(())
 }
 
@@ -199,20 +485,75 @@ fn compute_interleaved(
         bail!("interleaving columns of incoherent lengths")
     }
 
-    let final_len = froms.iter().map(|h| cs.columns.len(h).unwrap()).sum();
+    // The spilling of the sources is interleaved as well, so that the target
+    // spills over as many of its own rows as the sources do over theirs
+    let from_spilling = cs.spilling_for_column(&froms[0]).unwrap();
+    let spilling = cs.spilling_for_column(target).unwrap();
     let count = froms.len();
+    let final_len = (cs.columns.len(&froms[0]).unwrap() + from_spilling as usize) * count;
     let values = (0..final_len)
         .map(|k| {
-            let i = k / count;
+            let i = (k / count) as isize - from_spilling;
             let j = k % count;
-            cs.columns
-                .get(&froms[j], i as isize, false)
-                .unwrap()
-                .clone()
+            cs.columns.get(&froms[j], i, false).unwrap().clone()
         })
         .collect();
 
-    Ok(vec![(target.to_owned(), ValueBacking::from_vec(values, 0))])
+    Ok(vec![(
+        target.to_owned(),
+        ValueBacking::from_vec(values, spilling),
+    )])
+}
+
+fn compute_interleaved_strided(
+    cs: &ConstraintSet,
+    froms: &[ColumnRef],
+    strides: &[usize],
+    target: &ColumnRef,
+) -> Result<Vec<ComputedColumn>> {
+    for from in froms.iter() {
+        ensure_is_computed(from, cs)?;
+    }
+
+    // Every source, including its spilling, must be made of as many blocks of
+    // its stride as the others
+    let spillings = froms
+        .iter()
+        .map(|from| cs.spilling_for_column(from).unwrap())
+        .collect::<Vec<_>>();
+    let blocks = froms
+        .iter()
+        .zip(spillings.iter())
+        .zip(strides.iter())
+        .map(|((from, spilling), stride)| {
+            let len = cs.columns.len(from).unwrap() + *spilling as usize;
+            if len % stride == 0 {
+                Some(len / stride)
+            } else {
+                None
+            }
+        })
+        .collect::<Option<Vec<_>>>();
+    let blocks = match blocks {
+        Some(blocks) if blocks.windows(2).all(|w| w[0] == w[1]) => blocks[0],
+        _ => bail!("interleaving columns of incoherent lengths"),
+    };
+
+    let spilling = cs.spilling_for_column(target).unwrap();
+    let mut values = Vec::with_capacity(blocks * strides.iter().sum::<usize>());
+    for b in 0..blocks {
+        for ((from, from_spilling), stride) in froms.iter().zip(spillings.iter()).zip(strides) {
+            for k in 0..*stride {
+                let i = (b * stride + k) as isize - from_spilling;
+                values.push(cs.columns.get(from, i, false).unwrap().clone());
+            }
+        }
+    }
+
+    Ok(vec![(
+        target.to_owned(),
+        ValueBacking::from_vec(values, spilling),
+    )])
 }
 
 fn compute_sorted(
@@ -287,6 +628,110 @@ fn compute_exoconstant(
     )])
 }
 
+fn compute_constant_table(
+    cs: &ConstraintSet,
+    target: &ColumnRef,
+    values: &[Value],
+) -> Result<Vec<ComputedColumn>> {
+    let spilling = cs.spilling_for_column(target).unwrap();
+
+    // The padding row of the table is 0, so that padding rows of the
+    // looked-up columns always find a match
+    let value: Vec<Value> = vec![Value::zero(); spilling as usize + 1]
+        .into_iter()
+        .chain(values.iter().cloned())
+        .collect();
+
+    Ok(vec![(
+        target.to_owned(),
+        ValueBacking::from_vec(value, spilling),
+    )])
+}
+
+fn compute_active_rows(cs: &ConstraintSet, target: &ColumnRef) -> Result<Vec<ComputedColumn>> {
+    let module = cs.columns.module_of(target);
+    let spilling = cs.spilling_for_column(target).unwrap();
+    let len = cs.iter_len(&module);
+    // Modules that have not been imported are only made of padding
+    let padding = cs.columns.padding.get(&module).cloned().unwrap_or(len);
+
+    let values = vec![Value::zero(); spilling as usize]
+        .into_iter()
+        .chain((0..len).map(|i| {
+            if i < padding {
+                Value::zero()
+            } else {
+                Value::one()
+            }
+        }))
+        .collect();
+
+    Ok(vec![(
+        target.to_owned(),
+        ValueBacking::from_vec(values, spilling),
+    )])
+}
+
+fn compute_lookup_multiplicity(
+    cs: &ConstraintSet,
+    including: &[Node],
+    included: &[Node],
+    target: &ColumnRef,
+) -> Result<Vec<ComputedColumn>> {
+    for from in including
+        .iter()
+        .chain(included.iter())
+        .flat_map(|e| e.dependencies())
+    {
+        ensure_is_computed(&from, cs)?;
+    }
+
+    let row = |exps: &[Node], i: usize| -> Vec<Value> {
+        exps.iter()
+            .map(|e| {
+                e.eval(
+                    i as isize,
+                    |handle, j, wrap| {
+                        cs.columns
+                            .get(handle, j, wrap.explicit())
+                            .or_else(|| cs.columns.column(handle).unwrap().padding_value.clone())
+                    },
+                    &mut None,
+                    &EvalSettings::default(),
+                )
+                .unwrap_or_else(Value::zero)
+            })
+            .collect()
+    };
+
+    let spilling = cs.spilling_for_column(target).unwrap();
+    let including_len = cs
+        .iter_len_of_exprs(including)
+        .unwrap_or_else(|| cs.iter_len(&cs.columns.module_of(target)));
+    let included_len = cs.iter_len_of_exprs(included).unwrap_or(0);
+
+    let mut looked_up = HashMap::<Vec<Value>, usize>::new();
+    for i in 0..included_len {
+        *looked_up.entry(row(included, i)).or_default() += 1;
+    }
+    // If a row is repeated in the table, its first occurrence accounts for
+    // all of its lookups
+    let values = vec![Value::zero(); spilling as usize]
+        .into_iter()
+        .chain((0..including_len).map(|i| {
+            looked_up
+                .remove(&row(including, i))
+                .map(Value::from)
+                .unwrap_or_else(Value::zero)
+        }))
+        .collect();
+
+    Ok(vec![(
+        target.to_owned(),
+        ValueBacking::from_vec(values, spilling),
+    )])
+}
+
 fn compute_exooperation(
     cs: &ConstraintSet,
     op: ExoOperation,
@@ -300,8 +745,8 @@ fn compute_exooperation(
         .unwrap();
 
     let mut cache = Some(cached::SizedCache::with_size(200000)); // ~1.60MB cache
-    let getter = |handle: &ColumnRef, j, _| {
-        cs.columns.get(handle, j, false).or_else(|| {
+    let getter = |handle: &ColumnRef, j, wrap| {
+        cs.columns.get(handle, j, wrap).or_else(|| {
             cs.columns
                 .column(handle)
                 .unwrap()
@@ -386,7 +831,7 @@ pub fn compute_expression(
     }
 
     let module = cs.columns.module_of(target);
-    let spilling = cs.spilling_of(&module).unwrap();
+    let spilling = cs.spilling_for_column(target).unwrap();
 
     Ok(vec![(
         target.to_owned(),
@@ -432,6 +877,209 @@ pub fn compute_expression(
     )])
 }
 
+fn compute_clamp(
+    cs: &ConstraintSet,
+    exp: &Node,
+    lo: &Value,
+    hi: &Value,
+    [target, below, above]: [&ColumnRef; 3],
+) -> Result<Vec<ComputedColumn>> {
+    for from in exp.dependencies() {
+        ensure_is_computed(&from, cs)?;
+    }
+
+    let spilling = cs.spilling_for_column(target).unwrap();
+    let length = cs
+        .dependencies_len(exp, false)?
+        .ok_or_else(|| anyhow!("unable to find the length of {}", exp.pretty()))?;
+
+    let mut targets = Vec::with_capacity(length + spilling as usize);
+    let mut belows = Vec::with_capacity(length + spilling as usize);
+    let mut aboves = Vec::with_capacity(length + spilling as usize);
+    for i in -spilling..length as isize {
+        let x = exp
+            .eval(
+                i,
+                |handle, j, wrap| {
+                    cs.columns
+                        .get(handle, j, wrap.explicit())
+                        .or_else(|| cs.columns.column(handle).unwrap().padding_value.clone())
+                },
+                &mut None,
+                &EvalSettings::default(),
+            )
+            .unwrap_or_else(Value::zero);
+        let (r, below, above) = if x.to_bi() < lo.to_bi() {
+            (lo.clone().same_as(&x), Value::one(), Value::zero())
+        } else if x.to_bi() > hi.to_bi() {
+            (hi.clone().same_as(&x), Value::zero(), Value::one())
+        } else {
+            (x, Value::zero(), Value::zero())
+        };
+        targets.push(r);
+        belows.push(below);
+        aboves.push(above);
+    }
+
+    Ok(vec![
+        (target.to_owned(), ValueBacking::from_vec(targets, spilling)),
+        (below.to_owned(), ValueBacking::from_vec(belows, spilling)),
+        (above.to_owned(), ValueBacking::from_vec(aboves, spilling)),
+    ])
+}
+
+fn compute_recurrence(
+    cs: &ConstraintSet,
+    exp: &Node,
+    init: &Value,
+    target: &ColumnRef,
+) -> Result<Vec<ComputedColumn>> {
+    let froms = exp
+        .dependencies()
+        .into_iter()
+        .filter(|from| from != target)
+        .collect::<Vec<_>>();
+    for from in froms.iter() {
+        ensure_is_computed(from, cs)?;
+    }
+
+    let spilling = cs.spilling_for_column(target).unwrap();
+    let length = froms
+        .iter()
+        .find_map(|from| cs.columns.len(from))
+        .unwrap_or_else(|| cs.iter_len(&cs.columns.module_of(target)));
+
+    // Rows have to be computed sequentially, as each of them may depend on
+    // the previous one
+    let mut values: Vec<Value> = Vec::with_capacity(length + spilling as usize);
+    for i in -spilling..length as isize {
+        let x = exp
+            .eval(
+                i,
+                |handle, j, wrap| {
+                    if handle == target {
+                        let j = j + spilling;
+                        if j < 0 {
+                            Some(init.clone())
+                        } else {
+                            values.get(j as usize).cloned()
+                        }
+                    } else {
+                        cs.columns
+                            .get(handle, j, wrap.explicit())
+                            .or_else(|| cs.columns.column(handle).unwrap().padding_value.clone())
+                    }
+                },
+                &mut None,
+                &EvalSettings::default(),
+            )
+            .unwrap_or_else(Value::zero);
+        values.push(x);
+    }
+
+    Ok(vec![(
+        target.to_owned(),
+        ValueBacking::from_vec(values, spilling),
+    )])
+}
+
+/// How many times a group of fixpoint computations may be iterated before
+/// giving up
+const MAX_FIXPOINT_ITERATIONS: usize = 64;
+
+/// Jointly compute a group of mutually dependent fixpoint computations.
+///
+/// All the targets start at zero, then each one of them is recomputed in turn
+/// from the current values of the others until none of them change anymore.
+fn compute_fixpoint(
+    cs: &ConstraintSet,
+    group: &[(&ColumnRef, &Node)],
+) -> Result<Vec<ComputedColumn>> {
+    let targets = group
+        .iter()
+        .map(|(t, _)| (*t).clone())
+        .collect::<HashSet<_>>();
+    let mut values = HashMap::new();
+    let mut spillings = HashMap::new();
+    for (target, exp) in group.iter() {
+        let froms = exp
+            .dependencies()
+            .into_iter()
+            .filter(|from| !targets.contains(from))
+            .collect::<Vec<_>>();
+        for from in froms.iter() {
+            ensure_is_computed(from, cs)?;
+        }
+
+        let spilling = cs.spilling_for_column(target).unwrap();
+        let length = froms
+            .iter()
+            .find_map(|from| cs.columns.len(from))
+            .unwrap_or_else(|| cs.iter_len(&cs.columns.module_of(target)));
+        spillings.insert((*target).clone(), spilling);
+        values.insert(
+            (*target).clone(),
+            vec![Value::zero(); length + spilling as usize],
+        );
+    }
+
+    for i in 1..=MAX_FIXPOINT_ITERATIONS {
+        let mut changed = false;
+        for (target, exp) in group.iter() {
+            let spilling = spillings[*target];
+            let len = values[*target].len() as isize - spilling;
+            let new_values = (-spilling..len)
+                .map(|i| {
+                    exp.eval(
+                        i,
+                        |handle, j, wrap| {
+                            if let Some(xs) = values.get(handle) {
+                                let j = j + spillings[handle];
+                                if j < 0 {
+                                    None
+                                } else {
+                                    xs.get(j as usize).cloned()
+                                }
+                            } else {
+                                cs.columns.get(handle, j, wrap.explicit()).or_else(|| {
+                                    cs.columns.column(handle).unwrap().padding_value.clone()
+                                })
+                            }
+                        },
+                        &mut None,
+                        &EvalSettings::default(),
+                    )
+                    .unwrap_or_else(Value::zero)
+                })
+                .collect::<Vec<_>>();
+            if new_values != values[*target] {
+                changed = true;
+                values.insert((*target).clone(), new_values);
+            }
+        }
+        if !changed {
+            trace!(
+                "{} converged after {} iterations",
+                targets.iter().map(|t| t.pretty()).join(", "),
+                i
+            );
+            return Ok(values
+                .into_iter()
+                .map(|(target, xs)| {
+                    let spilling = spillings[&target];
+                    (target, ValueBacking::from_vec(xs, spilling))
+                })
+                .collect());
+        }
+    }
+
+    bail!(
+        "fixpoint computation of {} did not converge after {} iterations",
+        targets.iter().map(|t| t.pretty()).sorted().join(", "),
+        MAX_FIXPOINT_ITERATIONS
+    )
+}
+
 fn compute_sorting_auxs(cs: &ConstraintSet, comp: &Computation) -> Result<Vec<ComputedColumn>> {
     if let Computation::SortingConstraints {
         ats,
@@ -441,6 +1089,7 @@ fn compute_sorting_auxs(cs: &ConstraintSet, comp: &Computation) -> Result<Vec<Co
         signs,
         froms,
         sorted,
+        endianness,
     } = comp
     {
         assert!(delta_bytes.len() == 16);
@@ -501,14 +1150,10 @@ fn compute_sorting_auxs(cs: &ConstraintSet, comp: &Computation) -> Result<Vec<Co
             eq_values.push(if found { Value::zero() } else { Value::one() });
 
             // Compute delta bytes-decomposition
-            delta
-                .to_bytes()
+            endianness
+                .decompose(&delta, delta_bytes.len()) // TODO: ensure that stays coherent with field size
                 .into_iter()
-                .rev()
-                .map(|i| Value::from(i as usize))
-                .chain(std::iter::repeat(Value::zero()))
                 .enumerate()
-                .take(16) // TODO: ensure that stays coherent with field size
                 .for_each(|(i, b)| delta_bytes_values[i].push(b));
         }
 
@@ -556,6 +1201,20 @@ pub fn apply_computation(
                 None
             }
         }
+        Computation::Fixpoint { target, exp } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_fixpoint(cs, &[(target, exp)]))
+            } else {
+                None
+            }
+        }
+        Computation::Recurrence { target, exp, init } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_recurrence(cs, exp, init, target))
+            } else {
+                None
+            }
+        }
         Computation::Interleaved { target, froms } => {
             if !cs.columns.is_computed(target) {
                 Some(compute_interleaved(cs, froms, target))
@@ -563,7 +1222,20 @@ pub fn apply_computation(
                 None
             }
         }
-        Computation::Sorted { froms, tos, signs } => {
+        Computation::InterleavedStrided {
+            target,
+            froms,
+            strides,
+        } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_interleaved_strided(cs, froms, strides, target))
+            } else {
+                None
+            }
+        }
+        Computation::Sorted {
+            froms, tos, signs, ..
+        } => {
             if !cs.columns.is_computed(&tos[0]) {
                 Some(compute_sorted(cs, froms, tos, signs))
             } else {
@@ -600,6 +1272,45 @@ pub fn apply_computation(
                 None
             }
         }
+        Computation::ConstantTable { target, values } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_constant_table(cs, target, values))
+            } else {
+                None
+            }
+        }
+        Computation::LookupMultiplicity {
+            target,
+            including,
+            included,
+        } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_lookup_multiplicity(cs, including, included, target))
+            } else {
+                None
+            }
+        }
+        Computation::ActiveRows { target } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_active_rows(cs, target))
+            } else {
+                None
+            }
+        }
+        Computation::Clamp {
+            target,
+            below,
+            above,
+            exp,
+            lo,
+            hi,
+        } => {
+            if !cs.columns.is_computed(target) {
+                Some(compute_clamp(cs, exp, lo, hi, [target, below, above]))
+            } else {
+                None
+            }
+        }
         comp @ Computation::SortingConstraints { eq, .. } => {
             // NOTE all are computed at once, checking an arbitrary one (here
             // eq) is enough
@@ -620,8 +1331,29 @@ fn err_missing_column(c: &crate::column::Column) -> RuntimeError {
     }
 }
 
-pub fn prepare(cs: &mut ConstraintSet, fail_on_missing: bool) -> Result<()> {
-    compute_all(cs).with_context(|| "while computing columns")?;
+impl ConstraintSet {
+    /// Fill the computed columns of an imported trace, only warning about the
+    /// computations that fail; their target columns are left empty
+    pub fn compute_all(&mut self) -> Result<()> {
+        compute_all(self, None, false)
+    }
+
+    /// Fill the computed columns of an imported trace, returning the first
+    /// failing computation as an error
+    pub fn compute_all_strict(&mut self) -> Result<()> {
+        compute_all(self, None, true)
+    }
+}
+
+/// Fill the computed columns of an imported trace; if `fail_fast` is set, the
+/// first failing computation aborts the process instead of being reported
+pub fn prepare(cs: &mut ConstraintSet, fail_on_missing: bool, fail_fast: bool) -> Result<()> {
+    if fail_fast {
+        cs.compute_all_strict()
+    } else {
+        cs.compute_all()
+    }
+    .with_context(|| "while computing columns")?;
     for h in cs.columns.all() {
         if !cs.columns.is_computed(&h) {
             let err = err_missing_column(cs.columns.column(&h).unwrap());
@@ -636,13 +1368,43 @@ pub fn prepare(cs: &mut ConstraintSet, fail_on_missing: bool) -> Result<()> {
     Ok(())
 }
 
-pub fn compute_trace(tracefile: &str, cs: &mut ConstraintSet, fail_on_missing: bool) -> Result<()> {
+/// Fill the computed columns of an imported trace, measuring the time spent
+/// on each kind of computation
+pub fn bench(cs: &mut ConstraintSet, fail_fast: bool) -> Result<ComputeTimings> {
+    let mut timings = ComputeTimings::default();
+    compute_all(cs, Some(&mut timings), fail_fast).with_context(|| "while computing columns")?;
+    Ok(timings)
+}
+
+pub fn import_trace(tracefile: &str, cs: &mut ConstraintSet) -> Result<()> {
+    #[cfg(feature = "arrow")]
+    if tracefile.ends_with(".parquet") {
+        return import::parse_parquet_trace(tracefile, cs, false);
+    }
     if tracefile.ends_with("lt") {
-        import::parse_binary_trace(tracefile, cs, false)?;
+        import::parse_binary_trace(tracefile, cs, false)
     } else {
-        import::parse_json_trace(tracefile, cs, false)?;
+        import::parse_json_trace(tracefile, cs, false)
     }
-    prepare(cs, fail_on_missing)
+}
+
+/// Build a dedicated pool of `threads` workers to run the commands on, so that
+/// computations do not depend on the size of the global rayon pool
+pub fn thread_pool(threads: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .with_context(|| anyhow!("while building a pool of {} threads", threads))
+}
+
+pub fn compute_trace(
+    tracefile: &str,
+    cs: &mut ConstraintSet,
+    fail_on_missing: bool,
+    fail_fast: bool,
+) -> Result<()> {
+    import_trace(tracefile, cs)?;
+    prepare(cs, fail_on_missing, fail_fast)
 }
 
 // This is only used by the lib
@@ -651,7 +1413,8 @@ pub fn compute_trace_str(
     trace: &[u8],
     cs: &mut ConstraintSet,
     fail_on_missing: bool,
+    fail_fast: bool,
 ) -> Result<()> {
     import::read_trace_str(trace, cs, false)?;
-    prepare(cs, fail_on_missing)
+    prepare(cs, fail_on_missing, fail_fast)
 }
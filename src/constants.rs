@@ -1,7 +1,20 @@
+use std::sync::OnceLock;
+
+use ark_bls12_377::fr::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use num_bigint::{BigInt, Sign};
+
 use crate::compiler::Magma;
 
 pub const FIELD_BITSIZE: usize = 254;
 
+static FIELD_MODULUS: OnceLock<BigInt> = OnceLock::new();
+
+/// The order of the field in which the constraints are expressed
+pub fn field_modulus() -> &'static BigInt {
+    FIELD_MODULUS.get_or_init(|| BigInt::from_bytes_le(Sign::Plus, &Fr::MODULUS.to_bytes_le()))
+}
+
 pub fn col_count_bits(x: usize) -> usize {
     (x + FIELD_BITSIZE - 1) / FIELD_BITSIZE
 }
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{column::Computation, compiler::ColumnRef};
 
@@ -6,6 +6,8 @@ use crate::{column::Computation, compiler::ColumnRef};
 pub(crate) struct ComputationDag {
     nodes: HashSet<ColumnRef>,
     edges: HashSet<(ColumnRef, ColumnRef)>,
+    /// the targets of fixpoint computations, that may depend on each other
+    fixpoints: HashSet<ColumnRef>,
 }
 
 impl ComputationDag {
@@ -23,6 +25,11 @@ impl ComputationDag {
         self.edges.insert((n1.clone(), n2.clone()));
     }
 
+    /// Iterate over the `(from, to)` pairs, where `to` is computed from `from`
+    pub fn edges(&self) -> impl Iterator<Item = &(ColumnRef, ColumnRef)> {
+        self.edges.iter()
+    }
+
     fn sinks(&self) -> Vec<ColumnRef> {
         self.nodes
             .iter()
@@ -58,7 +65,19 @@ impl ComputationDag {
                     self.depends(&from, target);
                 }
             }
-            Computation::Interleaved { target, froms } => {
+            Computation::Fixpoint { target, exp } => {
+                // Cycles between fixpoint columns are allowed, and resolved
+                // by job_slices() by grouping them together
+                self.nodes.insert(target.clone());
+                self.fixpoints.insert(target.clone());
+                for from in exp.dependencies() {
+                    if from != *target {
+                        self.depends(&from, target);
+                    }
+                }
+            }
+            Computation::Interleaved { target, froms }
+            | Computation::InterleavedStrided { target, froms, .. } => {
                 for from in froms.iter() {
                     self.depends(from, target);
                 }
@@ -83,6 +102,46 @@ impl ComputationDag {
                 }
             }
             Computation::ExoConstant { .. } => {}
+            Computation::ConstantTable { target, .. } | Computation::ActiveRows { target } => {
+                self.nodes.insert(target.clone());
+            }
+            Computation::LookupMultiplicity {
+                target,
+                including,
+                included,
+            } => {
+                self.nodes.insert(target.clone());
+                for from in including
+                    .iter()
+                    .chain(included.iter())
+                    .flat_map(|e| e.dependencies())
+                {
+                    self.depends(&from, target);
+                }
+            }
+            Computation::Recurrence { target, exp, .. } => {
+                // The self-reference is resolved row by row when computing
+                // the column, and must not appear in the DAG
+                self.nodes.insert(target.clone());
+                for from in exp.dependencies() {
+                    if from != *target {
+                        self.depends(&from, target);
+                    }
+                }
+            }
+            Computation::Clamp {
+                target,
+                below,
+                above,
+                exp,
+                ..
+            } => {
+                for from in exp.dependencies() {
+                    for to in [target, below, above] {
+                        self.depends(&from, to);
+                    }
+                }
+            }
             Computation::SortingConstraints {
                 ats,
                 eq,
@@ -104,8 +163,75 @@ impl ComputationDag {
         }
     }
 
-    /// Returns a pseudo-topological sorting, a list of sets of independent columns
+    /// Map every fixpoint column to the representative of the group of the
+    /// fixpoint columns it is mutually dependent with, i.e. the smallest
+    /// column of the group
+    pub fn fixpoint_groups(&self) -> HashMap<ColumnRef, ColumnRef> {
+        let mut r = self
+            .fixpoints
+            .iter()
+            .map(|f| (f.clone(), f.clone()))
+            .collect::<HashMap<_, _>>();
+        for (from, to) in self.edges.iter() {
+            if let (Some(a), Some(b)) = (r.get(from).cloned(), r.get(to).cloned()) {
+                if a != b {
+                    let (keep, drop) = if a < b { (a, b) } else { (b, a) };
+                    for representative in r.values_mut() {
+                        if *representative == drop {
+                            *representative = keep.clone();
+                        }
+                    }
+                }
+            }
+        }
+        r
+    }
+
+    /// Returns a pseudo-topological sorting, a list of sets of independent
+    /// columns; mutually dependent fixpoint columns are always in the same set
     pub fn job_slices(&self) -> Vec<HashSet<ColumnRef>> {
+        if self.fixpoints.is_empty() {
+            return self.acyclic_job_slices();
+        }
+
+        // Each group of fixpoint columns is collapsed into its representative,
+        // so that the graph is acyclic; then expanded back once sorted
+        let groups = self.fixpoint_groups();
+        let representative = |n: &ColumnRef| groups.get(n).unwrap_or(n).clone();
+        let mut collapsed = ComputationDag::default();
+        for n in self.nodes.iter() {
+            collapsed.nodes.insert(representative(n));
+        }
+        for (from, to) in self.edges.iter() {
+            let (from, to) = (representative(from), representative(to));
+            if from != to {
+                collapsed.depends(&from, &to);
+            }
+        }
+
+        collapsed
+            .acyclic_job_slices()
+            .into_iter()
+            .map(|slice| {
+                slice
+                    .into_iter()
+                    .flat_map(|n| {
+                        let mut members = groups
+                            .iter()
+                            .filter(|(_, r)| **r == n)
+                            .map(|(m, _)| m.clone())
+                            .collect::<Vec<_>>();
+                        if members.is_empty() {
+                            members.push(n);
+                        }
+                        members
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn acyclic_job_slices(&self) -> Vec<HashSet<ColumnRef>> {
         let mut r = Vec::new();
         let mut visited = HashSet::new();
 
@@ -1,6 +1,6 @@
 use super::pretty::Pretty;
+use crate::utils::Colorize;
 use crate::{column::Value, pretty::Base};
-use owo_colors::OwoColorize;
 use thiserror::Error;
 
 use crate::{
@@ -10,8 +10,8 @@ use crate::{
 
 #[derive(Error, Debug)]
 pub(crate) enum CompileError<'a> {
-    #[error("{}", compiler::make_type_error_msg(.0, .1, .2))]
-    TypeError(String, &'a [&'a [Type]], Vec<Type>),
+    #[error("{}", compiler::make_type_error_msg(.0, .1, .2, .3))]
+    TypeError(String, &'a [&'a [Type]], Vec<Type>, Option<String>),
 
     #[error("{} expects a condition, found {}", .0, .1.red().bold())]
     ConditioningError(String, Type),
@@ -19,9 +19,24 @@ pub(crate) enum CompileError<'a> {
     #[error("{} is never used", .0.pretty())]
     NotUsed(Handle),
 
+    #[error("constant {} is never referenced", .0.pretty())]
+    UnreferencedConstant(Handle),
+
     #[error("column {} not found", .0.pretty())]
     NotFound(Handle),
 
+    #[error("{} is not divisible by {}", .0.bold(), .1.bold())]
+    InexactDivision(String, String),
+
+    #[error("{} may only be used in computed columns and constants, found in {}", .0.red().bold(), .1.pretty())]
+    IntegerOperation(String, Handle),
+
+    #[error("{} expects compile-time constants, found {}", .0, .1.red().bold())]
+    NotConstant(String, String),
+
+    #[error("exponent {} must be a non-negative compile-time constant", .0.red().bold())]
+    InvalidExponent(String),
+
     #[error("ambiguous {} module for {} {}", .0, .1, .2.pretty())]
     AmbiguousModule(&'static str, &'static str, Handle),
 }
@@ -39,10 +54,16 @@ pub enum RuntimeError {
 
     #[error("expected an array, found {:?}", .0)]
     NotAnArray(Expression),
+
+    #[error("{} reduced modulo zero", .0.pretty())]
+    DivisionByZero(Value),
+
+    #[error("{} is not divisible by {}", .0.pretty(), .1.pretty())]
+    InexactDivision(Value, Value),
 }
 
 pub mod parser {
-    use owo_colors::OwoColorize;
+    use crate::utils::Colorize;
 
     pub fn make_src_error(src: &str, lc: (usize, usize)) -> String {
         let src_str = src
@@ -64,8 +85,8 @@ pub mod parser {
 
 pub(crate) mod compiler {
     use crate::compiler::Type;
+    use crate::utils::Colorize;
     use itertools::Itertools;
-    use owo_colors::OwoColorize;
     use thiserror::Error;
 
     #[derive(Error, Debug)]
@@ -169,19 +190,28 @@ pub(crate) mod compiler {
         (expected_str, found_str)
     }
 
-    pub(crate) fn make_type_error_msg(fname: &str, expected: &[&[Type]], found: &[Type]) -> String {
+    pub(crate) fn make_type_error_msg(
+        fname: &str,
+        expected: &[&[Type]],
+        found: &[Type],
+        location: &Option<String>,
+    ) -> String {
         let (expected_str, found_str) = cyclic_type_comparison_message(expected, found);
         format!(
-            "{} expects {}, found {}",
+            "{} expects {}, found {}{}",
             fname.yellow().bold(),
             expected_str,
-            found_str
+            found_str,
+            location
+                .as_ref()
+                .map(|l| format!(" {}", l))
+                .unwrap_or_default()
         )
     }
 }
 
 pub mod symbols {
-    use owo_colors::OwoColorize;
+    use crate::utils::Colorize;
     use thiserror::Error;
 
     #[derive(Error, Debug)]
@@ -1,6 +1,9 @@
 use crate::compiler::ConstraintSet;
 use crate::{check, compiler, compute, import};
-use crate::{transformer::ExpansionLevel, ConstraintSetBuilder};
+use crate::{
+    transformer::{AutoConstraint, ExpansionLevel},
+    ConstraintSetBuilder,
+};
 use anyhow::*;
 use std::fs;
 use std::sync::Once;
@@ -79,19 +82,29 @@ fn compile(source: &str, level: ExpansionLevel) -> Result<ConstraintSet> {
 }
 
 /// Check a given constraint set against a given trace (in JSON).
-fn check_json_trace(trace: &str, mut cs: ConstraintSet, report: bool) -> Result<bool> {
+fn check_json_trace(trace: &str, cs: ConstraintSet, report: bool) -> Result<bool> {
+    check_json_trace_with(trace, cs, check::DebugSettings::new().report(report))
+}
+
+/// Check a given constraint set against a given trace (in JSON), with the
+/// given checker settings.
+fn check_json_trace_with(
+    trace: &str,
+    mut cs: ConstraintSet,
+    settings: check::DebugSettings,
+) -> Result<bool> {
     let keep_raw = false; // what does this do?
     let fail_on_missing = true;
     // Read trace data into constraint set
     import::read_trace_str(trace.as_bytes(), &mut cs, keep_raw)?;
     // Perform trace expansion
-    compute::prepare(&mut cs, fail_on_missing)?;
+    compute::prepare(&mut cs, fail_on_missing, false)?;
     // Check whether constraints accepted or not.
     let r = check::check(
         &cs,
         &None, // Consider all columns
         &[],   // Consider all constraints
-        check::DebugSettings::new().report(report),
+        settings,
     );
     //
     match r {
@@ -99,3 +112,1551 @@ fn check_json_trace(trace: &str, mut cs: ConstraintSet, report: bool) -> Result<
         _ => Ok(false),
     }
 }
+
+/// Check that `source` accepts, or rejects, each of the given traces at every
+/// expansion level.
+fn check_traces(source: &str, traces: &[(&str, bool)]) {
+    check_traces_with(source, traces, check::DebugSettings::new())
+}
+
+/// Check that `source` accepts, or rejects, each of the given traces at every
+/// expansion level, with the given checker settings.
+fn check_traces_with(source: &str, traces: &[(&str, bool)], settings: check::DebugSettings) {
+    for (trace, expected) in traces {
+        for level in [ExpansionLevel::None, ExpansionLevel::top()] {
+            let cs = compile(source, level).unwrap();
+            assert_eq!(
+                *expected,
+                check_json_trace_with(trace, cs, settings).unwrap(),
+                "{trace}"
+            );
+        }
+    }
+}
+
+/// Compile `source`, then import `trace` and compute the columns it lacks.
+fn expand(source: &str, trace: &str, level: ExpansionLevel) -> ConstraintSet {
+    let mut cs = compile(source, level).unwrap();
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    compute::prepare(&mut cs, true, false).unwrap();
+    cs
+}
+
+/// The values of the column `name` on the given rows, left empty where there
+/// is none.
+fn column_values(
+    cs: &ConstraintSet,
+    name: &str,
+    rows: impl IntoIterator<Item = isize>,
+) -> Vec<String> {
+    let c = cs
+        .columns
+        .all()
+        .into_iter()
+        .find(|c| cs.handle(c).name == name)
+        .unwrap();
+    rows.into_iter()
+        .map(|i| {
+            cs.columns
+                .get(&c, i, false)
+                .map(|x| x.to_string())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[test]
+fn clamp_byte() {
+    let source = "(defcolumns (X :byte) Y (A :binary))
+                  (defconstraint clamped () (if-not-zero A (eq! Y (clamp X 10 20))))";
+    check_traces(
+        source,
+        &[
+            (
+                r#"{"<prelude>": {"X": [3, 15, 200], "Y": [10, 15, 20], "A": [1, 1, 1]}}"#,
+                true,
+            ),
+            (
+                r#"{"<prelude>": {"X": [3, 15, 200], "Y": [3, 15, 20], "A": [1, 1, 1]}}"#,
+                false,
+            ),
+            (
+                r#"{"<prelude>": {"X": [3, 15, 200], "Y": [10, 15, 200], "A": [1, 1, 1]}}"#,
+                false,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn compute_output_is_stable() {
+    let source = "(module a) (defcolumns X Y) (defconstraint c () (vanishes! (- Y (* 2 X))))
+                  (module b) (defcolumns Z (W :byte)) (defconstraint d () (vanishes! (* Z (- W 1))))";
+    let trace = r#"{"a": {"X": [1, 2, 3], "Y": [2, 4, 6]}, "b": {"Z": [0, 0], "W": [1, 5]}}"#;
+    let output = || {
+        let mut out = Vec::new();
+        expand(source, trace, ExpansionLevel::top())
+            .write(&mut out)
+            .unwrap();
+        out
+    };
+    let reference = output();
+    for _ in 0..5 {
+        assert_eq!(reference, output());
+    }
+}
+
+#[test]
+fn compute_is_independent_of_threads() {
+    let source = "(defcolumns A B (C :comp (* A B)) (D :comp (+ C (prev A))))
+                  (definterleaved I (A B))
+                  (defpermutation (S T) ((+ A) (- B)))";
+    let trace = r#"{"<prelude>": {"A": [4, 1, 3, 2], "B": [5, 8, 6, 7]}}"#;
+    let expand = |threads| {
+        let mut cs = compile(source, ExpansionLevel::top()).unwrap();
+        import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+        compute::thread_pool(threads)
+            .unwrap()
+            .install(|| compute::prepare(&mut cs, true, false))
+            .unwrap();
+        let mut out = Vec::new();
+        cs.write(&mut out).unwrap();
+        out
+    };
+    let reference = expand(1);
+    for threads in [2, 4, 8] {
+        assert_eq!(reference, expand(threads));
+    }
+}
+
+#[test]
+fn permutation_traces() {
+    let source = "(defcolumns A B) (defpermutation (SA SB) ((+ A) (- B)))";
+    check_traces(
+        source,
+        &[
+            // Permuted columns are computed from the sources
+            (r#"{"<prelude>": {"A": [3, 1, 1], "B": [5, 2, 7]}}"#, true),
+            (
+                r#"{"<prelude>": {"A": [3, 1, 1], "B": [5, 2, 7], "SA": [1, 1, 3], "SB": [7, 2, 5]}}"#,
+                true,
+            ),
+            // Not a reordering of the sources
+            (
+                r#"{"<prelude>": {"A": [3, 1, 1], "B": [5, 2, 7], "SA": [1, 1, 3], "SB": [7, 2, 2]}}"#,
+                false,
+            ),
+            // A reordering, but not sorted along B
+            (
+                r#"{"<prelude>": {"A": [3, 1, 1], "B": [5, 2, 7], "SA": [1, 1, 3], "SB": [2, 7, 5]}}"#,
+                false,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn permutation_mismatch_row() {
+    let source = "(defcolumns A B) (defpermutation (SA SB) ((+ A) (- B)))";
+    // SB holds 6 instead of 5
+    let trace =
+        r#"{"<prelude>": {"A": [3, 1, 1], "B": [5, 2, 7], "SA": [1, 1, 3], "SB": [7, 2, 6]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+
+    let violations = cs.check().unwrap();
+    assert_eq!(violations.len(), 1);
+    // rows sorted as (A ↑, B ↓) are the padding one, then (1, 7), (1, 2),
+    // and (3, 5) against (3, 6)
+    assert_eq!(violations[0].row, 3);
+    assert!(violations[0].snippet.contains('5') && violations[0].snippet.contains('6'));
+}
+
+#[test]
+fn lookup_into_constant_set() {
+    check_traces(
+        "(defcolumns X) (deflookup small ({1 2 3}) (X))",
+        &[
+            (r#"{"<prelude>": {"X": [1, 3, 2, 2]}}"#, true),
+            (r#"{"<prelude>": {"X": [1, 4, 2]}}"#, false),
+            (r#"{"<prelude>": {"X": [3, 5]}}"#, false),
+        ],
+    );
+
+    // the sides of a lookup are never swapped, so a constant set can not be
+    // the included one
+    assert!(compile(
+        "(defcolumns X) (deflookup small (X) ({1 2 3}))",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn lookup_row_tuples() {
+    let source = "(module table) (defcolumns A B)
+                  (module user) (defcolumns X Y)
+                  (deflookup tuples (table.A table.B) (X Y))";
+    check_traces(
+        source,
+        &[
+            (
+                r#"{"table": {"A": [3, 1], "B": [0, 4]}, "user": {"X": [1, 3, 1], "Y": [4, 0, 4]}}"#,
+                true,
+            ),
+            // (0, 2) is not in the table, even though a linear combination of
+            // its entries may match the one of (3, 0): 2×0 + 3×2 = 2×3 + 3×0
+            (
+                r#"{"table": {"A": [3, 1], "B": [0, 4]}, "user": {"X": [1, 0], "Y": [4, 2]}}"#,
+                false,
+            ),
+            (
+                r#"{"table": {"A": [3, 1], "B": [0, 4]}, "user": {"X": [3, 1], "Y": [4, 0]}}"#,
+                false,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn lookup_multiplicity() {
+    let source = "(module table) (defcolumns V W)
+                  (module user) (defcolumns X Y)
+                  (deflookup-multiplicity l (table.V table.W) (X Y))";
+    let trace = r#"{"table": {"V": [1, 2, 3, 2], "W": [5, 6, 7, 6]},
+                    "user": {"X": [2, 2, 1, 2, 1], "Y": [6, 6, 5, 6, 5]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+
+    // both padding rows are matched, and repeated table rows only count once
+    assert_eq!(
+        column_values(&cs, "l-multiplicity", 0..5),
+        ["1", "2", "3", "0", "0"]
+    );
+    check::check(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+
+    check_traces(
+        source,
+        &[
+            // multiplicities given in the trace are checked against the lookups
+            (
+                r#"{"table": {"V": [1, 2], "W": [5, 6], "l-multiplicity": [0, 1]},
+                    "user": {"X": [1], "Y": [5]}}"#,
+                false,
+            ),
+            // a row missing from the table still fails the lookup
+            (
+                r#"{"table": {"V": [1, 2], "W": [5, 6]}, "user": {"X": [3], "Y": [7]}}"#,
+                false,
+            ),
+        ],
+    );
+
+    // WizardIOP lookups can not carry the multiplicity column
+    #[cfg(feature = "exporters")]
+    {
+        let cs = compile(source, ExpansionLevel::top()).unwrap();
+        assert!(crate::exporters::wizardiop::render(&cs, &None).is_err());
+    }
+}
+
+#[test]
+fn imported_padding_value() {
+    let source = "(defcolumns (NOT :byte :padding 255) Y)
+                  (defconstraint saturated () (vanishes! (- NOT 255)))";
+    let trace = r#"{"<prelude>": {"NOT": [255, 255], "Y": [1, 2]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+
+    // the padding row
+    assert_eq!(column_values(&cs, "NOT", 0..1), ["255"]);
+    assert_eq!(column_values(&cs, "Y", 0..1), ["0"]);
+    check::check(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+}
+
+#[test]
+fn wrapping_shift() {
+    let source = "(defcolumns X (ROT :comp (rot-shift X -1)) (SHIFTED :comp (shift X -1)))
+                  (defconstraint rotated () (vanishes! (- ROT (rot-shift X -1))))";
+    let trace = r#"{"<prelude>": {"X": [1, 2, 3]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+
+    // the first row of the trace reads its last one, instead of the padding
+    // before it; the padding row is rotated along the trace as well
+    assert_eq!(column_values(&cs, "ROT", 0..4), ["2", "3", "1", "2"]);
+    assert_eq!(column_values(&cs, "SHIFTED", 0..4), ["0", "0", "1", "2"]);
+    check::check(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+
+    // none of the backends can express the wrapping
+    let cs = compile(source, ExpansionLevel::top()).unwrap();
+    assert!(crate::transpilers::rust::render(&cs).is_err());
+    assert!(crate::transpilers::solidity::render(&cs).is_err());
+    assert!(crate::transpilers::gnark::render(&cs).is_err());
+    assert!(
+        crate::transpilers::smtlib::render(&cs, crate::transpilers::smtlib::Theory::Field).is_err()
+    );
+    #[cfg(feature = "exporters")]
+    assert!(crate::exporters::wizardiop::render(&cs, &None).is_err());
+}
+
+#[test]
+fn trace_template_round_trips() {
+    let source = "(defcolumns A (B :comp (* A 2)))
+        (module m) (defcolumns P X (Y :byte))
+        (defperspective p1 P ((Z :byte))) (defperspective p2 (- 1 P) ((W :byte)))";
+    let mut cs = compile(source, ExpansionLevel::top()).unwrap();
+    let template = cs.trace_template();
+
+    let mut columns = vec![];
+    for (module, trace) in template.as_object().unwrap() {
+        for name in trace["Trace"].as_object().unwrap().keys() {
+            columns.push(format!("{module}.{name}"));
+            let h = crate::structs::Handle::new(module, name).into();
+            assert!(
+                cs.columns.column(&h).is_ok() || cs.columns.register(&h).is_some(),
+                "unknown column {module}.{name}"
+            );
+        }
+    }
+    columns.sort();
+    assert_eq!(columns, ["<prelude>.A", "m.P", "m.X", "m.Y", "m.Z_xor_W"]);
+
+    import::read_trace_str(template.to_string().as_bytes(), &mut cs, false).unwrap();
+}
+
+#[test]
+fn if_without_else() {
+    use crate::column::Computation;
+    use crate::compiler::{Expression, Magma, Type};
+
+    let source = "(defcolumns (A :binary@bool) (C :comp (* 3 (if-not-zero A 7))))";
+    let cs = compile(source, ExpansionLevel::None).unwrap();
+    let exp = cs
+        .computations
+        .iter()
+        .find_map(|c| match c {
+            Computation::Composite { exp, .. } => Some(exp.clone()),
+            _ => None,
+        })
+        .unwrap();
+    let Expression::Funcall { args, .. } = exp.e() else {
+        panic!("unexpected {:?}", exp)
+    };
+    // the conditional varies along with A
+    assert!(matches!(args[1].t(), Type::Column(_)));
+    assert_eq!(exp.t(), Type::Column(Magma::native()));
+
+    let cs = expand(
+        source,
+        r#"{"<prelude>": {"A": [1, 0, 1]}}"#,
+        ExpansionLevel::None,
+    );
+    assert_eq!(column_values(&cs, "C", 0..4), ["0", "21", "0", "21"]);
+}
+
+#[test]
+fn bench_reports_every_computation_kind() {
+    let source = "(defcolumns A B (C :comp (* A B)))
+        (definterleaved I (A B))
+        (defpermutation (SA SB) ((+ A) (- B)))";
+    let mut cs = compile(source, ExpansionLevel::top()).unwrap();
+    let trace = r#"{"<prelude>": {"A": [3, 1, 1, 5], "B": [5, 2, 7, 1]}}"#;
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    let timings = compute::bench(&mut cs, false).unwrap();
+
+    let kinds = cs
+        .computations
+        .iter()
+        .map(|c| c.kind_name())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert!(kinds.is_superset(&["composite", "interleaved", "sorted"].into()));
+    assert_eq!(
+        timings.kinds.keys().cloned().collect::<Vec<_>>(),
+        kinds.into_iter().collect::<Vec<_>>()
+    );
+    let report = timings.to_string();
+    for kind in timings.kinds.keys() {
+        assert!(report.contains(kind), "{kind} missing from\n{report}");
+    }
+    assert!(timings.rows > 0);
+}
+
+#[test]
+fn fail_fast_computations() {
+    let source = "(defcolumns A B (C :comp (* A B)))";
+    // B is missing, so C can not be computed
+    let trace = r#"{"<prelude>": {"A": [1, 2, 3]}}"#;
+    for fail_fast in [true, false] {
+        let mut cs = compile(source, ExpansionLevel::None).unwrap();
+        import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+        assert_eq!(
+            compute::prepare(&mut cs, false, fail_fast).is_err(),
+            fail_fast
+        );
+    }
+}
+
+#[test]
+fn recurrence_prefix_sum() {
+    let source = "(defcolumns INCR (ACC :recurrence (+ (shift ACC -1) INCR) :init 5))";
+    let trace = r#"{"<prelude>": {"INCR": [1, 2, 3, 4]}}"#;
+    for level in [ExpansionLevel::None, ExpansionLevel::top()] {
+        let cs = expand(source, trace, level);
+        // the first row is the padding of the imported trace
+        assert_eq!(column_values(&cs, "ACC", 0..5), ["5", "6", "8", "11", "15"]);
+        assert!(check::check(&cs, &None, &[], check::DebugSettings::new()).is_ok());
+    }
+
+    // Only the previous row of the column may be referenced
+    assert!(compile(
+        "(defcolumns INCR (ACC :recurrence (+ (shift ACC -2) INCR)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+    assert!(compile(
+        "(defcolumns INCR (ACC :recurrence (+ ACC INCR)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn fixpoint_columns() {
+    // A and B refer to each other; starting from zero, the first iteration
+    // sets them to their final value, that the second one confirms
+    let source = "(defcolumns X (A :comp (+ X (* 0 B)) :fixpoint) (B :comp (+ A 1) :fixpoint))";
+    let trace = r#"{"<prelude>": {"X": [1, 2, 3]}}"#;
+    for level in [ExpansionLevel::None, ExpansionLevel::top()] {
+        let cs = expand(source, trace, level);
+        assert_eq!(column_values(&cs, "A", 1..4), ["1", "2", "3"]);
+        assert_eq!(column_values(&cs, "B", 1..4), ["2", "3", "4"]);
+        assert!(check::check(&cs, &None, &[], check::DebugSettings::new()).is_ok());
+    }
+
+    // A diverging fixpoint is an error
+    let mut cs = compile(
+        "(defcolumns X (A :comp (+ X B) :fixpoint) (B :comp (+ A 1) :fixpoint))",
+        ExpansionLevel::None,
+    )
+    .unwrap();
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    let err = compute::prepare(&mut cs, true, true).unwrap_err();
+    assert!(format!("{:#}", err).contains("did not converge"));
+
+    // Only computed columns may be fixpoints
+    assert!(compile("(defcolumns (A :fixpoint))", ExpansionLevel::None).is_err());
+}
+
+#[test]
+fn strided_interleaving() {
+    // two rows of AB, i.e. one of A and one of B, then one of C
+    let source = "(defcolumns A B C)
+                  (definterleaved AB (A B))
+                  (definterleaved T (AB C) :strides (2 1))
+                  (definterleaved U (A B C))";
+    let trace = r#"{"<prelude>": {"A": [1, 2, 3], "B": [4, 5, 6], "C": [7, 8, 9]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::top());
+    // the padding row and the 3 rows of the sources, thrice
+    let values = |name: &str| column_values(&cs, name, 0..12);
+    assert_eq!(values("T"), values("U"));
+    assert!(values("T").ends_with(&["1", "4", "7", "2", "5", "8", "3", "6", "9"].map(String::from)));
+
+    // strides must match the sources, be positive, and fit their lengths
+    for source in [
+        "(defcolumns A B) (definterleaved T (A B) :strides (1))",
+        "(defcolumns A B) (definterleaved T (A B) :strides (1 0))",
+        "(defcolumns A B) (definterleaved T (A B) :strides (2 1))",
+    ] {
+        assert!(compile(source, ExpansionLevel::None).is_err());
+    }
+
+    // WizardIOP interleavings take a single row from each source
+    #[cfg(feature = "exporters")]
+    {
+        use crate::exporters::wizardiop;
+        for (source, expected) in [
+            ("(definterleaved T (AB C) :strides (2 1))", false),
+            ("(definterleaved T (A B) :strides (1 1))", true),
+        ] {
+            let source = format!(
+                "(defcolumns A B C) (definterleaved AB (A B)) {} (defconstraint c () T)",
+                source
+            );
+            let cs = compile(&source, ExpansionLevel::top()).unwrap();
+            assert_eq!(wizardiop::render(&cs, &None).is_ok(), expected);
+        }
+    }
+}
+
+#[test]
+fn dynamic_range() {
+    // the bound must also hold on the padding rows, hence M = N + 1
+    let source = "(defcolumns (X :i8) (N :i8) (M :i9 :comp (+ N 1))) (definrange X M)";
+    check_traces(
+        source,
+        &[
+            (r#"{"<prelude>": {"X": [1, 4, 0], "N": [1, 4, 0]}}"#, true),
+            (r#"{"<prelude>": {"X": [1, 5, 0], "N": [1, 4, 0]}}"#, false),
+            (r#"{"<prelude>": {"X": [1, 4, 3], "N": [1, 4, 0]}}"#, false),
+        ],
+    );
+
+    // a bound as wide as the field can not be checked
+    assert!(compile("(defcolumns X M) (definrange X M)", ExpansionLevel::None).is_err());
+    // nothing is ever in a constant empty range
+    for bound in ["0", "-3", "(- 2 5)"] {
+        assert!(compile(
+            &format!("(defcolumns X) (definrange X {bound})"),
+            ExpansionLevel::None
+        )
+        .is_err());
+    }
+}
+
+#[test]
+fn export_witness_separately() {
+    let source = "(defcolumns X) (defpermutation (Y) ((+ X)))";
+    let trace = r#"{"<prelude>": {"X": [3, 1, 2]}}"#;
+    // as in `corset export-witness`, with the sorting constraints
+    let mut builder = ConstraintSetBuilder::from_sources(false, false);
+    builder.add_source(source).unwrap();
+    builder.expand_to(ExpansionLevel::top());
+    builder.auto_constraints(AutoConstraint::all());
+    let mut cs = builder.into_constraint_set().unwrap();
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    compute::prepare(&mut cs, true, false).unwrap();
+
+    let columns = |out: Vec<u8>| {
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        json["columns"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let mut committed = Vec::new();
+    cs.write_committed(&mut committed).unwrap();
+    let mut witness = Vec::new();
+    cs.write_witness(&mut witness).unwrap();
+    let (committed, witness) = (columns(committed), columns(witness));
+
+    assert_eq!(committed, ["X"]);
+    assert!(witness.iter().any(|c| c == "Y"));
+    assert!(!witness.iter().any(|c| c == "X"));
+    // the auxiliary columns enforcing the sorting are part of the witness
+    for aux in ["SRT__at_0_", "SRT__Eq_", "SRT__Delta_"] {
+        assert!(
+            witness.iter().any(|c| c.contains(aux)),
+            "{aux} in {witness:?}"
+        );
+    }
+}
+
+#[test]
+fn shifts_over_interleaved_columns() {
+    let source = "(defcolumns X Y (A :length 2))
+                  (definterleaved (Z) (X Y))
+                  (defcolumns (C :comp (+ Z (shift Z 1))))
+                  (defconstraint same () (vanishes! (- (shift A -1) (shift Z -1))))";
+    let trace = r#"{"<prelude>": {"X": [1, 3], "Y": [2, 4], "A": [1, 2, 3, 4]}}"#;
+    check_traces(
+        source,
+        &[
+            (trace, true),
+            (
+                r#"{"<prelude>": {"X": [1, 3], "Y": [2, 4], "A": [2, 1, 3, 4]}}"#,
+                false,
+            ),
+        ],
+    );
+
+    // Z is [0, 0, 1, 2, 3, 4] after the padding row of its sources, and
+    // spills over two rows, i.e. one row of its sources
+    let cs = expand(source, trace, ExpansionLevel::None);
+    assert_eq!(
+        column_values(&cs, "C", -2..6),
+        ["0", "0", "0", "1", "3", "5", "7", ""]
+    );
+}
+
+#[test]
+fn sorting_delta_endianness() {
+    use crate::column::{Endianness, Value};
+    use crate::transformer::AutoConstraint;
+
+    let x = Value::from(0x1234usize);
+    let bytes = |v: Vec<Value>| v.iter().map(|b| b.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        bytes(Endianness::Little.decompose(&x, 4)),
+        ["52", "18", "0", "0"]
+    );
+    assert_eq!(
+        bytes(Endianness::Big.decompose(&x, 4)),
+        ["0", "0", "18", "52"]
+    );
+
+    let trace = r#"{"<prelude>": {"X": [1, 4661]}}"#;
+    for (option, (lsb, msb)) in [("", (0, 1)), (":big-endian", (15, 14))] {
+        let source = format!("(defcolumns X) (defpermutation (Y) ((+ X)) {option})");
+        let compile = || {
+            let mut r = ConstraintSetBuilder::from_sources(false, false);
+            r.add_source(&source).unwrap();
+            r.expand_to(ExpansionLevel::top());
+            r.auto_constraints(AutoConstraint::all());
+            r.into_constraint_set().unwrap()
+        };
+        // the decomposition constraint must agree with the computed bytes
+        assert!(check_json_trace(trace, compile(), false).unwrap());
+
+        let mut cs = compile();
+        import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+        compute::prepare(&mut cs, true, false).unwrap();
+        let byte = |i: usize| {
+            let c = cs
+                .columns
+                .all()
+                .into_iter()
+                .find(|c| cs.handle(c).name.starts_with(&format!("__SRT__Delta_{i}_")))
+                .unwrap();
+            // the last row holds the delta between 1 and 4661, i.e. 0x1234
+            cs.columns.get(&c, 2, false).unwrap().to_string()
+        };
+        assert_eq!((byte(lsb), byte(msb)), ("52".into(), "18".into()));
+    }
+}
+
+#[test]
+fn shifts_of_array_elements() {
+    let source = "(defcolumns (A :array [3]) B)
+                  (defconstraint previous () (vanishes! (- B (shift [A 2] -1))))";
+    check_traces(
+        source,
+        &[
+            (
+                r#"{"<prelude>": {"A_1": [0, 0, 0], "A_2": [5, 6, 7], "A_3": [0, 0, 0], "B": [0, 5, 6]}}"#,
+                true,
+            ),
+            (
+                r#"{"<prelude>": {"A_1": [0, 0, 0], "A_2": [5, 6, 7], "A_3": [0, 0, 0], "B": [5, 6, 7]}}"#,
+                false,
+            ),
+        ],
+    );
+
+    let cs = compile(source, ExpansionLevel::None).unwrap();
+    assert_eq!(cs.spilling_of(crate::compiler::MAIN_MODULE), Some(1));
+}
+
+#[test]
+fn bench_report_formats() {
+    use crate::reports::{Report, ReportFormat};
+
+    let mut cs = compile("(defcolumns A B (C :comp (* A B)))", ExpansionLevel::top()).unwrap();
+    let trace = r#"{"<prelude>": {"A": [3, 1], "B": [5, 2]}}"#;
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    let report = compute::BenchReport::from(&compute::bench(&mut cs, false).unwrap());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&report.render(ReportFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["kinds"][0]["kind"], "composite");
+    assert_eq!(json["kinds"][0]["count"], 1);
+    assert_eq!(json["total"]["kind"], "total");
+    assert!(json["total"]["rows"].as_u64().unwrap() > 0);
+    assert!(json["total"]["time_ms"].is_number());
+
+    let csv = report.render(ReportFormat::Csv).unwrap();
+    assert_eq!(csv.lines().next(), Some("kind,count,rows,time_ms"));
+    assert_eq!(csv.lines().count(), 3);
+}
+
+#[test]
+fn map_lambda_over_array() {
+    let source = "(defcolumns (A :array [3]) S)
+                  (defconstraint squares () (eq! S (reduce + (map (lambda (x) (* x x)) A))))";
+    check_traces(
+        source,
+        &[
+            (
+                r#"{"<prelude>": {"A_1": [1, 2], "A_2": [3, 0], "A_3": [5, 4], "S": [35, 20]}}"#,
+                true,
+            ),
+            (
+                r#"{"<prelude>": {"A_1": [1, 2], "A_2": [3, 0], "A_3": [5, 4], "S": [9, 6]}}"#,
+                false,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn coverage_of_guarded_constraints() {
+    let source = "(defcolumns G H X)
+                  (defconstraint never () (if-not-zero G (vanishes! X)))
+                  (defconstraint sometimes () (if-not-zero H (vanishes! X)))
+                  (defconstraint always () (vanishes! (* X G)))";
+    let trace = r#"{"<prelude>": {"G": [0, 0, 0], "H": [1, 0, 1], "X": [0, 5, 0]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+    check::check(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+
+    let report = check::coverage(&cs, &None, &[]).unwrap();
+    let rows = report
+        .constraints
+        .iter()
+        .map(|c| (c.name.as_str(), c.active, c.vacuous))
+        .collect::<Vec<_>>();
+    // the padding row is checked alongside the 3 rows of the trace
+    assert_eq!(
+        rows,
+        [("always", 4, 0), ("never", 0, 4), ("sometimes", 2, 2)]
+    );
+}
+
+#[test]
+fn json_violation_report() {
+    let source = "(defcolumns X Y) (defconstraint same () (vanishes! (- X Y)))";
+    let trace = r#"{"<prelude>": {"X": [1, 2, 3], "Y": [1, 5, 3]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+    assert!(check::check(&cs, &None, &[], check::DebugSettings::new()).is_err());
+
+    let report = check::violations(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+    let json = serde_json::to_string(&report).unwrap();
+    let report: check::ViolationReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(report.violations.len(), 1);
+    let violation = &report.violations[0];
+    assert_eq!(violation.name, "same");
+    assert_eq!(violation.module, "<prelude>");
+    // the first row is the padding of the imported trace
+    assert_eq!(violation.row, 2);
+    assert!(violation.value.is_some());
+    assert!(violation.snippet.contains('X') && violation.snippet.contains('Y'));
+
+    // the constraints that are not vanishing ones are reported as well
+    let source = "(defcolumns X Y (T :i8)) (deflookup l (X) (Y)) (definrange T 8)";
+    let trace = r#"{"<prelude>": {"X": [1, 2, 3], "Y": [1, 5, 3], "T": [1, 9, 3]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+    let report = check::violations(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+    let violations = report
+        .violations
+        .iter()
+        .map(|v| (v.name.as_str(), v.row))
+        .collect::<Vec<_>>();
+    assert_eq!(violations, [("T_lt_8", 2), ("l", 2)]);
+}
+
+#[test]
+fn constraint_set_check() {
+    let source = "(defcolumns X Y)
+(defconstraint same () (vanishes! (- X Y)))
+(defconstraint first (:domain {0}) (vanishes! X))";
+    let trace = r#"{"<prelude>": {"X": [1, 2, 3, 4], "Y": [1, 5, 3, 7]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+
+    let violations = cs.check().unwrap();
+    let rows = violations
+        .iter()
+        .map(|v| (v.name.as_str(), v.row, v.value.is_some()))
+        .collect::<Vec<_>>();
+    // the first row is the padding of the imported trace, and the domain of
+    // `first` is only this one
+    assert_eq!(rows, vec![("same", 2, true), ("same", 4, true)]);
+}
+
+#[test]
+fn minimize_single_row() -> Result<()> {
+    let source = "(defcolumns X Y) (defconstraint binary () (vanishes! (* X (- X 1))))";
+    let path = std::env::temp_dir().join(format!("corset-minimize-{}.json", std::process::id()));
+    fs::write(
+        &path,
+        r#"{"<prelude>": {"X": [0, 1, 1, 0, 5, 1, 0, 1], "Y": [1, 2, 3, 4, 5, 6, 7, 8]}}"#,
+    )?;
+    let cs = compile(source, ExpansionLevel::top())?;
+    let r = crate::minimize::minimize(cs, path.to_str().unwrap(), false);
+    fs::remove_file(&path)?;
+    let minimal = r?;
+
+    assert_eq!(minimal.constraint, "binary");
+    assert_eq!(minimal.rows.get("<prelude>"), Some(&vec![4]));
+    assert_eq!(
+        minimal.trace,
+        serde_json::json!({"<prelude>": {"X": ["5"], "Y": ["5"]}})
+    );
+    Ok(())
+}
+
+#[test]
+fn expected_computed_columns() {
+    let source = "(module m) (defcolumns A (B :comp (* A 2)))";
+    let trace = r#"{"m": {"A": [1, 2, 3]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::top());
+
+    let matching = serde_json::json!({"m": {"B": [2, "4", 6]}});
+    check::check_expected(&cs, &matching).unwrap();
+
+    let mismatching = serde_json::json!({"m": {"A": [1, 2, 4], "B": [2, 5, 6]}});
+    let err = check::check_expected(&cs, &mismatching)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("B") && err.contains("row 1"), "{}", err);
+
+    let too_short = serde_json::json!({"m": {"B": [2, 4]}});
+    assert!(check::check_expected(&cs, &too_short).is_err());
+}
+
+#[test]
+fn range_margins() {
+    let source = "(defcolumns X Y) (definrange X 256) (definrange (+ X Y) 1024)";
+    let trace = r#"{"<prelude>": {"X": [3, 100, 42], "Y": [1, 1, 900]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+    check::check(&cs, &None, &[], check::DebugSettings::new()).unwrap();
+
+    let report = check::margins(&cs, &None, &[]).unwrap();
+    let rows = report
+        .constraints
+        .iter()
+        .map(|c| {
+            (
+                c.min.as_str(),
+                c.max.as_str(),
+                c.bound.as_str(),
+                c.margin.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        [("4", "942", "1024", "81"), ("3", "100", "256", "155")]
+    );
+}
+
+#[test]
+fn evaluate_single_row() {
+    let source = "(defcolumns X Y)
+                  (defconstraint doubled () (vanishes! (- Y (* 2 X))))
+                  (defconstraint offset () (vanishes! (- Y X 4)))";
+    let trace = r#"{"<prelude>": {"X": [1, 2, 3, 4], "Y": [2, 4, 7, 8]}}"#;
+    let cs = expand(source, trace, ExpansionLevel::None);
+
+    // the trace is preceded by a padding row, so row 3 holds X = 3, Y = 7
+    let report = check::evaluate_row(&cs, &None, &[], 3);
+    let rows = report
+        .constraints
+        .iter()
+        .map(|c| (c.name.as_str(), c.values.clone(), c.failing))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        [
+            ("doubled", vec![Some("1".to_string())], true),
+            ("offset", vec![Some("0".to_string())], false)
+        ]
+    );
+}
+
+#[test]
+fn compute_estimate_matches_computation() {
+    let computed = |cs: &ConstraintSet| {
+        cs.columns
+            .iter()
+            .filter(|(h, _)| cs.computations.computation_for(h).is_some())
+            .map(|(h, _)| cs.columns.padded_len(&h).unwrap())
+            .sum::<usize>()
+    };
+    let source = "(module m :height 4)
+                  (defcolumns A B (C :comp (* A B)) (D :comp (+ C (prev A))))
+                  (definterleaved I (A B))
+                  (defconstraint c () (vanishes! (- D (+ (* A B) (prev A)))))";
+    let trace = r#"{"m": {"A": [1, 2, 3, 4], "B": [5, 6, 7, 8]}}"#;
+
+    // the declared height is enough to size the module
+    let mut cs = compile(source, ExpansionLevel::None).unwrap();
+    let estimate = compute::estimate(&cs);
+    assert_eq!(estimate.modules[0].rows, Some(5));
+    assert_eq!(estimate.modules[0].columns, 3);
+
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    assert_eq!(compute::estimate(&cs).values, estimate.values);
+    compute::prepare(&mut cs, true, false).unwrap();
+    assert_eq!(computed(&cs), estimate.values);
+
+    // otherwise, the module is sized by the imported trace
+    let mut cs = compile(&source.replace(":height 4", ""), ExpansionLevel::None).unwrap();
+    assert_eq!(compute::estimate(&cs).values, 0);
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+    let estimate = compute::estimate(&cs);
+    compute::prepare(&mut cs, true, false).unwrap();
+    assert_eq!(computed(&cs), estimate.values);
+}
+
+#[test]
+fn declared_module_height() {
+    let source = "(module m :height 3) (defcolumns A B) (defconstraint c () (vanishes! (- A B)))";
+    let cs = compile(source, ExpansionLevel::None).unwrap();
+    assert_eq!(cs.columns.heights.get("m"), Some(&3));
+
+    check_traces(
+        source,
+        &[(r#"{"m": {"A": [1, 2, 3], "B": [1, 2, 3]}}"#, true)],
+    );
+
+    let mut cs = compile(source, ExpansionLevel::None).unwrap();
+    let trace = r#"{"m": {"A": [1, 2], "B": [1, 2]}}"#;
+    let err = import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap_err();
+    assert!(format!("{:#}", err).contains("declared with a height of"));
+
+    // the padding up to the minimal module length is not part of the height
+    let mut cs = compile(source, ExpansionLevel::None).unwrap();
+    cs.columns.set_min_len("m", 8);
+    let trace = r#"{"m": {"A": [1, 2, 3], "B": [1, 2, 3]}}"#;
+    import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+
+    assert!(compile(
+        "(module m :height 3) (module m :height 4)",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn horner_matches_polynomial() {
+    // the constant term is a column, so that the padding row holds
+    let source = "(defcolumns X C A P)
+                  (defconstraint poly () (eq! P (horner X C A 5 7)))";
+    let poly = |x: u64, a: u64| 3 + a * x + 5 * x * x + 7 * x * x * x;
+    let xs = [0u64, 1, 2, 5, 11];
+    let as_ = [4u64, 0, 9, 1, 3];
+    let column = |vs: &[u64]| {
+        vs.iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let ps = xs
+        .iter()
+        .zip(as_.iter())
+        .map(|(x, a)| poly(*x, *a))
+        .collect::<Vec<_>>();
+    let trace = format!(
+        r#"{{"<prelude>": {{"X": [{}], "C": [3, 3, 3, 3, 3], "A": [{}], "P": [{}]}}}}"#,
+        column(&xs),
+        column(&as_),
+        column(&ps)
+    );
+    let wrong = trace.replace(
+        &format!("\"P\": [{}", ps[0]),
+        &format!("\"P\": [{}", ps[0] + 1),
+    );
+    check_traces(source, &[(&trace, true), (&wrong, false)]);
+
+    // Horner form keeps the degree to the one of the polynomial
+    let cs = compile(source, ExpansionLevel::None).unwrap();
+    assert_eq!(cs.constraints[0].degree(), 3);
+
+    let cs = compile("(defconst V (horner 2 1 3 5))", ExpansionLevel::None).unwrap();
+    assert_eq!(
+        cs.constants.values().next().unwrap(),
+        &num_bigint::BigInt::from(1 + 2 * 3 + 4 * 5)
+    );
+}
+
+#[test]
+fn rlc_expansion() {
+    let source = "(defcolumns R C1 C2 C3 F)
+                  (defconstraint fingerprint () (eq! F (rlc R C1 C2 C3)))";
+    let rows: [[u64; 4]; 4] = [[2, 1, 3, 5], [7, 0, 0, 1], [1, 4, 4, 4], [13, 6, 0, 9]];
+    let column = |f: &dyn Fn(&[u64; 4]) -> u64| {
+        rows.iter()
+            .map(|r| f(r).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let fingerprint = |r: &[u64; 4]| r[1] + r[0] * r[2] + r[0] * r[0] * r[3];
+    let trace = format!(
+        r#"{{"<prelude>": {{"R": [{}], "C1": [{}], "C2": [{}], "C3": [{}], "F": [{}]}}}}"#,
+        column(&|r| r[0]),
+        column(&|r| r[1]),
+        column(&|r| r[2]),
+        column(&|r| r[3]),
+        column(&fingerprint),
+    );
+    let wrong = trace.replace("\"C2\": [3", "\"C2\": [4");
+    check_traces(source, &[(&trace, true), (&wrong, false)]);
+
+    let cs = compile("(defconst V (rlc 3 1 2 4))", ExpansionLevel::None).unwrap();
+    assert_eq!(
+        cs.constants.values().next().unwrap(),
+        &num_bigint::BigInt::from(1 + 3 * 2 + 9 * 4)
+    );
+
+    // the challenge must be a constant or a column
+    assert!(compile(
+        "(defcolumns R C1 C2 F) (defconstraint bad () (eq! F (rlc (* R R) C1 C2)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn fixed_point_scaling() {
+    let source = "(defcolumns A B F)
+                  (defconstraint scaled () (eq! F (scale-up A 4)))
+                  (defconstraint round-trip () (eq! A (scale-down (scale-up A 8) 8)))
+                  (defconstraint sum () (eq! B (scale-down (+ (scale-up A 8) (scale-up F 8)) 8)))";
+    let trace = r#"{"<prelude>": {"A": [0, 3, 7], "B": [0, 51, 119], "F": [0, 48, 112]}}"#;
+    let wrong = trace.replace("48", "49");
+    check_traces(source, &[(trace, true), (&wrong, false)]);
+
+    let cs = compile(
+        "(defconst V (scale-down (scale-up 11 5) 5) W (scale-down 96 5))",
+        ExpansionLevel::None,
+    )
+    .unwrap();
+    let mut values = cs.constants.values().cloned().collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(
+        values,
+        vec![num_bigint::BigInt::from(3), num_bigint::BigInt::from(11)]
+    );
+
+    // division must be provably exact
+    assert!(compile("(defconst V (scale-down 97 5))", ExpansionLevel::None).is_err());
+    assert!(compile(
+        "(defcolumns A B) (defconstraint inexact () (eq! B (scale-down (scale-up A 2) 3)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn composite_of_array_element() {
+    let source = "(defcolumns (A :array [5]) (B :comp (* 2 (nth A 3))) C)
+                  (defconstraint c () (eq! C (+ B [A 1])))";
+    let trace = r#"{"<prelude>": {"A_1": [1, 2], "A_2": [0, 0], "A_3": [5, 7], "A_4": [0, 0], "A_5": [0, 0], "C": [11, 16]}}"#;
+    let wrong = trace.replace("\"A_3\": [5", "\"A_3\": [6");
+    check_traces(source, &[(trace, true), (&wrong, false)]);
+
+    // the index must lie within the array domain
+    assert!(compile(
+        "(defcolumns (A :array [5]) (B :comp (nth A 6)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn padding_rows_are_tolerated() {
+    // on the padding row, A = 0 while its successor is the first row of the
+    // actual trace
+    let source = "(defcolumns A) (defconstraint counter () (eq! (next A) (+ A 1)))";
+    let trace = r#"{"<prelude>": {"A": [5, 6, 7]}}"#;
+    let wrong = r#"{"<prelude>": {"A": [5, 6, 8]}}"#;
+    // constraints not reading across the padding boundary still hold there
+    let local = "(defcolumns A) (defconstraint local () (eq! A 5))";
+    let uniform = r#"{"<prelude>": {"A": [5, 5, 5]}}"#;
+    check_traces(source, &[(trace, true), (wrong, false)]);
+    check_traces(local, &[(uniform, false)]);
+
+    let settings = check::DebugSettings::new().check_padding(true);
+    check_traces_with(source, &[(trace, false)], settings);
+}
+
+#[test]
+fn sampled_rows() {
+    let source = "(defcolumns A) (defconstraint binary () (vanishes! (* A (- A 1))))";
+    // the trace is preceded by the padding row, so that A[3] lies on row 4
+    let trace = |bad: usize| {
+        let a = (0..10)
+            .map(|i| if i == bad { "2" } else { "1" })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(r#"{{"<prelude>": {{"A": [{a}]}}}}"#)
+    };
+    let settings = check::DebugSettings::new().and_sample(Some(4));
+    check_traces_with(
+        source,
+        &[
+            // sampled rows, and the last row of the trace
+            (&trace(3), false),
+            (&trace(7), false),
+            (&trace(9), false),
+            // rows skipped by the sampling go unnoticed
+            (&trace(4), true),
+        ],
+        settings,
+    );
+    check_traces(source, &[(&trace(4), false)]);
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn parquet_import_matches_json() -> Result<()> {
+    use arrow_array::{ArrayRef, BinaryArray, Int64Array, RecordBatch, StringArray, UInt8Array};
+    use std::sync::Arc;
+
+    let source = "(defcolumns (A :byte) B C) (module m) (defcolumns D)";
+    let batch = RecordBatch::try_from_iter([
+        (
+            "A",
+            Arc::new(UInt8Array::from(vec![1u8, 2, 255])) as ArrayRef,
+        ),
+        (
+            "B",
+            Arc::new(StringArray::from(vec![
+                "3",
+                "123456789012345678901234567890",
+                "0",
+            ])) as ArrayRef,
+        ),
+        (
+            "C",
+            Arc::new(BinaryArray::from(vec![&[1u8, 0][..], &[], &[0xff]])) as ArrayRef,
+        ),
+        (
+            "m.D",
+            Arc::new(Int64Array::from(vec![4i64, 0, 7])) as ArrayRef,
+        ),
+    ])?;
+    let path = std::env::temp_dir().join(format!("corset-{}.parquet", std::process::id()));
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(fs::File::create(&path)?, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    let mut from_parquet = compile(source, ExpansionLevel::top())?;
+    let r = import::parse_parquet_trace(path.to_str().unwrap(), &mut from_parquet, false);
+    fs::remove_file(&path)?;
+    r?;
+
+    let mut from_json = compile(source, ExpansionLevel::top())?;
+    import::read_trace_str(
+        br#"{"<prelude>": {"A": [1, 2, 255],
+                           "B": [3, "123456789012345678901234567890", 0],
+                           "C": [256, 0, 255]},
+             "m": {"D": [4, 0, 7]}}"#,
+        &mut from_json,
+        false,
+    )?;
+
+    let values = |cs: &ConstraintSet| {
+        cs.columns
+            .iter()
+            .map(|(h, c)| {
+                (
+                    c.handle.to_string(),
+                    cs.columns
+                        .backing(&h)
+                        .map(|b| b.iter(&cs.columns).collect::<Vec<_>>()),
+                )
+            })
+            .collect::<std::collections::BTreeMap<_, _>>()
+    };
+    assert_eq!(values(&from_parquet), values(&from_json));
+    assert!(values(&from_parquet).values().all(|v| v.is_some()));
+
+    // type validation applies to Parquet traces as well
+    let batch =
+        RecordBatch::try_from_iter([("A", Arc::new(Int64Array::from(vec![256i64])) as ArrayRef)])?;
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(fs::File::create(&path)?, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    let mut cs = compile(source, ExpansionLevel::top())?;
+    let r = import::parse_parquet_trace(path.to_str().unwrap(), &mut cs, false);
+    fs::remove_file(&path)?;
+    assert!(r.is_err());
+    Ok(())
+}
+
+#[test]
+fn modular_reduction() {
+    let cs = compile("(defconst A (mod 17 5) B (mod -3 5))", ExpansionLevel::None).unwrap();
+    let mut values = cs.constants.values().cloned().collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(
+        values,
+        vec![num_bigint::BigInt::from(2), num_bigint::BigInt::from(2)]
+    );
+    assert!(compile("(defconst A (mod 17 0))", ExpansionLevel::None).is_err());
+
+    let cs = expand(
+        "(defcolumns X M (R :comp (mod X M)))",
+        r#"{"<prelude>": {"X": [17, 255, 3], "M": [5, 16, 7]}}"#,
+        ExpansionLevel::None,
+    );
+    assert_eq!(column_values(&cs, "R", 1..4), ["2", "15", "3"]);
+
+    // a zero divisor is reported rather than panicking
+    assert!(crate::column::Value::from(3usize)
+        .checked_rem(&crate::column::Value::from(0usize))
+        .is_err());
+}
+
+#[test]
+fn constant_extrema() {
+    let cs = compile(
+        "(defconst A 3 B (max A 7 -2) C (min B (* A 4) 5))
+         (defcolumns (X :array [1:(min B 4)]))",
+        ExpansionLevel::None,
+    )
+    .unwrap();
+    let constant = |name: &str| {
+        cs.constants
+            .iter()
+            .find(|(h, _)| h.name == name)
+            .map(|(_, v)| v.to_string())
+            .unwrap()
+    };
+    assert_eq!((constant("B"), constant("C")), ("7".into(), "5".into()));
+    // the array domain has been folded as well
+    assert_eq!(
+        cs.columns
+            .iter()
+            .filter(|(h, _)| cs.handle(h).name.starts_with("X_"))
+            .count(),
+        4
+    );
+
+    // only compile-time constants are accepted
+    assert!(compile(
+        "(defcolumns X) (defconst A (max X 2))",
+        ExpansionLevel::None
+    )
+    .is_err());
+    assert!(compile(
+        "(defcolumns X) (defconstraint c () (vanishes! (min X 2)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+    assert!(compile("(defconst A (max 2))", ExpansionLevel::None).is_err());
+}
+
+#[test]
+fn constant_exponent() {
+    use crate::column::Value;
+
+    let source = "(defcolumns X Y) (defconstraint pow () (vanishes! (- Y (^ X 13))))";
+    let trace = |y: &str| format!(r#"{{"<prelude>": {{"X": [2, 3], "Y": [8192, {y}]}}}}"#);
+    check_traces(
+        source,
+        &[(&trace("1594323"), true), (&trace("1594324"), false)],
+    );
+
+    // square-and-multiply agrees with the multiplication chain, both in the
+    // field and over the integers
+    let x = Value::from(ark_bls12_377::Fr::from(3));
+    let mut chain = x.clone();
+    for _ in 1..256 {
+        chain.mul_assign(&x);
+    }
+    assert_eq!(x.pow(&256.into()), chain);
+    assert!(x.pow(&0.into()).is_one());
+    let x = num_bigint::BigInt::from(3);
+    assert_eq!(
+        Value::big_int(x.clone()).pow(&256.into()),
+        Value::big_int(x.pow(256))
+    );
+
+    // exponents must be non-negative compile-time constants
+    let pow = |exp: &str| {
+        compile(
+            &format!("(defcolumns X Y) (defconstraint pow () (vanishes! (^ X {exp})))"),
+            ExpansionLevel::None,
+        )
+    };
+    for exp in ["-1", "Y", "(+ 2 Y)"] {
+        let err = pow(exp).err().unwrap();
+        assert!(format!("{:?}", err).contains("non-negative compile-time constant"));
+    }
+}
+
+#[test]
+fn next_row_equality() {
+    let stays = "(defcolumns C) (defconstraint stays () (vanishes! (stays C)))";
+    let changes = "(defcolumns C F) (defconstraint changes () (vanishes! (- F (changes C))))";
+    let trace = |c: &str| format!(r#"{{"<prelude>": {{"C": {c}}}}}"#);
+    check_traces(
+        stays,
+        &[
+            (&trace("[5, 5, 5]"), true),
+            (&trace("[5, 6, 6]"), false),
+            (&trace("[5, 5, 6]"), false),
+        ],
+    );
+    let trace = |f: &str| format!(r#"{{"<prelude>": {{"C": [5, 5, 7], "F": {f}}}}}"#);
+    check_traces(
+        changes,
+        &[(&trace("[0, 1, 0]"), true), (&trace("[0, 0, 0]"), false)],
+    );
+
+    // both forms are usable as conditions
+    let cs = compile(
+        "(defcolumns C D)
+         (defconstraint c () (if (stays C) (vanishes! D)))
+         (defconstraint d () (if (changes C) (vanishes! D)))",
+        ExpansionLevel::None,
+    );
+    assert!(cs.is_ok());
+}
+
+#[test]
+fn last_rows_domain() {
+    let source = |domain: &str| {
+        format!("(defcolumns A) (defconstraint final (:domain {domain}) (vanishes! (- A 7)))")
+    };
+    let trace = |a: &str| format!(r#"{{"<prelude>": {{"A": {a}}}}}"#);
+    check_traces(
+        &source("(last)"),
+        &[
+            (&trace("[1, 2, 7]"), true),
+            (&trace("[1, 7, 2]"), false),
+            (&trace("[1, 2, 3, 4, 7]"), true),
+        ],
+    );
+    check_traces(
+        &source("(last 2)"),
+        &[(&trace("[1, 7, 7]"), true), (&trace("[7, 1, 7]"), false)],
+    );
+    assert!(compile(&source("(last 0)"), ExpansionLevel::None).is_err());
+    assert!(compile(&source("(last 1 2)"), ExpansionLevel::None).is_err());
+}
+
+#[test]
+fn no_padding_constraints() {
+    let source = |limiters: &str| {
+        format!(
+            "(defcolumns A B)
+             (defconstraint ones ({limiters}) (vanishes! (- A 1)))
+             (defconstraint steady ({limiters}) (vanishes! (stays B)))"
+        )
+    };
+    let trace = |a: &str| format!(r#"{{"<prelude>": {{"A": {a}, "B": [3, 3, 3]}}}}"#);
+    check_traces(
+        &source(":no-padding"),
+        &[
+            // the padding row, where A = 0, is not constrained
+            (&trace("[1, 1, 1]"), true),
+            // but the rows of the trace still are
+            (&trace("[1, 2, 1]"), false),
+            // and the flag of the active rows can not be forged to skip them
+            (
+                r#"{"<prelude>": {"A": [1, 2, 1], "B": [3, 3, 3], "__ACTIVE__": [0, 0, 0]}}"#,
+                false,
+            ),
+            (
+                r#"{"<prelude>": {"A": [1, 2, 1], "B": [3, 3, 3], "__ACTIVE__": [1, 0, 1]}}"#,
+                false,
+            ),
+            (
+                r#"{"<prelude>": {"A": [1, 2, 1], "B": [3, 3, 3], "__ACTIVE__": [0, 0, 1]}}"#,
+                false,
+            ),
+        ],
+    );
+    check_traces(&source(""), &[(&trace("[1, 1, 1]"), false)]);
+
+    // the padding is that of the module of the constraint
+    check_traces(
+        &format!("(module m) {}", source(":no-padding")),
+        &[(r#"{"m": {"A": [1, 1], "B": [3, 3]}}"#, true)],
+    );
+
+    // and it is named after the prefix of the auxiliary columns
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.add_source(&source(":no-padding")).unwrap();
+    r.aux_prefix(Some("AUX_".into()));
+    let cs = r.into_constraint_set().unwrap();
+    assert!(cs
+        .columns
+        .iter_cols()
+        .any(|c| c.handle.name == "AUX_ACTIVE__"));
+    assert!(cs
+        .columns
+        .iter_cols()
+        .all(|c| c.handle.name != "__ACTIVE__"));
+}
+
+#[test]
+fn strict_computations() {
+    // A is missing from the trace, so that B can not be computed
+    let source = "(defcolumns A C (B :comp (* A 2)))";
+    let trace = r#"{"<prelude>": {"C": [1, 2]}}"#;
+    let imported = || {
+        let mut cs = compile(source, ExpansionLevel::None).unwrap();
+        import::read_trace_str(trace.as_bytes(), &mut cs, false).unwrap();
+        cs
+    };
+
+    assert!(imported().compute_all().is_ok());
+    let err = imported().compute_all_strict().err().unwrap();
+    assert!(format!("{:?}", err).starts_with("while computing B"));
+}
+
+#[test]
+fn exact_division() {
+    let cs = compile("(defconst A (/ 12 4) B (/ -12 4))", ExpansionLevel::None).unwrap();
+    let mut values = cs.constants.values().cloned().collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(
+        values,
+        vec![num_bigint::BigInt::from(-3), num_bigint::BigInt::from(3)]
+    );
+    let err = compile("(defconst A (/ 13 4))", ExpansionLevel::None)
+        .err()
+        .unwrap();
+    assert!(format!("{:?}", err).contains("is not divisible by"));
+    assert!(compile("(defconst A (/ 12 0))", ExpansionLevel::None).is_err());
+
+    // a constant divisor is multiplied by its inverse in the field, or
+    // divides exactly over the integers
+    let source = "(defcolumns x q)
+                  (defconst FOUR 4)
+                  (defconstraint quotient () (eq! q (/ x FOUR)))
+                  (defconstraint scaled () (eq! q (/ (* FOUR q) FOUR)))";
+    check_traces(
+        source,
+        &[
+            (r#"{"<prelude>": {"x": [8, 12, 0], "q": [2, 3, 0]}}"#, true),
+            (r#"{"<prelude>": {"x": [8, 12, 0], "q": [2, 4, 0]}}"#, false),
+        ],
+    );
+
+    let cs = expand(
+        "(defcolumns X D (Q :comp (/ X D)))",
+        r#"{"<prelude>": {"X": [18, 255, 0], "D": [6, 5, 7]}}"#,
+        ExpansionLevel::None,
+    );
+    assert_eq!(column_values(&cs, "Q", 1..4), ["3", "51", "0"]);
+
+    // but not in computed columns, so that an inexact division is not
+    // silently accepted
+    let cs = expand(
+        "(defcolumns X (Q :comp (/ X 4)))",
+        r#"{"<prelude>": {"X": [8, 9]}}"#,
+        ExpansionLevel::None,
+    );
+    assert_eq!(column_values(&cs, "Q", 1..3), ["2", ""]);
+}
+
+#[test]
+fn xor_of_binary_columns() {
+    let source = "(defcolumns (A :binary) (B :binary) (X :binary))
+                  (defconstraint exclusive () (eq! X (xor A B)))";
+    let trace = r#"{"<prelude>": {"A": [0, 0, 1, 1], "B": [0, 1, 0, 1], "X": [0, 1, 1, 0]}}"#;
+    let wrong = r#"{"<prelude>": {"A": [0, 0, 1, 1], "B": [0, 1, 0, 1], "X": [0, 1, 1, 1]}}"#;
+    check_traces(source, &[(trace, true), (wrong, false)]);
+
+    // wider arguments are not silently accepted
+    assert!(compile(
+        "(defcolumns (A :byte) (B :binary) X) (defconstraint c () (eq! X (xor A B)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+    assert!(compile(
+        "(defcolumns (A :binary) (B :binary) X) (defconstraint c () (eq! X (xor A 3)))",
+        ExpansionLevel::None
+    )
+    .is_err());
+    // a loobean, i.e. true when zero
+    assert!(compile(
+        "(defcolumns (A :binary) (B :binary) X) (defconstraint c () (eq! X (xor A (neq! A B))))",
+        ExpansionLevel::None
+    )
+    .is_err());
+}
+
+#[test]
+fn or_of_binary_columns() {
+    let source = "(defcolumns (A :binary) (B :binary) (C :binary) (Y :binary) X)
+                  (defconstraint disjunction () (eq! Y (or A B C)))
+                  (defconstraint guarded () (if (or A B) (vanishes! X)))";
+    let trace = r#"{"<prelude>": {"A": [0, 0, 1, 0], "B": [0, 1, 1, 0], "C": [0, 0, 0, 1], "Y": [0, 1, 1, 1], "X": [5, 0, 0, 5]}}"#;
+    let wrong_or = trace.replace(r#""Y": [0, 1, 1, 1]"#, r#""Y": [0, 1, 1, 0]"#);
+    let wrong_guard = trace.replace(r#""X": [5, 0, 0, 5]"#, r#""X": [5, 0, 3, 5]"#);
+    check_traces(
+        source,
+        &[(trace, true), (&wrong_or, false), (&wrong_guard, false)],
+    );
+
+    for rejected in [
+        // not binary
+        "(defcolumns (A :byte) (B :binary) X) (defconstraint c () (eq! X (or A B)))",
+        // a loobean, i.e. true when zero
+        "(defcolumns (A :binary) (B :binary) X) (defconstraint c () (eq! X (or A (neq! A B))))",
+    ] {
+        assert!(compile(rejected, ExpansionLevel::None).is_err());
+    }
+}
+
+#[test]
+fn streamed_trace() -> Result<()> {
+    use std::io::Write;
+
+    let source = "(module m) (defcolumns A B)";
+    let trace = r#"{"m": {"Trace": {"A": [1, 2, 3], "UNKNOWN": [[7], {"x": 8}], "id": "m", "B": ["4", 5, 6]}}}"#;
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(trace.as_bytes())?;
+    let gzipped = gz.finish()?;
+    for reader in [trace.as_bytes(), gzipped.as_slice()] {
+        let mut cs = compile(source, ExpansionLevel::None)?;
+        import::read_trace(reader, &mut cs, false)?;
+        assert_eq!(column_values(&cs, "A", 0..4), ["0", "1", "2", "3"]);
+        assert_eq!(column_values(&cs, "B", 0..4), ["0", "4", "5", "6"]);
+    }
+
+    // errors raised while importing a column are reported as they are
+    let mut cs = compile(source, ExpansionLevel::None)?;
+    let err = import::read_trace(
+        r#"{"m": {"Trace": {"A": [1, 2, 3], "B": [4, 5]}}}"#.as_bytes(),
+        &mut cs,
+        false,
+    )
+    .unwrap_err();
+    assert!(format!("{:#}", err).contains("incorrect length"));
+
+    let mut cs = compile(source, ExpansionLevel::None)?;
+    assert!(import::read_trace(r#"{"m": {"A": [1, 2"#.as_bytes(), &mut cs, false).is_err());
+    Ok(())
+}
+
+#[test]
+fn diff_expected_witness() -> Result<()> {
+    let source = "(module m) (defcolumns A (B :comp (* 3 A)))";
+    let trace = r#"{"m": {"A": [1, 2, 3]}}"#;
+    let mut cs = expand(source, trace, ExpansionLevel::top());
+    let mut out = Vec::new();
+    cs.write(&mut out)?;
+    let mut golden: serde_json::Value = serde_json::from_slice(&out)?;
+
+    let diff = check::diff_expected(&cs, &golden)?;
+    assert!(diff.is_clean());
+    assert_eq!(diff.columns.len(), 2);
+    assert!(diff
+        .to_string()
+        .ends_with("2 columns compared, 0 diverging, 0 missing, 0 unknown"));
+
+    // B diverges on its two last rows, and the golden witness lacks A
+    let b = golden["columns"]["m.B"]["values"].as_array_mut().unwrap();
+    let computed = b[2].clone();
+    b[2] = "0x042".into();
+    b.pop();
+    golden["columns"].as_object_mut().unwrap().remove("m.A");
+    let diff = check::diff_expected(&cs, &golden)?;
+    assert!(!diff.is_clean());
+    assert_eq!(diff.missing, ["m.A"]);
+    let diverging = diff.diverging().collect::<Vec<_>>();
+    assert_eq!(diverging.len(), 1);
+    let b = diverging[0];
+    assert_eq!(b.name, "m.B");
+    assert_eq!(b.mismatches, 2);
+    assert_eq!((b.golden_rows, b.computed_rows), (3, 4));
+    let first = b.first_divergence.as_ref().unwrap();
+    assert_eq!(first.row, 2);
+    assert_eq!(first.golden.as_deref(), Some("42"));
+    assert_eq!(
+        first.computed.as_deref(),
+        computed.as_str().and_then(|x| x.strip_prefix("0x0"))
+    );
+
+    // values are compared as field elements, whatever their JSON type
+    let mut golden: serde_json::Value = serde_json::from_slice(&out)?;
+    golden["columns"]["m.A"]["values"] = serde_json::json!([0, 1, "2", "0x03"]);
+    assert!(check::diff_expected(&cs, &golden)?.is_clean());
+    Ok(())
+}
@@ -4,6 +4,7 @@ use std::path::Path;
 use std::println;
 
 use crate::compiler::RawMagma;
+use crate::utils::Colorize;
 use crate::{
     compiler::{ConstraintSet, Kind, Magma},
     structs::Handle,
@@ -12,7 +13,6 @@ use anyhow::*;
 use convert_case::{Case, Casing};
 use handlebars::Handlebars;
 use itertools::Itertools;
-use owo_colors::OwoColorize;
 use serde::Serialize;
 
 use super::reg_to_string;
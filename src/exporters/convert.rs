@@ -1,3 +1,4 @@
+use crate::utils::Colorize;
 use crate::{
     column::ValueBacking,
     compiler::{ColumnRef, ConstraintSet, Kind},
@@ -7,7 +8,6 @@ use crate::{
 use anyhow::*;
 use itertools::Itertools;
 use log::*;
-use owo_colors::OwoColorize;
 use rayon::prelude::*;
 use std::fs::File;
 use std::io::{BufWriter, Write};
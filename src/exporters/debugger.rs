@@ -4,12 +4,13 @@ use crate::compiler::{Constraint, ConstraintSet, Expression, Intrinsic, Node};
 use crate::constants;
 use crate::pretty::Pretty;
 use crate::structs::Handle;
+use crate::utils::Colorize;
 use anyhow::*;
 use convert_case::{Case, Casing};
 use ellipse::Ellipse;
 use itertools::Itertools;
+use owo_colors::colored::Color;
 use owo_colors::XtermColors;
-use owo_colors::{colored::Color, OwoColorize};
 use std::cmp::Ordering;
 
 fn priority(a: Intrinsic, b: Intrinsic) -> Ordering {
@@ -104,6 +105,17 @@ fn pretty_expr(n: &Node, prev: Option<Intrinsic>, tty: &mut Tty, show_types: boo
                 pretty_expr(&args[0], prev, tty, show_types);
                 tty.write(")");
             }
+            Intrinsic::Mod | Intrinsic::Div => {
+                tty.write(if matches!(f, Intrinsic::Mod) {
+                    "MOD("
+                } else {
+                    "DIV("
+                });
+                pretty_expr(&args[0], None, tty, show_types);
+                tty.write(", ");
+                pretty_expr(&args[1], None, tty, show_types);
+                tty.write(")");
+            }
             Intrinsic::Begin => todo!(),
             Intrinsic::IfZero => {
                 tty.write("if-zero ".color(c).bold().to_string());
@@ -232,6 +244,20 @@ fn render_constraints(
                             .join(", "),
                     )
                 }
+                Constraint::LookupMultiplicity {
+                    handle,
+                    including,
+                    included,
+                    multiplicity,
+                } => {
+                    println!("\n{}", handle.pretty());
+                    println!(
+                        "{{{}}} ⊂ {{{}}} × {}",
+                        included.iter().map(|n| n.pretty()).join(", "),
+                        including.iter().map(|n| n.pretty()).join(", "),
+                        cs.handle(multiplicity).pretty()
+                    )
+                }
                 Constraint::Permutation {
                     handle, from, to, ..
                 } => {
@@ -305,12 +331,12 @@ fn render_columns(cs: &ConstraintSet) {
     println!("\n{}", "=== Columns ===".bold().yellow());
 
     println!(
-        "{:>4}{:>80}{:>6}{:>4}{:>50}",
+        "{:>4}{:>80}{:>6}{:>4}{:>50}  Tags",
         "ID", "Name", "Type", "×", "Reg."
     );
     for (r, col) in cs.columns.iter().sorted_by_key(|c| c.1.register) {
         println!(
-            "{:>4}{:>80}{:>6}{:>4}{:>50}",
+            "{:>4}{:>80}{:>6}{:>4}{:>50}  {}",
             r.as_id(),
             col.handle.to_string().as_str().truncate_ellipse(75),
             col.t.to_string(),
@@ -328,7 +354,8 @@ fn render_columns(cs: &ConstraintSet) {
                 ))
                 .unwrap_or_default()
                 .as_str()
-                .truncate_ellipse(45)
+                .truncate_ellipse(45),
+            col.tags.join(" ")
         );
     }
 }
@@ -347,7 +374,24 @@ fn render_computations(cs: &ConstraintSet) {
                     froms.iter().map(|c| cs.handle(c).pretty()).join(", ")
                 )
             }
-            Computation::Sorted { froms, tos, signs } => println!(
+            Computation::InterleavedStrided {
+                target,
+                froms,
+                strides,
+            } => {
+                println!(
+                    "{} ⪡ {}",
+                    cs.handle(target).pretty(),
+                    froms
+                        .iter()
+                        .zip(strides.iter())
+                        .map(|(c, s)| format!("{}×{}", cs.handle(c).pretty(), s))
+                        .join(", ")
+                )
+            }
+            Computation::Sorted {
+                froms, tos, signs, ..
+            } => println!(
                 "[{}] ⇳ [{}]",
                 tos.iter().map(|c| cs.handle(c).pretty()).join(" "),
                 froms
@@ -383,10 +427,59 @@ fn render_computations(cs: &ConstraintSet) {
             Computation::ExoConstant { value, target } => {
                 println!("{} := {}", target.pretty(), value)
             }
+            Computation::ConstantTable { target, values } => println!(
+                "{} := {{{}}}",
+                cs.handle(target).pretty(),
+                values.iter().map(|v| v.pretty()).join(" ")
+            ),
+            Computation::LookupMultiplicity {
+                target,
+                including,
+                included,
+            } => println!(
+                "{} ≜ #[{}] in [{}]",
+                cs.handle(target).pretty(),
+                included.iter().map(|e| e.pretty()).join(" "),
+                including.iter().map(|e| e.pretty()).join(" ")
+            ),
+            Computation::ActiveRows { target } => {
+                println!("{} ≜ 1 outside of the padding", cs.handle(target).pretty())
+            }
+            Computation::Fixpoint { target, exp } => println!(
+                "{} ≜ fixpoint({})",
+                cs.handle(target).pretty(),
+                exp.pretty()
+            ),
+            Computation::Recurrence { target, exp, init } => println!(
+                "{} ≜ {} from {}",
+                cs.handle(target).pretty(),
+                exp.pretty(),
+                init.pretty()
+            ),
+            Computation::Clamp {
+                target,
+                exp,
+                lo,
+                hi,
+                ..
+            } => println!(
+                "{} ≜ clamp({}, {}, {})",
+                cs.handle(target).pretty(),
+                exp.pretty(),
+                lo.pretty(),
+                hi.pretty()
+            ),
         }
     }
 }
 
+fn render_computation_order(cs: &ConstraintSet) {
+    println!("\n{}", "=== Computation Order ===".bold().yellow());
+    for (i, h) in cs.computation_order().iter().enumerate() {
+        println!("{:>4}. {}", i + 1, h.pretty())
+    }
+}
+
 fn render_perspectives(cs: &ConstraintSet) {
     println!("\n{}", "=== Perspectives ===".bold().yellow());
     for (module, persps) in cs.perspectives.iter() {
@@ -407,6 +500,24 @@ fn render_spilling(cs: &ConstraintSet) {
     }
 }
 
+fn render_spilling_explanation(cs: &ConstraintSet) {
+    println!("\n{}", "=== Spilling ===".bold().yellow());
+    for module in cs.columns.spilling.keys().sorted() {
+        let explanation = cs.explain_spilling(module);
+        println!("{}: {}", module.blue().bold(), explanation.spilling);
+        for (direction, shift) in [
+            ("backward", explanation.past),
+            ("forward", explanation.future),
+        ] {
+            if let Some((shift, origin)) = shift {
+                println!("  max. {:<8} shift {:>4} in {}", direction, shift, origin);
+            } else {
+                println!("  no {} shift", direction);
+            }
+        }
+    }
+}
+
 fn render_spilling_toml(cs: &ConstraintSet) {
     println!("# Automatically generated via `corset debug -s --toml`");
     println!("[spillings]");
@@ -427,6 +538,7 @@ pub(crate) struct DebugSettings {
     pub perspectives: bool,
     pub types: bool,
     pub spilling: bool,
+    pub explain_spilling: bool,
     pub toml: bool,
 }
 
@@ -450,12 +562,15 @@ pub(crate) fn debug(
     }
     if settings.computations {
         render_computations(cs);
+        render_computation_order(cs);
     }
     if settings.perspectives {
         render_perspectives(cs);
     }
     if settings.spilling && settings.toml {
         render_spilling_toml(cs);
+    } else if settings.explain_spilling {
+        render_spilling_explanation(cs);
     } else if settings.spilling {
         render_spilling(cs);
     }
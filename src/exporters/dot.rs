@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{
+    compiler::{Constraint, ConstraintSet},
+    dag::ComputationDag,
+    structs::Handle,
+};
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Returns, for each constraint, the columns it refers to, as a set of
+/// `(constraint, column)` edges sorted by name.
+pub fn constraint_edges(cs: &ConstraintSet) -> Vec<(Handle, Handle)> {
+    cs.constraints
+        .iter()
+        .flat_map(|c| {
+            let columns = match c {
+                Constraint::Vanishes { expr, .. } => expr.dependencies(),
+                Constraint::Lookup {
+                    including,
+                    included,
+                    ..
+                } => including
+                    .iter()
+                    .chain(included.iter())
+                    .flat_map(|n| n.dependencies())
+                    .collect(),
+                Constraint::LookupMultiplicity {
+                    including,
+                    included,
+                    multiplicity,
+                    ..
+                } => including
+                    .iter()
+                    .chain(included.iter())
+                    .flat_map(|n| n.dependencies())
+                    .chain(std::iter::once(multiplicity.clone()))
+                    .collect(),
+                Constraint::Permutation { from, to, .. } => {
+                    from.iter().chain(to.iter()).cloned().collect()
+                }
+                Constraint::InRange { exp, .. } => exp.dependencies(),
+                Constraint::Normalization {
+                    reference,
+                    inverted,
+                    ..
+                } => {
+                    let mut r = reference.dependencies();
+                    r.insert(inverted.clone());
+                    r
+                }
+            };
+            columns
+                .iter()
+                .map(|r| (c.handle().to_owned(), cs.handle(r).to_owned()))
+                .collect::<Vec<_>>()
+        })
+        .sorted_by_cached_key(|(c, col)| (c.to_string(), col.to_string()))
+        .dedup()
+        .collect()
+}
+
+/// Render the bipartite graph linking constraints to the columns they refer
+/// to in the DOT format
+pub fn render_constraints(cs: &ConstraintSet) -> String {
+    let edges = constraint_edges(cs);
+    let constraints = edges
+        .iter()
+        .map(|e| e.0.to_string())
+        .collect::<HashSet<_>>();
+    let columns = edges
+        .iter()
+        .map(|e| e.1.to_string())
+        .collect::<HashSet<_>>();
+
+    let mut r = String::from("digraph constraints {\n  rankdir=LR;\n");
+    for c in constraints.iter().sorted() {
+        r.push_str(&format!(
+            "  {} [label={}, shape=box];\n",
+            quote(&format!("constraint:{}", c)),
+            quote(c)
+        ));
+    }
+    for c in columns.iter().sorted() {
+        r.push_str(&format!(
+            "  {} [label={}, shape=ellipse];\n",
+            quote(&format!("column:{}", c)),
+            quote(c)
+        ));
+    }
+    for (c, col) in edges.iter() {
+        r.push_str(&format!(
+            "  {} -> {};\n",
+            quote(&format!("constraint:{}", c)),
+            quote(&format!("column:{}", col))
+        ));
+    }
+    r.push_str("}\n");
+    r
+}
+
+/// Render the dependency graph of the computed columns in the DOT format
+pub fn render_computations(cs: &ConstraintSet) -> String {
+    let dag = ComputationDag::from_computations(cs.computations.iter());
+    let mut r = String::from("digraph computations {\n");
+    for (from, to) in dag
+        .edges()
+        .map(|(from, to)| (cs.handle(from).to_string(), cs.handle(to).to_string()))
+        .sorted()
+    {
+        r.push_str(&format!("  {} -> {};\n", quote(&from), quote(&to)));
+    }
+    r.push_str("}\n");
+    r
+}
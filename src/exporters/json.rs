@@ -0,0 +1,64 @@
+//! Export the whole compiled constraint system as JSON, so that external tools
+//! may consume it without having to parse Corset sources.
+//!
+//! The layout is the one of the compiled binfiles, but for the field elements
+//! and constants, that are written as decimal strings instead of their internal
+//! representation. Column references keep both their handle and their ID, as
+//! `module.name#id`. As JSON objects are rendered with sorted keys, the output
+//! is stable, and may be diffed across Corset versions.
+use anyhow::*;
+use serde_json::Value as JsonValue;
+
+use crate::{column::Value, compiler::ConstraintSet};
+
+/// Replace every serialized [`Value`] in `j` by its decimal representation
+fn decimalize(j: &mut JsonValue) {
+    let is_value = j
+        .as_object()
+        .map(|o| o.len() == 1 && (o.contains_key("BigInt") || o.contains_key("Native")))
+        .unwrap_or(false);
+    if is_value {
+        if let Result::Ok(x) = serde_json::from_value::<Value>(j.clone()) {
+            *j = JsonValue::String(x.to_bi().to_string());
+            return;
+        }
+    }
+
+    match j {
+        JsonValue::Object(o) => {
+            for v in o.values_mut() {
+                decimalize(v);
+            }
+        }
+        JsonValue::Array(xs) => {
+            for x in xs.iter_mut() {
+                decimalize(x);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Serialize `cs` to a JSON tree, field elements being written as decimal
+/// strings
+pub fn to_json(cs: &ConstraintSet) -> Result<JsonValue> {
+    let mut r = serde_json::to_value(cs)?;
+    decimalize(&mut r);
+    r["constants"] = cs
+        .constants
+        .iter()
+        .map(|(h, x)| (h.to_string(), JsonValue::String(x.to_string())))
+        .collect::<serde_json::Map<_, _>>()
+        .into();
+    Ok(r)
+}
+
+/// Render `cs` as a JSON string, indented if `pretty` is set
+pub fn render(cs: &ConstraintSet, pretty: bool) -> Result<String> {
+    let r = to_json(cs)?;
+    Ok(if pretty {
+        serde_json::to_string_pretty(&r)?
+    } else {
+        serde_json::to_string(&r)?
+    })
+}
@@ -192,7 +192,7 @@ fn render_form(args: &[AstNode], state: State) -> Result<String> {
                 render_node(&args[2], state)?
             )),
             "vanishes" => Ok(format!("{} = 0", render_node(&args[1], state)?,)),
-            "let" => {
+            "let" | "let*" => {
                 let mut r = String::new();
                 for xs in args[1]
                     .as_list()
@@ -235,6 +235,7 @@ fn with_env(env: &str, x: &str) -> String {
 fn render_node(n: &AstNode, state: State) -> Result<String> {
     match &n.class {
         Token::Value(x) => Ok(x.to_string()),
+        Token::Boolean(x) => Ok(x.to_string()),
         Token::Symbol(name) => Ok(if state.columns.contains(name) {
             as_col(name.as_str())
         } else {
@@ -261,6 +262,7 @@ fn render_node(n: &AstNode, state: State) -> Result<String> {
             domain,
             guard: _,
             perspective: _,
+            no_padding: _,
             body,
         } => Ok(format!(
             "\n\\begin{{constraint}}[{}{} {}]\n\\begin{{gather*}}\n{}\n\\end{{gather*}}\n\\end{{constraint}}\n",
@@ -333,8 +335,8 @@ fn constraints(ast: &Ast) -> Vec<LatexConstraint> {
     ast.exprs
         .iter()
         .filter_map(|n| match &n.class {
-            Token::DefModule(m) => {
-                module = m.to_owned();
+            Token::DefModule { name, .. } => {
+                module = name.to_owned();
                 None
             }
             Token::DefConstraint {
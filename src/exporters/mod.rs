@@ -6,6 +6,8 @@ pub mod besu;
 pub mod conflater;
 pub mod convert;
 pub(crate) mod debugger;
+pub mod dot;
+pub mod json;
 #[cfg(feature = "exporters")]
 pub mod latex;
 #[cfg(feature = "exporters")]
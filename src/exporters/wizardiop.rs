@@ -139,98 +139,114 @@ fn render_funcall(cs: &ConstraintSet, func: &Intrinsic, args: &[Node]) -> String
     }
 }
 
-fn render_constraints(cs: &ConstraintSet) -> Vec<String> {
-    cs.constraints
+fn render_constraints(cs: &ConstraintSet) -> Result<Vec<String>> {
+    Ok(cs
+        .constraints
         .iter()
         .sorted_by_key(|c| c.name())
-        .flat_map(|constraint| match constraint {
-            Constraint::Vanishes {
-                handle,
-                domain,
-                expr,
-            } => render_constraint(cs, &handle.to_string(), domain.clone(), expr),
-            Constraint::Lookup {
-                handle,
-                including,
-                included,
-            } => vec![format!(
-                "build.Inclusion(\"{}\", []Handle{{{}}}, []Handle{{{}}})",
-                handle,
-                including
-                    .iter()
-                    .map(|h| render_maybe_exo_handle(cs, h))
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                included
-                    .iter()
-                    .map(|h| render_maybe_exo_handle(cs, h))
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            )],
-            Constraint::Permutation {
-                handle, from, to, ..
-            } => vec![format!(
-                "build.Permutation(\"{}\", []Handle{{{}}}, []Handle{{{}}})",
-                handle.mangle().to_case(Case::Snake),
-                from.iter()
-                    .map(|c| reg_mangle(cs, c).unwrap())
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                to.iter()
-                    .map(|h| reg_mangle(cs, h).unwrap())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            )],
-            Constraint::InRange { handle, exp, max } => vec![format!(
-                "build.Range(\"{}\", {}, {})",
-                handle.mangle().to_case(Case::Snake),
-                render_handle(cs, exp),
-                max.pretty()
-            )],
-            Constraint::Normalization {
-                handle,
-                reference,
-                inverted,
-            } => {
-                let mut r = Vec::new();
-                let x = reference.clone();
-                let inv_x = Node::column().handle(inverted.clone()).build();
-                let x_times_inv_x = Intrinsic::Mul.call(&[x.clone(), inv_x.clone()]).unwrap();
-                let one = Node::from_isize(1);
+        .map(|constraint| -> Result<Vec<String>> {
+            if constraint.wraps() {
+                bail!(
+                    "constraint {} wraps around its columns, which can not be exported to WizardIOP",
+                    constraint.handle().pretty()
+                )
+            }
+            Ok(match constraint {
+                Constraint::Vanishes {
+                    handle,
+                    domain,
+                    expr,
+                } => render_constraint(cs, &handle.to_string(), domain.clone(), expr),
+                // WizardIOP computes its own multiplicities, and would leave the
+                // one of the constraint set unconstrained
+                Constraint::LookupMultiplicity { handle, .. } => bail!(
+                    "lookup {} with an explicit multiplicity can not be exported to WizardIOP",
+                    handle.pretty()
+                ),
+                Constraint::Lookup {
+                    handle,
+                    including,
+                    included,
+                } => vec![format!(
+                    "build.Inclusion(\"{}\", []Handle{{{}}}, []Handle{{{}}})",
+                    handle,
+                    including
+                        .iter()
+                        .map(|h| render_maybe_exo_handle(cs, h))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    included
+                        .iter()
+                        .map(|h| render_maybe_exo_handle(cs, h))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )],
+                Constraint::Permutation {
+                    handle, from, to, ..
+                } => vec![format!(
+                    "build.Permutation(\"{}\", []Handle{{{}}}, []Handle{{{}}})",
+                    handle.mangle().to_case(Case::Snake),
+                    from.iter()
+                        .map(|c| reg_mangle(cs, c).unwrap())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    to.iter()
+                        .map(|h| reg_mangle(cs, h).unwrap())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )],
+                Constraint::InRange { handle, exp, max } => vec![format!(
+                    "build.Range(\"{}\", {}, {})",
+                    handle.mangle().to_case(Case::Snake),
+                    render_handle(cs, exp),
+                    max.pretty()
+                )],
+                Constraint::Normalization {
+                    handle,
+                    reference,
+                    inverted,
+                } => {
+                    let mut r = Vec::new();
+                    let x = reference.clone();
+                    let inv_x = Node::column().handle(inverted.clone()).build();
+                    let x_times_inv_x = Intrinsic::Mul.call(&[x.clone(), inv_x.clone()]).unwrap();
+                    let one = Node::from_isize(1);
 
-                // X × (1 - X × /X)
-                r.append(&mut render_constraint(
-                    cs,
-                    &format!("{}#1", handle),
-                    None,
-                    &Intrinsic::Mul
-                        .call(&[
-                            x.clone(),
-                            Intrinsic::Sub
-                                .call(&[one.clone(), x_times_inv_x.clone()])
-                                .unwrap(),
-                        ])
-                        .unwrap(),
-                ));
-                // /X × (1 - X × /X)
-                r.append(&mut render_constraint(
-                    cs,
-                    &format!("{}#2", handle),
-                    None,
-                    &Intrinsic::Mul
-                        .call(&[
-                            inv_x.clone(),
-                            Intrinsic::Sub
-                                .call(&[one.clone(), x_times_inv_x.clone()])
-                                .unwrap(),
-                        ])
-                        .unwrap(),
-                ));
+                    // X × (1 - X × /X)
+                    r.append(&mut render_constraint(
+                        cs,
+                        &format!("{}#1", handle),
+                        None,
+                        &Intrinsic::Mul
+                            .call(&[
+                                x.clone(),
+                                Intrinsic::Sub
+                                    .call(&[one.clone(), x_times_inv_x.clone()])
+                                    .unwrap(),
+                            ])
+                            .unwrap(),
+                    ));
+                    // /X × (1 - X × /X)
+                    r.append(&mut render_constraint(
+                        cs,
+                        &format!("{}#2", handle),
+                        None,
+                        &Intrinsic::Mul
+                            .call(&[
+                                inv_x.clone(),
+                                Intrinsic::Sub
+                                    .call(&[one.clone(), x_times_inv_x.clone()])
+                                    .unwrap(),
+                            ])
+                            .unwrap(),
+                    ));
 
-                r
-            }
+                    r
+                }
+            })
         })
-        .collect()
+        .collect::<Result<Vec<_>>>()?
+        .concat())
 }
 
 fn make_size(h: &Handle, sizes: &mut HashSet<String>) -> String {
@@ -347,7 +363,10 @@ fn render_columns(cs: &ConstraintSet, sizes: &mut HashSet<String>) -> Vec<WiopCo
     w_cols
 }
 
-fn render_interleaved(cs: &ConstraintSet, _sizes: &mut HashSet<String>) -> Vec<WiopInterleaved> {
+fn render_interleaved(
+    cs: &ConstraintSet,
+    _sizes: &mut HashSet<String>,
+) -> Result<Vec<WiopInterleaved>> {
     cs.columns
         .iter()
         .filter(|col| {
@@ -357,25 +376,30 @@ fn render_interleaved(cs: &ConstraintSet, _sizes: &mut HashSet<String>) -> Vec<W
                 == Some(true)
         })
         .sorted_by_cached_key(|col| col.1.handle.mangle())
-        .filter_map(|(h, column)| {
-            if column.used {
-                Some(WiopInterleaved {
-                    go_id: reg_mangle(cs, &h).unwrap(),
-                    interleaving: if let Some(Computation::Interleaved { froms, .. }) =
-                        cs.computations.computation_for(&h)
-                    {
-                        froms
-                            .iter()
-                            .map(|c| reg_mangle(cs, c).unwrap())
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    } else {
-                        unreachable!()
-                    },
-                })
-            } else {
-                None
-            }
+        .filter(|(_, column)| column.used)
+        .map(|(h, column)| {
+            let froms = match cs.computations.computation_for(&h) {
+                Some(Computation::Interleaved { froms, .. }) => froms,
+                // a single row from each source is a plain interleaving
+                Some(Computation::InterleavedStrided { froms, strides, .. })
+                    if strides.iter().all(|s| *s == 1) =>
+                {
+                    froms
+                }
+                Some(Computation::InterleavedStrided { .. }) => bail!(
+                    "strided interleaving {} can not be exported to WizardIOP",
+                    column.handle.pretty()
+                ),
+                _ => unreachable!(),
+            };
+            Ok(WiopInterleaved {
+                go_id: reg_mangle(cs, &h)?,
+                interleaving: froms
+                    .iter()
+                    .map(|c| reg_mangle(cs, c))
+                    .collect::<Result<Vec<_>>>()?
+                    .join(", "),
+            })
         })
         .collect()
 }
@@ -462,8 +486,8 @@ pub fn render(cs: &ConstraintSet, out_filename: &Option<String>) -> Result<()> {
         TEMPLATE,
         &TemplateData {
             columns: render_columns(cs, &mut sizes),
-            interleaved: render_interleaved(cs, &mut sizes),
-            constraints: render_constraints(cs),
+            interleaved: render_interleaved(cs, &mut sizes)?,
+            constraints: render_constraints(cs)?,
         },
     )?;
 
@@ -4,6 +4,10 @@ import (
 	"github.com/ethereum/go-ethereum/zk-evm/zeroknowledge/witnessdata/column"
 )
 
+{{#if ce}}
+const ColumnsAssignment = "{{ce}}"
+{{/if}}
+
 const (
 {{#each constants}}
 	{{this.name}} = {{this.value}}
@@ -21,12 +21,15 @@ struct GoColumn {
 #[derive(Serialize)]
 struct TemplateData {
     module: String,
+    ce: Option<String>,
     columns: Vec<GoColumn>,
     constants: Vec<GoConstant>,
     registers: Vec<(usize, String)>,
 }
 
-pub fn render(cs: &ConstraintSet, package: &str, outfile: Option<&String>) -> Result<()> {
+/// Render the Go columns definitions; the columns assignment (CE) name
+/// declared in the source, if any, takes precedence over `ce`
+pub fn render_to_string(cs: &ConstraintSet, package: &str, ce: Option<&str>) -> Result<String> {
     const TEMPLATE: &str = include_str!("zkgeth.go");
     let columns = cs
         .columns
@@ -65,15 +68,30 @@ pub fn render(cs: &ConstraintSet, package: &str, outfile: Option<&String>) -> Re
         .sorted_by(|a, b| a.name.cmp(&b.name))
         .collect::<Vec<_>>();
 
-    let r = Handlebars::new().render_template(
-        TEMPLATE,
-        &TemplateData {
-            module: package.to_owned(),
-            columns,
-            registers,
-            constants,
-        },
-    )?;
+    Handlebars::new()
+        .render_template(
+            TEMPLATE,
+            &TemplateData {
+                module: package.to_owned(),
+                ce: cs
+                    .columns_assignment
+                    .clone()
+                    .or_else(|| ce.map(|ce| ce.to_owned())),
+                columns,
+                registers,
+                constants,
+            },
+        )
+        .map_err(Into::into)
+}
+
+pub fn render(
+    cs: &ConstraintSet,
+    package: &str,
+    ce: Option<&str>,
+    outfile: Option<&String>,
+) -> Result<()> {
+    let r = render_to_string(cs, package, ce)?;
 
     if let Some(filename) = outfile.as_ref() {
         std::fs::File::create(filename)
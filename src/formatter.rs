@@ -1,6 +1,9 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::matches;
 use std::unreachable;
 
+use anyhow::{bail, Result};
+
 use crate::compiler::codetyper::Tty;
 use crate::compiler::parser::Ast;
 use crate::compiler::parser::AstNode;
@@ -200,8 +203,8 @@ fn format_defcolumns(xs: &[AstNode], tty: &mut Tty) {
     });
 }
 
-fn format_deflookup(xs: &[AstNode], tty: &mut Tty) {
-    tty.within("deflookup", None, |tty| {
+fn format_deflookup(kw: &str, xs: &[AstNode], tty: &mut Tty) {
+    tty.within(kw, None, |tty| {
         tty.cr();
         tty.each_but_last(
             xs.iter().skip(1),
@@ -303,8 +306,29 @@ fn format_defconstraint(xs: &[AstNode], tty: &mut Tty) {
     });
 }
 
-fn format_let(n: &[AstNode], tty: &mut Tty) {
-    tty.within("let", Some("let".len() + 2), |tty| {
+fn format_conditional(xs: &[AstNode], tty: &mut Tty) {
+    tty.write("(#if ");
+    if let Some(condition) = xs.get(1) {
+        condition.format(tty);
+    }
+    tty.shift(2);
+    for n in xs
+        .iter()
+        .skip(2)
+        .filter(|n| n.as_symbol().map(|s| s != "#endif").unwrap_or(true))
+    {
+        if !n.is_inline_comment() {
+            tty.cr();
+        }
+        n.format(tty);
+    }
+    tty.unshift();
+    tty.cr();
+    tty.write("#endif)");
+}
+
+fn format_let(kw: &str, n: &[AstNode], tty: &mut Tty) {
+    tty.within(kw, Some(kw.len() + 2), |tty| {
         if let Some(ls) = n.get(1).and_then(|ls| ls.as_list().ok()) {
             tty.within("", Some(1), |tty| {
                 tty.each_but_last(
@@ -549,6 +573,193 @@ impl Ast {
         tty.page_feed()
     }
 }
+/// The symbols defined by the top-level form `n`
+fn defined_symbols(n: &AstNode) -> Vec<String> {
+    fn first_symbol(n: &AstNode) -> Option<String> {
+        match &n.class {
+            Token::Symbol(s) => Some(s.to_owned()),
+            Token::List(xs) => xs.first().and_then(first_symbol),
+            _ => None,
+        }
+    }
+
+    let xs = if let Token::List(xs) = &n.class {
+        xs
+    } else {
+        return vec![];
+    };
+    match xs.first().and_then(|x| x.as_symbol().ok()) {
+        Some("defcolumns") => xs.iter().skip(1).filter_map(first_symbol).collect(),
+        Some("defconst") | Some("defalias") | Some("defunalias") => xs
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter_map(first_symbol)
+            .collect(),
+        Some("defun") | Some("defpurefun") | Some("definterleaved") => {
+            xs.get(1).and_then(first_symbol).into_iter().collect()
+        }
+        Some("defpermutation") => xs
+            .get(1)
+            .and_then(|x| x.as_list().ok())
+            .map(|targets| targets.iter().filter_map(first_symbol).collect())
+            .unwrap_or_default(),
+        Some("defperspective") => xs
+            .get(1)
+            .and_then(first_symbol)
+            .into_iter()
+            .chain(
+                xs.get(3)
+                    .and_then(|x| x.as_list().ok())
+                    .map(|cols| cols.iter().filter_map(first_symbol).collect::<Vec<_>>())
+                    .unwrap_or_default(),
+            )
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// The symbols referred to by `n` and its children
+fn used_symbols(n: &AstNode, ax: &mut HashSet<String>) {
+    match &n.class {
+        Token::Symbol(s) => {
+            ax.insert(s.to_owned());
+        }
+        Token::IndexedSymbol { name, index } => {
+            ax.insert(name.to_owned());
+            used_symbols(index, ax);
+        }
+        Token::List(xs) => {
+            for x in xs {
+                used_symbols(x, ax);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Ast {
+    /// Reorder the top-level definitions of every module so that each symbol
+    /// is defined before being used. Comments move along with the definition
+    /// they precede, or follow on the same line; definitions that do not
+    /// depend on each other keep their relative order.
+    pub fn sort_definitions(&mut self) -> Result<()> {
+        // Group each definition with its comments
+        let mut chunks: Vec<Vec<AstNode>> = vec![];
+        let mut pending = vec![];
+        for n in self.exprs.drain(..) {
+            if n.is_inline_comment() && pending.is_empty() && !chunks.is_empty() {
+                chunks.last_mut().unwrap().push(n);
+            } else if n.is_comment() {
+                pending.push(n);
+            } else {
+                pending.push(n);
+                chunks.push(std::mem::take(&mut pending));
+            }
+        }
+        let trailing = pending;
+
+        // Modules are barriers that definitions can not cross
+        let is_module = |chunk: &[AstNode]| {
+            chunk
+                .iter()
+                .find(|n| !n.is_comment())
+                .and_then(|n| n.as_list().ok())
+                .and_then(|xs| xs.first().and_then(|x| x.as_symbol().ok()))
+                == Some("module")
+        };
+        let mut sections: Vec<Vec<Vec<AstNode>>> = vec![vec![]];
+        for chunk in chunks {
+            if is_module(&chunk) {
+                sections.push(vec![chunk]);
+                sections.push(vec![]);
+            } else {
+                sections.last_mut().unwrap().push(chunk);
+            }
+        }
+
+        for section in sections {
+            self.exprs
+                .extend(sort_section(section)?.into_iter().flatten());
+        }
+        self.exprs.extend(trailing);
+        Ok(())
+    }
+}
+
+/// Topologically sort a list of definitions, each along with its comments,
+/// preferring the original order among the ones ready to be emitted
+fn sort_section(mut chunks: Vec<Vec<AstNode>>) -> Result<Vec<Vec<AstNode>>> {
+    let main = |chunk: &[AstNode]| chunk.iter().find(|n| !n.is_comment()).unwrap().clone();
+    let mut definers = HashMap::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        for name in defined_symbols(&main(chunk)) {
+            definers.insert(name, i);
+        }
+    }
+    let mut dependencies = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let n = main(chunk);
+            let mut used = HashSet::new();
+            used_symbols(&n, &mut used);
+            // The arguments of a function are local to its body
+            if let Some(args) = n
+                .as_list()
+                .ok()
+                .filter(|xs| {
+                    matches!(
+                        xs.first().and_then(|x| x.as_symbol().ok()),
+                        Some("defun") | Some("defpurefun")
+                    )
+                })
+                .and_then(|xs| xs.get(1).and_then(|x| x.as_list().ok()))
+            {
+                for arg in args.iter().skip(1) {
+                    if let Token::Symbol(a) = &arg.class {
+                        used.remove(a);
+                    }
+                }
+            }
+            used.iter()
+                .filter_map(|s| definers.get(s))
+                .filter(|j| **j != i)
+                .cloned()
+                .collect::<HashSet<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = vec![];
+    let mut ready = (0..chunks.len())
+        .filter(|i| dependencies[*i].is_empty())
+        .collect::<BTreeSet<_>>();
+    while let Some(i) = ready.pop_first() {
+        order.push(i);
+        for (j, deps) in dependencies.iter_mut().enumerate() {
+            if deps.remove(&i) && deps.is_empty() {
+                ready.insert(j);
+            }
+        }
+    }
+    if order.len() != chunks.len() {
+        bail!(
+            "circular definitions: {}",
+            (0..chunks.len())
+                .filter(|i| !order.contains(i))
+                .flat_map(|i| defined_symbols(&main(&chunks[i])))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    let mut sorted = vec![vec![]; chunks.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        sorted[rank] = std::mem::take(&mut chunks[i]);
+    }
+    Ok(sorted)
+}
+
 impl AstNode {
     fn len(&self) -> usize {
         match &self.class {
@@ -563,7 +774,7 @@ impl AstNode {
                     .sum::<usize>()
                     + 1
             }
-            Token::DefModule(m) => 2 + "module".len() + 1 + m.len(),
+            Token::DefModule { name, .. } => 2 + "module".len() + 1 + name.len(),
             _ => 0,
         }
     }
@@ -616,10 +827,13 @@ impl AstNode {
                                 format_defpairs(ns, tty)
                             }
                             Some("defperspective") => format_defperspective(ns, tty),
-                            Some("deflookup") => format_deflookup(ns, tty),
+                            Some(kw @ ("deflookup" | "deflookup-multiplicity")) => {
+                                format_deflookup(kw, ns, tty)
+                            }
                             Some("defpermutation") => format_defpermutation(ns, tty),
                             Some("definterleaved") => format_definterleaved(ns, tty),
-                            Some("let") => format_let(ns, tty),
+                            Some(kw @ ("let" | "let*")) => format_let(kw, ns, tty),
+                            Some("#if") => format_conditional(ns, tty),
                             Some("module") | Some("definrange") => {
                                 format_list(self, false, true, false, tty);
                             }
@@ -1,5 +1,6 @@
 use super::compiler::{ColumnRef, Magma};
 use crate::column::Value as CValue;
+use crate::utils::Colorize;
 use anyhow::*;
 use cached::Cached;
 use flate2::bufread::GzDecoder;
@@ -7,21 +8,20 @@ use itertools::Itertools;
 use log::*;
 use logging_timer::time;
 use num_bigint::{BigInt, Sign};
-use owo_colors::OwoColorize;
 use rayon::prelude::*;
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
 use serde_json::Value;
 #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
 use simd_json::BorrowedValue as Value;
-#[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
-use std::io::Read;
 use std::{
     fs::File,
-    io::{BufReader, Seek},
+    io::{BufRead, BufReader, Read},
 };
 
 use crate::{
-    column::{Column, Register},
+    column::{Column, Computation, Register},
     compiler::ConstraintSet,
     pretty::Pretty,
     structs::Handle,
@@ -156,6 +156,9 @@ pub fn parse_binary_trace(tracefile: &str, cs: &mut ConstraintSet, keep_raw: boo
     let trace_map = trace_reader.map()?;
     for trace_register in trace_map.headers.into_iter() {
         let column_ref: ColumnRef = trace_register.handle.clone().into();
+        if is_active_rows(cs, &column_ref) {
+            continue;
+        }
         let register_bytes = trace_reader
             .slice(trace_register.length as usize * trace_register.bytes_per_element)?;
 
@@ -165,11 +168,23 @@ pub fn parse_binary_trace(tracefile: &str, cs: &mut ConstraintSet, keep_raw: boo
             ..
         }) = cs.columns.register(&column_ref)
         {
-            let mut xs = Vec::new();
+            // Registers named after a column are padded with its padding value
+            let padding = cs
+                .columns
+                .column(&column_ref)
+                .ok()
+                .and_then(|c| c.padding_value.clone())
+                .unwrap_or_default();
+            let mut xs = Vec::with_capacity(column_capacity(
+                cs,
+                &trace_register.handle.module,
+                trace_register.length as usize,
+                *length_multiplier,
+            ));
             if !keep_raw {
                 // Add initial padding row
                 for _i in 0..*length_multiplier {
-                    xs.push(CValue::zero());
+                    xs.push(padding.clone());
                 }
             }
             // Read data
@@ -225,16 +240,21 @@ pub fn parse_binary_trace(tracefile: &str, cs: &mut ConstraintSet, keep_raw: boo
             })?;
 
             // If the parsed column is not long enought w.r.t. the
-            // minimal module length, prepend it with as many zeroes as
-            // required.
-            // Atomic columns are always padded with zeroes, so there is
-            // no need to trigger a more complex padding system.
+            // minimal module length, prepend it with as many padding values
+            // as required.
             if !keep_raw && xs_len < module_min_len {
                 xs.reverse();
-                xs.resize(module_min_len * length_multiplier, CValue::zero()); // TODO: register padding values
+                xs.resize(module_min_len * length_multiplier, padding);
                 xs.reverse();
             }
 
+            check_declared_height(cs, &trace_register.handle, xs_len, keep_raw)?;
+            record_padding(
+                cs,
+                &trace_register.handle.module,
+                xs.len() / length_multiplier,
+                trace_register.length as usize / length_multiplier,
+            );
             let module_raw_size =
                 cs.effective_len_or_set(&trace_register.handle.module, xs_len as isize);
             if xs_len as isize != module_raw_size {
@@ -258,92 +278,326 @@ pub fn parse_binary_trace(tracefile: &str, cs: &mut ConstraintSet, keep_raw: boo
 #[time("info", "Parsing trace from JSON file with SIMD")]
 pub fn parse_json_trace(tracefile: &str, cs: &mut ConstraintSet, keep_raw: bool) -> Result<()> {
     let mut f = File::open(tracefile).with_context(|| format!("while opening `{}`", tracefile))?;
+    read_trace(&mut f, cs, keep_raw).with_context(|| format!("while reading `{}`", tracefile))
+}
 
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
-    {
-        let mut content = Vec::new();
-        let mut gz = GzDecoder::new(BufReader::new(&f));
-        match gz.header() {
-            Some(_) => gz.read_to_end(&mut content),
-            None => {
-                f.rewind()?;
-                BufReader::new(&f).read_to_end(&mut content)
+/// Decode the values of an Arrow array holding a column of type `t` into `r`.
+/// Binary values are read as big-endian integers, as in binary traces.
+#[cfg(feature = "arrow")]
+fn arrow_values(a: &dyn arrow_array::Array, t: Magma, r: &mut Vec<CValue>) -> Result<()> {
+    use arrow_array::{cast::AsArray, types::*};
+
+    macro_rules! decode {
+        ($xs:expr, $to_bi:expr) => {{
+            for x in $xs {
+                let x = x.ok_or_else(|| anyhow!("null values are not allowed"))?;
+                r.push(t.rm().validate(CValue::try_from($to_bi(x)?)?)?);
             }
-        }
-        .with_context(|| format!("while reading `{}`", tracefile))?;
-        let v = simd_json::to_borrowed_value(&mut content)
-            .map_err(|e| anyhow!("while parsing json: {}", e))?;
-        fill_traces_from_json(&v, vec![], cs, &mut None, keep_raw)
-            .with_context(|| "while reading columns")
+            Ok(())
+        }};
     }
-    #[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
-    {
-        let gz = GzDecoder::new(BufReader::new(&f));
-        let v: Value = match gz.header() {
-            Some(_) => serde_json::from_reader(gz),
-            None => {
-                f.rewind()?;
-                serde_json::from_reader(BufReader::new(&f))
+    macro_rules! decode_ints {
+        ($xs:expr) => {
+            decode!($xs.iter(), |x| Result::<_>::Ok(BigInt::from(x)))
+        };
+    }
+    macro_rules! decode_strings {
+        ($xs:expr) => {
+            decode!($xs.iter(), |x: &str| x
+                .parse::<BigInt>()
+                .with_context(|| anyhow!("invalid value `{}`", x)))
+        };
+    }
+    macro_rules! decode_bytes {
+        ($xs:expr) => {
+            decode!($xs.iter(), |bs| Result::<_>::Ok(BigInt::from_bytes_be(
+                Sign::Plus,
+                bs
+            )))
+        };
+    }
+
+    if let Some(xs) = a.as_primitive_opt::<Int8Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<Int16Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<Int32Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<Int64Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<UInt8Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<UInt16Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<UInt32Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_primitive_opt::<UInt64Type>() {
+        decode_ints!(xs)
+    } else if let Some(xs) = a.as_string_opt::<i32>() {
+        decode_strings!(xs)
+    } else if let Some(xs) = a.as_string_opt::<i64>() {
+        decode_strings!(xs)
+    } else if let Some(xs) = a.as_binary_opt::<i32>() {
+        decode_bytes!(xs)
+    } else if let Some(xs) = a.as_binary_opt::<i64>() {
+        decode_bytes!(xs)
+    } else if let Some(xs) = a.as_fixed_size_binary_opt() {
+        decode_bytes!(xs)
+    } else {
+        bail!("unsupported column type {}", a.data_type())
+    }
+}
+
+/// Import a trace stored as a Parquet file, where every column is named
+/// `module.column`, or simply `column` for the main module.
+#[cfg(feature = "arrow")]
+#[time("info", "Parsing trace from Parquet file")]
+pub fn parse_parquet_trace(tracefile: &str, cs: &mut ConstraintSet, keep_raw: bool) -> Result<()> {
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let f = File::open(tracefile).with_context(|| format!("while opening `{}`", tracefile))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(f)
+        .and_then(|b| b.build())
+        .with_context(|| format!("while reading `{}`", tracefile))?;
+
+    // a column may be split across several record batches
+    let mut columns: Vec<(String, Vec<arrow_array::ArrayRef>)> = Vec::new();
+    for batch in reader {
+        let batch = batch.with_context(|| format!("while reading `{}`", tracefile))?;
+        for (i, (field, a)) in batch
+            .schema()
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .enumerate()
+        {
+            if let Some((_, arrays)) = columns.get_mut(i) {
+                arrays.push(a.clone());
+            } else {
+                columns.push((field.name().to_owned(), vec![a.clone()]));
             }
         }
-        .with_context(|| format!("while reading `{}`", tracefile))?;
-        fill_traces_from_json(&v, vec![], cs, &mut None, keep_raw)
-            .with_context(|| "while reading columns")
     }
+
+    let mut first_column = String::new();
+    for (name, arrays) in columns {
+        let (module, column) = name
+            .rsplit_once('.')
+            .unwrap_or((crate::compiler::MAIN_MODULE, &name));
+        fill_column(
+            arrays.iter().map(|a| a.len()).sum(),
+            |t, r| {
+                arrays
+                    .iter()
+                    .try_for_each(|a| arrow_values(a.as_ref(), t, r))
+                    .with_context(|| anyhow!("while decoding {}", name))
+            },
+            &[module.to_owned(), column.to_owned()],
+            cs,
+            &mut Some(&mut first_column),
+            keep_raw,
+        )?;
+    }
+    Ok(())
 }
 
 #[time("info", "Parsing trace from JSON with SIMD")]
 pub fn read_trace_str(tracestr: &[u8], cs: &mut ConstraintSet, keep_raw: bool) -> Result<()> {
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
-    {
-        let mut content = Vec::new();
-        let mut gz = GzDecoder::new(BufReader::new(tracestr));
-        match gz.header() {
-            Some(_) => {
-                gz.read_to_end(&mut content)?;
+    read_trace(tracestr, cs, keep_raw)
+}
+
+/// Fill the columns of `cs` from a JSON trace, possibly gzipped, read from
+/// `reader`. SIMD parsing needs the whole trace in memory, so that, unlike
+/// the other builds, this one does not stream it.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+pub fn read_trace<R: Read>(reader: R, cs: &mut ConstraintSet, keep_raw: bool) -> Result<()> {
+    let mut reader = BufReader::new(reader);
+    let gzipped = reader
+        .fill_buf()
+        .with_context(|| "while reading trace")?
+        .starts_with(&[0x1f, 0x8b]);
+    let mut content = Vec::new();
+    if gzipped {
+        GzDecoder::new(reader).read_to_end(&mut content)
+    } else {
+        reader.read_to_end(&mut content)
+    }
+    .with_context(|| "while reading trace")?;
+    let v = simd_json::to_borrowed_value(&mut content)
+        .map_err(|e| anyhow!("while parsing json: {}", e))?;
+    fill_traces_from_json(&v, vec![], cs, &mut None, keep_raw)
+        .with_context(|| "while reading columns")
+}
+
+/// Fill the columns of `cs` from a JSON trace, possibly gzipped, read from
+/// `reader`. The trace is streamed: every column is imported as soon as it has
+/// been read, so that only one of them is ever held in memory as JSON.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+pub fn read_trace<R: Read>(reader: R, cs: &mut ConstraintSet, keep_raw: bool) -> Result<()> {
+    let mut reader = BufReader::new(reader);
+    let gzipped = reader
+        .fill_buf()
+        .with_context(|| "while reading trace")?
+        .starts_with(&[0x1f, 0x8b]);
+    if gzipped {
+        stream_traces(BufReader::new(GzDecoder::new(reader)), cs, keep_raw)
+    } else {
+        stream_traces(reader, cs, keep_raw)
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+fn stream_traces<R: Read>(reader: R, cs: &mut ConstraintSet, keep_raw: bool) -> Result<()> {
+    let mut stream = TraceStream {
+        cs,
+        keep_raw,
+        initiator: None,
+        error: None,
+    };
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let r = TraceSeed {
+        stream: &mut stream,
+        path: Vec::new(),
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|_| deserializer.end());
+    // Errors raised while importing a column are reported as they are, rather
+    // than through their serde rendering
+    if let Some(err) = stream.error {
+        return Err(err).with_context(|| "while reading columns");
+    }
+    r.map_err(|e| anyhow!("while parsing json: {}", e))
+}
+
+/// The state shared by the whole import of a streamed trace
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+struct TraceStream<'a> {
+    cs: &'a mut ConstraintSet,
+    keep_raw: bool,
+    /// the first column imported in the current `Trace` object, that set the
+    /// length of its module
+    initiator: Option<String>,
+    /// the error having interrupted the import, if any
+    error: Option<anyhow::Error>,
+}
+
+/// Import the JSON value found at `path` in a streamed trace; arrays are
+/// imported as columns, objects are walked through, and anything else is
+/// skipped.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+struct TraceSeed<'s, 'a> {
+    stream: &'s mut TraceStream<'a>,
+    path: Vec<String>,
+}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+impl<'de> DeserializeSeed<'de> for TraceSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+impl<'de> Visitor<'de> for TraceSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a trace")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(k) = map.next_key::<String>()? {
+            if k == "Trace" {
+                debug!("Importing {}", self.path[self.path.len() - 1]);
+                let outer = self.stream.initiator.replace(String::new());
+                map.next_value_seed(TraceSeed {
+                    stream: self.stream,
+                    path: self.path.clone(),
+                })?;
+                self.stream.initiator = outer;
+            } else {
+                let mut path = self.path.clone();
+                path.push(k);
+                map.next_value_seed(TraceSeed {
+                    stream: self.stream,
+                    path,
+                })?;
             }
-            None => {
-                content = tracestr.to_vec();
+        }
+        std::result::Result::Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let known = self.path.len() >= 2 && {
+            let handle: ColumnRef = Handle::new(
+                &self.path[self.path.len() - 2],
+                &self.path[self.path.len() - 1],
+            )
+            .into();
+            let known = self.stream.cs.columns.column(&handle).is_ok()
+                || self.stream.cs.columns.register(&handle).is_some();
+            if !known {
+                debug!("ignoring unknown column {}", handle.pretty());
             }
+            known
         };
-        let v = simd_json::to_borrowed_value(&mut content)
-            .map_err(|e| anyhow!("while parsing json: {}", e))?;
-        fill_traces_from_json(&v, vec![], cs, &mut None, keep_raw)
-            .with_context(|| "while reading columns")
+        if !known {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            return std::result::Result::Ok(());
+        }
+
+        let mut xs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(x) = seq.next_element::<Value>()? {
+            xs.push(x);
+        }
+        let stream = self.stream;
+        fill_column(
+            xs.len(),
+            |t, r| json_values(&xs, t, r),
+            &self.path,
+            stream.cs,
+            &mut stream.initiator.as_mut(),
+            stream.keep_raw,
+        )
+        .map_err(|err| {
+            let msg = err.to_string();
+            stream.error = Some(err);
+            de::Error::custom(msg)
+        })
     }
-    #[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
-    {
-        let gz = GzDecoder::new(BufReader::new(tracestr));
-        let v: Value = match gz.header() {
-            Some(_) => serde_json::from_reader(gz),
-            None => serde_json::from_reader(BufReader::new(tracestr)),
-        }?;
-        fill_traces_from_json(&v, vec![], cs, &mut None, keep_raw)
-            .with_context(|| "while reading columns")
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        std::result::Result::Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        std::result::Result::Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        std::result::Result::Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        std::result::Result::Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        std::result::Result::Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        std::result::Result::Ok(())
     }
 }
 
+/// Decode the values `xs` of a JSON column of type `t` into `r`
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
-fn parse_column(
-    xs: &[Value],
-    h: &Handle,
-    t: Magma,
-    keep_raw: bool,
-    length_multiplier: usize,
-) -> Result<Vec<CValue>> {
+fn json_values(xs: &[Value], t: Magma, r: &mut Vec<CValue>) -> Result<()> {
     let mut cache_num = cached::SizedCache::with_size(200000); // ~1.60MB cache
     let mut cache_str = cached::SizedCache::with_size(200000); // ~1.60MB cache
-    let mut r = Vec::new();
-
-    if !keep_raw {
-        // Add initial padding row
-        for _i in 0..length_multiplier {
-            r.push(CValue::zero());
-        }
-    }
-    let xs = xs
-        .iter()
-        .map(|x| match x {
+    for x in xs.iter() {
+        r.push(match x {
             Value::Number(n) => t.rm().validate(
                 cache_num
                     .cache_get_or_set_with(n, || CValue::from(n.as_str()))
@@ -355,59 +609,135 @@ fn parse_column(
                     .to_owned(),
             ),
             _ => bail!("expected numeric value, found `{}`", x),
-        })
-        .collect::<Result<Vec<_>>>()?;
-
-    if let Err(msg) = crate::utils::maybe_warn(t, &r, h) {
-        error!("{}", msg);
-    };
-    r.extend(xs);
-    Ok(r)
+        }?);
+    }
+    Ok(())
 }
 
+/// Decode the values `xs` of a JSON column of type `t` into `r`
 #[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
-fn parse_column(
-    xs: &[Value],
+fn json_values(xs: &[Value], t: Magma, r: &mut Vec<CValue>) -> Result<()> {
+    let mut cache = cached::SizedCache::with_size(200000); // ~1.60MB cache
+    for x in xs.iter() {
+        let s = match x {
+            Value::Static(n) => match n {
+                simd_json::StaticNode::I64(i) => i.to_string(),
+                simd_json::StaticNode::U64(i) => i.to_string(),
+                _ => {
+                    unreachable!()
+                }
+            },
+            Value::String(s) => s.to_string(),
+            _ => bail!("expected numeric value, found `{}`", x),
+        };
+        r.push(
+            t.rm().validate(
+                cache
+                    .cache_get_or_set_with(s.clone(), || CValue::from(s.as_str()))
+                    .to_owned(),
+            )?,
+        );
+    }
+    Ok(())
+}
+
+/// Read the values of the column `h` of type `t` through `decode`, after its
+/// initial padding rows unless `keep_raw` is set
+fn read_column(
+    decode: impl FnOnce(Magma, &mut Vec<CValue>) -> Result<()>,
     h: &Handle,
     t: Magma,
     keep_raw: bool,
     length_multiplier: usize,
+    padding: &CValue,
+    capacity: usize,
 ) -> Result<Vec<CValue>> {
-    let mut cache = cached::SizedCache::with_size(200000); // ~1.60MB cache
+    let mut r = Vec::with_capacity(capacity);
     if !keep_raw {
         // Add initial padding row
-        for i in 0..length_multiplier {
-            r.push(CValue::zero());
+        for _i in 0..length_multiplier {
+            r.push(padding.clone());
         }
     }
-    let xs = xs
-        .iter()
-        .map(|x| {
-            let s = match x {
-                Value::Static(n) => match n {
-                    simd_json::StaticNode::I64(i) => i.to_string(),
-                    simd_json::StaticNode::U64(i) => i.to_string(),
-                    _ => {
-                        unreachable!()
-                    }
-                },
-                Value::String(s) => s.to_string(),
-                _ => bail!("expected numeric value, found `{}`", x),
-            };
-            t.rm().validate(
-                cache
-                    .cache_get_or_set_with(s.clone(), || CValue::from(s.as_str()))
-                    .to_owned(),
-            )
-        })
-        .collect::<Result<Vec<_>>>()?;
-    r.extend(xs);
     if let Err(msg) = crate::utils::maybe_warn(t, &r, h) {
         error!("{}", msg);
     };
+    decode(t, &mut r)?;
     Ok(r)
 }
 
+/// The number of values to reserve for a column of `rows` values in `module`:
+/// its declared height if any, otherwise its actual length, and its padding
+/// rows
+fn column_capacity(
+    cs: &ConstraintSet,
+    module: &str,
+    rows: usize,
+    length_multiplier: usize,
+) -> usize {
+    let module_min_len = cs.columns.min_len.get(module).cloned().unwrap_or(0);
+    let rows = cs
+        .columns
+        .heights
+        .get(module)
+        .map(|h| h * length_multiplier)
+        .unwrap_or(rows);
+    (rows + length_multiplier).max(module_min_len * length_multiplier)
+}
+
+/// Ensure that a column spanning `xs_len` rows, including its initial padding
+/// row unless `keep_raw` is set, matches the height declared for its module,
+/// if any
+fn check_declared_height(
+    cs: &ConstraintSet,
+    handle: &Handle,
+    xs_len: usize,
+    keep_raw: bool,
+) -> Result<()> {
+    if let Some(height) = cs.columns.heights.get(&handle.module) {
+        let rows = if keep_raw { xs_len } else { xs_len - 1 };
+        if rows != *height {
+            bail!(
+                "{} has {} rows, but module {} is declared with a height of {}",
+                handle.to_string().blue(),
+                rows.to_string().yellow().bold(),
+                handle.module.blue(),
+                height.to_string().red().bold(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether `handle` flags the active rows of its module; as these are derived
+/// from the recorded padding, any value found for them in a trace is ignored
+/// rather than trusted
+fn is_active_rows(cs: &ConstraintSet, handle: &ColumnRef) -> bool {
+    if matches!(
+        cs.computations.computation_for(handle),
+        Some(Computation::ActiveRows { .. })
+    ) {
+        warn!(
+            "ignoring the values of {} found in the trace",
+            handle.pretty()
+        );
+        true
+    } else {
+        false
+    }
+}
+
+/// Remember how many of the `padded_len` rows of `module` are padding, the
+/// `rows` other ones coming from the trace; the first column imported in a
+/// module sets it for the whole module
+fn record_padding(cs: &mut ConstraintSet, module: &str, padded_len: usize, rows: usize) {
+    cs.columns
+        .padding
+        .entry(module.to_owned())
+        .or_insert(padded_len.saturating_sub(rows));
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
 pub fn fill_traces_from_json(
     v: &Value,
     path: Vec<String>,
@@ -433,132 +763,191 @@ pub fn fill_traces_from_json(
         }
         Value::Array(xs) => {
             if path.len() >= 2 {
-                let module = path[path.len() - 2].to_string();
-                let handle: ColumnRef = Handle::new(&module, &path[path.len() - 1]).into();
-
-                // The min length can be set if the module contains range
-                // proofs, that require a minimal length of a certain power of 2
-                let module_min_len = cs.columns.min_len.get(&module).cloned().unwrap_or(0);
-                let module_spilling = cs.spilling_for_column(&handle);
-
-                if let Result::Ok(Column {
-                    t,
-                    padding_value,
-                    intrinsic_size_factor,
-                    ..
-                }) = cs.columns.column(&handle)
-                {
-                    // Determing length multiplier (if none, then default to 1)
-                    let length_multiplier = intrinsic_size_factor.unwrap_or(1);
-                    trace!("inserting {} ({})", handle, xs.len());
-                    if let Some(first_column) = initiator.as_mut() {
-                        if first_column.is_empty() {
-                            first_column.push_str(&handle.pretty());
-                        }
-                    }
-
-                    let module_spilling = module_spilling
-                        .ok_or_else(|| anyhow!("no spilling found for {}", handle.pretty()))?;
-
-                    let mut xs =
-                        parse_column(xs, handle.as_handle(), *t, keep_raw, length_multiplier)
-                            .with_context(|| anyhow!("importing {}", handle.pretty()))?;
-
-                    // Sanity check length has multiplier as factor
-                    if xs.len() % length_multiplier != 0 {
-                        bail!(
-                            "{} has an incorrect length multiplier: length {} not divisible by {}",
-                            handle.to_string().blue(),
-                            xs.len(),
-                            length_multiplier,
-                        );
-                    }
-                    // If the parsed column is not long enought w.r.t. the
-                    // minimal module length, prepend it with as many zeroes as
-                    // required.
-                    // Atomic columns are always padded with zeroes, so there is
-                    // no need to trigger a more complex padding system.
-                    if !keep_raw && xs.len() < module_min_len * length_multiplier {
-                        trace!(
-                            "padding {} to min module length ({} => {})",
-                            handle,
-                            xs.len() * length_multiplier,
-                            module_min_len
-                        );
-                        xs.reverse();
-                        xs.resize_with(module_min_len * length_multiplier, || {
-                            padding_value.clone().unwrap_or_default()
-                        });
-                        xs.reverse();
-                    }
-                    let xs_len = xs.len() / length_multiplier;
-                    // The first column sets the size of its module
-                    let module_raw_size = cs.effective_len_or_set(&module, xs_len as isize);
-                    if xs_len as isize != module_raw_size {
-                        bail!(
-                            "{} has an incorrect length: expected {} (from {}), found {}",
-                            handle.to_string().blue(),
-                            module_raw_size.to_string().red().bold(),
-                            initiator.as_ref().unwrap(),
-                            xs_len.to_string().yellow().bold(),
-                        );
-                    }
-
-                    cs.columns.set_column_value(&handle, xs, module_spilling)?
-                } else if let Some(Register {
-                    magma,
-                    length_multiplier,
-                    ..
-                }) = cs.columns.register(&handle)
-                {
-                    let module_spilling = module_spilling
-                        .ok_or_else(|| anyhow!("no spilling found for {}", handle.pretty()))?;
-
-                    let mut xs =
-                        parse_column(xs, handle.as_handle(), *magma, keep_raw, *length_multiplier)
-                            .with_context(|| anyhow!("importing {}", handle.pretty()))?;
-                    // Sanity check length has multiplier as factor
-                    if xs.len() % length_multiplier != 0 {
-                        bail!(
-                            "{} has an incorrect length multiplier: length {} not divisible by {}",
-                            handle.to_string().blue(),
-                            xs.len(),
-                            length_multiplier,
-                        );
-                    }
-                    // Extract module-normalised length
-                    let xs_len = xs.len() / length_multiplier;
-
-                    // If the parsed column is not long enought w.r.t. the
-                    // minimal module length, prepend it with as many zeroes as
-                    // required.
-                    // Atomic columns are always padded with zeroes, so there is
-                    // no need to trigger a more complex padding system.
-                    if !keep_raw && xs_len < module_min_len {
-                        xs.reverse();
-                        xs.resize(module_min_len * length_multiplier, CValue::zero()); // TODO: register padding values
-                        xs.reverse();
-                    }
-
-                    let module_raw_size = cs.effective_len_or_set(&module, xs_len as isize);
-                    if xs_len as isize != module_raw_size {
-                        bail!(
-                            "{} has an incorrect length: expected {} (from {}), found {}",
-                            handle.to_string().blue(),
-                            module_raw_size.to_string().red().bold(),
-                            initiator.as_ref().unwrap(),
-                            xs_len.to_string().yellow().bold(),
-                        );
-                    }
-
-                    cs.columns
-                        .set_register_value(&handle, xs, module_spilling)?
-                } else {
-                    debug!("ignoring unknown column {}", handle.pretty());
-                }
+                fill_column(
+                    xs.len(),
+                    |t, r| json_values(xs, t, r),
+                    &path,
+                    cs,
+                    initiator,
+                    keep_raw,
+                )?;
             }
             Ok(())
         }
         _ => Ok(()),
     }
 }
+
+/// Import the `raw_rows` values of the column at `path` -- its module and name
+/// being the two last components -- in `cs` through `decode`, padding it as
+/// required; the `initiator` is the first column imported in its module, whose
+/// length all the other ones must share
+fn fill_column(
+    raw_rows: usize,
+    decode: impl FnOnce(Magma, &mut Vec<CValue>) -> Result<()>,
+    path: &[String],
+    cs: &mut ConstraintSet,
+    initiator: &mut Option<&mut String>,
+    keep_raw: bool,
+) -> Result<()> {
+    let module = path[path.len() - 2].to_string();
+    let handle: ColumnRef = Handle::new(&module, &path[path.len() - 1]).into();
+    if is_active_rows(cs, &handle) {
+        return Ok(());
+    }
+
+    // The min length can be set if the module contains range
+    // proofs, that require a minimal length of a certain power of 2
+    let module_min_len = cs.columns.min_len.get(&module).cloned().unwrap_or(0);
+    let module_spilling = cs.spilling_for_column(&handle);
+
+    if let Result::Ok(Column {
+        t,
+        padding_value,
+        intrinsic_size_factor,
+        ..
+    }) = cs.columns.column(&handle)
+    {
+        // Determing length multiplier (if none, then default to 1)
+        let length_multiplier = intrinsic_size_factor.unwrap_or(1);
+        trace!("inserting {} ({})", handle, raw_rows);
+        if let Some(first_column) = initiator.as_mut() {
+            if first_column.is_empty() {
+                first_column.push_str(&handle.pretty());
+            }
+        }
+
+        let module_spilling =
+            module_spilling.ok_or_else(|| anyhow!("no spilling found for {}", handle.pretty()))?;
+
+        let mut xs = read_column(
+            decode,
+            handle.as_handle(),
+            *t,
+            keep_raw,
+            length_multiplier,
+            &padding_value.clone().unwrap_or_default(),
+            column_capacity(cs, &module, raw_rows, length_multiplier),
+        )
+        .with_context(|| anyhow!("importing {}", handle.pretty()))?;
+
+        // Sanity check length has multiplier as factor
+        if xs.len() % length_multiplier != 0 {
+            bail!(
+                "{} has an incorrect length multiplier: length {} not divisible by {}",
+                handle.to_string().blue(),
+                xs.len(),
+                length_multiplier,
+            );
+        }
+        // The declared height applies to the trace, not to the padding added
+        // to reach the minimal module length
+        check_declared_height(
+            cs,
+            handle.as_handle(),
+            xs.len() / length_multiplier,
+            keep_raw,
+        )?;
+        // If the parsed column is not long enought w.r.t. the
+        // minimal module length, prepend it with as many copies of
+        // its `:padding` value (zero by default) as required.
+        if !keep_raw && xs.len() < module_min_len * length_multiplier {
+            trace!(
+                "padding {} to min module length ({} => {})",
+                handle,
+                xs.len() * length_multiplier,
+                module_min_len
+            );
+            xs.reverse();
+            xs.resize_with(module_min_len * length_multiplier, || {
+                padding_value.clone().unwrap_or_default()
+            });
+            xs.reverse();
+        }
+        let xs_len = xs.len() / length_multiplier;
+        // The first column sets the size of its module
+        record_padding(cs, &module, xs_len, raw_rows / length_multiplier);
+        let module_raw_size = cs.effective_len_or_set(&module, xs_len as isize);
+        if xs_len as isize != module_raw_size {
+            bail!(
+                "{} has an incorrect length: expected {} (from {}), found {}",
+                handle.to_string().blue(),
+                module_raw_size.to_string().red().bold(),
+                initiator.as_ref().unwrap(),
+                xs_len.to_string().yellow().bold(),
+            );
+        }
+
+        cs.columns.set_column_value(&handle, xs, module_spilling)?
+    } else if let Some(Register {
+        magma,
+        length_multiplier,
+        ..
+    }) = cs.columns.register(&handle)
+    {
+        let module_spilling =
+            module_spilling.ok_or_else(|| anyhow!("no spilling found for {}", handle.pretty()))?;
+        // Registers named after a column are padded with its padding value
+        let padding = cs
+            .columns
+            .column(&handle)
+            .ok()
+            .and_then(|c| c.padding_value.clone())
+            .unwrap_or_default();
+
+        let mut xs = read_column(
+            decode,
+            handle.as_handle(),
+            *magma,
+            keep_raw,
+            *length_multiplier,
+            &padding,
+            column_capacity(cs, &module, raw_rows, *length_multiplier),
+        )
+        .with_context(|| anyhow!("importing {}", handle.pretty()))?;
+        // Sanity check length has multiplier as factor
+        if xs.len() % length_multiplier != 0 {
+            bail!(
+                "{} has an incorrect length multiplier: length {} not divisible by {}",
+                handle.to_string().blue(),
+                xs.len(),
+                length_multiplier,
+            );
+        }
+        // Extract module-normalised length
+        let xs_len = xs.len() / length_multiplier;
+
+        // If the parsed column is not long enought w.r.t. the
+        // minimal module length, prepend it with as many copies of
+        // its padding value as required.
+        if !keep_raw && xs_len < module_min_len {
+            xs.reverse();
+            xs.resize(module_min_len * length_multiplier, padding);
+            xs.reverse();
+        }
+
+        check_declared_height(cs, handle.as_handle(), xs_len, keep_raw)?;
+        record_padding(
+            cs,
+            &module,
+            xs.len() / length_multiplier,
+            raw_rows / length_multiplier,
+        );
+        let module_raw_size = cs.effective_len_or_set(&module, xs_len as isize);
+        if xs_len as isize != module_raw_size {
+            bail!(
+                "{} has an incorrect length: expected {} (from {}), found {}",
+                handle.to_string().blue(),
+                module_raw_size.to_string().red().bold(),
+                initiator.as_ref().unwrap(),
+                xs_len.to_string().yellow().bold(),
+            );
+        }
+
+        cs.columns
+            .set_register_value(&handle, xs, module_spilling)?
+    } else {
+        debug!("ignoring unknown column {}", handle.pretty());
+    }
+    Ok(())
+}
@@ -122,7 +122,7 @@ pub extern "C" fn corset_from_string(zkevmstr: *const c_char) -> *mut Corset {
 }
 
 fn _trace_check(corset: &mut ConstraintSet, tracefile: &str, fail_on_missing: bool) -> Result<()> {
-    compute::compute_trace(tracefile, corset, fail_on_missing)
+    compute::compute_trace(tracefile, corset, fail_on_missing, false)
         .with_context(|| format!("while expanding `{}`", tracefile))?;
 
     check::check(
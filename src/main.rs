@@ -1,17 +1,19 @@
 #![cfg(feature = "cli")]
 #[macro_use]
 extern crate pest_derive;
+use crate::utils::Colorize;
 use anyhow::*;
 use compiler::parser::Ast;
-use compiler::ConstraintSet;
+use compiler::{Constraint, ConstraintSet};
 use either::Either;
+use itertools::Itertools;
 use log::*;
 use logging_timer::time;
-use owo_colors::OwoColorize;
+use reports::{DegreeReport, Report, ReportFormat, StatsReport};
 use std::sync::RwLock;
 use std::{
     io::{Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 use transformer::{AutoConstraint, ExpansionLevel};
 
@@ -31,11 +33,15 @@ mod formatter;
 mod import;
 #[cfg(feature = "inspector")]
 mod inspect;
+mod minimize;
 mod pretty;
+mod reports;
 mod structs;
+mod symbolic;
 #[cfg(test)]
 mod tests;
 mod transformer;
+mod transpilers;
 mod utils;
 
 pub(crate) static IS_NATIVE: RwLock<bool> = RwLock::new(false);
@@ -58,9 +64,45 @@ pub struct Args {
     #[arg(long="auto-constraints", value_parser=["sorts", "nhood"], value_delimiter=',', global=true)]
     auto_constraints: Vec<String>,
 
+    #[arg(
+        long = "no-auto-constraints",
+        help = "only keep the constraints explicitly written in the sources, even for the commands enabling all the auto-constraints",
+        conflicts_with = "auto_constraints",
+        global = true
+    )]
+    no_auto_constraints: bool,
+
+    #[arg(
+        long = "aux-prefix",
+        help = "prefix to use instead of the default ones in the names of the auxiliary columns introduced by the expansions and the auto-constraints",
+        global = true
+    )]
+    aux_prefix: Option<String>,
+
+    #[arg(
+        long = "max-degree",
+        help = "fail if a vanishing constraint has a multiplicative degree higher than this",
+        global = true
+    )]
+    max_degree: Option<usize>,
+
     #[arg(long = "debug", help = "Compile code in debug mode", global = true)]
     debug: bool,
 
+    #[arg(
+        long = "reduce-constants",
+        help = "reduce modulo the field order the constants too large to fit in a field element, instead of rejecting them",
+        global = true
+    )]
+    reduce_constants: bool,
+
+    #[arg(
+        long = "warn-unreferenced-constant",
+        help = "warn about the constants that are defined but never referenced",
+        global = true
+    )]
+    warn_unreferenced_constant: bool,
+
     #[arg(
         long,
         help = "generate binfile using Rusty Object Notation (RON) instead of JSON",
@@ -68,6 +110,14 @@ pub struct Args {
     )]
     ron: bool,
 
+    #[arg(
+        long,
+        help = "read and write binfiles in the bincode binary format, much faster to load, instead of JSON",
+        conflicts_with = "ron",
+        global = true
+    )]
+    bincode: bool,
+
     #[arg(
         short = 't',
         long = "threads",
@@ -88,10 +138,71 @@ pub struct Args {
     #[arg(long = "no-stdlib")]
     no_stdlib: bool,
 
+    #[arg(
+        long = "fail-fast",
+        help = "abort on the first failing computation (default)",
+        conflicts_with = "keep_going",
+        global = true
+    )]
+    fail_fast: bool,
+
+    #[arg(
+        long = "keep-going",
+        help = "only warn on failing computations and carry on",
+        global = true
+    )]
+    keep_going: bool,
+
+    #[arg(
+        long = "format",
+        help = "how to write the analysis reports",
+        value_parser = ["text", "json", "csv"],
+        default_value = "text",
+        global = true
+    )]
+    format: String,
+
+    #[arg(
+        long = "color",
+        help = "when to colorize the output",
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        global = true
+    )]
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ColorChoice {
+    /// colorize only when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+impl ColorChoice {
+    /// Force colors on or off, or let them depend on whether the output is a
+    /// terminal
+    fn apply(&self) -> buche::ColorChoice {
+        match self {
+            ColorChoice::Auto => {
+                owo_colors::unset_override();
+                buche::ColorChoice::Auto
+            }
+            ColorChoice::Always => {
+                owo_colors::set_override(true);
+                buche::ColorChoice::Always
+            }
+            ColorChoice::Never => {
+                owo_colors::set_override(false);
+                buche::ColorChoice::Never
+            }
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     #[cfg(feature = "exporters")]
@@ -105,6 +216,12 @@ enum Commands {
         )]
         package: String,
 
+        #[arg(
+            long = "CE",
+            help = "the columns assignment identifier; overridden by a `defce` in the source"
+        )]
+        ce: Option<String>,
+
         #[arg(
             short = 'o',
             long = "columns-file",
@@ -113,6 +230,45 @@ enum Commands {
         filename: Option<String>,
     },
     #[cfg(feature = "exporters")]
+    /// Produce an SMT-LIB script asserting the vanishing of the constraints
+    Smtlib {
+        #[arg(short = 'o', long = "out", help = "where to render the SMT-LIB script")]
+        out_filename: Option<String>,
+
+        #[arg(
+            long = "theory",
+            value_parser = ["field", "bitvector"],
+            default_value = "field",
+            help = "represent column values as field elements or as bitvectors"
+        )]
+        theory: String,
+
+        #[arg(
+            long = "width",
+            default_value_t = 256,
+            help = "bitvectors width when using the bitvector theory"
+        )]
+        width: usize,
+    },
+    #[cfg(feature = "exporters")]
+    /// Produce Rust functions evaluating the constraints over an arkworks field
+    Rust {
+        #[arg(short = 'o', long = "out", help = "where to render the Rust code")]
+        out_filename: Option<String>,
+    },
+    #[cfg(feature = "exporters")]
+    /// Produce a Solidity library checking the constraints over the bn256 scalar field
+    Solidity {
+        #[arg(short = 'o', long = "out", help = "where to render the Solidity code")]
+        out_filename: Option<String>,
+    },
+    #[cfg(feature = "exporters")]
+    /// Produce a gnark circuit asserting the constraints through the frontend API
+    Gnark {
+        #[arg(short = 'o', long = "out", help = "where to render the Go code")]
+        out_filename: Option<String>,
+    },
+    #[cfg(feature = "exporters")]
     /// Produce a WizardIOP constraint system
     WizardIOP {
         #[arg(short = 'o', long = "out", help = "where to render the constraints")]
@@ -200,15 +356,42 @@ enum Commands {
         #[arg(long, help = "exit on failing columns")]
         fail_on_missing: bool,
     },
+    /// Given a set of constraints and a trace file, fill the computed columns and write the committed columns and the auxiliary witness to separate files
+    ExportWitness {
+        #[arg(
+            short = 'T',
+            long = "trace",
+            required = true,
+            help = "the trace to compute the witness from"
+        )]
+        tracefile: String,
+
+        #[arg(
+            long = "committed",
+            required = true,
+            help = "where to write the committed columns"
+        )]
+        committed: String,
+
+        #[arg(
+            long = "witness",
+            required = true,
+            help = "where to write the computed columns"
+        )]
+        witness: String,
+
+        #[arg(long, help = "exit on failing columns")]
+        fail_on_missing: bool,
+    },
     /// Given a set of constraints and a filled trace, check the validity of the constraints
     Check {
         #[arg(
             short = 'T',
             long = "trace",
-            required = true,
+            required_unless_present = "symbolic",
             help = "the trace to compute & verify"
         )]
-        tracefile: String,
+        tracefile: Option<String>,
 
         #[arg(
             short = 'F',
@@ -268,6 +451,108 @@ enum Commands {
 
         #[arg(short = 'A', long = "trace-span-after", help = "")]
         trace_span_after: Option<isize>,
+
+        #[arg(
+            long = "coverage",
+            help = "report, for each constraint, the rows where its guards are triggered and the ones where it is vacuously satisfied"
+        )]
+        coverage: bool,
+
+        #[arg(
+            long = "margins",
+            help = "report, for each range constraint, the extremal values taken in the trace and their distance to the bound"
+        )]
+        margins: bool,
+
+        #[arg(
+            long = "row",
+            help = "print the value taken by every vanishing constraint at the given row of the trace"
+        )]
+        row: Option<isize>,
+
+        #[arg(
+            long = "check-padding",
+            help = "also check, on the padding rows, the transition constraints reading across the padding boundary"
+        )]
+        check_padding: bool,
+
+        #[arg(
+            long = "sample",
+            help = "for a quick, partial check, only check the vanishing constraints every K rows and at the boundaries of the trace",
+            value_name = "K",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        sample: Option<u64>,
+
+        #[arg(
+            long = "symbolic",
+            help = "report the constraints that are identically zero as polynomials, and thus hold on any trace"
+        )]
+        symbolic: bool,
+
+        #[arg(
+            long = "expected",
+            help = "a JSON file of reference values, in the trace format, that the computed columns must match",
+            value_name = "FILE"
+        )]
+        expected: Option<String>,
+
+        #[arg(
+            long = "diff-expected",
+            help = "a golden witness, as written by `compute`, to diff all the computed columns against",
+            value_name = "FILE"
+        )]
+        diff_expected: Option<String>,
+    },
+    /// Given a set of constraints and a trace violating them, shrink it to a minimal set of rows still violating the same constraint
+    Minimize {
+        #[arg(
+            short = 'T',
+            long = "trace",
+            required = true,
+            help = "the failing trace to minimize"
+        )]
+        tracefile: String,
+
+        #[arg(
+            short = 'o',
+            long = "out",
+            help = "where to write the minimized trace, as JSON"
+        )]
+        outfile: Option<String>,
+    },
+    /// Given a set of constraints and a filled trace, only check the validity of the permutations
+    VerifyPermutation {
+        #[arg(
+            short = 'T',
+            long = "trace",
+            required = true,
+            help = "the trace to verify"
+        )]
+        tracefile: String,
+    },
+    /// Given a set of constraints and a trace file, time the filling of the computed columns
+    Bench {
+        #[arg(
+            short = 'T',
+            long = "trace",
+            required = true,
+            help = "the trace to compute"
+        )]
+        tracefile: String,
+    },
+    /// Display the size, degree and spilling of the constraint system of every module
+    Stats {
+        #[arg(
+            long = "by-degree",
+            help = "instead, list the vanishing constraints grouped by degree"
+        )]
+        by_degree: bool,
+    },
+    /// Produce an empty trace, listing all the columns expected from a trace
+    TraceTemplate {
+        #[arg(short = 'o', long = "out", help = "where to write the template")]
+        outfile: Option<String>,
     },
     /// Inspect a trace file
     #[cfg(feature = "inspector")]
@@ -330,6 +615,11 @@ enum Commands {
             help = "display spilling for all modules"
         )]
         show_spilling: bool,
+        #[arg(
+            long = "explain-spilling",
+            help = "display spilling for all modules, along with the shifts causing it"
+        )]
+        explain_spilling: bool,
         #[arg(short = 'T', long = "types", help = "display types information")]
         show_types: bool,
         #[arg(
@@ -353,6 +643,16 @@ enum Commands {
         )]
         toml: bool,
     },
+    /// Render the dependencies between columns as a DOT graph
+    Graph {
+        #[arg(
+            long = "constraints",
+            help = "render the constraints and the columns they refer to instead of the computations"
+        )]
+        constraints: bool,
+        #[arg(short = 'o', long = "out", help = "where to write the graph")]
+        outfile: Option<String>,
+    },
     /// Format the given source in an idiomatic way
     Format {
         #[arg(
@@ -361,6 +661,12 @@ enum Commands {
             help = "format the given file in-place"
         )]
         inplace: bool,
+
+        #[arg(
+            long = "dependency-order",
+            help = "reorder the definitions of each module so that every symbol is defined before being used"
+        )]
+        dependency_order: bool,
     },
     /// Given a set of constraints, indefinitely check the traces from an SQL table
     #[cfg(feature = "postgres")]
@@ -390,59 +696,122 @@ enum Commands {
         #[arg(long = "skip", help = "skip these constraints", value_delimiter = ',')]
         skip: Vec<String>,
     },
+    /// Produce a canonical JSON IR of the constraint system, identical for semantically equivalent sources
+    Normalize {
+        #[arg(
+            short = 'o',
+            long = "out",
+            help = "where to write the normalized IR; defaults to stdout"
+        )]
+        outfile: Option<String>,
+    },
+    /// Dump the whole compiled constraint system as JSON, field elements being written in decimal
+    Dump {
+        #[arg(
+            short = 'o',
+            long = "out",
+            help = "where to write the constraint system; defaults to stdout"
+        )]
+        outfile: Option<String>,
+
+        #[arg(long, help = "indent the JSON output")]
+        pretty: bool,
+    },
     /// Given a set of Corset files, compile them into a single file for faster later use
     Compile {
         #[arg(
             short = 'o',
             long = "out",
-            required = true,
+            required_unless_present = "dry_run",
             help = "compiled Corset file to create"
         )]
-        outfile: String,
+        outfile: Option<String>,
 
         #[arg(long, help = "human-readably serialize the constraint system")]
         pretty: bool,
+
+        #[arg(
+            long = "dry-run",
+            help = "do not write the constraint system, but estimate the resources required to compute a trace"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            short = 'T',
+            long = "trace",
+            requires = "dry_run",
+            help = "a trace setting the module sizes for the estimate, instead of their declared heights"
+        )]
+        tracefile: Option<String>,
+
+        #[arg(
+            long = "emit-deps",
+            requires = "outfile",
+            value_name = "FILE",
+            help = "write the files the compilation depends on as a Makefile rule in FILE, or to stdout if `-`"
+        )]
+        emit_deps: Option<String>,
     },
 }
 
 type SourceMapping = Vec<(String, String)>;
 struct ConstraintSetBuilder {
     debug: bool,
+    reduce_constants: bool,
+    no_auto_constraints: bool,
+    warn_unreferenced_constant: bool,
     no_stdlib: bool,
     source: Either<SourceMapping, ConstraintSet>,
     expand_to: ExpansionLevel,
     auto_constraints: Vec<AutoConstraint>,
+    aux_prefix: Option<String>,
+    max_degree: Option<usize>,
 }
 impl ConstraintSetBuilder {
     fn from_sources(no_stdlib: bool, debug: bool) -> ConstraintSetBuilder {
         ConstraintSetBuilder {
             debug,
+            reduce_constants: false,
+            no_auto_constraints: false,
+            warn_unreferenced_constant: false,
             no_stdlib,
             source: Either::Left(Vec::new()),
             expand_to: Default::default(),
             auto_constraints: Default::default(),
+            aux_prefix: None,
+            max_degree: None,
         }
     }
 
-    fn from_bin(ron: bool, filename: &str) -> Result<ConstraintSetBuilder> {
-        // Read the constraint-set bin file
-        let contents = &std::fs::read_to_string(filename)
-            .with_context(|| anyhow!("while reading `{}`", filename))?;
-        // format.
-        let cs = if ron {
-            ron::from_str(contents)
-                .with_context(|| anyhow!("while parsing `{}` (RON)", filename))?
+    fn from_bin(ron: bool, bincode: bool, filename: &str) -> Result<ConstraintSetBuilder> {
+        let cs = if bincode {
+            ConstraintSet::load_bin(filename)
+                .with_context(|| anyhow!("while parsing `{}` (bincode)", filename))?
         } else {
-            serde_json::from_str(contents)
-                .with_context(|| anyhow!("while parsing `{}` (JSON)", filename))?
+            // Read the constraint-set bin file
+            let contents = &std::fs::read_to_string(filename)
+                .with_context(|| anyhow!("while reading `{}`", filename))?;
+            // format.
+            ConstraintSet::load(contents, ron).with_context(|| {
+                anyhow!(
+                    "while parsing `{}` ({})",
+                    filename,
+                    if ron { "RON" } else { "JSON" }
+                )
+            })?
         };
         //
         Ok(ConstraintSetBuilder {
             debug: false,
+            reduce_constants: false,
+            no_auto_constraints: false,
+            warn_unreferenced_constant: false,
             no_stdlib: false,
             source: Either::Right(cs),
             expand_to: Default::default(),
             auto_constraints: Default::default(),
+            aux_prefix: None,
+            max_degree: None,
         })
     }
 
@@ -450,10 +819,30 @@ impl ConstraintSetBuilder {
         self.expand_to = to;
     }
 
+    fn reduce_constants(&mut self, reduce: bool) {
+        self.reduce_constants = reduce;
+    }
+
+    fn no_auto_constraints(&mut self, no_auto: bool) {
+        self.no_auto_constraints = no_auto;
+    }
+
+    fn warn_unreferenced_constant(&mut self, warn: bool) {
+        self.warn_unreferenced_constant = warn;
+    }
+
     fn auto_constraints(&mut self, auto: &[AutoConstraint]) {
         self.auto_constraints = auto.to_vec();
     }
 
+    fn aux_prefix(&mut self, prefix: Option<String>) {
+        self.aux_prefix = prefix;
+    }
+
+    fn max_degree(&mut self, max: Option<usize>) {
+        self.max_degree = max;
+    }
+
     fn find_section(root: &Path, section: &str) -> Result<Option<SourceMapping>> {
         let section_file = root.join(format!("{}.lisp", section));
         let section_str = section_file.to_str().unwrap();
@@ -497,7 +886,7 @@ impl ConstraintSetBuilder {
                         let content = std::fs::read_to_string(&p).with_context(|| {
                             anyhow!("reading {}", section_file.to_str().unwrap().yellow().bold())
                         })?;
-                        r.push((p.file_name().unwrap().to_str().unwrap().to_owned(), content))
+                        r.push((p.to_str().unwrap().to_owned(), content))
                     }
                 }
                 Ok(Some(r))
@@ -536,28 +925,91 @@ impl ConstraintSetBuilder {
         Ok(sources)
     }
 
+    /// Add the files included by `content` to `sources`, resolving them
+    /// relative to `dir`; `including` is the chain of the files currently
+    /// being loaded, used to detect circular includes
+    fn add_includes(
+        sources: &mut SourceMapping,
+        dir: &Path,
+        content: &str,
+        including: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        for include in compiler::parser::includes(content)? {
+            let path = dir.join(&include);
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| anyhow!("reading {}", path.display().yellow().bold()))?;
+            Self::add_file(sources, path.to_str().unwrap(), content, including)
+                .with_context(|| anyhow!("while including `{}`", include))?;
+        }
+        Ok(())
+    }
+
+    /// Add the file `name` to `sources`, preceded by the files it includes;
+    /// a file already present in `sources` is only added once
+    fn add_file(
+        sources: &mut SourceMapping,
+        name: &str,
+        content: String,
+        including: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        let canonical = Path::new(name)
+            .canonicalize()
+            .with_context(|| anyhow!("reading {}", name.yellow().bold()))?;
+        if including.contains(&canonical) {
+            bail!(
+                "circular include: {} -> {}",
+                including.iter().map(|p| p.display()).join(" -> "),
+                canonical.display().yellow().bold()
+            );
+        }
+        if sources.iter().any(|(other, _)| {
+            Path::new(other)
+                .canonicalize()
+                .map(|other| other == canonical)
+                .unwrap_or(false)
+        }) {
+            return Ok(());
+        }
+
+        let dir = Path::new(name).parent().unwrap_or(Path::new("")).to_owned();
+        including.push(canonical);
+        Self::add_includes(sources, &dir, &content, including)
+            .with_context(|| anyhow!("parsing `{}`", name))?;
+        including.pop();
+        sources.push((name.to_owned(), content));
+        Ok(())
+    }
+
     /// Add a source to the sources to compile:
     ///   - if it's a filename, add its content;
     ///   - if it's a path, tries to parse it following the standardized
     ///     hierarchy;
     ///   - if it's `-`, plug in STDIN;
     ///   - otherwise, just include it as an immediate expression.
+    ///
+    /// In any case, the files it `include`s are added before it, paths being
+    /// resolved relative to the including file, or to the current directory
+    /// for STDIN and immediate expressions.
     fn add_source(&mut self, src: &str) -> Result<()> {
         if let Either::Left(ref mut sources) = self.source {
             let as_path = std::path::Path::new(src);
             if as_path.is_dir() {
-                sources.append(&mut Self::parse_dir(as_path)?);
+                for (name, content) in Self::parse_dir(as_path)? {
+                    Self::add_file(sources, &name, content, &mut Vec::new())?;
+                }
             } else if as_path.is_file() {
-                sources.push((
-                    src.to_string(),
-                    std::fs::read_to_string(src)
-                        .with_context(|| anyhow!("reading {}", src.yellow().bold()))?,
-                ));
+                let content = std::fs::read_to_string(src)
+                    .with_context(|| anyhow!("reading {}", src.yellow().bold()))?;
+                Self::add_file(sources, src, content, &mut Vec::new())?;
             } else if src == "-" {
                 let mut buffer = String::new();
                 std::io::stdin().read_to_string(&mut buffer)?;
+                Self::add_includes(sources, Path::new(""), &buffer, &mut Vec::new())
+                    .context("parsing `STDIN`")?;
                 sources.push(("STDIN".to_string(), buffer));
             } else {
+                Self::add_includes(sources, Path::new(""), src, &mut Vec::new())
+                    .context("parsing `Immediate expression`")?;
                 sources.push(("Immediate expression".to_string(), src.into()));
             }
             Ok(())
@@ -579,6 +1031,41 @@ impl ConstraintSetBuilder {
         sources
     }
 
+    /// The files this compilation depends on, i.e. the source files and, as
+    /// the stdlib is embedded in it, the Corset executable itself
+    fn dependencies(&self) -> Result<Vec<String>> {
+        let mut r = Vec::new();
+        if let Either::Left(sources) = &self.source {
+            if !self.no_stdlib {
+                r.push(
+                    std::env::current_exe()
+                        .context("while locating the stdlib")?
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+            }
+            for (name, _) in sources {
+                if Path::new(name).is_file() && !r.contains(name) {
+                    r.push(name.to_owned());
+                }
+            }
+        }
+        Ok(r)
+    }
+
+    /// Render the dependencies of the compilation of `target` as a Makefile
+    /// rule
+    fn make_rule(&self, target: &str) -> Result<String> {
+        let escape = |s: &str| s.replace('$', "$$").replace(' ', "\\ ");
+        let mut r = format!("{}:", escape(target));
+        for dep in self.dependencies()? {
+            r.push_str(" \\\n  ");
+            r.push_str(&escape(&dep));
+        }
+        r.push('\n');
+        Ok(r)
+    }
+
     /// Builds a simple AST that will be used by the formatter
     fn to_simple_ast(&self) -> Result<Vec<(String, Ast)>> {
         match self.source.as_ref() {
@@ -599,37 +1086,58 @@ impl ConstraintSetBuilder {
 
     #[time("info", "Compiling into constraint set")]
     fn into_constraint_set(self) -> Result<ConstraintSet> {
+        let settings = compiler::CompileSettings {
+            debug: self.debug,
+            reduce_constants: self.reduce_constants,
+            no_auto_constraints: self.no_auto_constraints,
+            warn_unreferenced_constant: self.warn_unreferenced_constant,
+            aux_prefix: self.aux_prefix.clone(),
+        };
         let mut cs = match self.source {
-            Either::Left(ref sources) => compiler::make(
-                &self.prepare_sources(sources),
-                &compiler::CompileSettings { debug: self.debug },
-            )
-            .map(|r| r.1),
+            Either::Left(ref sources) => {
+                compiler::make(&self.prepare_sources(sources), &settings).map(|r| r.1)
+            }
             Either::Right(cs) => Ok(cs),
         }?;
-        transformer::expand_to(&mut cs, self.expand_to, &self.auto_constraints)?;
+        let auto_constraints = if settings.no_auto_constraints {
+            &[]
+        } else {
+            self.auto_constraints.as_slice()
+        };
+        if self.aux_prefix.is_some() {
+            cs.aux_prefix = self.aux_prefix.clone();
+        }
+        transformer::expand_to(&mut cs, self.expand_to, auto_constraints)?;
         transformer::concretize(&mut cs);
+        if let Some(max) = self.max_degree {
+            cs.check_max_degree(max)?;
+        }
         Ok(cs)
     }
 }
 
 #[cfg(feature = "cli")]
 fn main() -> Result<()> {
+    let args = Args::parse();
+    // every command runs on the pool sized by --threads
+    compute::thread_pool(args.threads)?.install(|| run(args))
+}
+
+#[cfg(feature = "cli")]
+fn run(args: Args) -> Result<()> {
     use crate::{inspect::InspectorSettings, transformer::concretize};
 
-    let args = Args::parse();
     *crate::IS_NATIVE.write().unwrap() = args.native_arithmetic;
+    // --fail-fast is the default, and only exists to be explicit about it
+    let fail_fast = args.fail_fast || !args.keep_going;
+    let report_format: ReportFormat = args.format.parse()?;
     buche::new()
         .verbosity(args.verbose.log_level_filter())
         .quiet(args.verbose.is_silent())
+        .color(args.color.apply())
         .init()
         .unwrap();
 
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(args.threads)
-        .build_global()
-        .unwrap();
-
     let mut builder = if matches!(args.command, Commands::Format { .. }) {
         if args.source.len() != 1 {
             bail!(
@@ -657,7 +1165,7 @@ fn main() -> Result<()> {
             .unwrap_or(false)
     {
         info!("Loading `{}`", &args.source[0]);
-        ConstraintSetBuilder::from_bin(args.ron, &args.source[0])?
+        ConstraintSetBuilder::from_bin(args.ron, args.bincode, &args.source[0])?
     } else {
         info!("Parsing Corset source files...");
         let mut r = ConstraintSetBuilder::from_sources(args.no_stdlib, args.debug);
@@ -668,14 +1176,24 @@ fn main() -> Result<()> {
     };
 
     builder.expand_to(args.expand.into());
+    builder.reduce_constants(args.reduce_constants);
+    builder.no_auto_constraints(args.no_auto_constraints);
+    builder.warn_unreferenced_constant(args.warn_unreferenced_constant);
     builder.auto_constraints(&AutoConstraint::parse(&args.auto_constraints));
+    builder.aux_prefix(args.aux_prefix.clone());
+    builder.max_degree(args.max_degree);
 
     match args.command {
         #[cfg(feature = "exporters")]
-        Commands::Go { package, filename } => {
+        Commands::Go {
+            package,
+            ce,
+            filename,
+        } => {
             exporters::zkgeth::render(
                 &builder.into_constraint_set()?,
                 &package,
+                ce.as_deref(),
                 filename.as_ref(),
             )?;
         }
@@ -697,6 +1215,67 @@ fn main() -> Result<()> {
             exporters::conflater::render(&builder.to_constraint_set(), filename.as_ref())?;
         }
         #[cfg(feature = "exporters")]
+        Commands::Smtlib {
+            out_filename,
+            theory,
+            width,
+        } => {
+            builder.expand_to(ExpansionLevel::top());
+            builder.auto_constraints(AutoConstraint::all());
+            let cs = builder.into_constraint_set()?;
+            let theory = match theory.as_str() {
+                "field" => transpilers::smtlib::Theory::Field,
+                "bitvector" => transpilers::smtlib::Theory::BitVector(width),
+                _ => unreachable!(),
+            };
+
+            let r = transpilers::smtlib::render(&cs, theory)?;
+            if let Some(filename) = out_filename.as_ref() {
+                utils::write_atomically(filename, |out| Ok(out.write_all(r.as_bytes())?))?;
+            } else {
+                print!("{}", r);
+            }
+        }
+        #[cfg(feature = "exporters")]
+        Commands::Rust { out_filename } => {
+            builder.expand_to(ExpansionLevel::top());
+            builder.auto_constraints(AutoConstraint::all());
+            let cs = builder.into_constraint_set()?;
+
+            let r = transpilers::rust::render(&cs)?;
+            if let Some(filename) = out_filename.as_ref() {
+                utils::write_atomically(filename, |out| Ok(out.write_all(r.as_bytes())?))?;
+            } else {
+                print!("{}", r);
+            }
+        }
+        #[cfg(feature = "exporters")]
+        Commands::Solidity { out_filename } => {
+            builder.expand_to(ExpansionLevel::top());
+            builder.auto_constraints(AutoConstraint::all());
+            let cs = builder.into_constraint_set()?;
+
+            let r = transpilers::solidity::render(&cs)?;
+            if let Some(filename) = out_filename.as_ref() {
+                utils::write_atomically(filename, |out| Ok(out.write_all(r.as_bytes())?))?;
+            } else {
+                print!("{}", r);
+            }
+        }
+        #[cfg(feature = "exporters")]
+        Commands::Gnark { out_filename } => {
+            builder.expand_to(ExpansionLevel::top());
+            builder.auto_constraints(AutoConstraint::all());
+            let cs = builder.into_constraint_set()?;
+
+            let r = transpilers::gnark::render(&cs)?;
+            if let Some(filename) = out_filename.as_ref() {
+                utils::write_atomically(filename, |out| Ok(out.write_all(r.as_bytes())?))?;
+            } else {
+                print!("{}", r);
+            }
+        }
+        #[cfg(feature = "exporters")]
         Commands::WizardIOP { out_filename } => {
             *crate::IS_NATIVE.write().unwrap() = true;
             builder.expand_to(ExpansionLevel::top());
@@ -727,7 +1306,7 @@ fn main() -> Result<()> {
             exclude,
         } => {
             let mut cs = builder.into_constraint_set()?;
-            compute::compute_trace(&tracefile, &mut cs, false)
+            compute::compute_trace(&tracefile, &mut cs, false, fail_fast)
                 .with_context(|| format!("while expanding `{}`", tracefile))?;
 
             match format.as_str() {
@@ -758,17 +1337,56 @@ fn main() -> Result<()> {
             builder.auto_constraints(AutoConstraint::all());
             let mut cs = builder.into_constraint_set()?;
 
-            compute::compute_trace(&tracefile, &mut cs, fail_on_missing)
+            compute::compute_trace(&tracefile, &mut cs, fail_on_missing, fail_fast)
                 .with_context(|| format!("while computing from `{}`", tracefile))?;
 
             let outfile = outfile.as_ref().unwrap();
-            let mut f = std::fs::File::create(outfile)
-                .with_context(|| format!("while creating `{}`", &outfile))?;
+            utils::write_atomically(outfile, |out| cs.write(out))?;
+        }
+        Commands::ExportWitness {
+            tracefile,
+            committed,
+            witness,
+            fail_on_missing,
+        } => {
+            builder.expand_to(ExpansionLevel::top());
+            builder.auto_constraints(AutoConstraint::all());
+            let mut cs = builder.into_constraint_set()?;
+
+            compute::compute_trace(&tracefile, &mut cs, fail_on_missing, fail_fast)
+                .with_context(|| format!("while computing from `{}`", tracefile))?;
 
-            let mut out = std::io::BufWriter::with_capacity(10_000_000, &mut f);
-            cs.write(&mut out)
-                .with_context(|| format!("while writing to `{}`", &outfile))?;
-            out.flush()?;
+            for (outfile, is_witness) in [(committed, false), (witness, true)] {
+                utils::write_atomically(&outfile, |out| {
+                    if is_witness {
+                        cs.write_witness(out)
+                    } else {
+                        cs.write_committed(out)
+                    }
+                })?;
+            }
+        }
+        Commands::Bench { tracefile } => {
+            builder.expand_to(ExpansionLevel::top());
+            builder.auto_constraints(AutoConstraint::all());
+            let mut cs = builder.into_constraint_set()?;
+
+            compute::import_trace(&tracefile, &mut cs)
+                .with_context(|| format!("while importing `{}`", tracefile))?;
+            let timings = compute::bench(&mut cs, fail_fast)
+                .with_context(|| format!("while computing from `{}`", tracefile))?;
+            println!(
+                "{}",
+                compute::BenchReport::from(&timings).render(report_format)?
+            );
+        }
+        Commands::Stats { by_degree } => {
+            let cs = builder.into_constraint_set()?;
+            if by_degree {
+                println!("{}", DegreeReport::from(&cs).render(report_format)?);
+            } else {
+                println!("{}", StatsReport::from(&cs).render(report_format)?);
+            }
         }
         #[cfg(feature = "postgres")]
         Commands::CheckLoop {
@@ -813,6 +1431,7 @@ fn main() -> Result<()> {
                         payload,
                         &mut local_constraints,
                         false,
+                        fail_fast,
                     )
                         .with_context(|| format!("while expanding from {}", id))?;
 
@@ -863,34 +1482,147 @@ fn main() -> Result<()> {
             trace_span,
             trace_span_before,
             trace_span_after,
+            coverage,
+            margins,
+            row,
+            check_padding,
+            sample,
+            symbolic,
+            expected,
+            diff_expected,
         } => {
-            if utils::is_file_empty(&tracefile)? {
-                warn!("`{}` is empty, exiting", tracefile);
-                return Ok(());
+            if let Some(tracefile) = tracefile.as_ref() {
+                if utils::is_file_empty(tracefile)? {
+                    warn!("`{}` is empty, exiting", tracefile);
+                    return Ok(());
+                }
             }
 
             let mut cs = builder.into_constraint_set()?;
 
-            compute::compute_trace(&tracefile, &mut cs, false)
+            if symbolic {
+                for h in symbolic::tautologies(&cs, &only, &skip) {
+                    println!("{} is identically zero", h.to_string().yellow().bold());
+                }
+            }
+            let tracefile = match tracefile {
+                Some(tracefile) => tracefile,
+                None => return Ok(()),
+            };
+
+            compute::compute_trace(&tracefile, &mut cs, false, fail_fast)
+                .with_context(|| format!("while expanding `{}`", tracefile))?;
+            if let Some(expected) = expected {
+                let reference = serde_json::from_reader(std::io::BufReader::new(
+                    std::fs::File::open(&expected)
+                        .with_context(|| format!("while opening `{}`", expected))?,
+                ))
+                .with_context(|| format!("while parsing `{}`", expected))?;
+                check::check_expected(&cs, &reference)
+                    .with_context(|| format!("while comparing to `{}`", expected))?;
+            }
+            if let Some(golden) = diff_expected {
+                let golden_witness = serde_json::from_reader(std::io::BufReader::new(
+                    std::fs::File::open(&golden)
+                        .with_context(|| format!("while opening `{}`", golden))?,
+                ))
+                .with_context(|| format!("while parsing `{}`", golden))?;
+                let diff = check::diff_expected(&cs, &golden_witness)
+                    .with_context(|| format!("while comparing to `{}`", golden))?;
+                println!("{}", diff.render(report_format)?);
+                if !diff.is_clean() {
+                    bail!("computed witness diverges from `{}`", golden);
+                }
+            }
+            let settings = check::DebugSettings::new()
+                .unclutter(unclutter)
+                .dim(dim)
+                .src(with_src)
+                .continue_on_error(continue_on_error)
+                .report(report)
+                .full_trace(full_trace)
+                .context_span(trace_span)
+                .and_context_span_before(trace_span_before)
+                .and_context_span_after(trace_span_after)
+                .check_padding(check_padding)
+                .and_sample(sample.map(|k| k as usize));
+            // machine-readable formats list the violations rather than
+            // reporting them as they are found
+            let r = if report_format == ReportFormat::Text {
+                check::check(&cs, &only, &skip, settings)
+            } else {
+                let report = check::violations(&cs, &only, &skip, settings)?;
+                println!("{}", report.render(report_format)?);
+                if report.violations.is_empty() {
+                    Ok(())
+                } else {
+                    Err(anyhow!("{} violations found", report.violations.len()))
+                }
+            };
+            if coverage {
+                println!(
+                    "{}",
+                    check::coverage(&cs, &only, &skip)?.render(report_format)?
+                );
+            }
+            if margins {
+                println!(
+                    "{}",
+                    check::margins(&cs, &only, &skip)?.render(report_format)?
+                );
+            }
+            if let Some(row) = row {
+                println!(
+                    "{}",
+                    check::evaluate_row(&cs, &only, &skip, row).render(report_format)?
+                );
+            }
+            r.with_context(|| format!("while checking {}", tracefile.bright_white().bold()))?;
+            info!("{}: SUCCESS", tracefile)
+        }
+        Commands::Minimize { tracefile, outfile } => {
+            let cs = builder.into_constraint_set()?;
+            let minimal = minimize::minimize(cs, &tracefile, fail_fast)
+                .with_context(|| format!("while minimizing {}", tracefile.bright_white().bold()))?;
+            if let Some(outfile) = outfile {
+                utils::write_atomically(&outfile, |out| {
+                    Ok(serde_json::to_writer(out, &minimal.trace)?)
+                })?;
+            }
+            print!("{}", minimal.render(report_format)?);
+        }
+        Commands::VerifyPermutation { tracefile } => {
+            let mut cs = builder.into_constraint_set()?;
+            let permutations = cs
+                .constraints
+                .iter()
+                .filter(|c| matches!(c, Constraint::Permutation { .. }))
+                .map(|c| c.name())
+                .collect::<Vec<_>>();
+            if permutations.is_empty() {
+                bail!("no permutation found")
+            }
+
+            compute::compute_trace(&tracefile, &mut cs, false, fail_fast)
                 .with_context(|| format!("while expanding `{}`", tracefile))?;
             check::check(
                 &cs,
-                &only,
-                &skip,
-                check::DebugSettings::new()
-                    .unclutter(unclutter)
-                    .dim(dim)
-                    .src(with_src)
-                    .continue_on_error(continue_on_error)
-                    .report(report)
-                    .full_trace(full_trace)
-                    .context_span(trace_span)
-                    .and_context_span_before(trace_span_before)
-                    .and_context_span_after(trace_span_after),
+                &Some(permutations),
+                &[],
+                check::DebugSettings::new().report(true),
             )
             .with_context(|| format!("while checking {}", tracefile.bright_white().bold()))?;
             info!("{}: SUCCESS", tracefile)
         }
+        Commands::TraceTemplate { outfile } => {
+            let cs = builder.into_constraint_set()?;
+            let template = serde_json::to_string_pretty(&cs.trace_template())?;
+            if let Some(outfile) = outfile.as_ref() {
+                utils::write_atomically(outfile, |out| Ok(out.write_all(template.as_bytes())?))?;
+            } else {
+                println!("{}", template);
+            }
+        }
         #[cfg(feature = "inspector")]
         Commands::Inspect {
             tracefile,
@@ -903,7 +1635,7 @@ fn main() -> Result<()> {
             }
             let mut cs = builder.into_constraint_set()?;
 
-            compute::compute_trace(&tracefile, &mut cs, false)
+            compute::compute_trace(&tracefile, &mut cs, false, fail_fast)
                 .with_context(|| format!("while expanding `{}`", tracefile))?;
 
             inspect::inspect(
@@ -926,6 +1658,7 @@ fn main() -> Result<()> {
             show_perspectives,
             show_types,
             show_spilling,
+            explain_spilling,
             only,
             skip,
             toml,
@@ -943,41 +1676,117 @@ fn main() -> Result<()> {
                     perspectives: show_perspectives,
                     computations: show_computations,
                     spilling: show_spilling,
+                    explain_spilling,
                     toml: toml,
                 },
                 only.as_ref(),
                 &skip,
             )?;
         }
-        Commands::Format { inplace } => {
+        Commands::Graph {
+            constraints,
+            outfile,
+        } => {
+            let cs = builder.into_constraint_set()?;
+            let graph = if constraints {
+                exporters::dot::render_constraints(&cs)
+            } else {
+                exporters::dot::render_computations(&cs)
+            };
+            if let Some(outfile) = outfile {
+                utils::write_atomically(&outfile, |out| Ok(out.write_all(graph.as_bytes())?))?;
+            } else {
+                print!("{}", graph);
+            }
+        }
+        Commands::Format {
+            inplace,
+            dependency_order,
+        } => {
             builder.no_stdlib = true;
-            let asts = builder.to_simple_ast()?;
+            let mut asts = builder.to_simple_ast()?;
+            if dependency_order {
+                for (filename, ast) in asts.iter_mut() {
+                    ast.sort_definitions()
+                        .with_context(|| format!("while reordering `{}`", filename))?;
+                }
+            }
             for (filename, ast) in asts.iter() {
                 let formatted = ast.format();
                 if inplace {
-                    std::fs::File::create(filename)?.write_all(formatted.as_bytes())?;
+                    utils::write_atomically(filename, |out| {
+                        Ok(out.write_all(formatted.as_bytes())?)
+                    })?;
                 } else {
                     println!("{}", formatted);
                 }
             }
         }
-        Commands::Compile { outfile, pretty } => {
-            let constraints = builder.into_constraint_set()?;
-            std::fs::File::create(&outfile)
-                .with_context(|| format!("while creating `{}`", &outfile))?
-                .write_all(
-                    if args.ron && pretty {
-                        ron::ser::to_string_pretty(&constraints, ron::ser::PrettyConfig::default())?
-                    } else if args.ron {
-                        ron::ser::to_string(&constraints)?
-                    } else if pretty {
-                        serde_json::to_string_pretty(&constraints)?
-                    } else {
-                        serde_json::to_string(&constraints)?
-                    }
-                    .as_bytes(),
-                )
-                .with_context(|| format!("while writing to `{}`", &outfile))?;
+        Commands::Normalize { outfile } => {
+            let mut cs = builder.into_constraint_set()?;
+            let ir = serde_json::to_string_pretty(&transformer::normalize::normalize(&mut cs))?;
+            if let Some(outfile) = outfile {
+                utils::write_atomically(&outfile, |out| Ok(out.write_all(ir.as_bytes())?))?;
+            } else {
+                println!("{}", ir);
+            }
+        }
+        Commands::Dump { outfile, pretty } => {
+            let cs = builder.into_constraint_set()?;
+            let json = exporters::json::render(&cs, pretty)?;
+            if let Some(outfile) = outfile {
+                utils::write_atomically(&outfile, |out| Ok(out.write_all(json.as_bytes())?))?;
+            } else {
+                println!("{}", json);
+            }
+        }
+        Commands::Compile {
+            outfile,
+            pretty,
+            dry_run,
+            tracefile,
+            emit_deps,
+        } => {
+            if let Some(deps_file) = emit_deps {
+                let rule = builder.make_rule(outfile.as_ref().unwrap())?;
+                if deps_file == "-" {
+                    print!("{}", rule);
+                } else {
+                    std::fs::write(&deps_file, rule)
+                        .with_context(|| format!("while writing to `{}`", &deps_file))?;
+                }
+            }
+            let mut constraints = builder.into_constraint_set()?;
+            if dry_run {
+                if let Some(tracefile) = tracefile {
+                    compute::import_trace(&tracefile, &mut constraints)
+                        .with_context(|| format!("while importing `{}`", tracefile))?;
+                }
+                println!("{}", compute::estimate(&constraints).render(report_format)?);
+                return Ok(());
+            }
+            let outfile = outfile.unwrap();
+            if args.bincode {
+                constraints.save_bin(&outfile)?;
+            } else {
+                utils::write_atomically(&outfile, |out| {
+                    Ok(out.write_all(
+                        if args.ron && pretty {
+                            ron::ser::to_string_pretty(
+                                &constraints,
+                                ron::ser::PrettyConfig::default(),
+                            )?
+                        } else if args.ron {
+                            ron::ser::to_string(&constraints)?
+                        } else if pretty {
+                            serde_json::to_string_pretty(&constraints)?
+                        } else {
+                            serde_json::to_string(&constraints)?
+                        }
+                        .as_bytes(),
+                    )?)
+                })?;
+            }
         }
     }
 
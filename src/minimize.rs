@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use crate::utils::Colorize;
+use anyhow::*;
+use itertools::Itertools;
+use log::*;
+use serde::Serialize;
+
+use crate::{column::Value, compiler::ConstraintSet, compute, structs::Handle};
+
+/// The rows of a module imported from a trace, column by column
+struct ImportedModule {
+    name: String,
+    columns: Vec<(Handle, usize, Vec<Value>)>,
+    rows: usize,
+}
+
+/// The smallest subset of the rows of a trace that has been found to still
+/// violate a constraint
+#[derive(Serialize)]
+pub struct MinimalTrace {
+    /// the violated constraint
+    pub constraint: String,
+    /// the rows of the original trace that have been kept, by module
+    pub rows: BTreeMap<String, Vec<usize>>,
+    /// the minimized trace, in the JSON trace format
+    #[serde(skip)]
+    pub trace: serde_json::Value,
+}
+impl std::fmt::Display for MinimalTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} still fails on {} rows:",
+            self.constraint.red().bold(),
+            self.rows.values().map(Vec::len).sum::<usize>()
+        )?;
+        for (module, rows) in self.rows.iter() {
+            writeln!(f, "  {}: {}", module.blue(), rows.iter().join(", "))?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+/// Extract, for every module, the values of the trace imported into `cs`,
+/// without their padding rows; a single column is kept per register.
+fn imported_modules(cs: &ConstraintSet) -> Vec<ImportedModule> {
+    let mut modules = BTreeMap::<String, ImportedModule>::new();
+    let mut seen_registers = Vec::new();
+    for (h, column) in cs.columns.iter() {
+        let Some(backing) = cs.columns.backing(&h) else {
+            continue;
+        };
+        if column
+            .register
+            .map(|r| seen_registers.contains(&r))
+            .unwrap_or(true)
+        {
+            continue;
+        }
+        seen_registers.push(column.register.unwrap());
+
+        let module = &column.handle.module;
+        let length_multiplier = column.intrinsic_size_factor.unwrap_or(1);
+        let padding = cs.columns.padding.get(module).cloned().unwrap_or(0) * length_multiplier;
+        let values = (padding..backing.len())
+            .map(|i| {
+                cs.columns
+                    .get_raw(&h, i as isize, false)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let imported = modules
+            .entry(module.to_owned())
+            .or_insert_with(|| ImportedModule {
+                name: module.to_owned(),
+                columns: Vec::new(),
+                rows: values.len() / length_multiplier,
+            });
+        imported
+            .columns
+            .push((column.handle.clone(), length_multiplier, values));
+    }
+    modules.into_values().collect()
+}
+
+/// Build a JSON trace from the given rows of the imported modules
+fn make_trace(modules: &[ImportedModule], rows: &[(usize, usize)]) -> serde_json::Value {
+    let mut trace = serde_json::Map::new();
+    for (i, module) in modules.iter().enumerate() {
+        let kept = rows
+            .iter()
+            .filter(|(m, _)| *m == i)
+            .map(|(_, r)| *r)
+            .collect::<Vec<_>>();
+        let columns = module
+            .columns
+            .iter()
+            .map(|(handle, length_multiplier, values)| {
+                (
+                    handle.name.clone(),
+                    serde_json::Value::Array(
+                        kept.iter()
+                            .flat_map(|r| {
+                                values[r * length_multiplier..(r + 1) * length_multiplier]
+                                    .iter()
+                                    .map(|x| serde_json::Value::String(x.to_bi().to_string()))
+                            })
+                            .collect(),
+                    ),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        trace.insert(module.name.clone(), serde_json::Value::Object(columns));
+    }
+    serde_json::Value::Object(trace)
+}
+
+/// Whether `constraint` is violated once the given trace has been imported
+/// and computed in a fresh copy of the constraint set
+fn still_fails(
+    pristine: &str,
+    trace: &serde_json::Value,
+    constraint: &str,
+    fail_fast: bool,
+) -> Result<bool> {
+    let mut cs = ConstraintSet::load(pristine, false)?;
+    let trace = serde_json::to_vec(trace)?;
+    if let Err(e) = crate::import::read_trace_str(&trace, &mut cs, false)
+        .and_then(|_| compute::prepare(&mut cs, false, fail_fast))
+    {
+        debug!("candidate trace rejected: {:?}", e);
+        return Ok(false);
+    }
+    Ok(cs.check()?.iter().any(|v| v.name == constraint))
+}
+
+/// Shrink the trace in `tracefile` to a minimal set of rows still violating
+/// the first constraint it fails, by delta-debugging over its rows.
+pub fn minimize(cs: ConstraintSet, tracefile: &str, fail_fast: bool) -> Result<MinimalTrace> {
+    let pristine = serde_json::to_string(&cs)?;
+    let mut cs = cs;
+    compute::import_trace(tracefile, &mut cs)
+        .with_context(|| format!("while importing `{}`", tracefile))?;
+    let modules = imported_modules(&cs);
+    compute::prepare(&mut cs, false, fail_fast)
+        .with_context(|| format!("while expanding `{}`", tracefile))?;
+    let constraint = match cs.check()?.first() {
+        Some(v) => v.name.clone(),
+        None => bail!("{} does not violate any constraint", tracefile),
+    };
+    drop(cs);
+    info!("minimizing {} w.r.t. {}", tracefile, constraint);
+
+    let mut rows = modules
+        .iter()
+        .enumerate()
+        .flat_map(|(m, module)| (0..module.rows).map(move |r| (m, r)))
+        .collect::<Vec<_>>();
+    let test = |rows: &[(usize, usize)]| {
+        still_fails(
+            &pristine,
+            &make_trace(&modules, rows),
+            &constraint,
+            fail_fast,
+        )
+    };
+    if !test(&rows)? {
+        bail!(
+            "{} does not fail any more once re-imported as a JSON trace",
+            constraint
+        )
+    }
+
+    let mut n = 2;
+    while rows.len() >= 2 {
+        let chunk_size = (rows.len() + n - 1) / n;
+        let chunks = rows
+            .chunks(chunk_size)
+            .map(<[_]>::to_vec)
+            .collect::<Vec<_>>();
+        let mut reduced = None;
+        for (i, chunk) in chunks.iter().enumerate() {
+            if test(chunk)? {
+                reduced = Some((chunk.clone(), 2));
+                break;
+            }
+            let complement = chunks
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .flat_map(|(_, c)| c.iter().cloned())
+                .collect::<Vec<_>>();
+            if chunks.len() > 2 && test(&complement)? {
+                reduced = Some((complement, (n - 1).max(2)));
+                break;
+            }
+        }
+        match reduced {
+            Some((smaller, new_n)) => {
+                debug!("{} rows left", smaller.len());
+                rows = smaller;
+                n = new_n;
+            }
+            None => {
+                if n >= rows.len() {
+                    break;
+                }
+                n = (2 * n).min(rows.len());
+            }
+        }
+    }
+
+    let mut kept = BTreeMap::<String, Vec<usize>>::new();
+    for (m, r) in rows.iter() {
+        kept.entry(modules[*m].name.clone()).or_default().push(*r);
+    }
+    Ok(MinimalTrace {
+        constraint,
+        trace: make_trace(&modules, &rows),
+        rows: kept,
+    })
+}
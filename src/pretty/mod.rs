@@ -1,8 +1,9 @@
+use crate::utils::Colorize;
 use ark_bls12_377::Fr;
 use ark_ff::{BigInteger, PrimeField};
 use itertools::Itertools;
 use num_traits::Zero;
-use owo_colors::{colored::Color, OwoColorize};
+use owo_colors::colored::Color;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -22,7 +23,7 @@ pub const COLORS: [Color; 7] = [
     Color::BrightWhite,
 ];
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Base {
     Dec,
     Hex,
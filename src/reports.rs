@@ -0,0 +1,371 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use anyhow::*;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    check::{CoverageReport, MarginReport, RowReport, ViolationReport, WitnessDiff},
+    compiler::{Constraint, ConstraintSet, Kind},
+    compute::{BenchReport, ComputeEstimate},
+    minimize::MinimalTrace,
+};
+
+/// How the analysis reports are written out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => bail!("unknown report format `{}`", s),
+        }
+    }
+}
+
+/// A report that can be rendered as human-readable text, as JSON, or as a
+/// CSV table
+pub trait Report: Serialize + Display {
+    fn header(&self) -> Vec<&'static str>;
+    fn rows(&self) -> Vec<Vec<String>>;
+
+    fn render(&self, format: ReportFormat) -> Result<String> {
+        Ok(match format {
+            ReportFormat::Text => self.to_string(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Csv => std::iter::once(self.header().join(","))
+                .chain(self.rows().into_iter().map(|row| {
+                    row.iter()
+                        .map(|cell| {
+                            if cell.contains([',', '"', '\n']) {
+                                format!("\"{}\"", cell.replace('"', "\"\""))
+                            } else {
+                                cell.to_owned()
+                            }
+                        })
+                        .join(",")
+                }))
+                .join("\n"),
+        })
+    }
+}
+
+impl Report for BenchReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["kind", "count", "rows", "time_ms"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.kinds
+            .iter()
+            .chain(std::iter::once(&self.total))
+            .map(|k| {
+                vec![
+                    k.kind.clone(),
+                    k.count.to_string(),
+                    k.rows.to_string(),
+                    k.time_ms.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Report for ComputeEstimate {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["module", "rows", "computed", "values"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let or_empty = |x: Option<usize>| x.map(|x| x.to_string()).unwrap_or_default();
+        self.modules
+            .iter()
+            .map(|m| {
+                vec![
+                    m.module.clone(),
+                    or_empty(m.rows),
+                    m.columns.to_string(),
+                    or_empty(m.values),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Report for CoverageReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["constraint", "active", "vacuous"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.constraints
+            .iter()
+            .map(|c| vec![c.name.clone(), c.active.to_string(), c.vacuous.to_string()])
+            .collect()
+    }
+}
+
+impl Report for MarginReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["constraint", "min", "max", "bound", "margin"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.constraints
+            .iter()
+            .map(|c| {
+                vec![
+                    c.name.clone(),
+                    c.min.clone(),
+                    c.max.clone(),
+                    c.bound.clone(),
+                    c.margin.clone(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Report for ViolationReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["constraint", "module", "row", "value", "snippet"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.violations
+            .iter()
+            .map(|v| {
+                vec![
+                    v.name.clone(),
+                    v.module.clone(),
+                    v.row.to_string(),
+                    v.value.clone().unwrap_or_default(),
+                    v.snippet.clone(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Report for WitnessDiff {
+    fn header(&self) -> Vec<&'static str> {
+        vec![
+            "column",
+            "golden_rows",
+            "computed_rows",
+            "mismatches",
+            "first_divergence",
+            "golden",
+            "computed",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.columns
+            .iter()
+            .map(|c| {
+                let d = c.first_divergence.as_ref();
+                vec![
+                    c.name.clone(),
+                    c.golden_rows.to_string(),
+                    c.computed_rows.to_string(),
+                    c.mismatches.to_string(),
+                    d.map(|d| d.row.to_string()).unwrap_or_default(),
+                    d.and_then(|d| d.golden.clone()).unwrap_or_default(),
+                    d.and_then(|d| d.computed.clone()).unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl Report for MinimalTrace {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["constraint", "module", "row"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .flat_map(|(module, rows)| {
+                rows.iter()
+                    .map(|r| vec![self.constraint.clone(), module.clone(), r.to_string()])
+            })
+            .collect()
+    }
+}
+
+impl Report for RowReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["row", "constraint", "values", "failing"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.constraints
+            .iter()
+            .map(|c| {
+                vec![
+                    self.row.to_string(),
+                    c.name.clone(),
+                    c.values
+                        .iter()
+                        .map(|v| v.as_deref().unwrap_or("-"))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    c.failing.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct ModuleStats {
+    pub module: String,
+    pub columns: usize,
+    pub commitments: usize,
+    pub constraints: usize,
+    pub max_degree: usize,
+    pub spilling: isize,
+}
+
+/// The size and the degree of the constraint system of every module
+#[derive(Serialize)]
+pub struct StatsReport {
+    pub modules: Vec<ModuleStats>,
+}
+impl From<&ConstraintSet> for StatsReport {
+    fn from(cs: &ConstraintSet) -> Self {
+        let mut modules: BTreeMap<String, ModuleStats> = BTreeMap::new();
+        for c in cs.columns.iter_cols() {
+            let m = modules.entry(c.handle.module.clone()).or_default();
+            m.columns += 1;
+            if c.kind == Kind::Commitment {
+                m.commitments += 1;
+            }
+        }
+        for c in cs.constraints.iter() {
+            let m = modules.entry(c.handle().module.clone()).or_default();
+            m.constraints += 1;
+            m.max_degree = m.max_degree.max(c.degree());
+        }
+
+        StatsReport {
+            modules: modules
+                .into_iter()
+                .map(|(module, stats)| ModuleStats {
+                    spilling: cs.spilling_of(&module).unwrap_or(0),
+                    module,
+                    ..stats
+                })
+                .collect(),
+        }
+    }
+}
+impl Display for StatsReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>20} {:>8} {:>12} {:>12} {:>10} {:>9}",
+            "module", "columns", "commitments", "constraints", "degree", "spilling"
+        )?;
+        for m in self.modules.iter() {
+            write!(
+                f,
+                "\n{:>20} {:>8} {:>12} {:>12} {:>10} {:>9}",
+                m.module, m.columns, m.commitments, m.constraints, m.max_degree, m.spilling
+            )?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+impl Report for StatsReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec![
+            "module",
+            "columns",
+            "commitments",
+            "constraints",
+            "max_degree",
+            "spilling",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.modules
+            .iter()
+            .map(|m| {
+                vec![
+                    m.module.clone(),
+                    m.columns.to_string(),
+                    m.commitments.to_string(),
+                    m.constraints.to_string(),
+                    m.max_degree.to_string(),
+                    m.spilling.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// The names of the vanishing constraints, bucketed by degree
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct DegreeReport {
+    pub degrees: BTreeMap<usize, Vec<String>>,
+}
+impl From<&ConstraintSet> for DegreeReport {
+    fn from(cs: &ConstraintSet) -> Self {
+        let mut degrees: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for c in cs.constraints.iter() {
+            if let Constraint::Vanishes { expr, .. } = c {
+                degrees.entry(expr.degree()).or_default().push(c.name());
+            }
+        }
+        for names in degrees.values_mut() {
+            names.sort();
+        }
+
+        DegreeReport { degrees }
+    }
+}
+impl Display for DegreeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>8} {:>12}  names", "degree", "constraints")?;
+        for (degree, names) in self.degrees.iter() {
+            write!(
+                f,
+                "\n{:>8} {:>12}  {}",
+                degree,
+                names.len(),
+                names.join(" ")
+            )?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+impl Report for DegreeReport {
+    fn header(&self) -> Vec<&'static str> {
+        vec!["degree", "constraint"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.degrees
+            .iter()
+            .flat_map(|(degree, names)| {
+                names
+                    .iter()
+                    .map(move |name| vec![degree.to_string(), name.clone()])
+            })
+            .collect()
+    }
+}
@@ -199,10 +199,13 @@ impl Handle {
     }
     pub fn from_serialized_string(input: &str) -> Result<Handle, String> {
         let p1: Vec<&str> = input.split(":").collect();
-        // Split up module / name
-        let p2: Vec<&str> = p1[0].split(".").collect();
+        // Split up module / name; nested modules contain dots themselves
+        let p2: Vec<&str> = match p1[0].rsplit_once('.') {
+            Some((module, name)) => vec![module, name],
+            None => vec![p1[0]],
+        };
         // Error check
-        if p1.len() > 2 || p2.len() > 2 {
+        if p1.len() > 2 || p2.len() != 2 {
             Err(format!("invalid serialized Handle: {}", input))
         } else {
             // Attempt to extract perspective (if present)
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use anyhow::*;
+use ark_bls12_377::fr::Fr;
+use ark_ff::{One, Zero};
+use num_traits::ToPrimitive;
+
+use crate::{
+    column::Value,
+    compiler::{Constraint, ConstraintSet, Expression, Intrinsic, Node},
+    structs::Handle,
+};
+
+/// Expanding a polynomial further than this many terms is given up on
+const MAX_TERMS: usize = 1 << 12;
+
+/// A product of variables, each raised to some power; variables are columns
+/// at a given shift, or opaque sub-expressions that can not be expanded
+type Monomial = BTreeMap<String, usize>;
+
+/// A multivariate polynomial over the field, in canonical form; i.e. a sum of
+/// distinct monomials with non-zero coefficients.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Polynomial(BTreeMap<Monomial, Fr>);
+impl Polynomial {
+    fn constant(x: Fr) -> Self {
+        let mut r = Polynomial::default();
+        if !x.is_zero() {
+            r.0.insert(Monomial::new(), x);
+        }
+        r
+    }
+
+    fn variable(name: String) -> Self {
+        Polynomial([(Monomial::from([(name, 1)]), Fr::one())].into())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn add(mut self, other: &Polynomial) -> Self {
+        for (m, c) in other.0.iter() {
+            let x = self.0.entry(m.clone()).or_insert_with(Fr::zero);
+            *x += c;
+            if x.is_zero() {
+                self.0.remove(m);
+            }
+        }
+        self
+    }
+
+    fn neg(mut self) -> Self {
+        for c in self.0.values_mut() {
+            *c = -*c;
+        }
+        self
+    }
+
+    fn mul(&self, other: &Polynomial) -> Result<Self> {
+        if self.0.len() * other.0.len() > MAX_TERMS {
+            bail!("expansion exceeds {} terms", MAX_TERMS)
+        }
+        let mut r = Polynomial::default();
+        for (m1, c1) in self.0.iter() {
+            for (m2, c2) in other.0.iter() {
+                let mut m = m1.clone();
+                for (v, k) in m2.iter() {
+                    *m.entry(v.clone()).or_insert(0) += k;
+                }
+                r = r.add(&Polynomial([(m, *c1 * c2)].into()));
+            }
+        }
+        Ok(r)
+    }
+}
+
+fn to_fr(x: &Value) -> Result<Fr> {
+    match x.clone().into_native() {
+        Value::Native(f) => Ok(f),
+        _ => bail!("{} does not fit in a field element", x),
+    }
+}
+
+/// Expand `e` into a canonical polynomial. Sub-expressions that are not
+/// polynomial, e.g. inverses or conditionals, are considered as independent
+/// variables, so that a zero expansion is still a proof that `e` always
+/// vanishes.
+fn expand(e: &Node) -> Result<Polynomial> {
+    match e.e() {
+        Expression::Const(x) => Ok(Polynomial::constant(to_fr(x)?)),
+        Expression::Column { handle, shift, .. } | Expression::ExoColumn { handle, shift, .. } => {
+            Ok(Polynomial::variable(format!("{}#{}", handle, shift)))
+        }
+        Expression::Funcall { func, args } => match func {
+            Intrinsic::Add | Intrinsic::VectorAdd => args
+                .iter()
+                .try_fold(Polynomial::default(), |ax, a| Ok(ax.add(&expand(a)?))),
+            Intrinsic::Sub | Intrinsic::VectorSub => {
+                let mut r = expand(&args[0])?;
+                for a in args.iter().skip(1) {
+                    r = r.add(&expand(a)?.neg());
+                }
+                Ok(r)
+            }
+            Intrinsic::Mul | Intrinsic::VectorMul => args
+                .iter()
+                .try_fold(Polynomial::constant(Fr::one()), |ax, a| ax.mul(&expand(a)?)),
+            Intrinsic::Neg => Ok(expand(&args[0])?.neg()),
+            Intrinsic::Exp => {
+                let base = expand(&args[0])?;
+                let exponent = args[1]
+                    .pure_eval()
+                    .ok()
+                    .and_then(|x| x.to_usize())
+                    .ok_or_else(|| anyhow!("non-constant exponent {}", args[1]))?;
+                // square-and-multiply, so that even huge exponents only take
+                // a few steps, each of them bounded by MAX_TERMS
+                let (mut r, mut square, mut exponent) =
+                    (Polynomial::constant(Fr::one()), base, exponent);
+                while exponent > 0 {
+                    if exponent & 1 == 1 {
+                        r = r.mul(&square)?;
+                    }
+                    exponent >>= 1;
+                    if exponent > 0 {
+                        square = square.mul(&square)?;
+                    }
+                }
+                Ok(r)
+            }
+            Intrinsic::Inv
+            | Intrinsic::Normalize
+            | Intrinsic::Mod
+            | Intrinsic::Div
+            | Intrinsic::Begin
+            | Intrinsic::IfZero
+            | Intrinsic::IfNotZero => Ok(Polynomial::variable(e.to_string())),
+        },
+        Expression::ArrayColumn { .. } | Expression::List(_) | Expression::Void => {
+            bail!("{} can not be expanded into a polynomial", e)
+        }
+    }
+}
+
+/// Whether `e` is identically zero as a polynomial, regardless of the values
+/// taken by the columns it refers to
+pub fn is_identically_zero(e: &Node) -> Result<bool> {
+    match e.e() {
+        Expression::List(es) => {
+            for e in es {
+                if !is_identically_zero(e)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        _ => Ok(expand(e)?.is_zero()),
+    }
+}
+
+/// List the vanishing constraints whose expression is identically zero, and
+/// are thus satisfied by any trace
+pub fn tautologies(cs: &ConstraintSet, only: &Option<Vec<String>>, skip: &[String]) -> Vec<Handle> {
+    cs.constraints
+        .iter()
+        .filter(|c| only.as_ref().map(|o| o.contains(&c.name())).unwrap_or(true))
+        .filter(|c| !skip.contains(&c.name()))
+        .filter_map(|c| match c {
+            Constraint::Vanishes { handle, expr, .. } if !matches!(expr.e(), Expression::Void) => {
+                match is_identically_zero(expr) {
+                    Result::Ok(true) => Some(handle.to_owned()),
+                    Result::Ok(false) => None,
+                    Err(e) => {
+                        log::debug!("skipping {}: {}", handle, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
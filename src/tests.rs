@@ -1,5 +1,14 @@
-use crate::{transformer::ExpansionLevel, ConstraintSetBuilder};
+use crate::{
+    column::Computation,
+    compiler::{self, ConstraintSet, Expression},
+    exporters,
+    transformer::{self, ExpansionLevel},
+    transpilers, ConstraintSetBuilder,
+};
 use anyhow::*;
+use ark_bls12_377::fr::Fr;
+use itertools::Itertools;
+use num_bigint::BigInt;
 
 fn make(name: &str, source: &str) -> Result<()> {
     let mut r = ConstraintSetBuilder::from_sources(false, false);
@@ -9,6 +18,15 @@ fn make(name: &str, source: &str) -> Result<()> {
     r.into_constraint_set().map(|_| ())
 }
 
+/// Compile `source` like [`make`], and return the resulting constraint set
+fn compile(source: &str) -> Result<ConstraintSet> {
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.add_source(source)?;
+    r.expand_to(ExpansionLevel::top());
+
+    r.into_constraint_set()
+}
+
 fn must_run(name: &str, source: &str) {
     let r = make(name, source);
     if let Err(err) = &r {
@@ -22,6 +40,22 @@ fn must_fail(name: &str, source: &str) {
     assert!(r.is_err());
 }
 
+/// Compile a constraint vanishing on `A mod B`, which the compiler would reject
+/// in a constraint, to check that the backends reject it as well instead of
+/// mistranslating it.
+fn compile_integer_operation() -> Result<ConstraintSet> {
+    let mut cs = compile("(defcolumns A B) (defconstraint c () (vanishes! (- A B)))")?;
+    for c in cs.constraints.iter_mut() {
+        if let compiler::Constraint::Vanishes { expr, .. } = c {
+            if let Expression::Funcall { args, .. } = expr.e() {
+                let args = args.clone();
+                *expr.e_mut() = compiler::Intrinsic::Mod.raw_call(&args);
+            }
+        }
+    }
+    Ok(cs)
+}
+
 #[test]
 fn types_declaration() -> Result<()> {
     make("type", include_str!("../tests/old/types.lisp"))
@@ -59,6 +93,84 @@ fn defpure_ko() {
         )
 }
 
+#[test]
+fn defpure_uncalled_ko() {
+    // referencing a column is caught even if the function is never called
+    let err = make(
+        "defpurefun",
+        "(defcolumns X Y) (defpurefun (f a) (eq! a (shift Y 1)))",
+    )
+    .err()
+    .unwrap();
+    assert!(format!("{:?}", err).contains("pure function"));
+
+    // arguments and local bindings may shadow columns, and be shifted
+    must_run(
+        "defpurefun",
+        "(defcolumns X Y) (defpurefun (f Y) (let ((X (shift Y 1))) (eq! X Y))) (defconstraint asdf () (f X))",
+    );
+}
+
+#[test]
+fn located_type_error() {
+    let err = make(
+        "type error",
+        "(defcolumns A (B :binary))\n(defconstraint c () (vanishes! (xor A B)))",
+    )
+    .err()
+    .unwrap();
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("xor"));
+    assert!(msg.contains("at line"));
+    assert!(msg.contains("(xor A B)"));
+}
+
+#[test]
+fn aux_prefix() -> Result<()> {
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.add_source("(defcolumns A B) (defpermutation (X Y) ((+ A) (- B)))")?;
+    r.expand_to(ExpansionLevel::top());
+    r.auto_constraints(transformer::AutoConstraint::all());
+    r.aux_prefix(Some("AUX_".into()));
+    let cs = r.into_constraint_set()?;
+
+    let names = cs
+        .columns
+        .iter_cols()
+        .map(|c| c.handle.name.clone())
+        .collect::<Vec<_>>();
+    assert!(names.iter().any(|n| n.starts_with("AUX_SRT__Delta_")));
+    assert!(!names.iter().any(|n| n.starts_with("__SRT__")));
+    Ok(())
+}
+
+#[test]
+fn rename_module() -> Result<()> {
+    let mut cs = compile(
+        "(module old_mod) (defconst TWO 2) (defcolumns A (B :comp (* TWO A)))
+         (defconstraint twice () (vanishes! (- B (* TWO A))))
+         (defpermutation (X) ((+ A)))",
+    )?;
+    cs.rename_module("old_mod", "new_mod")?;
+
+    assert!(cs.columns.iter_cols().all(|c| c.handle.module != "old_mod"));
+    assert!(cs
+        .columns
+        .iter_cols()
+        .any(|c| c.handle.module == "new_mod" && c.handle.name == "B"));
+    assert!(cs
+        .constraints
+        .iter()
+        .all(|c| c.handle().module != "old_mod"));
+    assert!(cs.constants.keys().any(|h| h.to_string() == "new_mod.TWO"));
+    let json = serde_json::to_string(&cs)?;
+    assert!(json.contains("new_mod"));
+    assert!(!json.contains("old_mod"));
+
+    assert!(cs.rename_module("old_mod", "other").is_err());
+    Ok(())
+}
+
 #[test]
 fn huge_const() {
     must_run(
@@ -67,6 +179,24 @@ fn huge_const() {
     );
 }
 
+#[test]
+fn computed_const() -> Result<()> {
+    let cs = compile("(defconst ROWS 12 HALF_ROWS (/ ROWS 2) QUARTER_ROWS (/ HALF_ROWS 2))")?;
+    let value = |name: &str| {
+        cs.constants
+            .iter()
+            .find(|(h, _)| h.name == name)
+            .map(|(_, x)| x.to_string())
+            .unwrap()
+    };
+    assert_eq!(value("HALF_ROWS"), "6");
+    assert_eq!(value("QUARTER_ROWS"), "3");
+
+    let err = make("non-constant", "(defconst X (begin 1 2))").unwrap_err();
+    assert!(format!("{:#}", err).contains("defconst X expects compile-time constants"));
+    Ok(())
+}
+
 #[test]
 fn array_ok() {
     must_run(
@@ -136,6 +266,11 @@ fn ko_let() {
         "let-4",
         "(defcolumns a b c) (defconstraint test () (let  ((z 1) (q 3)) (+ a b c) (eq! 3 4)))",
     );
+
+    must_fail(
+        "let-5",
+        "(defcolumns a b c) (defconstraint test () (let (()) (+ a b c)))",
+    );
 }
 
 #[test]
@@ -234,6 +369,336 @@ fn complex_for() {
     )
 }
 
+#[test]
+fn directed_for() -> Result<()> {
+    // the columns referred to by every element of the loop, in order
+    let unrolled = |range: &str| -> Result<Vec<(String, i16)>> {
+        let cs = compile(&format!(
+                "(defcolumns (X :array [0:6])) (defconstraint c () (for i {} (vanishes! (shift [X (+ i 3)] i))))",
+                range
+            ),
+        )?;
+        let expr = cs
+            .constraints
+            .iter()
+            .find_map(|c| match c {
+                compiler::Constraint::Vanishes { handle, expr, .. } if handle.name == "c" => {
+                    Some(expr.clone())
+                }
+                _ => None,
+            })
+            .unwrap();
+        let Expression::List(xs) = expr.e() else {
+            unreachable!()
+        };
+        Ok(xs
+            .iter()
+            .map(|x| match x.e() {
+                Expression::Column { handle, shift, .. } => {
+                    (cs.handle(handle).name.clone(), *shift)
+                }
+                _ => unreachable!(),
+            })
+            .collect())
+    };
+    let xs = |is: &[i16]| {
+        is.iter()
+            .map(|i| (format!("X_{}", i + 3), *i))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(unrolled("[1:3]")?, xs(&[1, 2, 3]));
+    assert_eq!(unrolled("[3:1]")?, xs(&[3, 2, 1]));
+    assert_eq!(unrolled("[3:-3:3]")?, xs(&[3, 0, -3]));
+    assert_eq!(unrolled("{-1 -3 2}")?, xs(&[-1, -3, 2]));
+    assert!(unrolled("[1:3:0]").is_err());
+    Ok(())
+}
+
+#[test]
+fn smtlib_bitvector() -> Result<()> {
+    let mut cs = compile(
+        "(module m) (defcolumns A B) (defconstraint c1 () (eq! (* A B) 3)) (defconstraint c2 () (vanishes! (- (next A) A 1))) (defconstraint c3 (:domain {1}) (vanishes! A)) (defconstraint c4 () (vanishes! (* A -1)))",
+    )?;
+    let expected = r#"(set-logic QF_BV)
+(define-sort F () (_ BitVec 8))
+(declare-const |m.A| F)
+(declare-const |m.A@1| F)
+(declare-const |m.A#1| F)
+(declare-const |m.B| F)
+(assert (! (= (bvadd (bvmul |m.A| |m.B|) (bvneg (_ bv3 8))) (_ bv0 8)) :named |m.c1|))
+(assert (! (= (bvadd |m.A@1| (bvneg |m.A|) (bvneg (_ bv1 8))) (_ bv0 8)) :named |m.c2|))
+(assert (! (= |m.A#1| (_ bv0 8)) :named |m.c3@1|))
+(assert (! (= (bvmul |m.A| (_ bv255 8)) (_ bv0 8)) :named |m.c4|))
+(check-sat)
+"#;
+    assert_eq!(
+        transpilers::smtlib::render(&cs, transpilers::smtlib::Theory::BitVector(8))?,
+        expected
+    );
+    // negative constants are rendered the same once they are field elements,
+    // i.e. -1 as p - 1
+    for c in cs.constraints.iter_mut() {
+        if let compiler::Constraint::Vanishes { handle, expr, .. } = c {
+            if let (Expression::Funcall { args, .. }, "c4") = (expr.e_mut(), handle.name.as_str()) {
+                args[1] = compiler::Node::from_value(crate::column::Value::Native(-Fr::from(1)));
+            }
+        }
+    }
+    assert_eq!(
+        transpilers::smtlib::render(&cs, transpilers::smtlib::Theory::BitVector(8))?,
+        expected
+    );
+    Ok(())
+}
+
+#[test]
+fn integer_operations() -> Result<()> {
+    // integer operations can not be arithmetized, so they are rejected in
+    // constraints...
+    for source in [
+        "(defcolumns A B) (defconstraint c () (vanishes! (mod A B)))",
+        "(defcolumns A B) (defconstraint c () (eq! A (/ B A)))",
+        "(defcolumns A B) (deflookup l (A) ((mod B 4)))",
+        "(defcolumns A B) (definrange (/ A B) 4)",
+    ] {
+        let err = make("integer-operation", source).unwrap_err();
+        assert!(
+            format!("{:?}", err).contains("may only be used in computed columns"),
+            "{}",
+            source
+        );
+    }
+
+    assert!(make(
+        "integer-operation-zero",
+        "(defcolumns A B) (defconstraint c () (eq! A (/ B 0)))"
+    )
+    .is_err());
+
+    // ...unless they can be reduced at compile time
+    must_run(
+        "integer-operation-folded",
+        "(defconst N (mod 17 5)) (defcolumns A) (defconstraint c () (eq! (* 4 A) (/ (* 4 N) 2)))",
+    );
+
+    // while computed columns are not arithmetized, and must be constrained by
+    // the user
+    let cs = compile("(defcolumns A B (R :comp (mod A B))) (defconstraint c () (vanishes! R))")?;
+    assert!(cs.constraints.iter().all(|c| !c.name().contains("prove-")));
+    Ok(())
+}
+
+#[test]
+fn rust_transpilation() -> Result<()> {
+    // a constraint named after a helper does not collide with it, and every
+    // module is checked over its own length
+    let cs = compile(
+        "(defcolumns X) (defconstraint check_all () (vanishes! X)) (module m) (defcolumns A B) (defconstraint c1 () (eq! (* A B) 3)) (defconstraint c2 (:domain {-1}) (vanishes! (- (next A) (^ A 2))))",
+    )?;
+    assert_eq!(
+        transpilers::rust::render(&cs)?,
+        r#"// Generated by Corset; do not edit.
+#![allow(unused_imports)]
+use ark_ff::{Field, Zero};
+
+pub mod constraints {
+    use ark_ff::{Field, One, Zero};
+    use std::ops::{Add, Mul, Neg, Sub};
+
+    /// check_all
+    pub fn check_all<F: Field>(row: isize, col: &impl Fn(&str, isize) -> F) -> F {
+        col("X", row)
+    }
+
+    /// m.c1
+    pub fn m_c1<F: Field>(row: isize, col: &impl Fn(&str, isize) -> F) -> F {
+        col("m.A", row).mul(col("m.B", row)).sub(F::from(3u128))
+    }
+
+    /// m.c2
+    pub fn m_c2<F: Field>(row: isize, col: &impl Fn(&str, isize) -> F) -> F {
+        col("m.A", row + 1).sub(col("m.A", row).pow([2u64]))
+    }
+}
+
+/// Check all the constraints over a trace whose modules span `len(module)` rows, returning the name and the row of the first failing one
+pub fn check_all<F: Field>(len: &impl Fn(&str) -> isize, col: &impl Fn(&str, isize) -> F) -> Result<(), (&'static str, isize)> {
+    for row in 0..len("") {
+        if !constraints::check_all(row, col).is_zero() {
+            return Err(("check_all", row));
+        }
+    }
+    for row in 0..len("m") {
+        if !constraints::m_c1(row, col).is_zero() {
+            return Err(("m.c1", row));
+        }
+    }
+    for row in [-1].into_iter().map(|i: isize| if i < 0 { len("m") + i } else { i }) {
+        if !constraints::m_c2(row, col).is_zero() {
+            return Err(("m.c2", row));
+        }
+    }
+    Ok(())
+}
+"#
+    );
+
+    // interleaved columns span several rows per row of their module
+    let cs = compile(
+        "(module m) (defcolumns A B) (definterleaved I (A B)) (defconstraint c () (vanishes! I))",
+    )?;
+    assert!(transpilers::rust::render(&cs)?.contains("for row in 0..len(\"m\") * 2 {"));
+
+    // non-field operations are rejected instead of being mistranslated
+    let cs = compile_integer_operation()?;
+    assert!(transpilers::rust::render(&cs).is_err());
+    Ok(())
+}
+
+#[test]
+fn solidity_transpilation() -> Result<()> {
+    let cs = compile(
+        "(module m) (defcolumns A B) (defconstraint c1 () (eq! (* A B) 3)) (defconstraint c2 (:domain {-1}) (vanishes! (- (next A) (^ A 2))))",
+    )?;
+    let r = transpilers::solidity::render(&cs)?;
+    assert!(r.contains("    uint256 internal constant COL_M_A = "));
+    assert!(r.contains("    uint256 internal constant COL_M_B = "));
+    assert!(r.contains(
+        r#"        require(submod(mulmod(cols[COL_M_A][row], cols[COL_M_B][row], P), 3) == 0, "m.c1");"#
+    ));
+    assert!(r.contains(
+        r#"        require(submod(cols[COL_M_A][row + 1], pow(cols[COL_M_A][row], 2)) == 0, "m.c2");"#
+    ));
+
+    let cs = compile_integer_operation()?;
+    assert!(transpilers::solidity::render(&cs).is_err());
+    Ok(())
+}
+
+#[test]
+fn gnark_transpilation() -> Result<()> {
+    let cs = compile(
+        "(module m) (defcolumns A B) (defconstraint c1 () (eq! (* A B) 3)) (defconstraint c2 (:domain {-1}) (vanishes! (- (next A) (^ A 2))))",
+    )?;
+    assert_eq!(
+        transpilers::gnark::render(&cs)?,
+        r#"// Code generated by Corset; DO NOT EDIT.
+
+package constraints
+
+import "github.com/consensys/gnark/frontend"
+
+// Circuit holds the columns of the trace, indexed by row
+type Circuit struct {
+	Col_m_A []frontend.Variable
+	Col_m_B []frontend.Variable
+}
+
+// Define asserts the constraints over the whole trace
+func (t *Circuit) Define(api frontend.API) error {
+	// m.c1
+	for row := 0; row < len(t.Col_m_A); row++ {
+		api.AssertIsEqual(api.Sub(api.Mul(t.Col_m_A[row], t.Col_m_B[row]), 3), 0)
+	}
+	// m.c2
+	for _, row := range []int{len(t.Col_m_A)-1} {
+		api.AssertIsEqual(api.Sub(t.Col_m_A[wrap(row+1, len(t.Col_m_A))], api.Mul(t.Col_m_A[row], t.Col_m_A[row])), 0)
+	}
+	return nil
+}
+
+// wrap brings row back within a trace of length len
+func wrap(row int, len int) int {
+	return ((row % len) + len) % len
+}
+"#
+    );
+
+    let cs = compile_integer_operation()?;
+    assert!(transpilers::gnark::render(&cs).is_err());
+    Ok(())
+}
+
+#[test]
+fn defun_constant_folding() -> Result<()> {
+    let cs = compile(
+        "(defpurefun (f a b) (* (+ a b) (- b 2))) (defun (g x) (f x 4)) (defcolumns A (X :comp (g 3)) (Y :comp (g A)))",
+    )?;
+    let computed = |name: &str| {
+        cs.computations
+            .iter()
+            .find_map(|c| match c {
+                Computation::Composite { target, exp } if cs.handle(target).name == name => {
+                    Some(exp.clone())
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+    assert!(matches!(computed("X").e(), Expression::Const(x) if x.to_string() == "14"));
+    assert!(matches!(computed("Y").e(), Expression::Funcall { .. }));
+    Ok(())
+}
+
+#[test]
+fn boolean_literals() -> Result<()> {
+    let settings = compiler::CompileSettings {
+        debug: false,
+        reduce_constants: false,
+        no_auto_constraints: false,
+        warn_unreferenced_constant: false,
+        aux_prefix: None,
+    };
+    let (mut ctx, _) = compiler::parser::parse(
+        compiler::tables::Scope::new(),
+        &[("booleans", "(defconst ENABLED true DISABLED false)")],
+        &settings,
+    )?;
+    let mut ctx = ctx.switch_to_module(compiler::MAIN_MODULE)?;
+    for (name, value) in [("ENABLED", "1"), ("DISABLED", "0")] {
+        let c = ctx.resolve_symbol(name, false)?;
+        assert!(matches!(c.e(), Expression::Const(x) if x.to_string() == value));
+        assert!(c.t().m().is_boolean());
+        assert!(c.t().m().is_binary());
+    }
+
+    must_run(
+        "if-boolean",
+        "(defconst ENABLED true) (defcolumns X Y) (defconstraint c () (if ENABLED (vanishes! X) (vanishes! Y)))",
+    );
+    Ok(())
+}
+
+#[test]
+fn explain_spilling() -> Result<()> {
+    let cs = compile(
+        "(module m) (defcolumns C D) (defconstraint back () (vanishes! (- C (shift C -3)))) (defconstraint fwd () (vanishes! (- D (next D))))",
+    )?;
+    let explanation = cs.explain_spilling("m");
+    assert_eq!(explanation.spilling, 3);
+    assert_eq!(explanation.past, Some((-3, "constraint m.back".to_owned())));
+    assert_eq!(explanation.future, Some((1, "constraint m.fwd".to_owned())));
+    Ok(())
+}
+
+#[test]
+fn go_columns_assignment() -> Result<()> {
+    let source = "(defce \"MyCE\") (module m) (defcolumns A)";
+    let cs = compile(source)?;
+    let go = exporters::zkgeth::render_to_string(&cs, "m", Some("FromFlag"))?;
+    assert!(go.contains(r#"const ColumnsAssignment = "MyCE""#));
+    assert!(!go.contains("FromFlag"));
+
+    let cs = compile("(module m) (defcolumns A)")?;
+    let go = exporters::zkgeth::render_to_string(&cs, "m", Some("FromFlag"))?;
+    assert!(go.contains(r#"const ColumnsAssignment = "FromFlag""#));
+
+    must_fail("defce-twice", "(defce \"A\") (defce \"B\")");
+    must_fail("defce-symbol", "(defce A)");
+    Ok(())
+}
+
 // #[test]
 // fn exo_if() {
 //     must_run(
@@ -246,3 +711,1037 @@ fn complex_for() {
 //     //     "(module foobar) (defcolumns A B (C :bool) (D :i32)) (defconstraint pipo () (if (eq! A D) C D))",
 //     // );
 // }
+
+#[test]
+fn alias_chains_are_compressed() -> Result<()> {
+    use crate::compiler::tables::ALIAS_HOPS;
+    const CHAIN: usize = 50;
+
+    let settings = compiler::CompileSettings {
+        debug: false,
+        reduce_constants: false,
+        no_auto_constraints: false,
+        warn_unreferenced_constant: false,
+        aux_prefix: None,
+    };
+    let (mut ctx, _) = compiler::parser::parse(
+        compiler::tables::Scope::new(),
+        &[("aliases", "(defcolumns X)")],
+        &settings,
+    )?;
+    let mut ctx = ctx.switch_to_module(compiler::MAIN_MODULE)?;
+    for i in 0..CHAIN {
+        let (to, fto) = if i + 1 == CHAIN {
+            ("X".to_string(), "+".to_string())
+        } else {
+            (format!("A{}", i + 1), format!("f{}", i + 1))
+        };
+        ctx.insert_alias(&format!("A{}", i), &to)?;
+        ctx.insert_funalias(&format!("f{}", i), &fto)?;
+    }
+
+    let hops = |ctx: &mut compiler::tables::Scope| -> Result<usize> {
+        ALIAS_HOPS.with(|hops| hops.set(0));
+        ctx.resolve_symbol("A0", false)?;
+        ctx.resolve_function("f0")?;
+        Ok(ALIAS_HOPS.with(|hops| hops.get()))
+    };
+    assert_eq!(hops(&mut ctx)?, 2 * CHAIN);
+    assert_eq!(hops(&mut ctx)?, 2);
+    Ok(())
+}
+
+#[test]
+fn struct_eq_ignores_types() -> Result<()> {
+    use crate::compiler::{Conditioning, Intrinsic, Magma, Node, Type};
+    use crate::structs::Handle;
+
+    let column = |name: &str, shift: i16, t: Magma| {
+        Node::column()
+            .handle(Handle::new(compiler::MAIN_MODULE, name))
+            .shift(shift)
+            .t(t)
+            .build()
+    };
+    let boolean = Magma::binary().with_conditioning(Conditioning::Boolean);
+    let integer = Magma::integer(64);
+
+    let sum_boolean = Intrinsic::Add
+        .call(&[column("a", 0, boolean), column("b", 0, boolean)])?
+        .with_type(Type::Column(boolean));
+    let sum_integer = Intrinsic::Add
+        .call(&[
+            column("a", 0, Magma::native()),
+            column("b", 0, Magma::native()),
+        ])?
+        .with_type(Type::Column(integer));
+    assert!(sum_boolean.e().struct_eq(sum_integer.e()));
+
+    let shifted = Intrinsic::Add.call(&[column("a", 0, boolean), column("b", 1, boolean)])?;
+    let difference = Intrinsic::Sub.call(&[column("a", 0, boolean), column("b", 0, boolean)])?;
+    assert!(!sum_boolean.e().struct_eq(shifted.e()));
+    assert!(!sum_boolean.e().struct_eq(difference.e()));
+    Ok(())
+}
+
+#[test]
+fn partition_constraints() -> Result<()> {
+    let cs = compile(
+        "(defcolumns A B C D) (defconstraint first (:domain {0}) (vanishes! A)) (defconstraint always () (vanishes! (- A B)))
+         (deflookup l (A) (B)) (defpermutation (S) ((+ C))) (definrange D 4)",
+    )?;
+    let (boundary, transition) = cs.partition_constraints();
+    let names = |cs: &[&compiler::Constraint]| cs.iter().map(|c| c.name()).collect::<Vec<_>>();
+    assert_eq!(names(&boundary), ["first"]);
+    assert_eq!(names(&transition), ["always"]);
+    Ok(())
+}
+
+#[test]
+fn constant_by_name() -> Result<()> {
+    let mut cs = compile(
+        "(defconst FIVE 5 MINUS_ONE -1) (defalias CINQ FIVE) (module m) (defconst SEVEN 7)
+         (module m.n) (defconst NINE 9)",
+    )?;
+    assert_eq!(cs.constant("FIVE"), Some(&BigInt::from(5)));
+    assert_eq!(cs.constant("CINQ"), Some(&BigInt::from(5)));
+    assert_eq!(cs.constant("m.SEVEN"), Some(&BigInt::from(7)));
+    assert_eq!(cs.constant("SEVEN"), None);
+    // only the last dot separates the name from its module
+    assert_eq!(cs.constant("m.n.NINE"), Some(&BigInt::from(9)));
+    assert_eq!(cs.constant_as_fr("FIVE"), Some(Fr::from(5u64)));
+    assert_eq!(cs.constant_as_fr("MINUS_ONE"), Some(-Fr::from(1u64)));
+    cs.constants.insert(
+        crate::structs::Handle::new(compiler::MAIN_MODULE, "P"),
+        crate::constants::field_modulus().clone(),
+    );
+    assert_eq!(cs.constant_as_fr("P"), None);
+    Ok(())
+}
+
+#[test]
+fn conditional_blocks() -> Result<()> {
+    let settings = compiler::CompileSettings {
+        debug: false,
+        reduce_constants: false,
+        no_auto_constraints: false,
+        warn_unreferenced_constant: false,
+        aux_prefix: None,
+    };
+    let (mut ctx, _) = compiler::parser::parse(
+        compiler::tables::Scope::new(),
+        &[(
+            "conditionals",
+            "(defconst ENABLED true DISABLED false)
+             (#if ENABLED (defcolumns A) (defconst N 3) (#if DISABLED (defcolumns B) #endif) #endif)
+             (#if DISABLED (defcolumns C) #endif)
+             (#if (- N 3) (defcolumns D) #endif)
+             (#if ENABLED (defpurefun (f x) x) #endif)
+             (#if DISABLED (defpurefun (g x) A) #endif)",
+        )],
+        &settings,
+    )?;
+    let mut ctx = ctx.switch_to_module(compiler::MAIN_MODULE)?;
+    assert!(ctx.resolve_symbol("A", false).is_ok());
+    assert!(ctx.resolve_symbol("N", false).is_ok());
+    for disabled in ["B", "C", "D"] {
+        assert!(ctx.resolve_symbol(disabled, false).is_err());
+    }
+    assert!(ctx.resolve_function("f").is_ok());
+    assert!(ctx.resolve_function("g").is_err());
+
+    must_run(
+        "conditional-constraints",
+        "(defconst FEATURE 1) (defcolumns X) (#if FEATURE (defconstraint c () (vanishes! X)) #endif)",
+    );
+    // only the pure function of the enabled block is defined
+    must_run(
+        "conditional-purefuns",
+        "(defconst FEATURE 1) (defcolumns X)
+         (#if FEATURE (defpurefun (f x) x) #endif)
+         (#if (- 1 FEATURE) (defpurefun (f x) (* 2 x)) #endif)
+         (defconstraint c () (vanishes! (f X)))",
+    );
+    must_fail(
+        "conditional-module",
+        "(defconst FEATURE 1) (#if FEATURE (module m) (defcolumns X) #endif)",
+    );
+
+    // conditional blocks are formatted as such, and still compile
+    let asts = compiler::parser::parse_simple_ast(&[(
+        "conditional-format",
+        "(defconst FEATURE 1) (#if FEATURE (defcolumns X) (defconstraint c () (vanishes! X)) #endif)",
+    )])?;
+    let formatted = asts[0].1.format();
+    assert!(formatted.contains("\n#endif)"));
+    must_run("conditional-format", &formatted);
+    Ok(())
+}
+
+#[test]
+fn normalize_equivalent_specs() -> Result<()> {
+    let normalized = |source: &str| -> Result<serde_json::Value> {
+        Ok(transformer::normalize::normalize(&mut compile(source)?))
+    };
+    let reference = normalized(
+        "(defcolumns A B C) (defconstraint c () (vanishes! (+ (* A 2) (shift B 1) (- C 3))))",
+    )?;
+    assert_eq!(
+        reference,
+        normalized(
+            "(defcolumns A B C)
+             (defconstraint c () (vanishes! (+ (shift (- C 3) -1) (+ B 0) (* 2 (shift A -1)))))"
+        )?
+    );
+    assert_ne!(
+        reference,
+        normalized("(defcolumns A B C) (defconstraint c () (vanishes! (+ (* A 2) B (- C 3))))")?
+    );
+    Ok(())
+}
+
+#[test]
+fn duplicate_constraint_handles() {
+    let err = make(
+        "duplicates",
+        "(defcolumns A B)
+         (defconstraint c () (vanishes! A))
+         (defconstraint c () (vanishes! B))",
+    )
+    .unwrap_err();
+    let message = regex_lite::Regex::new("\x1b\\[[0-9;]*m")
+        .unwrap()
+        .replace_all(&format!("{:#}", err), "")
+        .to_string();
+    assert!(message.contains("defined twice"));
+    assert!(message.contains("Immediate expression:2 and Immediate expression:3"));
+
+    must_fail(
+        "duplicate-across-kinds",
+        "(defcolumns A B) (defconstraint c () (vanishes! A)) (deflookup c (A) (B))",
+    );
+    must_run(
+        "same-name-across-modules",
+        "(module m1) (defcolumns A) (defconstraint c () (vanishes! A))
+         (module m2) (defcolumns A) (defconstraint c () (vanishes! A))",
+    );
+}
+
+#[test]
+fn stats_report_formats() -> Result<()> {
+    use crate::reports::{Report, ReportFormat, StatsReport};
+
+    let cs = compile(
+        "(module m) (defcolumns A B (C :comp (* A B)))
+         (defconstraint c () (vanishes! (* A (shift B -2) C)))",
+    )?;
+    let report = StatsReport::from(&cs);
+
+    let json: serde_json::Value = serde_json::from_str(&report.render(ReportFormat::Json)?)?;
+    let m = json["modules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["module"] == "m")
+        .unwrap();
+    assert_eq!(m["columns"], 3);
+    assert_eq!(m["commitments"], 2);
+    // C is constrained to its defining expression alongside c
+    assert_eq!(m["constraints"], 2);
+    assert_eq!(m["max_degree"], 3);
+    assert_eq!(m["spilling"], 2);
+
+    let csv = report.render(ReportFormat::Csv)?;
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("module,columns,commitments,constraints,max_degree,spilling")
+    );
+    assert!(lines.any(|l| l == "m,3,2,2,3,2"));
+    Ok(())
+}
+
+#[test]
+fn degree_report() -> Result<()> {
+    use crate::reports::{DegreeReport, Report, ReportFormat};
+
+    let cs = compile(
+        "(module m) (defcolumns A B C)
+         (defconstraint linear () (vanishes! (- A B)))
+         (defconstraint cubic () (vanishes! (* A B C)))",
+    )?;
+    let json: serde_json::Value =
+        serde_json::from_str(&DegreeReport::from(&cs).render(ReportFormat::Json)?)?;
+    assert_eq!(
+        json,
+        serde_json::json!({"1": ["m.linear"], "3": ["m.cubic"]})
+    );
+    Ok(())
+}
+
+#[test]
+fn max_degree() -> Result<()> {
+    let source = "(module m) (defcolumns A B C)
+         (defconstraint linear () (vanishes! (- A B)))
+         (defconstraint quintic () (vanishes! (* A (^ B 2) C C)))
+         (defconstraint cubic () (vanishes! (* A B C)))";
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.add_source(source)?;
+    r.max_degree(Some(3));
+    let err = r.into_constraint_set().err().unwrap().to_string();
+    assert!(err.contains("quintic"));
+    assert!(err.contains("5"));
+    assert!(!err.contains("cubic"));
+
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.add_source(source)?;
+    r.max_degree(Some(5));
+    assert!(r.into_constraint_set().is_ok());
+    Ok(())
+}
+
+#[test]
+fn custom_builtin() -> Result<()> {
+    #[derive(Debug)]
+    struct Double;
+    impl compiler::CustomBuiltin for Double {
+        fn name(&self) -> &str {
+            "double"
+        }
+
+        fn arity(&self) -> compiler::Arity {
+            compiler::Arity::Monadic
+        }
+
+        fn typing(&self, args: &[compiler::Type]) -> Result<compiler::Type> {
+            Ok(args[0].with_magma(compiler::Magma::native()))
+        }
+
+        fn lower(&self, args: Vec<compiler::Node>) -> Result<compiler::Node> {
+            compiler::Intrinsic::Mul.call(&[compiler::Node::from_isize(2), args[0].clone()])
+        }
+    }
+
+    let settings = compiler::CompileSettings {
+        debug: false,
+        reduce_constants: false,
+        no_auto_constraints: false,
+        warn_unreferenced_constant: false,
+        aux_prefix: None,
+    };
+    let compile = |source: &str| {
+        let mut ctx = compiler::tables::Scope::new();
+        ctx.register_builtin(std::sync::Arc::new(Double))?;
+        compiler::make_in(ctx, &[("custom", source)], &settings).map(|r| r.1)
+    };
+
+    let cs = compile("(defcolumns A B) (defconstraint doubled () (- B (double A)))")?;
+    let expr = cs
+        .constraints
+        .iter()
+        .find_map(|c| match c {
+            compiler::Constraint::Vanishes { handle, expr, .. } if handle.name == "doubled" => {
+                Some(expr.to_string())
+            }
+            _ => None,
+        })
+        .unwrap();
+    assert!(expr.contains("(* 2 A)"), "{}", expr);
+
+    assert!(compile("(defcolumns A B) (defconstraint doubled () (- B (double A B)))").is_err());
+
+    // custom builtins can not shadow existing functions
+    let mut ctx = compiler::tables::Scope::new();
+    ctx.register_builtin(std::sync::Arc::new(Double))?;
+    assert!(ctx.register_builtin(std::sync::Arc::new(Double)).is_err());
+    Ok(())
+}
+
+#[test]
+fn computation_order_follows_dependencies() -> Result<()> {
+    // C is declared first, but depends on B
+    let cs = compile("(module m) (defcolumns A (C :comp (+ B 1)) (B :comp (* A 2)))")?;
+    let order = cs
+        .computation_order()
+        .into_iter()
+        .map(|h| h.name)
+        .collect::<Vec<_>>();
+    let position = |name: &str| order.iter().position(|n| n == name).unwrap();
+    assert!(position("B") < position("C"));
+    // A is not computed
+    assert!(!order.contains(&"A".to_string()));
+    Ok(())
+}
+
+#[test]
+fn symbolic_tautologies() -> Result<()> {
+    use crate::symbolic::{is_identically_zero, tautologies};
+
+    let cs = compile(
+        "(defcolumns A B)
+         (defconstraint square ()
+           (- (* (+ A B) (+ A B)) (+ (* A A) (* 2 (* A B)) (* B B))))
+         (defconstraint shifted () (- (* (next A) B) (* B (shift A 1))))
+         (defconstraint genuine () (- (* A B) (* B B)))
+         (defconstraint power () (- (^ A 1000000000) (* A (^ A 999999999))))
+         (defconstraint too-wide () (- (^ (+ A B) 1000000000) (^ (+ A B) 1000000000)))",
+    )?;
+    assert_eq!(
+        tautologies(&cs, &None, &[])
+            .into_iter()
+            .map(|h| h.name)
+            .sorted()
+            .collect::<Vec<_>>(),
+        vec!["power", "shifted", "square"]
+    );
+
+    let genuine = cs
+        .constraints
+        .iter()
+        .find(|c| c.name() == "genuine")
+        .unwrap();
+    if let compiler::Constraint::Vanishes { expr, .. } = genuine {
+        assert!(!is_identically_zero(expr)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn format_version_is_checked() -> Result<()> {
+    use compiler::generator::FORMAT_VERSION;
+
+    let cs = compile("(defcolumns A B) (defconstraint c () (vanishes! (* A B)))")?;
+    assert_eq!(cs.format_version, FORMAT_VERSION);
+
+    let ron = ron::ser::to_string(&cs)?;
+    assert_eq!(
+        ConstraintSet::load(&ron, true)?.format_version,
+        FORMAT_VERSION
+    );
+
+    let mut json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&cs)?)?;
+    assert!(ConstraintSet::load(&json.to_string(), false).is_ok());
+
+    // sets serialized before versioning are migrated
+    json.as_object_mut().unwrap().remove("format_version");
+    assert_eq!(
+        ConstraintSet::load(&json.to_string(), false)?.format_version,
+        FORMAT_VERSION
+    );
+
+    // sets from a later format are rejected
+    json["format_version"] = (FORMAT_VERSION + 1).into();
+    let err = ConstraintSet::load(&json.to_string(), false).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&format!("format version {}", FORMAT_VERSION + 1)));
+    Ok(())
+}
+
+#[test]
+fn bincode_round_trip() -> Result<()> {
+    use compiler::generator::FORMAT_VERSION;
+
+    let cs = compile(
+        "(module m) (defconst K 3) (defcolumns A B (C :comp (* K A))) (defconstraint c () (vanishes! (* A (shift B 2))))
+         (defpermutation (X Y) ((+ A) (- B)))",
+    )?;
+    let dir = std::env::temp_dir().join(format!("corset-bincode-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("cs.bin").to_string_lossy().to_string();
+
+    cs.save_bin(&path)?;
+    let loaded = ConstraintSet::load_bin(&path)?;
+    assert_eq!(serde_json::to_value(&loaded)?, serde_json::to_value(&cs)?);
+    assert_eq!(loaded.spilling_of("m"), cs.spilling_of("m"));
+
+    // sets from another format are rejected
+    let mut contents = std::fs::read(&path)?;
+    contents[..4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    std::fs::write(&path, contents)?;
+    let err = ConstraintSet::load_bin(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&format!("format version {}", FORMAT_VERSION + 1)));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn columns_by_tag() -> Result<()> {
+    let cs = compile(
+        "(defcolumns (A :byte :tags (range-checked sorted)) B (C :array [2] :tags (range-checked)))
+         (module m)
+         (defcolumns (A :tags (sorted)))",
+    )?;
+    let names = |tag: &str| {
+        cs.columns_with_tag(tag)
+            .into_iter()
+            .map(|h| h.to_string())
+            .sorted()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("range-checked"), vec!["A", "C_1", "C_2"]);
+    assert_eq!(names("sorted"), vec!["A", "m.A"]);
+    assert!(names("unknown").is_empty());
+
+    must_fail("bad-tags", "(defcolumns (A :tags range-checked))");
+    must_fail("incomplete-tags", "(defcolumns (A :tags))");
+    Ok(())
+}
+
+#[test]
+fn constraint_graph_edges() -> Result<()> {
+    let cs =
+        compile("(module m) (defcolumns A B C) (defconstraint two-columns () (- A (next B)))")?;
+    let edges = exporters::dot::constraint_edges(&cs)
+        .into_iter()
+        .filter(|(c, _)| c.name == "two-columns")
+        .map(|(_, col)| col.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(edges, vec!["m.A", "m.B"]);
+
+    let dot = exporters::dot::render_constraints(&cs);
+    assert!(dot.contains("\"constraint:m.two-columns\" -> \"column:m.A\";"));
+    assert!(dot.contains("\"constraint:m.two-columns\" -> \"column:m.B\";"));
+    assert!(!dot.contains("\"column:m.C\""));
+    Ok(())
+}
+
+#[test]
+fn defun_overloading_by_arity() -> Result<()> {
+    let cs = compile(
+        "(defcolumns A B C)
+         (defun (f x) (* 2 x))
+         (defun (f x y) (+ x y))
+         (defconstraint one () (eq! A (f B)))
+         (defconstraint two () (eq! A (f B C)))",
+    )?;
+    let expr = |name: &str| {
+        cs.constraints
+            .iter()
+            .find(|c| c.name() == name)
+            .map(|c| match c {
+                compiler::Constraint::Vanishes { expr, .. } => expr.to_string(),
+                _ => unreachable!(),
+            })
+            .unwrap()
+    };
+    assert_ne!(expr("one"), expr("two"));
+    assert!(expr("one").contains('2'));
+    assert!(expr("two").contains('C'));
+
+    // no specialization takes 3 arguments
+    must_fail(
+        "wrong arity",
+        "(defcolumns A B C)
+         (defun (f x) (* 2 x))
+         (defun (f x y) (+ x y))
+         (defconstraint three () (eq! A (f A B C)))",
+    );
+    // neither specialization is more specific than the other
+    must_fail(
+        "ambiguous",
+        "(defcolumns (A :binary) (B :binary))
+         (defun (g (x :binary) y) x)
+         (defun (g x (y :binary)) y)
+         (defconstraint amb () (vanishes! (g A B)))",
+    );
+    Ok(())
+}
+
+#[test]
+fn constants_above_the_modulus() -> Result<()> {
+    let modulus = crate::constants::field_modulus();
+    let source = format!(
+        "(defconst BIG {} WRAPPED {}) (defcolumns A) (defconstraint c () (vanishes! (- A BIG)))",
+        modulus + 5,
+        modulus
+    );
+    assert!(make("strict", &source).is_err());
+
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.reduce_constants(true);
+    r.add_source(&source)?;
+    let cs = r.into_constraint_set()?;
+    let constant = |name: &str| {
+        cs.constants
+            .iter()
+            .find(|(h, _)| h.name == name)
+            .map(|(_, v)| v.clone())
+            .unwrap()
+    };
+    assert_eq!(constant("BIG"), BigInt::from(5));
+    assert_eq!(constant("WRAPPED"), BigInt::from(0));
+    Ok(())
+}
+
+#[test]
+fn nested_modules() -> Result<()> {
+    use crate::structs::Handle;
+
+    let cs = compile(
+        "(module a)
+         (defcolumns X)
+         (defconst K 3)
+         (defun (double x) (* 2 x))
+         (module a.b)
+         (defcolumns Y)
+         (defconstraint parent-symbols () (vanishes! (- Y (double K))))
+         (module z)
+         (defcolumns Z)
+         (deflookup qualified (a.b.Y) (Z))",
+    )?;
+
+    let y = Handle::new("a.b", "Y");
+    assert!(cs.columns.by_handle(&y).is_ok());
+    assert_eq!(y.to_string(), "a.b.Y");
+    assert_eq!(
+        Handle::from_serialized_string(&y.to_serialized_string()).unwrap(),
+        y
+    );
+
+    let constraint = |name: &str| cs.constraints.iter().find(|c| c.name() == name).unwrap();
+    match constraint("a.b.parent-symbols") {
+        compiler::Constraint::Vanishes { expr, .. } => assert!(expr.to_string().contains('6')),
+        _ => unreachable!(),
+    }
+    match constraint("z.qualified") {
+        compiler::Constraint::Lookup { including, .. } => {
+            let deps = including[0].dependencies();
+            assert_eq!(cs.handle(deps.iter().next().unwrap()), &y);
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+#[test]
+fn let_destructuring() -> Result<()> {
+    let cs = compile(
+        "(defcolumns A B)
+         (defun (split x) (begin (* 2 x) (+ x 1)))
+         (defconstraint destructured () (let (((lo hi) (split A))) (eq! B (+ lo hi))))
+         (defconstraint inlined () (eq! B (+ (* 2 A) (+ A 1))))",
+    )?;
+    let expr = |name: &str| {
+        cs.constraints
+            .iter()
+            .find(|c| c.name() == name)
+            .map(|c| match c {
+                compiler::Constraint::Vanishes { expr, .. } => expr.to_string(),
+                _ => unreachable!(),
+            })
+            .unwrap()
+    };
+    assert_eq!(expr("destructured"), expr("inlined"));
+
+    must_fail(
+        "too many symbols",
+        "(defcolumns A B)
+         (defun (split x) (begin (* 2 x) (+ x 1)))
+         (defconstraint c () (let (((lo mid hi) (split A))) (eq! B lo)))",
+    );
+    must_fail(
+        "not a list",
+        "(defcolumns A B) (defconstraint c () (let (((lo hi) A)) (eq! B lo)))",
+    );
+    Ok(())
+}
+
+#[test]
+fn let_star() -> Result<()> {
+    let cs = compile(
+        "(defcolumns A B)
+         (defconstraint staged () (let* ((x A) (x (+ x 1)) (y (* x 2)) (x y)) (eq! B x)))
+         (defconstraint inlined () (eq! B (* (+ A 1) 2)))",
+    )?;
+    let expr = |name: &str| {
+        cs.constraints
+            .iter()
+            .find(|c| c.name() == name)
+            .map(|c| match c {
+                compiler::Constraint::Vanishes { expr, .. } => expr.to_string(),
+                _ => unreachable!(),
+            })
+            .unwrap()
+    };
+    assert_eq!(expr("staged"), expr("inlined"));
+
+    // let does not allow shadowing within its own bindings
+    must_fail(
+        "let shadowing",
+        "(defcolumns A B) (defconstraint c () (let ((x A) (x (+ x 1))) (eq! B x)))",
+    );
+    // the bindings do not outlive the body
+    must_fail(
+        "let* scope",
+        "(defcolumns A B) (defconstraint c () (begin (let* ((x A)) (eq! B x)) (eq! B x)))",
+    );
+    Ok(())
+}
+
+#[test]
+fn dependency_ordered_source() -> Result<()> {
+    let source = "(defconstraint c () (vanishes! (double A)))
+                  (defun (double x) (* 2 x))
+                  (defcolumns A)
+                  (module m)
+                  (defconstraint d () (vanishes! X))
+                  (defcolumns X)";
+    let mut asts = compiler::parser::parse_simple_ast(&[("forward", source)])?;
+    let ast = &mut asts[0].1;
+    ast.sort_definitions()?;
+    let heads = ast
+        .exprs
+        .iter()
+        .map(|n| n.as_list().unwrap()[0].as_symbol().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        heads,
+        [
+            "defun",
+            "defcolumns",
+            "defconstraint",
+            "module",
+            "defcolumns",
+            "defconstraint"
+        ]
+    );
+    // the reordered source still compiles
+    must_run("reordered", &ast.format());
+
+    let mut asts =
+        compiler::parser::parse_simple_ast(&[("circular", "(defconst A B) (defconst B A)")])?;
+    assert!(asts[0].1.sort_definitions().is_err());
+    Ok(())
+}
+
+#[test]
+fn extreme_shifts() -> Result<()> {
+    let cs = compile(
+        "(defcolumns A B C)
+         (defconstraint nested () (vanishes! (+ (shift A 2) (* (prev B) (shift (next C) 3)))))
+         (defconstraint forward () (vanishes! (- (next A) (shift B 2))))
+         (defconstraint flat () (vanishes! (- A 1)))",
+    )?;
+    let expr = |name: &str| {
+        cs.constraints
+            .iter()
+            .find(|c| c.name() == name)
+            .map(|c| match c {
+                compiler::Constraint::Vanishes { expr, .. } => *expr.clone(),
+                _ => unreachable!(),
+            })
+            .unwrap()
+    };
+    let nested = expr("nested");
+    assert_eq!(
+        (nested.min_shift(), nested.max_shift()),
+        (Some(-1), Some(4))
+    );
+    assert_eq!((nested.past_spill(), nested.future_spill()), (-1, 4));
+
+    // all the shifts are in the future, there is no past spilling
+    let forward = expr("forward");
+    assert_eq!(
+        (forward.min_shift(), forward.max_shift()),
+        (Some(1), Some(2))
+    );
+    assert_eq!((forward.past_spill(), forward.future_spill()), (0, 2));
+
+    let flat = expr("flat");
+    assert_eq!((flat.min_shift(), flat.max_shift()), (Some(0), Some(0)));
+    assert_eq!(compiler::Node::from_isize(3).max_shift(), None);
+    Ok(())
+}
+
+#[test]
+fn emitted_dependencies() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("corset-deps-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let source = dir.join("my source.lisp");
+    std::fs::write(&source, "(defcolumns A)")?;
+    let source = source.to_str().unwrap();
+
+    let mut r = ConstraintSetBuilder::from_sources(false, false);
+    r.add_source(source)?;
+    r.add_source("(defcolumns B)")?;
+    let rule = r.make_rule("out.bin")?;
+    // the stdlib is embedded in the executable
+    let exe = std::env::current_exe()?;
+    assert_eq!(
+        rule,
+        format!(
+            "out.bin: \\\n  {} \\\n  {}\n",
+            exe.to_str().unwrap(),
+            source.replace(' ', "\\ ")
+        )
+    );
+
+    let mut r = ConstraintSetBuilder::from_sources(true, false);
+    r.add_source(source)?;
+    assert_eq!(r.dependencies()?, vec![source.to_owned()]);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn no_auto_constraints() -> Result<()> {
+    let in_ranges = |no_auto: bool| -> Result<usize> {
+        let mut r = ConstraintSetBuilder::from_sources(false, false);
+        r.add_source("(defcolumns (A :byte@prove))")?;
+        r.auto_constraints(crate::transformer::AutoConstraint::all());
+        r.no_auto_constraints(no_auto);
+        Ok(r.into_constraint_set()?
+            .constraints
+            .iter()
+            .filter(|c| matches!(c, compiler::Constraint::InRange { .. }))
+            .count())
+    };
+    assert_eq!(in_ranges(false)?, 1);
+    assert_eq!(in_ranges(true)?, 0);
+    Ok(())
+}
+
+#[test]
+fn module_base() -> Result<()> {
+    use crate::pretty::Base;
+
+    let mut cs = compile(
+        "(module m) (defcolumns A (B :display :bin) (C :padding 10))
+         (defmodule-base :dec)
+         (module n) (defcolumns (D :padding 10))",
+    )?;
+    let base = |name: &str| {
+        cs.columns
+            .iter_cols()
+            .find(|c| c.handle.name == name)
+            .unwrap()
+            .base
+    };
+    assert_eq!(base("A"), Base::Dec);
+    // an explicit base overrides the one of the module
+    assert_eq!(base("B"), Base::Bin);
+    assert_eq!(base("D"), Base::Hex);
+
+    let mut out = Vec::new();
+    cs.write(&mut out)?;
+    let out = String::from_utf8(out)?;
+    // the display base does not change how the padding is written
+    for c in ["m.C", "n.D"] {
+        assert!(out.contains(&format!(
+            "\"{}\":{{\n\"values\":[],\n\"padding_strategy\": {{\"action\": \"prepend\", \"value\": \"10\"}}",
+            c
+        )));
+    }
+
+    assert!(make(
+        "conflicting-bases",
+        "(module m) (defmodule-base :dec) (defmodule-base :bin)"
+    )
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn json_dump() -> Result<()> {
+    let cs = compile(
+        "(module m) (defconst K 5) (defcolumns A B) (definrange A 10) (defconstraint c () (eq! A (* 3 B)))",
+    )?;
+    let j = exporters::json::to_json(&cs)?;
+    assert_eq!(j["constants"]["m.K"], "5");
+    let constraints = j["constraints"].as_array().unwrap();
+    let in_range = constraints.iter().find_map(|c| c.get("InRange")).unwrap();
+    assert_eq!(in_range["max"], "10");
+    // column references carry both their handle and their ID
+    assert!(in_range["exp"]["_e"]["Column"]["handle"]
+        .as_str()
+        .unwrap()
+        .starts_with("m.A#"));
+
+    // native field elements survive serialization, and are therefore dumped
+    // with their actual value
+    let x = crate::column::Value::from(Fr::from(3));
+    let mut j = serde_json::json!({ "x": x });
+    assert_eq!(
+        serde_json::from_value::<crate::column::Value>(j["x"].take())?,
+        x
+    );
+    Ok(())
+}
+
+#[test]
+fn included_files() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("corset-include-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib"))?;
+    std::fs::write(dir.join("lib/columns.lisp"), "(defcolumns A B)")?;
+    std::fs::write(
+        dir.join("main.lisp"),
+        "(include \"lib/columns.lisp\")\n(defconstraint a-is-b () (- A B))",
+    )?;
+    std::fs::write(dir.join("a.lisp"), "(include \"b.lisp\") (defcolumns X)")?;
+    std::fs::write(dir.join("b.lisp"), "(include \"a.lisp\") (defcolumns Y)")?;
+
+    let mut r = ConstraintSetBuilder::from_sources(true, false);
+    r.add_source(dir.join("main.lisp").to_str().unwrap())?;
+    // the included file is also a dependency of the compilation
+    assert_eq!(r.dependencies()?.len(), 2);
+    let cs = r.into_constraint_set()?;
+    assert!(cs.columns.iter_cols().count() >= 2);
+    assert_eq!(cs.constraints.len(), 1);
+
+    let mut r = ConstraintSetBuilder::from_sources(true, false);
+    let err = r
+        .add_source(dir.join("a.lisp").to_str().unwrap())
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("circular include"));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn narrow_any_types() -> Result<()> {
+    use compiler::{Magma, RawMagma, Type};
+
+    /// An identity whose result type is left to the context it is used in
+    #[derive(Debug)]
+    struct Opaque;
+    impl compiler::CustomBuiltin for Opaque {
+        fn name(&self) -> &str {
+            "opaque"
+        }
+
+        fn arity(&self) -> compiler::Arity {
+            compiler::Arity::Monadic
+        }
+
+        fn typing(&self, _args: &[Type]) -> Result<Type> {
+            Ok(Type::Any(Magma::ANY))
+        }
+
+        fn lower(&self, args: Vec<compiler::Node>) -> Result<compiler::Node> {
+            Ok(args[0].clone())
+        }
+    }
+
+    let settings = compiler::CompileSettings {
+        debug: false,
+        reduce_constants: false,
+        no_auto_constraints: false,
+        warn_unreferenced_constant: false,
+        aux_prefix: None,
+    };
+    let mut ctx = compiler::tables::Scope::new();
+    ctx.register_builtin(std::sync::Arc::new(Opaque))?;
+    let (_, mut cs) = compiler::make_in(
+        ctx,
+        &[(
+            "any",
+            "(module hash)
+             (defun ((typed :i16 :force) x) (* 2 (opaque x)))
+             (defun (untyped x) (* 2 (opaque x)))
+             (module hash.keccak)
+             (defcolumns (Y :i16))
+             (defconstraint narrowed () (typed Y))
+             (defconstraint ambiguous () (untyped Y))",
+        )],
+        &settings,
+    )?;
+    let constraint = |name: &str| {
+        cs.constraints
+            .iter()
+            .find_map(|c| match c {
+                compiler::Constraint::Vanishes { handle, expr, .. } if handle.name == name => {
+                    Some(*expr.clone())
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    // The declared return type of the function bounds its operands
+    assert!(constraint("narrowed")
+        .leaves()
+        .iter()
+        .all(|l| l.t().rm() != RawMagma::Any));
+    assert!(matches!(
+        constraint("narrowed").leaves()[1].t(),
+        Type::Column(m) if m.rm() == RawMagma::Integer(16)
+    ));
+
+    // Without it, the result could be anything, whatever the type of Y
+    let ambiguous = cs.narrow_any_types();
+    assert_eq!(ambiguous.len(), 1);
+    assert_eq!(ambiguous[0].0.name, "ambiguous");
+    Ok(())
+}
+
+#[test]
+fn atomic_writes() -> Result<()> {
+    use std::io::Write;
+
+    let dir = std::env::temp_dir().join(format!("corset-atomic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let out = dir.join("out.bin");
+    let path = out.to_str().unwrap();
+
+    // An exporter failing midway leaves nothing behind
+    let r = crate::utils::write_atomically(path, |out| {
+        out.write_all(b"partial")?;
+        bail!("exporter failure")
+    });
+    assert!(r.is_err());
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+
+    // Nor does it clobber an existing file, that only a successful one
+    // replaces
+    std::fs::write(&out, "previous")?;
+    let r = crate::utils::write_atomically(path, |out| {
+        out.write_all(b"partial")?;
+        bail!("exporter failure")
+    });
+    assert!(r.is_err());
+    assert_eq!(std::fs::read_to_string(&out)?, "previous");
+    crate::utils::write_atomically(path, |out| Ok(out.write_all(b"complete")?))?;
+    assert_eq!(std::fs::read_to_string(&out)?, "complete");
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn warn_unreferenced_constant() -> Result<()> {
+    let compile = |warn_unreferenced_constant: bool| {
+        let settings = compiler::CompileSettings {
+            debug: false,
+            reduce_constants: false,
+            no_auto_constraints: false,
+            warn_unreferenced_constant,
+            aux_prefix: None,
+        };
+        compiler::make(
+            &[(
+                "constants",
+                "(defconst UNREFERENCED_K 3 USED_K 4) (defcolumns X) (defconstraint c () (- X USED_K))",
+            )],
+            &settings,
+        )
+    };
+    let warned = |cs: &ConstraintSet, name: &str| {
+        cs.warnings
+            .iter()
+            .any(|w| w.contains("constant") && w.contains(name))
+    };
+
+    let (_, cs) = compile(false)?;
+    assert!(!warned(&cs, "UNREFERENCED_K"));
+    let (_, cs) = compile(true)?;
+    assert!(warned(&cs, "UNREFERENCED_K"));
+    assert!(!warned(&cs, "USED_K"));
+    Ok(())
+}
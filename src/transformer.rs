@@ -2,6 +2,7 @@ mod concretize;
 mod ifs;
 mod inverses;
 mod nhood;
+pub(crate) mod normalize;
 mod selectors;
 mod sort;
 mod splatter;
@@ -148,8 +149,11 @@ fn validate_computation(cs: &mut Vec<Node>, x_expr: &Node, x_col: &Handle) {
     )
 }
 
-fn expression_to_name(e: &Node, prefix: &str) -> String {
-    format!("C/{}[{}]", prefix, e)
+/// The default prefix of the columns introduced to hold an expression
+const EXPRESSION_PREFIX: &str = "C/";
+
+fn expression_to_name(e: &Node, prefix: &str, kind: &str) -> String {
+    format!("{}{}[{}]", prefix, kind, e)
 }
 
 fn flatten_list(mut e: Node) -> Node {
@@ -35,7 +35,7 @@ impl ConstraintSet {
         for c in self.constraints.iter_mut() {
             match c {
                 Constraint::Vanishes { expr, .. } => expr.concretize(),
-                Constraint::Lookup { .. } => {}
+                Constraint::Lookup { .. } | Constraint::LookupMultiplicity { .. } => {}
                 Constraint::Permutation { .. } => {}
                 Constraint::InRange { exp, max, .. } => {
                     exp.concretize();
@@ -52,7 +52,9 @@ impl ConstraintSet {
     fn make_computations_native(&mut self) {
         for c in self.computations.iter_mut() {
             match c {
-                Computation::Composite { exp, .. } => exp.concretize(),
+                Computation::Composite { exp, .. } | Computation::Fixpoint { exp, .. } => {
+                    exp.concretize()
+                }
                 _ => {}
             }
         }
@@ -78,7 +78,9 @@ fn extract_condition(node: &Node) -> Option<Node> {
                 | Intrinsic::VectorAdd
                 | Intrinsic::VectorSub
                 | Intrinsic::VectorMul
-                | Intrinsic::Exp => {
+                | Intrinsic::Exp
+                | Intrinsic::Mod
+                | Intrinsic::Div => {
                     let mut r = None;
                     // Extract condition for each term
                     for n in args {
@@ -141,6 +143,8 @@ fn extract_body(node: &Node) -> Node {
                 Intrinsic::Neg
                 | Intrinsic::Inv
                 | Intrinsic::Normalize
+                | Intrinsic::Mod
+                | Intrinsic::Div
                 | Intrinsic::Exp
                 | Intrinsic::Add
                 | Intrinsic::Sub
@@ -7,7 +7,7 @@ use crate::{
 };
 use anyhow::*;
 
-use super::expression_to_name;
+use super::{expression_to_name, EXPRESSION_PREFIX};
 
 fn invert_expr(e: &Node) -> Node {
     Intrinsic::Inv.call(&[e.to_owned()]).unwrap()
@@ -20,17 +20,18 @@ impl Node {
     pub(crate) fn do_normalize(
         &mut self,
         get_module: &dyn Fn(&HashSet<ColumnRef>) -> String,
+        prefix: &str,
         new_cols: &mut Vec<(Handle, Node)>,
     ) {
         match self.e_mut() {
             Expression::List(es) => {
                 for e in es.iter_mut() {
-                    e.do_normalize(get_module, new_cols);
+                    e.do_normalize(get_module, prefix, new_cols);
                 }
             }
             Expression::Funcall { func, args, .. } => {
                 for e in args.iter_mut() {
-                    e.do_normalize(get_module, new_cols);
+                    e.do_normalize(get_module, prefix, new_cols);
                 }
                 if matches!(func, Intrinsic::Normalize) {
                     // Intrinsic::Inv should never have more than one argument
@@ -46,7 +47,8 @@ impl Node {
                         *self = arg.clone();
                     } else if true {
                         let module = get_module(&arg.dependencies());
-                        let inverted_handle = Handle::new(module, expression_to_name(arg, "INV"));
+                        let inverted_handle =
+                            Handle::new(module, expression_to_name(arg, prefix, "INV"));
                         new_cols.push((inverted_handle.clone(), arg.to_owned()));
                         *self = Intrinsic::Mul
                             .call(&[
@@ -89,9 +91,10 @@ impl ConstraintSet {
         let mut new_cols = vec![];
 
         let get_module = |rs: &HashSet<ColumnRef>| self.columns.module_for(rs.iter()).unwrap();
+        let prefix = self.aux_prefix(EXPRESSION_PREFIX).to_owned();
         for i in 0..self.constraints.len() {
             if let Constraint::Vanishes { expr: e, .. } = self.constraints.get_mut(i).unwrap() {
-                e.do_normalize(&get_module, &mut new_cols);
+                e.do_normalize(&get_module, &prefix, &mut new_cols);
             }
         }
 
@@ -1,9 +1,9 @@
+use crate::utils::Colorize;
 use crate::{
     compiler::{ColumnRef, Constraint, ConstraintSet, Intrinsic, Kind, Node, RawMagma},
     structs::Handle,
 };
 use anyhow::{bail, Result};
-use owo_colors::OwoColorize;
 
 fn process_binarity(column_ref: ColumnRef, cs: &mut ConstraintSet) {
     let handle = cs.handle(&column_ref);
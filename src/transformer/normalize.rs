@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde_json::{json, Value as JsonValue};
+
+use crate::compiler::{Constraint, ConstraintSet, Expression, Intrinsic, Node};
+
+use super::statics::do_precompute;
+
+/// Flatten nested associative operations, drop their neutral elements, and
+/// sort the arguments of commutative ones, so that e.g. `(+ (* 2 A) B)` and
+/// `(+ B (* A 2))` end up written the same way.
+fn canonicalize(e: &mut Node, cs: &ConstraintSet) {
+    if let Expression::Funcall { func, args } = e.e_mut() {
+        for a in args.iter_mut() {
+            canonicalize(a, cs);
+        }
+
+        if matches!(func, Intrinsic::Add | Intrinsic::Mul) {
+            let neutral = if *func == Intrinsic::Add {
+                Node::zero()
+            } else {
+                Node::one()
+            };
+            let mut flat = Vec::with_capacity(args.len());
+            for a in args.drain(..) {
+                match a.e() {
+                    Expression::Funcall {
+                        func: inner,
+                        args: inner_args,
+                    } if inner == func => flat.extend(inner_args.iter().cloned()),
+                    _ => flat.push(a),
+                }
+            }
+            flat.retain(|a| !a.e().struct_eq(neutral.e()));
+            if flat.is_empty() {
+                flat.push(neutral);
+            }
+            flat.sort_by_cached_key(|a| render(a, cs).to_string());
+            *args = flat;
+        }
+
+        if matches!(func, Intrinsic::Add | Intrinsic::Mul) && args.len() == 1 {
+            *e = args[0].clone();
+        }
+    }
+}
+
+/// Shift a transition constraint so that the earliest row it refers to is
+/// the current one
+fn normalize_shifts(e: &mut Node) {
+    let past = e.past_spill();
+    if past != 0 {
+        *e = e.clone().shift(-past as i16);
+    }
+}
+
+/// A self-standing, tree-shaped rendering of an expression
+fn render(e: &Node, cs: &ConstraintSet) -> JsonValue {
+    match e.e() {
+        Expression::Funcall { func, args } => json!({
+            "op": format!("{:?}", func),
+            "args": args.iter().map(|a| render(a, cs)).collect::<Vec<_>>(),
+        }),
+        Expression::Const(x) => json!({ "const": x.to_string() }),
+        Expression::Column { handle, shift, .. } | Expression::ExoColumn { handle, shift, .. } => {
+            json!({ "column": cs.handle(handle).to_string(), "shift": shift })
+        }
+        Expression::List(xs) => json!({
+            "op": "List",
+            "args": xs.iter().map(|a| render(a, cs)).collect::<Vec<_>>(),
+        }),
+        Expression::ArrayColumn { handle, .. } => json!({ "array": handle.to_string() }),
+        Expression::Void => JsonValue::Null,
+    }
+}
+
+/// Hash-conses expressions, so that every common sub-expression is only
+/// stored once and referred to by its index
+#[derive(Default)]
+struct ExpressionTable {
+    nodes: Vec<JsonValue>,
+    index: HashMap<String, usize>,
+}
+impl ExpressionTable {
+    fn insert(&mut self, e: &Node, cs: &ConstraintSet) -> usize {
+        let node = match e.e() {
+            Expression::Funcall { func, args } => json!({
+                "op": format!("{:?}", func),
+                "args": args.iter().map(|a| self.insert(a, cs)).collect::<Vec<_>>(),
+            }),
+            Expression::List(xs) => json!({
+                "op": "List",
+                "args": xs.iter().map(|a| self.insert(a, cs)).collect::<Vec<_>>(),
+            }),
+            _ => render(e, cs),
+        };
+        let key = node.to_string();
+        if let Some(i) = self.index.get(&key) {
+            *i
+        } else {
+            self.nodes.push(node);
+            self.index.insert(key, self.nodes.len() - 1);
+            self.nodes.len() - 1
+        }
+    }
+}
+
+/// Fold the constants, normalize the shifts and factor the common
+/// sub-expressions of `cs`, and return the result as a canonical JSON IR;
+/// two semantically equivalent constraint sets normalize to the same IR.
+pub fn normalize(cs: &mut ConstraintSet) -> JsonValue {
+    for c in cs.constraints.iter_mut() {
+        match c {
+            Constraint::Vanishes { expr, domain, .. } => {
+                do_precompute(expr);
+                if domain.is_none() {
+                    normalize_shifts(expr);
+                }
+            }
+            Constraint::Lookup {
+                including,
+                included,
+                ..
+            }
+            | Constraint::LookupMultiplicity {
+                including,
+                included,
+                ..
+            } => including
+                .iter_mut()
+                .chain(included.iter_mut())
+                .for_each(do_precompute),
+            Constraint::InRange { exp, .. } => do_precompute(exp),
+            Constraint::Normalization { reference, .. } => do_precompute(reference),
+            Constraint::Permutation { .. } => {}
+        }
+    }
+
+    let mut constraints = std::mem::take(&mut cs.constraints);
+    for c in constraints.iter_mut() {
+        match c {
+            Constraint::Vanishes { expr, .. } => canonicalize(expr, cs),
+            Constraint::Lookup {
+                including,
+                included,
+                ..
+            }
+            | Constraint::LookupMultiplicity {
+                including,
+                included,
+                ..
+            } => including
+                .iter_mut()
+                .chain(included.iter_mut())
+                .for_each(|e| canonicalize(e, cs)),
+            Constraint::InRange { exp, .. } => canonicalize(exp, cs),
+            Constraint::Normalization { reference, .. } => canonicalize(reference, cs),
+            Constraint::Permutation { .. } => {}
+        }
+    }
+    cs.constraints = constraints;
+
+    let mut table = ExpressionTable::default();
+    let constraints = cs
+        .constraints
+        .iter()
+        .sorted_by_cached_key(|c| c.name())
+        .map(|c| match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => json!({
+                "name": handle.to_string(),
+                "vanishes": table.insert(expr, cs),
+                "domain": domain.as_ref().map(|d| d.to_string()),
+            }),
+            Constraint::Lookup {
+                handle,
+                including,
+                included,
+            } => json!({
+                "name": handle.to_string(),
+                "including": including.iter().map(|e| table.insert(e, cs)).collect::<Vec<_>>(),
+                "included": included.iter().map(|e| table.insert(e, cs)).collect::<Vec<_>>(),
+            }),
+            Constraint::LookupMultiplicity {
+                handle,
+                including,
+                included,
+                multiplicity,
+            } => json!({
+                "name": handle.to_string(),
+                "including": including.iter().map(|e| table.insert(e, cs)).collect::<Vec<_>>(),
+                "included": included.iter().map(|e| table.insert(e, cs)).collect::<Vec<_>>(),
+                "multiplicity": cs.handle(multiplicity).to_string(),
+            }),
+            Constraint::Permutation { handle, from, to } => json!({
+                "name": handle.to_string(),
+                "from": from.iter().map(|c| cs.handle(c).to_string()).collect::<Vec<_>>(),
+                "to": to.iter().map(|c| cs.handle(c).to_string()).collect::<Vec<_>>(),
+            }),
+            Constraint::InRange { handle, exp, max } => json!({
+                "name": handle.to_string(),
+                "in-range": table.insert(exp, cs),
+                "max": max.to_string(),
+            }),
+            Constraint::Normalization {
+                handle,
+                reference,
+                inverted,
+            } => json!({
+                "name": handle.to_string(),
+                "normalized": table.insert(reference, cs),
+                "inverted": cs.handle(inverted).to_string(),
+            }),
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "columns": cs
+            .columns
+            .iter_cols()
+            .map(|c| (c.handle.to_string(), c.t.to_string()))
+            .sorted()
+            .map(|(h, t)| json!({ "handle": h, "type": t }))
+            .collect::<Vec<_>>(),
+        "constants": cs
+            .constants
+            .iter()
+            .map(|(h, v)| (h.to_string(), v.to_string()))
+            .sorted()
+            .map(|(h, v)| json!({ "name": h, "value": v }))
+            .collect::<Vec<_>>(),
+        "expressions": table.nodes,
+        "constraints": constraints,
+    })
+}
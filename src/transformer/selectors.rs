@@ -7,11 +7,12 @@ use crate::{
 };
 use anyhow::*;
 
-use super::{expression_to_name, validate_computation};
+use super::{expression_to_name, validate_computation, EXPRESSION_PREFIX};
 
 fn do_expand_expr(
     e: &Node,
     module: &str,
+    prefix: &str,
     cols: &mut ColumnSet,
     comps: &mut ComputationTable,
     new_cs: &mut Vec<Node>,
@@ -19,7 +20,7 @@ fn do_expand_expr(
     match e.e() {
         Expression::Column { .. } | Expression::ExoColumn { .. } => Ok(e.clone()),
         _ => {
-            let new_handle = Handle::new(module, expression_to_name(e, "#EXPAND"));
+            let new_handle = Handle::new(module, expression_to_name(e, prefix, "#EXPAND"));
             // TODO: replace name with exprs hash to 100% ensure bijectivity handle/expression
             // Only insert the computation if a column matching the expression has not already been created
             if cols
@@ -53,12 +54,19 @@ fn do_expand_expr(
 
 pub fn expand_constraints(cs: &mut ConstraintSet) -> Result<()> {
     let mut new_cs_exps = vec![];
+    let prefix = cs.aux_prefix(EXPRESSION_PREFIX).to_owned();
     for c in cs.constraints.iter_mut() {
         match c {
             Constraint::Lookup {
                 handle,
                 including: parents,
                 included: children,
+            }
+            | Constraint::LookupMultiplicity {
+                handle,
+                including: parents,
+                included: children,
+                ..
             } => {
                 let including_module = cs.columns.module_forall(parents.iter()).ok_or(
                     CompileError::AmbiguousModule("target", "lookup", handle.clone()),
@@ -71,6 +79,7 @@ pub fn expand_constraints(cs: &mut ConstraintSet) -> Result<()> {
                     *e = do_expand_expr(
                         e,
                         &including_module,
+                        &prefix,
                         &mut cs.columns,
                         &mut cs.computations,
                         &mut new_cs_exps,
@@ -80,6 +89,7 @@ pub fn expand_constraints(cs: &mut ConstraintSet) -> Result<()> {
                     *e = do_expand_expr(
                         e,
                         &included_module,
+                        &prefix,
                         &mut cs.columns,
                         &mut cs.computations,
                         &mut new_cs_exps,
@@ -106,6 +116,7 @@ pub fn expand_constraints(cs: &mut ConstraintSet) -> Result<()> {
                 *e = do_expand_expr(
                     e,
                     &module,
+                    &prefix,
                     &mut cs.columns,
                     &mut cs.computations,
                     &mut new_cs_exps,
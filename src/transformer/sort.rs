@@ -2,18 +2,22 @@ use anyhow::{anyhow, bail, Context, Result};
 use num_bigint::BigInt;
 
 use crate::{
-    column::{Column, Computation, Value},
+    column::{Column, Computation, Endianness, Value},
     compiler::{ColumnRef, Constraint, ConstraintSet, Intrinsic, Kind, Magma, Node},
     pretty::{Base, Pretty},
     structs::Handle,
     utils::hash_strings,
 };
 
+/// The default prefix of the auxiliary columns of the sorting constraints
+const SORT_PREFIX: &str = "__";
+
 fn create_sort_constraint(
     cs: &mut ConstraintSet,
     froms: &[ColumnRef],
     sorted: &[ColumnRef],
     signs: &[bool],
+    endianness: Endianness,
 ) -> Result<()> {
     if froms.len() != sorted.len() {
         bail!(
@@ -43,6 +47,7 @@ fn create_sort_constraint(
 
     // the suffix is required, in case a single module contains multiple sorts
     let suffix = hash_strings(sorted.iter());
+    let prefix = cs.aux_prefix(SORT_PREFIX).to_owned();
 
     // Create the columns
     let ats = (0..signs.len())
@@ -50,7 +55,10 @@ fn create_sort_constraint(
             let size = cs.length_multiplier(&froms[0]);
             cs.columns.insert_column_and_register(
                 Column::builder()
-                    .handle(Handle::new(&module, format!("__SRT__at_{i}_{suffix}")))
+                    .handle(Handle::new(
+                        &module,
+                        format!("{prefix}SRT__at_{i}_{suffix}"),
+                    ))
                     .kind(Kind::Computed)
                     .t(Magma::binary())
                     .intrinsic_size_factor(size)
@@ -61,7 +69,7 @@ fn create_sort_constraint(
     let eq_size = cs.length_multiplier(&froms[0]);
     let eq = cs.columns.insert_column_and_register(
         Column::builder()
-            .handle(Handle::new(&module, format!("__SRT__Eq_{suffix}")))
+            .handle(Handle::new(&module, format!("{prefix}SRT__Eq_{suffix}")))
             .t(Magma::binary())
             .intrinsic_size_factor(eq_size)
             .kind(Kind::Computed)
@@ -70,7 +78,7 @@ fn create_sort_constraint(
     )?;
     let delta = cs.columns.insert_column_and_register(
         Column::builder()
-            .handle(Handle::new(&module, format!("__SRT__Delta_{suffix}")))
+            .handle(Handle::new(&module, format!("{prefix}SRT__Delta_{suffix}")))
             .kind(Kind::Computed)
             .intrinsic_size_factor(cs.length_multiplier(&froms[0]))
             .base(Base::Hex)
@@ -80,7 +88,10 @@ fn create_sort_constraint(
         .map(|i| {
             cs.columns.insert_column_and_register(
                 Column::builder()
-                    .handle(Handle::new(&module, format!("__SRT__Delta_{i}_{suffix}")))
+                    .handle(Handle::new(
+                        &module,
+                        format!("{prefix}SRT__Delta_{i}_{suffix}"),
+                    ))
                     .kind(Kind::Computed)
                     .t(Magma::byte())
                     .base(Base::Hex)
@@ -132,7 +143,9 @@ fn create_sort_constraint(
                         .enumerate()
                         .map(|(i, byte)| {
                             Intrinsic::Mul.call(&[
-                                Node::from_bigint(BigInt::from(256).pow(i as u32)),
+                                Node::from_bigint(
+                                    BigInt::from(256).pow(endianness.weight(i, delta_bytes.len())),
+                                ),
                                 Node::column().handle(byte.clone()).t(Magma::byte()).build(),
                             ])
                         })
@@ -248,7 +261,7 @@ fn create_sort_constraint(
 
     // // Create the Eq[i] = 0 constraint
     cs.insert_constraint(Constraint::Vanishes {
-        handle: Handle::new(&module, format!("__SRT__Eq_i_{suffix}")),
+        handle: Handle::new(&module, format!("{prefix}SRT__Eq_i_{suffix}")),
         domain: None,
         expr: Box::new(
             Intrinsic::Mul.call(&[
@@ -315,6 +328,7 @@ fn create_sort_constraint(
             signs: signs.to_vec(),
             froms: froms.to_vec(),
             sorted: sorted.to_vec(),
+            endianness,
         },
     )?;
 
@@ -329,8 +343,14 @@ pub fn sorts(cs: &mut ConstraintSet) -> Result<()> {
         .collect::<Vec<_>>()
         .into_iter()
     {
-        if let Computation::Sorted { froms, tos, signs } = c {
-            create_sort_constraint(cs, &froms, &tos, &signs)
+        if let Computation::Sorted {
+            froms,
+            tos,
+            signs,
+            endianness,
+        } = c
+        {
+            create_sort_constraint(cs, &froms, &tos, &signs, endianness)
                 .with_context(|| anyhow!("while creating sort constraints"))?;
         }
     }
@@ -1,6 +1,6 @@
 use crate::compiler::{Constraint, ConstraintSet, Node};
 
-fn do_precompute(e: &mut Node) {
+pub(crate) fn do_precompute(e: &mut Node) {
     if let Result::Ok(value) = e.pure_eval() {
         *e = Node::from_bigint(value)
     } else {
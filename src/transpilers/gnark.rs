@@ -0,0 +1,301 @@
+//! Render a constraint system as a gnark circuit, i.e. Go code asserting the
+//! constraints through the `frontend.API` of gnark.
+//!
+//! Every column used by the constraints is mapped to a slice of witness
+//! variables in the `Circuit` structure, indexed by row; shifts are mapped to
+//! index arithmetic on the row. Each vanishing constraint is asserted on every
+//! row where its shifts fall within the trace, or on the rows of its domain,
+//! negative ones counting from the end of the trace; as when checking a trace,
+//! the shifts of the latter wrap around the trace.
+//!
+//! As the field operations of gnark do not map the inverse of zero to zero, the
+//! constraints must be expanded beforehand. Only the polynomial constraints are
+//! rendered; lookups, permutations, range and normalization constraints are
+//! left out of the generated code.
+use anyhow::*;
+use itertools::Itertools;
+use log::*;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use std::collections::BTreeMap;
+
+use crate::compiler::{ColumnRef, Constraint, ConstraintSet, Domain, Expression, Intrinsic, Node};
+
+/// A rendered vanishing constraint
+struct GnarkConstraint {
+    /// the name of the constraint, as known to Corset
+    name: String,
+    /// the rows it must be asserted on, or `None` for all of them
+    domain: Option<Domain<isize>>,
+    /// the gnark expression computing its value
+    body: String,
+    /// a column of the constraint, giving the length of the trace
+    column: Option<String>,
+    /// the shifts farthest in the past and in the future
+    spilling: (isize, isize),
+}
+
+struct GnarkRenderer<'a> {
+    cs: &'a ConstraintSet,
+    /// the columns used in the constraints, and the name of the field holding
+    /// them in the circuit
+    columns: BTreeMap<usize, String>,
+    /// whether shifted rows should wrap around the trace
+    wrap: bool,
+    /// whether the `wrap` helper is used by the rendered code
+    wrapped: bool,
+}
+impl<'a> GnarkRenderer<'a> {
+    /// Turn a Corset name into a Go identifier
+    fn identifier(name: &str) -> String {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    }
+
+    /// The name of the circuit field holding `handle`; it must be exported for
+    /// gnark to see it as a witness
+    fn column(&mut self, handle: &ColumnRef) -> String {
+        let id = self.cs.columns.id_of(handle);
+        let cs = self.cs;
+        self.columns
+            .entry(id)
+            .or_insert_with(|| format!("Col_{}", Self::identifier(&cs.handle(handle).to_string())))
+            .clone()
+    }
+
+    fn render_node(&mut self, e: &Node) -> Result<String> {
+        match e.e() {
+            Expression::Const(x) => Ok(render_constant(&x.to_bi())),
+            Expression::Column { wrap: true, .. } => {
+                bail!("unable to render the wrapping access {} for gnark", e)
+            }
+            Expression::Column { handle, shift, .. } => {
+                let column = self.column(handle);
+                let row = render_row(*shift as isize);
+                if self.wrap && *shift != 0 {
+                    self.wrapped = true;
+                    Ok(format!("t.{}[wrap({}, len(t.{}))]", column, row, column))
+                } else {
+                    Ok(format!("t.{}[{}]", column, row))
+                }
+            }
+            Expression::Funcall { func, args } => self.render_funcall(func, args),
+            Expression::Void => Ok("0".to_owned()),
+            Expression::List(_) => unreachable!("lists are flattened by render_vanishing"),
+            Expression::ArrayColumn { .. } | Expression::ExoColumn { .. } => {
+                bail!("unable to render {} for gnark", e)
+            }
+        }
+    }
+
+    fn render_funcall(&mut self, func: &Intrinsic, args: &[Node]) -> Result<String> {
+        let variadic = |method: &str, xs: Vec<String>| {
+            if xs.len() == 1 {
+                xs[0].clone()
+            } else {
+                format!("api.{}({})", method, xs.join(", "))
+            }
+        };
+        match func {
+            Intrinsic::Add | Intrinsic::VectorAdd => Ok(variadic("Add", self.render_args(args)?)),
+            Intrinsic::Sub | Intrinsic::VectorSub => Ok(variadic("Sub", self.render_args(args)?)),
+            Intrinsic::Mul | Intrinsic::VectorMul => Ok(variadic("Mul", self.render_args(args)?)),
+            Intrinsic::Neg => Ok(format!("api.Neg({})", self.render_node(&args[0])?)),
+            Intrinsic::Normalize => Ok(format!(
+                "api.Sub(1, api.IsZero({}))",
+                self.render_node(&args[0])?
+            )),
+            Intrinsic::Exp => {
+                let exp = args[1]
+                    .pure_eval()
+                    .ok()
+                    .and_then(|x| x.to_usize())
+                    .with_context(|| anyhow!("exponent `{}` is not a constant", &args[1]))?;
+                if exp == 0 {
+                    Ok("1".to_owned())
+                } else {
+                    let x = self.render_node(&args[0])?;
+                    Ok(variadic("Mul", vec![x; exp]))
+                }
+            }
+            Intrinsic::IfZero | Intrinsic::IfNotZero => {
+                let xs = self.render_args(args)?;
+                let (then, otherwise) = (&xs[1], xs.get(2).map(String::as_str).unwrap_or("0"));
+                let (zero, non_zero) = if matches!(func, Intrinsic::IfZero) {
+                    (then.as_str(), otherwise)
+                } else {
+                    (otherwise, then.as_str())
+                };
+                Ok(format!(
+                    "api.Select(api.IsZero({}), {}, {})",
+                    xs[0], zero, non_zero
+                ))
+            }
+            Intrinsic::Begin => unreachable!("lists are flattened by render_vanishing"),
+            x @ (Intrinsic::Inv | Intrinsic::Mod | Intrinsic::Div) => bail!(
+                "{} can not be rendered for gnark; expand the constraints first",
+                x
+            ),
+        }
+    }
+
+    fn render_args(&mut self, args: &[Node]) -> Result<Vec<String>> {
+        args.iter().map(|a| self.render_node(a)).collect()
+    }
+
+    fn render_vanishing(
+        &mut self,
+        name: &str,
+        domain: &Option<Domain<isize>>,
+        e: &Node,
+    ) -> Result<Vec<GnarkConstraint>> {
+        match e.e() {
+            Expression::List(xs) => Ok(xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| self.render_vanishing(&format!("{}#{}", name, i), domain, x))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect()),
+            _ => {
+                self.wrap = domain.is_some();
+                let body = self.render_node(e)?;
+                let column = e
+                    .dependencies()
+                    .iter()
+                    .map(|c| self.column(c))
+                    .sorted()
+                    .next();
+                Ok(vec![GnarkConstraint {
+                    name: name.to_owned(),
+                    domain: domain.clone(),
+                    body,
+                    column,
+                    spilling: (e.past_spill(), e.future_spill()),
+                }])
+            }
+        }
+    }
+
+    fn render_constraint(&mut self, c: &Constraint) -> Result<Vec<GnarkConstraint>> {
+        match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => self.render_vanishing(&handle.to_string(), domain, expr),
+            Constraint::InRange { handle, .. }
+            | Constraint::Lookup { handle, .. }
+            | Constraint::LookupMultiplicity { handle, .. }
+            | Constraint::Permutation { handle, .. }
+            | Constraint::Normalization { handle, .. } => {
+                warn!("skipping non-polynomial constraint {}", handle);
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+/// The row at `shift` from the current one
+fn render_row(shift: isize) -> String {
+    match shift {
+        0 => "row".to_owned(),
+        s if s > 0 => format!("row+{}", s),
+        s => format!("row-{}", -s),
+    }
+}
+
+/// Render an integer as a gnark constant, large ones being given as decimal
+/// strings
+fn render_constant(x: &BigInt) -> String {
+    if x.is_negative() {
+        format!("api.Neg({})", render_constant(&-x))
+    } else if x.to_i64().is_some() {
+        x.to_string()
+    } else {
+        format!("\"{}\"", x)
+    }
+}
+
+/// Render the vanishing constraints of `cs` as a gnark circuit
+pub fn render(cs: &ConstraintSet) -> Result<String> {
+    let mut renderer = GnarkRenderer {
+        cs,
+        columns: Default::default(),
+        wrap: false,
+        wrapped: false,
+    };
+    let constraints = cs
+        .constraints
+        .iter()
+        .sorted_by_key(|c| c.name())
+        .map(|c| renderer.render_constraint(c))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut r = vec![
+        "// Code generated by Corset; DO NOT EDIT.".to_owned(),
+        String::new(),
+        "package constraints".to_owned(),
+        String::new(),
+        "import \"github.com/consensys/gnark/frontend\"".to_owned(),
+        String::new(),
+        "// Circuit holds the columns of the trace, indexed by row".to_owned(),
+        "type Circuit struct {".to_owned(),
+    ];
+    for name in renderer.columns.values().sorted() {
+        r.push(format!("\t{} []frontend.Variable", name));
+    }
+    r.push("}".to_owned());
+    r.push(String::new());
+    r.push("// Define asserts the constraints over the whole trace".to_owned());
+    r.push("func (t *Circuit) Define(api frontend.API) error {".to_owned());
+    for c in constraints.iter() {
+        r.push(format!("\t// {}", c.name));
+        let assertion = format!("api.AssertIsEqual({}, 0)", c.body);
+        match (&c.domain, &c.column) {
+            (_, None) => r.push(format!("\t{}", assertion)),
+            (None, Some(column)) => {
+                let (past, future) = c.spilling;
+                let end = if future > 0 {
+                    format!("len(t.{})-{}", column, future)
+                } else {
+                    format!("len(t.{})", column)
+                };
+                r.push(format!("\tfor row := {}; row < {}; row++ {{", -past, end));
+                r.push(format!("\t\t{}", assertion));
+                r.push("\t}".to_owned());
+            }
+            (Some(domain), Some(column)) => {
+                let rows = domain
+                    .iter()
+                    .map(|i| {
+                        if i < 0 {
+                            format!("len(t.{})-{}", column, -i)
+                        } else {
+                            i.to_string()
+                        }
+                    })
+                    .join(", ");
+                r.push(format!("\tfor _, row := range []int{{{}}} {{", rows));
+                r.push(format!("\t\t{}", assertion));
+                r.push("\t}".to_owned());
+            }
+        }
+    }
+    r.push("\treturn nil".to_owned());
+    r.push("}".to_owned());
+    if renderer.wrapped {
+        r.push(String::new());
+        r.push("// wrap brings row back within a trace of length len".to_owned());
+        r.push("func wrap(row int, len int) int {".to_owned());
+        r.push("\treturn ((row % len) + len) % len".to_owned());
+        r.push("}".to_owned());
+    }
+
+    Ok(r.join("\n") + "\n")
+}
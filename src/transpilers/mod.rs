@@ -0,0 +1,8 @@
+#[cfg(feature = "exporters")]
+pub mod gnark;
+#[cfg(feature = "exporters")]
+pub mod rust;
+#[cfg(feature = "exporters")]
+pub mod smtlib;
+#[cfg(feature = "exporters")]
+pub mod solidity;
@@ -0,0 +1,311 @@
+//! Render a constraint system as Rust functions generic over an `arkworks`
+//! field, so that the constraints may be evaluated natively from Rust code.
+//!
+//! Every vanishing constraint is rendered as a function evaluating its
+//! expression at a given row, reading the columns through a closure taking a
+//! column name and an absolute row index; the constraint holds at this row iff
+//! the function returns zero. These functions are generated in a
+//! `constraints` module, so that they can not collide with the helpers.
+//!
+//! A `check_all` function then iterates every constraint over the rows it
+//! applies to, i.e. all of them for transition constraints, and the rows of
+//! their domain for the other ones, negative rows being counted from the end of
+//! the trace. As the modules may have different lengths, it reads them through
+//! a closure taking a module name, the main module being named `""`.
+//!
+//! Only the polynomial constraints are rendered; lookups, permutations, range
+//! and normalization constraints are left out of the generated code.
+use anyhow::*;
+use itertools::Itertools;
+use log::*;
+use num_bigint::{BigInt, Sign};
+use num_traits::ToPrimitive;
+use std::collections::HashSet;
+
+use crate::compiler::{
+    Constraint, ConstraintSet, Domain, Expression, Intrinsic, Node, MAIN_MODULE,
+};
+
+/// A rendered vanishing constraint
+struct RustConstraint {
+    /// the name of the constraint, as known to Corset
+    name: String,
+    /// the name of the Rust function evaluating it
+    function: String,
+    /// the rows it must be checked on, or `None` for all of them
+    domain: Option<Domain<isize>>,
+    /// the Rust expression computing the number of rows it spans
+    len: String,
+    /// the Rust expression computing its value
+    body: String,
+}
+
+struct RustRenderer<'a> {
+    cs: &'a ConstraintSet,
+    /// the names of the already generated functions
+    functions: HashSet<String>,
+}
+impl<'a> RustRenderer<'a> {
+    /// Turn a Corset name into a fresh Rust identifier
+    fn function_name(&mut self, name: &str) -> String {
+        let base = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+            .to_lowercase();
+        let base = if base.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("_{}", base)
+        } else {
+            base
+        };
+        let mut r = base.clone();
+        let mut i = 1;
+        while self.functions.contains(&r) {
+            r = format!("{}_{}", base, i);
+            i += 1;
+        }
+        self.functions.insert(r.clone());
+        r
+    }
+
+    /// The number of rows spanned by `e`, i.e. the length of its module times
+    /// the length multiplier of its columns
+    fn render_len(&self, e: &Node) -> String {
+        e.dependencies()
+            .iter()
+            .next()
+            .map(|h| {
+                let module = self.cs.columns.module_of(h);
+                let module = if module == MAIN_MODULE { "" } else { &module };
+                match self.cs.length_multiplier(h) {
+                    1 => format!("len({:?})", module),
+                    m => format!("len({:?}) * {}", module, m),
+                }
+            })
+            .unwrap_or_else(|| "1".to_owned())
+    }
+
+    fn render_node(&self, e: &Node) -> Result<String> {
+        match e.e() {
+            Expression::Const(x) => Ok(render_constant(&x.to_bi())),
+            Expression::Column { wrap: true, .. } => {
+                bail!("unable to render the wrapping access {} in Rust", e)
+            }
+            Expression::Column { handle, shift, .. } => Ok(format!(
+                "col({:?}, {})",
+                self.cs.handle(handle).to_string(),
+                render_row(*shift as isize)
+            )),
+            Expression::Funcall { func, args } => self.render_funcall(func, args),
+            Expression::Void => Ok("F::zero()".to_owned()),
+            Expression::List(_) => unreachable!("lists are flattened by render_vanishing"),
+            Expression::ArrayColumn { .. } | Expression::ExoColumn { .. } => {
+                bail!("unable to render {} in Rust", e)
+            }
+        }
+    }
+
+    fn render_funcall(&self, func: &Intrinsic, args: &[Node]) -> Result<String> {
+        let args_str = args
+            .iter()
+            .map(|a| self.render_node(a))
+            .collect::<Result<Vec<_>>>();
+        let chain = |method: &str, xs: Vec<String>| {
+            xs.iter()
+                .skip(1)
+                .fold(xs[0].clone(), |ax, x| format!("{}.{}({})", ax, method, x))
+        };
+        match func {
+            Intrinsic::Add | Intrinsic::VectorAdd => Ok(chain("add", args_str?)),
+            Intrinsic::Sub | Intrinsic::VectorSub => Ok(chain("sub", args_str?)),
+            Intrinsic::Mul | Intrinsic::VectorMul => Ok(chain("mul", args_str?)),
+            Intrinsic::Neg => Ok(format!("{}.neg()", self.render_node(&args[0])?)),
+            // Corset defines the inverse of 0 as 0
+            Intrinsic::Inv => Ok(format!(
+                "{}.inverse().unwrap_or_else(F::zero)",
+                self.render_node(&args[0])?
+            )),
+            Intrinsic::Normalize => Ok(format!(
+                "(if {}.is_zero() {{ F::zero() }} else {{ F::one() }})",
+                self.render_node(&args[0])?
+            )),
+            Intrinsic::Exp => {
+                let exp = args[1]
+                    .pure_eval()
+                    .ok()
+                    .and_then(|x| x.to_u64())
+                    .with_context(|| anyhow!("exponent `{}` is not a constant", &args[1]))?;
+                Ok(format!("{}.pow([{}u64])", self.render_node(&args[0])?, exp))
+            }
+            Intrinsic::IfZero | Intrinsic::IfNotZero => {
+                let xs = args_str?;
+                let (then, otherwise) =
+                    (&xs[1], xs.get(2).map(String::as_str).unwrap_or("F::zero()"));
+                let (zero, non_zero) = if matches!(func, Intrinsic::IfZero) {
+                    (then.as_str(), otherwise)
+                } else {
+                    (otherwise, then.as_str())
+                };
+                Ok(format!(
+                    "(if {}.is_zero() {{ {} }} else {{ {} }})",
+                    xs[0], zero, non_zero
+                ))
+            }
+            Intrinsic::Begin => unreachable!("lists are flattened by render_vanishing"),
+            x @ (Intrinsic::Mod | Intrinsic::Div) => bail!(
+                "{} can not be rendered in Rust; expand the constraints first",
+                x
+            ),
+        }
+    }
+
+    fn render_vanishing(
+        &mut self,
+        name: &str,
+        domain: &Option<Domain<isize>>,
+        e: &Node,
+    ) -> Result<Vec<RustConstraint>> {
+        match e.e() {
+            Expression::List(xs) => Ok(xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| self.render_vanishing(&format!("{}#{}", name, i), domain, x))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect()),
+            _ => Ok(vec![RustConstraint {
+                name: name.to_owned(),
+                function: self.function_name(name),
+                domain: domain.clone(),
+                len: self.render_len(e),
+                body: self.render_node(e)?,
+            }]),
+        }
+    }
+
+    fn render_constraint(&mut self, c: &Constraint) -> Result<Vec<RustConstraint>> {
+        match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => self.render_vanishing(&handle.to_string(), domain, expr),
+            Constraint::InRange { handle, .. }
+            | Constraint::Lookup { handle, .. }
+            | Constraint::LookupMultiplicity { handle, .. }
+            | Constraint::Permutation { handle, .. }
+            | Constraint::Normalization { handle, .. } => {
+                warn!("skipping non-polynomial constraint {}", handle);
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+/// The row at `shift` from the current one
+fn render_row(shift: isize) -> String {
+    match shift {
+        0 => "row".to_owned(),
+        s if s > 0 => format!("row + {}", s),
+        s => format!("row - {}", -s),
+    }
+}
+
+/// Render an integer as a field element, by 128-bit limbs if required
+fn render_constant(x: &BigInt) -> String {
+    let (sign, limbs) = x.to_u64_digits();
+    let limbs = limbs
+        .chunks(2)
+        .map(|l| l[0] as u128 + ((*l.get(1).unwrap_or(&0) as u128) << 64))
+        .collect::<Vec<_>>();
+    let r = if limbs.is_empty() {
+        "F::zero()".to_owned()
+    } else {
+        limbs.iter().rev().skip(1).fold(
+            format!("F::from({}u128)", limbs.last().unwrap()),
+            |ax, l| {
+                format!(
+                    "{}.mul(F::from(1u128 << 64).square()).add(F::from({}u128))",
+                    ax, l
+                )
+            },
+        )
+    };
+    if sign == Sign::Minus {
+        format!("{}.neg()", r)
+    } else {
+        r
+    }
+}
+
+/// Render the vanishing constraints of `cs` as Rust functions generic over a
+/// field `F`
+pub fn render(cs: &ConstraintSet) -> Result<String> {
+    let mut renderer = RustRenderer {
+        cs,
+        functions: Default::default(),
+    };
+    let constraints = cs
+        .constraints
+        .iter()
+        .sorted_by_key(|c| c.name())
+        .map(|c| renderer.render_constraint(c))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut r = vec![
+        "// Generated by Corset; do not edit.".to_owned(),
+        "#![allow(unused_imports)]".to_owned(),
+        "use ark_ff::{Field, Zero};".to_owned(),
+        String::new(),
+        "pub mod constraints {".to_owned(),
+        "    use ark_ff::{Field, One, Zero};".to_owned(),
+        "    use std::ops::{Add, Mul, Neg, Sub};".to_owned(),
+    ];
+    for c in constraints.iter() {
+        r.push(String::new());
+        r.push(format!("    /// {}", c.name));
+        r.push(format!(
+            "    pub fn {}<F: Field>(row: isize, col: &impl Fn(&str, isize) -> F) -> F {{",
+            c.function
+        ));
+        r.push(format!("        {}", c.body));
+        r.push("    }".to_owned());
+    }
+    r.push("}".to_owned());
+
+    r.push(String::new());
+    r.push(
+        "/// Check all the constraints over a trace whose modules span `len(module)` rows, returning the name and the row of the first failing one"
+            .to_owned(),
+    );
+    r.push(
+        "pub fn check_all<F: Field>(len: &impl Fn(&str) -> isize, col: &impl Fn(&str, isize) -> F) -> Result<(), (&'static str, isize)> {"
+            .to_owned(),
+    );
+    for c in constraints.iter() {
+        let rows = match &c.domain {
+            None => format!("0..{}", c.len),
+            Some(domain) => format!(
+                "[{}].into_iter().map(|i: isize| if i < 0 {{ {} + i }} else {{ i }})",
+                domain.iter().join(", "),
+                c.len
+            ),
+        };
+        r.push(format!("    for row in {} {{", rows));
+        r.push(format!(
+            "        if !constraints::{}(row, col).is_zero() {{",
+            c.function
+        ));
+        r.push(format!("            return Err(({:?}, row));", c.name));
+        r.push("        }".to_owned());
+        r.push("    }".to_owned());
+    }
+    r.push("    Ok(())".to_owned());
+    r.push("}".to_owned());
+
+    Ok(r.join("\n") + "\n")
+}
@@ -0,0 +1,317 @@
+//! Render a constraint system as an SMT-LIB v2 script, so that properties of
+//! the constraints may be checked with an SMT solver (e.g. `z3` or `cvc5`).
+//!
+//! As SMT-LIB has no notion of a trace, every `(column, shift)` pair appearing
+//! in a constraint is declared as a distinct free variable; a vanishing
+//! constraint is then asserted to be equal to zero for these variables. For
+//! constraints with an explicit domain, one assertion is emitted per row of
+//! the domain; the variables of such assertions are then indexed by absolute
+//! row (e.g. `|m.A#-1|`), distinct from the relative shifts (e.g. `|m.A@-1|`)
+//! of the transition constraints.
+//!
+//! Two theories are available:
+//!   - [`Theory::Field`] uses the finite-field theory (`QF_FF`, currently only
+//!     supported by `cvc5`) over the scalar field of BLS12-377, so that the
+//!     arithmetic exactly matches the one of the prover;
+//!   - [`Theory::BitVector`] uses fixed-width bitvectors (`QF_BV`, supported by
+//!     all mainstream solvers); arithmetic is then performed modulo 2^width
+//!     instead of modulo the field prime, which is sound only as long as no
+//!     intermediate result overflows. In this mode, range constraints are also
+//!     asserted.
+use anyhow::*;
+use ark_bls12_377::Fr;
+use ark_ff::PrimeField;
+use itertools::Itertools;
+use log::*;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{ToPrimitive, Zero};
+use std::collections::BTreeSet;
+
+use crate::compiler::{ColumnRef, Constraint, ConstraintSet, Domain, Expression, Intrinsic, Node};
+
+/// The SMT theory used to represent column values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theory {
+    /// Elements of the BLS12-377 scalar field
+    Field,
+    /// Bitvectors of the given width
+    BitVector(usize),
+}
+impl Theory {
+    fn logic(&self) -> &'static str {
+        match self {
+            Theory::Field => "QF_FF",
+            Theory::BitVector(_) => "QF_BV",
+        }
+    }
+
+    fn sort(&self) -> String {
+        match self {
+            Theory::Field => {
+                let p: BigUint = Fr::MODULUS.into();
+                format!("(_ FiniteField {})", p)
+            }
+            Theory::BitVector(w) => format!("(_ BitVec {})", w),
+        }
+    }
+
+    fn constant(&self, x: &BigInt) -> String {
+        match self {
+            Theory::Field => {
+                let p: BigInt = BigUint::from(Fr::MODULUS).into();
+                let x = ((x % &p) + &p) % &p;
+                format!("(as ff{} F)", x)
+            }
+            Theory::BitVector(w) => {
+                // field elements standing for negative numbers, e.g. p - 1 for
+                // -1, are brought back to them, so that they are reduced to
+                // their two's complement
+                let p: BigInt = BigUint::from(Fr::MODULUS).into();
+                let x = if x > &(&p >> 1) { x - &p } else { x.clone() };
+                let m = BigInt::from(1) << *w;
+                let x = ((x % &m) + &m) % &m;
+                format!("(_ bv{} {})", x, w)
+            }
+        }
+    }
+
+    fn add(&self) -> &'static str {
+        match self {
+            Theory::Field => "ff.add",
+            Theory::BitVector(_) => "bvadd",
+        }
+    }
+
+    fn mul(&self) -> &'static str {
+        match self {
+            Theory::Field => "ff.mul",
+            Theory::BitVector(_) => "bvmul",
+        }
+    }
+
+    fn neg(&self) -> &'static str {
+        match self {
+            Theory::Field => "ff.neg",
+            Theory::BitVector(_) => "bvneg",
+        }
+    }
+}
+
+struct SmtRenderer<'a> {
+    cs: &'a ConstraintSet,
+    theory: Theory,
+    /// all the (column, row) pairs that must be declared
+    variables: BTreeSet<(String, Row)>,
+}
+impl<'a> SmtRenderer<'a> {
+    fn variable(&mut self, c: &ColumnRef, row: Row) -> String {
+        let name = self.cs.handle(c).to_string();
+        let r = var_name(&name, row);
+        self.variables.insert((name, row));
+        r
+    }
+
+    fn render_node(&mut self, e: &Node, row: Row) -> Result<String> {
+        match e.e() {
+            Expression::Const(x) => Ok(self.theory.constant(&x.to_bi())),
+            Expression::Column { wrap: true, .. } => {
+                bail!("unable to render the wrapping access {} in SMT-LIB", e)
+            }
+            Expression::Column {
+                handle, shift: s, ..
+            } => Ok(self.variable(handle, row.shifted(*s as isize))),
+            Expression::Funcall { func, args } => self.render_funcall(func, args, row),
+            Expression::Void => Ok(self.theory.constant(&BigInt::zero())),
+            Expression::List(_) => unreachable!("lists are flattened by render_constraint"),
+            Expression::ArrayColumn { .. } | Expression::ExoColumn { .. } => {
+                bail!("unable to render {} in SMT-LIB", e)
+            }
+        }
+    }
+
+    fn render_funcall(&mut self, func: &Intrinsic, args: &[Node], row: Row) -> Result<String> {
+        let args_str = |r: &mut Self| -> Result<Vec<String>> {
+            args.iter().map(|a| r.render_node(a, row)).collect()
+        };
+        match func {
+            Intrinsic::Add | Intrinsic::VectorAdd => Ok(format!(
+                "({} {})",
+                self.theory.add(),
+                args_str(self)?.join(" ")
+            )),
+            Intrinsic::Mul | Intrinsic::VectorMul => Ok(format!(
+                "({} {})",
+                self.theory.mul(),
+                args_str(self)?.join(" ")
+            )),
+            Intrinsic::Sub | Intrinsic::VectorSub => {
+                // a - b - c ≡ a + (-b) + (-c), which works for both theories
+                let xs = args_str(self)?;
+                Ok(format!(
+                    "({} {} {})",
+                    self.theory.add(),
+                    xs[0],
+                    xs[1..]
+                        .iter()
+                        .map(|x| format!("({} {})", self.theory.neg(), x))
+                        .join(" ")
+                ))
+            }
+            Intrinsic::Neg => Ok(format!(
+                "({} {})",
+                self.theory.neg(),
+                self.render_node(&args[0], row)?
+            )),
+            Intrinsic::Exp => {
+                let exp = args[1]
+                    .pure_eval()
+                    .ok()
+                    .and_then(|x| x.to_usize())
+                    .with_context(|| anyhow!("exponent `{}` is not a constant", &args[1]))?;
+                match exp {
+                    0 => Ok(self.theory.constant(&BigInt::from(1))),
+                    1 => self.render_node(&args[0], row),
+                    _ => {
+                        let x = self.render_node(&args[0], row)?;
+                        Ok(format!(
+                            "({} {})",
+                            self.theory.mul(),
+                            std::iter::repeat(x).take(exp).join(" ")
+                        ))
+                    }
+                }
+            }
+            Intrinsic::Begin => unreachable!("lists are flattened by render_constraint"),
+            x => bail!(
+                "{} can not be rendered in SMT-LIB; expand the constraints first",
+                x
+            ),
+        }
+    }
+
+    fn render_vanishing(
+        &mut self,
+        name: &str,
+        domain: &Option<Domain<isize>>,
+        e: &Node,
+    ) -> Result<Vec<String>> {
+        match e.e() {
+            Expression::List(xs) => Ok(xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| self.render_vanishing(&format!("{}#{}", name, i), domain, x))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect()),
+            _ => {
+                let zero = self.theory.constant(&BigInt::zero());
+                match domain {
+                    None => Ok(vec![format!(
+                        "(assert (! (= {} {}) :named {}))",
+                        self.render_node(e, Row::Relative(0))?,
+                        zero,
+                        quote(name)
+                    )]),
+                    Some(domain) => domain
+                        .iter()
+                        .map(|i| {
+                            Ok(format!(
+                                "(assert (! (= {} {}) :named {}))",
+                                self.render_node(e, Row::Absolute(i))?,
+                                zero,
+                                quote(&format!("{}@{}", name, i))
+                            ))
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    fn render_constraint(&mut self, c: &Constraint) -> Result<Vec<String>> {
+        match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => self.render_vanishing(&handle.to_string(), domain, expr),
+            Constraint::InRange { handle, exp, max } => match self.theory {
+                Theory::BitVector(_) => Ok(vec![format!(
+                    "(assert (! (bvult {} {}) :named {}))",
+                    self.render_node(exp, Row::Relative(0))?,
+                    self.theory.constant(&max.to_bi()),
+                    quote(&handle.to_string())
+                )]),
+                Theory::Field => {
+                    warn!("range constraint {} can not be expressed in QF_FF", handle);
+                    Ok(vec![format!("; skipped range constraint {}", handle)])
+                }
+            },
+            Constraint::Lookup { handle, .. }
+            | Constraint::LookupMultiplicity { handle, .. }
+            | Constraint::Permutation { handle, .. }
+            | Constraint::Normalization { handle, .. } => {
+                warn!("skipping non-polynomial constraint {}", handle);
+                Ok(vec![format!("; skipped constraint {}", handle)])
+            }
+        }
+    }
+}
+
+/// Where a column is read: either relatively to the current row, or at an
+/// absolute row of the trace
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Row {
+    Relative(isize),
+    Absolute(isize),
+}
+impl Row {
+    fn shifted(self, s: isize) -> Row {
+        match self {
+            Row::Relative(x) => Row::Relative(x + s),
+            Row::Absolute(x) => Row::Absolute(x + s),
+        }
+    }
+}
+
+fn var_name(name: &str, row: Row) -> String {
+    match row {
+        Row::Relative(0) => quote(name),
+        Row::Relative(shift) => quote(&format!("{}@{}", name, shift)),
+        Row::Absolute(i) => quote(&format!("{}#{}", name, i)),
+    }
+}
+
+/// Quote a symbol, so that it may contain any character save `|` and `\`
+fn quote(s: &str) -> String {
+    format!("|{}|", s.replace(['|', '\\'], "_"))
+}
+
+/// Render the constraint set `cs` as an SMT-LIB script using the given theory
+pub fn render(cs: &ConstraintSet, theory: Theory) -> Result<String> {
+    let mut renderer = SmtRenderer {
+        cs,
+        theory,
+        variables: Default::default(),
+    };
+    let asserts = cs
+        .constraints
+        .iter()
+        .sorted_by_key(|c| c.name())
+        .map(|c| renderer.render_constraint(c))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut r = Vec::new();
+    r.push(format!("(set-logic {})", theory.logic()));
+    r.push(format!("(define-sort F () {})", theory.sort()));
+    for (name, row) in renderer.variables.iter() {
+        r.push(format!("(declare-const {} F)", var_name(name, *row)));
+    }
+    r.extend(asserts);
+    r.push("(check-sat)".to_owned());
+    Ok(r.join("\n") + "\n")
+}
@@ -0,0 +1,317 @@
+//! Render a constraint system as a Solidity library, so that the constraints
+//! may be verified on-chain over the scalar field of bn256.
+//!
+//! Every vanishing constraint is rendered as a function `require`-ing that its
+//! expression evaluates to zero at a given row. The trace is given as a
+//! `uint256[][]` indexed by column then row, the index of every column being
+//! exported as a constant; shifts are mapped to index arithmetic on the row,
+//! so that checking a constraint on a row where a shift falls out of the trace
+//! reverts.
+//!
+//! As Solidity has no native field inversion, the inverses required by a
+//! constraint are not computed on-chain, but precomputed off-chain and given to
+//! its function as an `inverses` array, in the order they appear in the
+//! constraint. Each one of them is checked to actually be the inverse of its
+//! operand, or zero if the operand is zero, before being used.
+//!
+//! Only the polynomial constraints are rendered; lookups, permutations, range
+//! and normalization constraints are left out of the generated code.
+use anyhow::*;
+use itertools::Itertools;
+use log::*;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+use crate::compiler::{ColumnRef, Constraint, ConstraintSet, Domain, Expression, Intrinsic, Node};
+
+/// The order of the scalar field of bn256
+const BN256_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+/// A rendered vanishing constraint
+struct SolidityConstraint {
+    /// the name of the constraint, as known to Corset
+    name: String,
+    /// the name of the Solidity function checking it
+    function: String,
+    /// the rows it must be checked on, or `None` for all of them
+    domain: Option<Domain<isize>>,
+    /// the Solidity expression computing its value
+    body: String,
+    /// how many precomputed inverses it requires
+    inverses: usize,
+}
+
+struct SolidityRenderer<'a> {
+    cs: &'a ConstraintSet,
+    /// the names of the already generated functions
+    functions: HashSet<String>,
+    /// the columns used in the constraints, and the name of the constant
+    /// holding their index in the trace
+    columns: BTreeMap<usize, String>,
+    /// the number of inverses used so far in the current constraint
+    inverses: usize,
+}
+impl<'a> SolidityRenderer<'a> {
+    /// Turn a Corset name into a Solidity identifier
+    fn identifier(name: &str) -> String {
+        let r = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        if r.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("_{}", r)
+        } else {
+            r
+        }
+    }
+
+    /// Turn a Corset name into a fresh Solidity function name
+    fn function_name(&mut self, name: &str) -> String {
+        let base = format!("check_{}", Self::identifier(name).to_lowercase());
+        let mut r = base.clone();
+        let mut i = 1;
+        while self.functions.contains(&r) {
+            r = format!("{}_{}", base, i);
+            i += 1;
+        }
+        self.functions.insert(r.clone());
+        r
+    }
+
+    /// The name of the constant holding the index of `handle` in the trace
+    fn column(&mut self, handle: &ColumnRef) -> String {
+        let id = self.cs.columns.id_of(handle);
+        let cs = self.cs;
+        self.columns
+            .entry(id)
+            .or_insert_with(|| {
+                format!(
+                    "COL_{}",
+                    Self::identifier(&cs.handle(handle).to_string()).to_uppercase()
+                )
+            })
+            .clone()
+    }
+
+    fn render_node(&mut self, e: &Node) -> Result<String> {
+        match e.e() {
+            Expression::Const(x) => Ok(render_constant(&x.to_bi())),
+            Expression::Column { wrap: true, .. } => {
+                bail!("unable to render the wrapping access {} in Solidity", e)
+            }
+            Expression::Column { handle, shift, .. } => Ok(format!(
+                "cols[{}][{}]",
+                self.column(handle),
+                render_row(*shift as isize)
+            )),
+            Expression::Funcall { func, args } => self.render_funcall(func, args),
+            Expression::Void => Ok("0".to_owned()),
+            Expression::List(_) => unreachable!("lists are flattened by render_vanishing"),
+            Expression::ArrayColumn { .. } | Expression::ExoColumn { .. } => {
+                bail!("unable to render {} in Solidity", e)
+            }
+        }
+    }
+
+    fn render_funcall(&mut self, func: &Intrinsic, args: &[Node]) -> Result<String> {
+        let chain = |method: &str, xs: Vec<String>| {
+            xs.iter().skip(1).fold(xs[0].clone(), |ax, x| {
+                format!("{}({}, {}, P)", method, ax, x)
+            })
+        };
+        match func {
+            Intrinsic::Add | Intrinsic::VectorAdd => Ok(chain("addmod", self.render_args(args)?)),
+            Intrinsic::Sub | Intrinsic::VectorSub => Ok(self
+                .render_args(args)?
+                .into_iter()
+                .reduce(|ax, x| format!("submod({}, {})", ax, x))
+                .unwrap()),
+            Intrinsic::Mul | Intrinsic::VectorMul => Ok(chain("mulmod", self.render_args(args)?)),
+            Intrinsic::Neg => Ok(format!("submod(0, {})", self.render_node(&args[0])?)),
+            Intrinsic::Inv => {
+                let x = self.render_node(&args[0])?;
+                let i = self.inverses;
+                self.inverses += 1;
+                Ok(format!("inverse({}, inverses[{}])", x, i))
+            }
+            Intrinsic::Normalize => Ok(format!("({} == 0 ? 0 : 1)", self.render_node(&args[0])?)),
+            Intrinsic::Exp => {
+                let exp = args[1]
+                    .pure_eval()
+                    .ok()
+                    .and_then(|x| x.to_u64())
+                    .with_context(|| anyhow!("exponent `{}` is not a constant", &args[1]))?;
+                Ok(format!("pow({}, {})", self.render_node(&args[0])?, exp))
+            }
+            Intrinsic::IfZero | Intrinsic::IfNotZero => {
+                let xs = self.render_args(args)?;
+                let (then, otherwise) = (&xs[1], xs.get(2).map(String::as_str).unwrap_or("0"));
+                let (zero, non_zero) = if matches!(func, Intrinsic::IfZero) {
+                    (then.as_str(), otherwise)
+                } else {
+                    (otherwise, then.as_str())
+                };
+                Ok(format!("({} == 0 ? {} : {})", xs[0], zero, non_zero))
+            }
+            Intrinsic::Begin => unreachable!("lists are flattened by render_vanishing"),
+            x @ (Intrinsic::Mod | Intrinsic::Div) => bail!(
+                "{} can not be rendered in Solidity; expand the constraints first",
+                x
+            ),
+        }
+    }
+
+    fn render_args(&mut self, args: &[Node]) -> Result<Vec<String>> {
+        args.iter().map(|a| self.render_node(a)).collect()
+    }
+
+    fn render_vanishing(
+        &mut self,
+        name: &str,
+        domain: &Option<Domain<isize>>,
+        e: &Node,
+    ) -> Result<Vec<SolidityConstraint>> {
+        match e.e() {
+            Expression::List(xs) => Ok(xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| self.render_vanishing(&format!("{}#{}", name, i), domain, x))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect()),
+            _ => {
+                self.inverses = 0;
+                let body = self.render_node(e)?;
+                Ok(vec![SolidityConstraint {
+                    name: name.to_owned(),
+                    function: self.function_name(name),
+                    domain: domain.clone(),
+                    body,
+                    inverses: self.inverses,
+                }])
+            }
+        }
+    }
+
+    fn render_constraint(&mut self, c: &Constraint) -> Result<Vec<SolidityConstraint>> {
+        match c {
+            Constraint::Vanishes {
+                handle,
+                domain,
+                expr,
+            } => self.render_vanishing(&handle.to_string(), domain, expr),
+            Constraint::InRange { handle, .. }
+            | Constraint::Lookup { handle, .. }
+            | Constraint::LookupMultiplicity { handle, .. }
+            | Constraint::Permutation { handle, .. }
+            | Constraint::Normalization { handle, .. } => {
+                warn!("skipping non-polynomial constraint {}", handle);
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+/// The row at `shift` from the current one
+fn render_row(shift: isize) -> String {
+    match shift {
+        0 => "row".to_owned(),
+        s if s > 0 => format!("row + {}", s),
+        s => format!("row - {}", -s),
+    }
+}
+
+/// Render an integer as an element of the bn256 scalar field
+fn render_constant(x: &BigInt) -> String {
+    let p = BigInt::from_str(BN256_MODULUS).unwrap();
+    let x = if x.is_negative() {
+        (x % &p) + &p
+    } else {
+        x % &p
+    };
+    x.to_string()
+}
+
+/// Render the vanishing constraints of `cs` as a Solidity library
+pub fn render(cs: &ConstraintSet) -> Result<String> {
+    let mut renderer = SolidityRenderer {
+        cs,
+        functions: Default::default(),
+        columns: Default::default(),
+        inverses: 0,
+    };
+    let constraints = cs
+        .constraints
+        .iter()
+        .sorted_by_key(|c| c.name())
+        .map(|c| renderer.render_constraint(c))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut r = vec![
+        "// SPDX-License-Identifier: UNLICENSED".to_owned(),
+        "// Generated by Corset; do not edit.".to_owned(),
+        "pragma solidity ^0.8.0;".to_owned(),
+        String::new(),
+        "library Constraints {".to_owned(),
+        format!("    uint256 internal constant P = {};", BN256_MODULUS),
+        String::new(),
+    ];
+    for (id, name) in renderer.columns.iter() {
+        r.push(format!("    uint256 internal constant {} = {};", name, id));
+    }
+    r.push(
+        r#"
+    function submod(uint256 a, uint256 b) internal pure returns (uint256) {
+        return addmod(a, P - b, P);
+    }
+
+    function pow(uint256 x, uint256 n) internal pure returns (uint256 r) {
+        r = 1;
+        for (; n > 0; n >>= 1) {
+            if (n & 1 == 1) {
+                r = mulmod(r, x, P);
+            }
+            x = mulmod(x, x, P);
+        }
+    }
+
+    /// Check that `hint` is the inverse of `x`, zero being its own inverse
+    function inverse(uint256 x, uint256 hint) internal pure returns (uint256) {
+        require(x == 0 ? hint == 0 : mulmod(x, hint, P) == 1, "invalid inverse");
+        return hint;
+    }"#
+        .to_owned(),
+    );
+    for c in constraints.iter() {
+        r.push(String::new());
+        r.push(format!("    /// {}", c.name));
+        r.push(match &c.domain {
+            None => "    /// must hold on every row".to_owned(),
+            Some(domain) => format!(
+                "    /// must hold on rows {}, negative ones counting from the end of the trace",
+                domain.iter().join(", ")
+            ),
+        });
+        let inverses = if c.inverses > 0 {
+            format!(", uint256[] memory inverses /* {} */", c.inverses)
+        } else {
+            String::new()
+        };
+        r.push(format!(
+            "    function {}(uint256[][] memory cols, uint256 row{}) internal pure {{",
+            c.function, inverses
+        ));
+        r.push(format!("        require({} == 0, {:?});", c.body, c.name));
+        r.push("    }".to_owned());
+    }
+    r.push("}".to_owned());
+
+    Ok(r.join("\n") + "\n")
+}
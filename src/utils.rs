@@ -5,6 +5,10 @@ use postgres::Client;
 #[cfg(feature = "postgres")]
 use std::io::Read;
 
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
 use crate::{column::Value, compiler::Magma, pretty::Pretty, structs::Handle};
 
 pub fn is_file_empty(f: &str) -> Result<bool> {
@@ -13,6 +17,53 @@ pub fn is_file_empty(f: &str) -> Result<bool> {
         .map(|f| f.len() == 0)
 }
 
+/// Write to `path` through `write`, going through a temporary file in the same
+/// directory that is only renamed to `path` once completely written; so that a
+/// failed or interrupted write never leaves a partial file behind.
+pub fn write_atomically<F: FnOnce(&mut BufWriter<File>) -> Result<()>>(
+    path: &str,
+    write: F,
+) -> Result<()> {
+    let target = Path::new(path);
+    // Special files, e.g. /dev/stdout, can not be replaced
+    if target.metadata().is_ok_and(|m| !m.is_file()) {
+        let mut out = BufWriter::new(
+            File::create(target).with_context(|| anyhow!("while creating `{}`", path))?,
+        );
+        write(&mut out).with_context(|| anyhow!("while writing to `{}`", path))?;
+        return out
+            .flush()
+            .with_context(|| anyhow!("while writing to `{}`", path));
+    }
+
+    let tmp = target.with_file_name(format!(
+        ".{}.{}.tmp",
+        target
+            .file_name()
+            .with_context(|| anyhow!("`{}` is not a file", path))?
+            .to_string_lossy(),
+        std::process::id()
+    ));
+
+    let r = File::create(&tmp)
+        .with_context(|| anyhow!("while creating `{}`", path))
+        .and_then(|f| {
+            let mut out = BufWriter::with_capacity(10_000_000, f);
+            write(&mut out).with_context(|| anyhow!("while writing to `{}`", path))?;
+            out.into_inner()
+                .map_err(|e| e.into_error())
+                .and_then(|f| f.sync_all())
+                .with_context(|| anyhow!("while writing to `{}`", path))
+        })
+        .and_then(|_| {
+            std::fs::rename(&tmp, target).with_context(|| anyhow!("while creating `{}`", path))
+        });
+    if r.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    r
+}
+
 #[cfg(feature = "postgres")]
 pub fn connect_to_db(
     user: &str,
@@ -96,3 +147,73 @@ pub fn hash_strings<S: ToString, I: Iterator<Item = S>>(xs: I) -> String {
     s.truncate(6);
     s
 }
+
+/// A value displayed in the given style if the standard output supports
+/// colors, or if they are forced by `--color`; and plainly otherwise.
+pub struct Colored<'a, T> {
+    value: &'a T,
+    style: owo_colors::Style,
+}
+
+macro_rules! style_methods {
+    ($($name:ident),* $(,)?) => {
+        $(
+            fn $name(&self) -> Colored<'_, Self> {
+                Colorize::style(self, owo_colors::Style::new().$name())
+            }
+        )*
+    };
+}
+
+macro_rules! restyle_methods {
+    ($($name:ident),* $(,)?) => {
+        $(
+            pub fn $name(self) -> Self {
+                Colored {
+                    value: self.value,
+                    style: self.style.$name(),
+                }
+            }
+        )*
+    };
+}
+
+/// Styles values in place of [`owo_colors::OwoColorize`], whose styles are
+/// unconditionally applied, so that `--color never` is honored everywhere.
+pub trait Colorize: std::fmt::Display + Sized {
+    fn style(&self, style: owo_colors::Style) -> Colored<'_, Self> {
+        Colored { value: self, style }
+    }
+
+    fn color<C: owo_colors::DynColor>(&self, color: C) -> Colored<'_, Self> {
+        self.style(owo_colors::Style::new().color(color))
+    }
+
+    style_methods!(bold, red, yellow, blue, magenta, white, bright_white);
+}
+impl<T: std::fmt::Display> Colorize for T {}
+
+impl<'a, T> Colored<'a, T> {
+    restyle_methods!(bold, red, yellow, white, bright_white);
+}
+
+macro_rules! impl_fmt {
+    ($($trait:path),* $(,)?) => {
+        $(
+            impl<T: $trait> $trait for Colored<'_, T> {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    use owo_colors::OwoColorize;
+                    <_ as $trait>::fmt(
+                        &self
+                            .value
+                            .if_supports_color(owo_colors::Stream::Stdout, |v| {
+                                self.style.style(v)
+                            }),
+                        f,
+                    )
+                }
+            }
+        )*
+    };
+}
+impl_fmt!(std::fmt::Display, std::fmt::Debug);
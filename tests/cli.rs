@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Write the given files to a fresh temporary directory, and return their
+/// paths.
+fn fixtures(name: &str, files: &[(&str, &str)]) -> Vec<String> {
+    let dir: PathBuf = std::env::temp_dir().join(format!("corset-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    files
+        .iter()
+        .map(|(file, content)| {
+            let path = dir.join(file);
+            std::fs::write(&path, content).unwrap();
+            path.to_str().unwrap().to_owned()
+        })
+        .collect()
+}
+
+/// Run corset with the given `--color` choice, and return everything it wrote
+/// to stdout and stderr.
+fn corset(color: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_corset"))
+        .args(["--color", color])
+        .args(args)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap() + &String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn color_never() {
+    let files = fixtures(
+        "colors",
+        &[
+            (
+                "doubled.lisp",
+                "(defcolumns X Y) (defconstraint doubled () (vanishes! (- Y (* 2 X))))",
+            ),
+            (
+                "duplicated.lisp",
+                "(defcolumns X) (defconstraint c () (vanishes! X)) (defconstraint c () (vanishes! X))",
+            ),
+            ("failing.json", r#"{"<prelude>": {"X": [1, 2], "Y": [2, 5]}}"#),
+            ("short.json", r#"{"<prelude>": {"Trace": {"X": [1, 2], "Y": [2]}}}"#),
+        ],
+    );
+    let [doubled, duplicated, failing, short] = &files[..] else {
+        unreachable!()
+    };
+
+    for args in [
+        // the section headers of the constraints
+        vec!["debug", "-c", doubled],
+        // a compilation error
+        vec!["debug", duplicated],
+        // the report of a failing constraint
+        vec!["check", "-r", "-T", failing, doubled],
+        // an import error
+        vec!["check", "-T", short, doubled],
+    ] {
+        assert!(corset("always", &args).contains('\x1b'), "{:?}", args);
+        assert!(!corset("never", &args).contains('\x1b'), "{:?}", args);
+    }
+}
+
+#[test]
+fn native_constant_divisor() {
+    let files = fixtures(
+        "divisor",
+        &[
+            (
+                "quotient.lisp",
+                "(defcolumns x q) (defconst FOUR 4) (defconstraint quotient () (eq! q (/ x FOUR)))",
+            ),
+            (
+                "exact.json",
+                r#"{"<prelude>": {"x": [8, 12], "q": [2, 3]}}"#,
+            ),
+            (
+                "wrong.json",
+                r#"{"<prelude>": {"x": [8, 12], "q": [2, 4]}}"#,
+            ),
+        ],
+    );
+    let [quotient, exact, wrong] = &files[..] else {
+        unreachable!()
+    };
+
+    // in the field, the divisor is lowered to a multiplication by its inverse
+    for (trace, expected) in [(exact, true), (wrong, false)] {
+        let status = Command::new(env!("CARGO_BIN_EXE_corset"))
+            .args(["--native", "check", "-T", trace, quotient])
+            .output()
+            .unwrap()
+            .status;
+        assert_eq!(status.success(), expected, "{}", trace);
+    }
+    assert!(!corset("never", &["--native", "debug", "-c", quotient]).contains("DIV"));
+}
@@ -0,0 +1,4 @@
+(defcolumns X)
+
+;; row 10 lies beyond the generated traces, and must be skipped
+(defconstraint constraint-1 (:domain {0 2 10}) (vanishes! X))
@@ -0,0 +1,2 @@
+(defcolumns X Y)
+(deflookup-multiplicity l (X) (Y))
@@ -0,0 +1,3 @@
+(defcolumns X Y W)
+(definterleaved (Z) (X Y))
+(deflookup-multiplicity l (Z) (W))
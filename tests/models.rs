@@ -275,6 +275,31 @@ static MODELS: &[Model] = &[
         cols: &["ST", "X", "Y"],
         oracle: Some(issue219_d_oracle),
     },
+    Model {
+        name: "domain_1",
+        cols: &["X"],
+        oracle: Some(domain_1_oracle),
+    },
+    Model {
+        name: "lookup_multiplicity_1",
+        cols: &["X", "Y"],
+        oracle: Some(lookup_multiplicity_1_oracle),
+    },
+    Model {
+        name: "lookup_1",
+        cols: &["X", "Y", "W"],
+        oracle: Some(lookup_1_oracle),
+    },
+    Model {
+        name: "lookup_2",
+        cols: &["X", "Y", "W"],
+        oracle: Some(lookup_2_oracle),
+    },
+    Model {
+        name: "lookup_multiplicity_2",
+        cols: &["X", "Y", "W"],
+        oracle: Some(lookup_1_oracle),
+    },
 ];
 
 // ===================================================================
@@ -413,3 +438,60 @@ fn issue219_d_oracle(tr: &Trace) -> bool {
     }
     true
 }
+
+// ===================================================================
+// Domains
+// ===================================================================
+
+#[allow(non_snake_case)]
+fn domain_1_oracle(tr: &Trace) -> bool {
+    let X = tr.col("X");
+
+    for k in [0, 2, 10] {
+        if k < tr.height() && X[k] != 0 {
+            return false;
+        }
+    }
+    true
+}
+
+// ===================================================================
+// Lookups
+// ===================================================================
+
+#[allow(non_snake_case)]
+fn lookup_multiplicity_1_oracle(tr: &Trace) -> bool {
+    let (X, Y) = (tr.col("X"), tr.col("Y"));
+
+    for k in 0..tr.height() {
+        if !(0..tr.height()).any(|j| X[j] == Y[k]) {
+            return false;
+        }
+    }
+    true
+}
+
+#[allow(non_snake_case)]
+fn lookup_1_oracle(tr: &Trace) -> bool {
+    let (X, Y, W) = (tr.col("X"), tr.col("Y"), tr.col("W"));
+
+    for k in 0..tr.height() {
+        if !(0..tr.height()).any(|j| X[j] == W[k] || Y[j] == W[k]) {
+            return false;
+        }
+    }
+    true
+}
+
+#[allow(non_snake_case)]
+fn lookup_2_oracle(tr: &Trace) -> bool {
+    let (X, Y, W) = (tr.col("X"), tr.col("Y"), tr.col("W"));
+
+    for k in 0..tr.height() {
+        let found = |v: isize| (0..tr.height()).any(|j| W[j] == v);
+        if !found(X[k]) || !found(Y[k]) {
+            return false;
+        }
+    }
+    true
+}